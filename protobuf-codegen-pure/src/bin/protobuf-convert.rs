@@ -0,0 +1,264 @@
+//! Convert messages between binary, text format and JSON
+//! using descriptors loaded from `.proto` files or a `FileDescriptorSet`,
+//! like `protoc --encode` and `protoc --decode`, but without `protoc`.
+
+extern crate protobuf;
+extern crate protobuf_codegen_pure;
+
+use std::env;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::io::Read;
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::exit;
+
+use protobuf::descriptor::FileDescriptorSet;
+use protobuf::json;
+use protobuf::raw_message;
+use protobuf::reflect::FileDescriptor;
+use protobuf::reflect::MessageDescriptor;
+use protobuf::reflect::TypeRegistry;
+use protobuf::text_format;
+use protobuf::Message;
+use protobuf::MessageDyn;
+
+const USAGE: &str = "\
+usage: protobuf-convert [OPTIONS] --type <MESSAGE> --from <FORMAT> --to <FORMAT> < input > output
+       protobuf-convert --decode-raw < input > output
+
+Converts a message read from stdin between formats.
+
+Formats: binary, text, json
+
+Options:
+    -I, --include <DIR>         directory to search imports in, can be repeated
+    --proto <FILE>              .proto file to load, can be repeated
+    --descriptor-set <FILE>     binary FileDescriptorSet to load, can be repeated
+    --type <MESSAGE>            fully qualified message name, e.g. foo.bar.Baz
+    --from <FORMAT>             input format
+    --to <FORMAT>               output format
+    --decode-raw                decode binary input without schema
+    -h, --help                  print this help
+";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+    Binary,
+    Text,
+    Json,
+}
+
+impl Format {
+    fn parse(s: &str) -> Result<Format, Error> {
+        match s {
+            "binary" => Ok(Format::Binary),
+            "text" => Ok(Format::Text),
+            "json" => Ok(Format::Json),
+            _ => Err(Error(format!("unknown format: {}", s))),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Error(String);
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Self {
+        Error(e.to_string())
+    }
+}
+
+impl From<protobuf::ProtobufError> for Error {
+    fn from(e: protobuf::ProtobufError) -> Self {
+        Error(e.to_string())
+    }
+}
+
+#[derive(Default)]
+struct Args {
+    includes: Vec<PathBuf>,
+    protos: Vec<PathBuf>,
+    descriptor_sets: Vec<PathBuf>,
+    message_type: Option<String>,
+    from: Option<Format>,
+    to: Option<Format>,
+    decode_raw: bool,
+    help: bool,
+}
+
+impl Args {
+    fn parse(args: Vec<String>) -> Result<Args, Error> {
+        let mut r = Args::default();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            let mut value = || {
+                args.next()
+                    .ok_or_else(|| Error(format!("missing value for {}", arg)))
+            };
+            match arg.as_str() {
+                "-I" | "--include" => r.includes.push(PathBuf::from(value()?)),
+                "--proto" => r.protos.push(PathBuf::from(value()?)),
+                "--descriptor-set" => r.descriptor_sets.push(PathBuf::from(value()?)),
+                "--type" => r.message_type = Some(value()?),
+                "--from" => r.from = Some(Format::parse(&value()?)?),
+                "--to" => r.to = Some(Format::parse(&value()?)?),
+                "--decode-raw" => r.decode_raw = true,
+                "-h" | "--help" => r.help = true,
+                _ => return Err(Error(format!("unknown argument: {}", arg))),
+            }
+        }
+        Ok(r)
+    }
+}
+
+/// Load all files from `.proto` files and descriptor sets.
+fn load_files(args: &Args) -> Result<Vec<FileDescriptor>, Error> {
+    let mut protos = Vec::new();
+
+    if !args.protos.is_empty() {
+        let mut includes = args.includes.clone();
+        if includes.is_empty() {
+            includes.push(PathBuf::from("."));
+        }
+        let parsed = protobuf_codegen_pure::parse_and_typecheck(&includes, &args.protos)?;
+        protos.extend(parsed.file_descriptors);
+    }
+
+    for path in &args.descriptor_sets {
+        let fds = FileDescriptorSet::parse_from_bytes(&fs::read(path)?)?;
+        for file in fds.file {
+            // the same file may be loaded from several sources
+            if !protos.iter().any(|p| p.get_name() == file.get_name()) {
+                protos.push(file);
+            }
+        }
+    }
+
+    Ok(FileDescriptor::new_dynamic_fds(protos))
+}
+
+fn read_message(
+    descriptor: &MessageDescriptor,
+    format: Format,
+    input: &[u8],
+    type_registry: &TypeRegistry,
+) -> Result<Box<dyn MessageDyn>, Error> {
+    let mut message = descriptor.new_instance();
+    match format {
+        Format::Binary => message.merge_from_bytes_dyn(input)?,
+        Format::Text | Format::Json => {
+            let input = std::str::from_utf8(input)
+                .map_err(|e| Error(format!("input is not UTF-8: {}", e)))?;
+            if format == Format::Text {
+                let options = text_format::ParseOptions {
+                    type_registry: type_registry.clone(),
+                    ..Default::default()
+                };
+                text_format::merge_from_str_with_options(&mut *message, input, &options)
+                    .map_err(|e| Error(e.to_string()))?;
+            } else {
+                let options = json::ParseOptions {
+                    type_registry: type_registry.clone(),
+                    ..Default::default()
+                };
+                json::merge_from_str_with_options(&mut *message, input, &options)
+                    .map_err(|e| Error(e.to_string()))?;
+            }
+        }
+    }
+    message.check_initialized_dyn()?;
+    Ok(message)
+}
+
+fn write_message(
+    message: &dyn MessageDyn,
+    format: Format,
+    type_registry: &TypeRegistry,
+) -> Result<Vec<u8>, Error> {
+    match format {
+        Format::Binary => Ok(message.write_to_bytes_dyn()?),
+        Format::Text => {
+            let options = text_format::PrintOptions {
+                type_registry: type_registry.clone(),
+                single_line: false,
+                ..Default::default()
+            };
+            Ok(text_format::print_to_string_with_options(message, &options).into_bytes())
+        }
+        Format::Json => {
+            let options = json::PrintOptions {
+                type_registry: type_registry.clone(),
+                ..Default::default()
+            };
+            let mut json = json::print_to_string_with_options(message, &options)
+                .map_err(|e| Error(e.to_string()))?;
+            json.push('\n');
+            Ok(json.into_bytes())
+        }
+    }
+}
+
+fn run(args: Args, input: &[u8]) -> Result<Vec<u8>, Error> {
+    if args.decode_raw {
+        return Ok(raw_message::decode_raw(input)?
+            .print_to_string()
+            .into_bytes());
+    }
+
+    let message_type = args
+        .message_type
+        .as_ref()
+        .ok_or_else(|| Error("--type is required".to_owned()))?;
+    let from = args
+        .from
+        .ok_or_else(|| Error("--from is required".to_owned()))?;
+    let to = args
+        .to
+        .ok_or_else(|| Error("--to is required".to_owned()))?;
+
+    let mut type_registry = TypeRegistry::new();
+    for file in load_files(&args)? {
+        type_registry.add_file(&file);
+    }
+
+    let descriptor = type_registry
+        .find_message_by_full_name(message_type.trim_start_matches('.'))
+        .ok_or_else(|| Error(format!("message type not found: {}", message_type)))?;
+
+    let message = read_message(&descriptor, from, input, &type_registry)?;
+    write_message(&*message, to, &type_registry)
+}
+
+fn main() {
+    let args = match Args::parse(env::args().skip(1).collect()) {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("{}\n\n{}", e, USAGE);
+            exit(1);
+        }
+    };
+
+    if args.help {
+        print!("{}", USAGE);
+        return;
+    }
+
+    let mut input = Vec::new();
+    let result = io::stdin()
+        .read_to_end(&mut input)
+        .map_err(Error::from)
+        .and_then(|_| run(args, &input))
+        .and_then(|output| Ok(io::stdout().write_all(&output)?));
+    if let Err(e) = result {
+        eprintln!("error: {}", e);
+        exit(1);
+    }
+}
@@ -25,6 +25,7 @@ use crate::protobuf_codegen::case_convert::camel_case;
 use crate::protobuf_codegen::ProtobufAbsolutePath;
 use crate::protobuf_codegen::ProtobufIdent;
 use crate::protobuf_codegen::ProtobufRelativePath;
+use crate::source_code_info;
 use protobuf::descriptor::descriptor_proto::ReservedRange;
 use protobuf::descriptor::field_descriptor_proto::Type;
 use protobuf::reflect::RuntimeTypeBox;
@@ -143,7 +144,7 @@ trait ProtobufOptions {
 impl<'a> ProtobufOptions for &'a [model::ProtobufOption] {
     fn by_name(&self, name: &str) -> Option<&model::ProtobufConstant> {
         let option_name = ProtobufOptionName::simple(name);
-        for model::ProtobufOption { name, value } in *self {
+        for model::ProtobufOption { name, value, .. } in *self {
             if name == &option_name {
                 return Some(&value);
             }
@@ -421,7 +422,7 @@ impl TypeResolved {
     }
 }
 
-struct Resolver<'a> {
+pub(crate) struct Resolver<'a> {
     current_file: &'a model::FileDescriptor,
     deps: &'a [FileDescriptorPair],
 }
@@ -583,6 +584,18 @@ impl<'a> Resolver<'a> {
                 }
             }
 
+            // Synthetic oneofs of proto3 `optional` fields follow regular oneofs
+            for field in &mut fields {
+                if field.get_proto3_optional() {
+                    let name =
+                        synthetic_oneof_name(field.get_name(), &input.fields, &output.oneof_decl);
+                    field.set_oneof_index(output.oneof_decl.len() as i32);
+                    let mut oneof = protobuf::descriptor::OneofDescriptorProto::new();
+                    oneof.set_name(name);
+                    output.oneof_decl.push(oneof);
+                }
+            }
+
             output.field = fields;
         }
 
@@ -647,6 +660,12 @@ impl<'a> Resolver<'a> {
                 .full_name
                 .to_string(),
         );
+        if input.client_streaming {
+            output.set_client_streaming(true);
+        }
+        if input.server_streaming {
+            output.set_server_streaming(true);
+        }
         Ok(output)
     }
 
@@ -677,11 +696,8 @@ impl<'a> Resolver<'a> {
     where
         M: Message,
     {
-        if M::descriptor_static().full_name() == "google.protobuf.FieldOptions" {
-            if option.get() == "default" || option.get() == "json_name" {
-                // some options are written to non-options message and handled outside
-                return Ok(());
-            }
+        if is_pseudo_option::<M>(option) {
+            return Ok(());
         }
         match M::descriptor_static().get_field_by_name(option.get()) {
             Some(field) => {
@@ -853,6 +869,79 @@ impl<'a> Resolver<'a> {
         }
     }
 
+    /// Field numbers of the option in options message `M`
+    /// and whether the last field is repeated,
+    /// or `None` if the option value is not stored in options message.
+    pub(crate) fn option_path<M>(
+        &self,
+        scope: &ProtobufAbsolutePath,
+        option: &model::ProtobufOption,
+    ) -> ConvertResult<Option<(Vec<i32>, bool)>>
+    where
+        M: Message,
+    {
+        let option_name = match &option.name {
+            ProtobufOptionName::Builtin(simple) => {
+                if is_pseudo_option::<M>(simple) {
+                    return Ok(None);
+                }
+                return match M::descriptor_static().get_field_by_name(simple.get()) {
+                    Some(field) => Ok(Some((
+                        vec![field.get_proto().get_number()],
+                        field.is_repeated_or_map(),
+                    ))),
+                    None => Err(ConvertError::BuiltinOptionNotFound(
+                        M::descriptor_static().full_name().to_owned(),
+                        simple.get().to_owned(),
+                    )),
+                };
+            }
+            ProtobufOptionName::Ext(e) => e,
+        };
+
+        let mut options_type = WithFullName {
+            full_name: ProtobufAbsolutePath::from_path_without_dot(
+                M::descriptor_static().full_name(),
+            ),
+            t: M::descriptor_static().get_proto().clone(),
+        };
+        let mut path = Vec::new();
+        for (i, component) in option_name.0.iter().enumerate() {
+            let field = self.ext_resolve_field(
+                scope,
+                &WithFullName {
+                    full_name: options_type.full_name.clone(),
+                    t: &options_type.t,
+                },
+                component,
+            )?;
+            path.push(field.get_number());
+            if i == option_name.0.len() - 1 {
+                let repeated = field.get_label()
+                    == protobuf::descriptor::field_descriptor_proto::Label::LABEL_REPEATED;
+                return Ok(Some((path, repeated)));
+            }
+            match TypeResolved::from_field(&field) {
+                TypeResolved::Message(message_name) => {
+                    let m = self.find_message_by_abs_name(&message_name)?;
+                    options_type = WithFullName {
+                        t: self.message(&message_name.parent().unwrap(), m.t)?,
+                        full_name: message_name,
+                    };
+                }
+                // Options in groups are not interpreted
+                TypeResolved::Group(..) => return Ok(None),
+                _ => {
+                    return Err(ConvertError::ExtensionIsNotMessage(format!(
+                        "{}",
+                        component
+                    )))
+                }
+            }
+        }
+        unreachable!("option name is not empty")
+    }
+
     fn custom_options<M>(
         &self,
         scope: &ProtobufAbsolutePath,
@@ -889,7 +978,13 @@ impl<'a> Resolver<'a> {
         if let model::FieldType::Map(..) = input.t.typ {
             output.set_label(protobuf::descriptor::field_descriptor_proto::Label::LABEL_REPEATED);
         } else {
-            output.set_label(label(input.t.rule));
+            output.set_label(label(input.t.rule.unwrap_or(model::Rule::Optional)));
+
+            if self.current_file.syntax == model::Syntax::Proto3
+                && input.t.rule == Some(model::Rule::Optional)
+            {
+                output.set_proto3_optional(true);
+            }
         }
 
         let t = self.field_type(scope, &input.t.name, &input.t.typ)?;
@@ -1396,6 +1491,12 @@ impl<'a> Resolver<'a> {
     }
 }
 
+/// Some field options are written to non-options message and handled outside
+fn is_pseudo_option<M: Message>(option: &ProtobufIdent) -> bool {
+    M::descriptor_static().full_name() == "google.protobuf.FieldOptions"
+        && (option.get() == "default" || option.get() == "json_name")
+}
+
 fn syntax(input: model::Syntax) -> String {
     match input {
         model::Syntax::Proto2 => "proto2".to_owned(),
@@ -1403,6 +1504,32 @@ fn syntax(input: model::Syntax) -> String {
     }
 }
 
+/// Name of synthetic oneof for proto3 `optional` field,
+/// computed the same way as `protoc` does.
+fn synthetic_oneof_name(
+    field_name: &str,
+    fields: &[model::WithLoc<model::FieldOrOneOf>],
+    oneofs: &[protobuf::descriptor::OneofDescriptorProto],
+) -> String {
+    let taken = |name: &str| {
+        oneofs.iter().any(|o| o.get_name() == name)
+            || fields.iter().any(|fo| match &fo.t {
+                model::FieldOrOneOf::Field(f) => f.t.name == name,
+                model::FieldOrOneOf::OneOf(o) => o.fields.iter().any(|f| f.t.name == name),
+            })
+    };
+
+    let mut name = if field_name.starts_with('_') {
+        field_name.to_owned()
+    } else {
+        format!("_{}", field_name)
+    };
+    while taken(&name) {
+        name = format!("X{}", name);
+    }
+    name
+}
+
 fn label(input: model::Rule) -> protobuf::descriptor::field_descriptor_proto::Label {
     match input {
         model::Rule::Optional => {
@@ -1494,5 +1621,10 @@ pub(crate) fn file_descriptor(
     output.options =
         Some(resolver.file_options(&resolver.current_file.package, &input.options)?).into();
 
+    output.source_code_info = Some(source_code_info::source_code_info(
+        &resolver, input, &output,
+    )?)
+    .into();
+
     Ok(output)
 }
@@ -41,6 +41,7 @@ mod linked_hash_map;
 mod model;
 mod parser;
 mod path;
+mod source_code_info;
 
 use linked_hash_map::LinkedHashMap;
 use protobuf_codegen::amend_io_error;
@@ -166,19 +166,60 @@ pub enum FieldType {
     Group(Group),
 }
 
+/// Span of source code, end is exclusive
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Span {
+    pub start: Loc,
+    pub end: Loc,
+}
+
+impl Default for Span {
+    fn default() -> Span {
+        Span {
+            start: Loc::start(),
+            end: Loc::start(),
+        }
+    }
+}
+
+/// Location and comments of a declaration
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SourceInfo {
+    /// Span of the whole declaration
+    pub span: Span,
+    /// Spans of declaration parts (like name or number),
+    /// by field number in the corresponding descriptor proto message
+    pub parts: Vec<(i32, Span)>,
+    /// Comments
+    pub comments: Comments,
+}
+
+/// Comments attached to a declaration
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Comments {
+    /// Comments before the declaration separated from it by blank lines
+    pub leading_detached: Vec<String>,
+    /// Comment immediately before the declaration
+    pub leading: Option<String>,
+    /// Comment after the declaration on the same or the next line
+    pub trailing: Option<String>,
+}
+
 /// A Protobuf Field
 #[derive(Debug, Clone, PartialEq)]
 pub struct Field {
     /// Field name
     pub name: String,
-    /// Field `Rule`
-    pub rule: Rule,
+    /// Field `Rule`, `None` if field is declared without label
+    pub rule: Option<Rule>,
     /// Field type
     pub typ: FieldType,
     /// Tag number
     pub number: i32,
     /// Non-builtin options
     pub options: Vec<ProtobufOption>,
+    /// Field location and comments
+    pub source: SourceInfo,
 }
 
 /// A Protobuf field of oneof group
@@ -197,6 +238,17 @@ pub struct FieldNumberRange {
     pub to: i32,
 }
 
+/// Location of `extensions` or `reserved` statement
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RangesStatement {
+    /// Field number of items in `DescriptorProto`
+    pub field_number: i32,
+    /// Statement location and comments
+    pub source: SourceInfo,
+    /// Locations of ranges (with `start` and `end` parts) or names
+    pub items: Vec<SourceInfo>,
+}
+
 /// A protobuf message
 #[derive(Debug, Clone, Default)]
 pub struct Message {
@@ -220,6 +272,10 @@ pub struct Message {
     pub extension_ranges: Vec<FieldNumberRange>,
     /// Extensions
     pub extensions: Vec<WithLoc<Extension>>,
+    /// Locations of `extensions` and `reserved` statements
+    pub range_statements: Vec<RangesStatement>,
+    /// Message location and comments
+    pub source: SourceInfo,
 }
 
 impl Message {
@@ -283,6 +339,8 @@ pub struct EnumValue {
     pub number: i32,
     /// enum value options
     pub options: Vec<ProtobufOption>,
+    /// enum value location and comments
+    pub source: SourceInfo,
 }
 
 /// A protobuf enumerator
@@ -294,6 +352,8 @@ pub struct Enumeration {
     pub values: Vec<EnumValue>,
     /// enum options
     pub options: Vec<ProtobufOption>,
+    /// enum location and comments
+    pub source: SourceInfo,
 }
 
 /// A OneOf
@@ -305,6 +365,8 @@ pub struct OneOf {
     pub fields: Vec<WithLoc<Field>>,
     /// oneof options
     pub options: Vec<ProtobufOption>,
+    /// oneof location and comments
+    pub source: SourceInfo,
 }
 
 #[derive(Debug, Clone)]
@@ -313,6 +375,8 @@ pub struct Extension {
     pub extendee: ProtobufPath,
     /// Extension field
     pub field: WithLoc<Field>,
+    /// Location and comments of `extend` block containing this extension
+    pub block: SourceInfo,
 }
 
 /// Service method
@@ -330,6 +394,8 @@ pub struct Method {
     pub server_streaming: bool,
     /// Method options
     pub options: Vec<ProtobufOption>,
+    /// Method location and comments
+    pub source: SourceInfo,
 }
 
 /// Service definition
@@ -339,6 +405,8 @@ pub struct Service {
     pub name: String,
     pub methods: Vec<Method>,
     pub options: Vec<ProtobufOption>,
+    /// Service location and comments
+    pub source: SourceInfo,
 }
 
 #[derive(Debug, Clone, PartialEq, Default)]
@@ -478,6 +546,8 @@ impl fmt::Display for ProtobufOptionName {
 pub struct ProtobufOption {
     pub name: ProtobufOptionName,
     pub value: ProtobufConstant,
+    /// Option location and comments
+    pub source: SourceInfo,
 }
 
 /// Visibility of import statement
@@ -499,6 +569,10 @@ impl Default for ImportVis {
 pub struct Import {
     pub path: String,
     pub vis: ImportVis,
+    /// Span of `public` or `weak` keyword
+    pub vis_span: Option<Span>,
+    /// Import statement location and comments
+    pub source: SourceInfo,
 }
 
 /// A File descriptor representing a whole .proto file
@@ -520,6 +594,12 @@ pub struct FileDescriptor {
     pub services: Vec<WithLoc<Service>>,
     /// Non-builtin options
     pub options: Vec<ProtobufOption>,
+    /// Span from the first to the last token of the file
+    pub span: Span,
+    /// Syntax statement location and comments
+    pub syntax_source: Option<SourceInfo>,
+    /// Package statement location and comments
+    pub package_source: Option<SourceInfo>,
 }
 
 impl FileDescriptor {
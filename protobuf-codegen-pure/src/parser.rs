@@ -1,6 +1,7 @@
 use std::str;
 
 use protobuf::text_format::lexer::LexerError;
+use protobuf::text_format::lexer::Loc;
 use protobuf::text_format::lexer::NumLit;
 use protobuf::text_format::lexer::ParserLanguage;
 use protobuf::text_format::lexer::StrLitDecodeError;
@@ -193,7 +194,13 @@ impl MessageBodyParseMode {
                 | MessageBodyParseMode::ExtendProto3 => true,
                 MessageBodyParseMode::Oneof => false,
             },
-            Rule::Optional | Rule::Required => match *self {
+            Rule::Optional => match *self {
+                MessageBodyParseMode::MessageProto2
+                | MessageBodyParseMode::MessageProto3
+                | MessageBodyParseMode::ExtendProto2 => true,
+                MessageBodyParseMode::ExtendProto3 | MessageBodyParseMode::Oneof => false,
+            },
+            Rule::Required => match *self {
                 MessageBodyParseMode::MessageProto2 | MessageBodyParseMode::ExtendProto2 => true,
                 MessageBodyParseMode::MessageProto3
                 | MessageBodyParseMode::ExtendProto3
@@ -250,6 +257,9 @@ pub struct MessageBody {
     pub options: Vec<ProtobufOption>,
     pub extension_ranges: Vec<FieldNumberRange>,
     pub extensions: Vec<WithLoc<Extension>>,
+    pub range_statements: Vec<RangesStatement>,
+    /// Comment after opening brace
+    pub trailing_comment: Option<String>,
 }
 
 trait NumLitEx {
@@ -275,6 +285,57 @@ impl<'a> Parser<'a> {
         }
     }
 
+    /// Detached and leading comments of the declaration starting at the next token
+    fn lookahead_comments(&mut self) -> ParserResult<Comments> {
+        let comments = self.tokenizer.lookahead_comments()?;
+        Ok(Comments {
+            leading_detached: comments.detached,
+            leading: comments.leading,
+            trailing: None,
+        })
+    }
+
+    /// Trailing comment of the last consumed token
+    fn last_token_trailing_comment(&self) -> Option<String> {
+        self.tokenizer.last_token_comments().trailing.clone()
+    }
+
+    /// Start of the declaration at the next token
+    fn begin_source(&mut self) -> ParserResult<SourceInfo> {
+        let start = self.tokenizer.lookahead_loc();
+        Ok(SourceInfo {
+            span: Span { start, end: start },
+            parts: Vec::new(),
+            comments: self.lookahead_comments()?,
+        })
+    }
+
+    /// Span from `start` to the end of the last consumed token
+    fn span_from(&self, start: Loc) -> Span {
+        Span {
+            start,
+            end: self.tokenizer.last_token_end_loc(),
+        }
+    }
+
+    /// Declaration ends with the last consumed token
+    fn end_source(&self, source: &mut SourceInfo) {
+        source.span = self.span_from(source.span.start);
+    }
+
+    /// Parse part of declaration and remember its span
+    fn next_part<R>(
+        &mut self,
+        source: &mut SourceInfo,
+        field_number: i32,
+        parse: impl FnOnce(&mut Self) -> ParserResult<R>,
+    ) -> ParserResult<R> {
+        let start = self.tokenizer.lookahead_loc();
+        let r = parse(self)?;
+        source.parts.push((field_number, self.span_from(start)));
+        Ok(r)
+    }
+
     // Protobuf grammar
 
     // fullIdent = ident { "." ident }
@@ -426,7 +487,8 @@ impl<'a> Parser<'a> {
 
     // syntax = "syntax" "=" quote "proto2" quote ";"
     // syntax = "syntax" "=" quote "proto3" quote ";"
-    fn next_syntax(&mut self) -> ParserResult<Option<Syntax>> {
+    fn next_syntax(&mut self) -> ParserResult<Option<(Syntax, SourceInfo)>> {
+        let mut source = self.begin_source()?;
         if self.tokenizer.next_ident_if_eq("syntax")? {
             self.tokenizer.next_symbol_expect_eq('=')?;
             let syntax_str = self.tokenizer.next_str_lit()?.decode_utf8()?;
@@ -438,7 +500,9 @@ impl<'a> Parser<'a> {
                 return Err(ParserError::UnknownSyntax);
             };
             self.tokenizer.next_symbol_expect_eq(';')?;
-            Ok(Some(syntax))
+            source.comments.trailing = self.last_token_trailing_comment();
+            self.end_source(&mut source);
+            Ok(Some((syntax, source)))
         } else {
             Ok(None)
         }
@@ -448,7 +512,9 @@ impl<'a> Parser<'a> {
 
     // import = "import" [ "weak" | "public" ] strLit ";"
     fn next_import_opt(&mut self) -> ParserResult<Option<Import>> {
+        let mut source = self.begin_source()?;
         if self.tokenizer.next_ident_if_eq("import")? {
+            let vis_start = self.tokenizer.lookahead_loc();
             let vis = if self.tokenizer.next_ident_if_eq("weak")? {
                 ImportVis::Weak
             } else if self.tokenizer.next_ident_if_eq("public")? {
@@ -456,9 +522,20 @@ impl<'a> Parser<'a> {
             } else {
                 ImportVis::Default
             };
+            let vis_span = match vis {
+                ImportVis::Default => None,
+                _ => Some(self.span_from(vis_start)),
+            };
             let path = self.tokenizer.next_str_lit()?.decode_utf8()?;
             self.tokenizer.next_symbol_expect_eq(';')?;
-            Ok(Some(Import { path, vis }))
+            source.comments.trailing = self.last_token_trailing_comment();
+            self.end_source(&mut source);
+            Ok(Some(Import {
+                path,
+                vis,
+                vis_span,
+                source,
+            }))
         } else {
             Ok(None)
         }
@@ -467,11 +544,14 @@ impl<'a> Parser<'a> {
     // Package
 
     // package = "package" fullIdent ";"
-    fn next_package_opt(&mut self) -> ParserResult<Option<ProtobufAbsolutePath>> {
+    fn next_package_opt(&mut self) -> ParserResult<Option<(ProtobufAbsolutePath, SourceInfo)>> {
+        let mut source = self.begin_source()?;
         if self.tokenizer.next_ident_if_eq("package")? {
             let package = self.next_full_ident_rel()?;
             self.tokenizer.next_symbol_expect_eq(';')?;
-            Ok(Some(package.into_absolute()))
+            source.comments.trailing = self.last_token_trailing_comment();
+            self.end_source(&mut source);
+            Ok(Some((package.into_absolute(), source)))
         } else {
             Ok(None)
         }
@@ -511,12 +591,19 @@ impl<'a> Parser<'a> {
 
     // option = "option" optionName  "=" constant ";"
     fn next_option_opt(&mut self) -> ParserResult<Option<ProtobufOption>> {
+        let mut source = self.begin_source()?;
         if self.tokenizer.next_ident_if_eq("option")? {
             let name = self.next_option_name()?;
             self.tokenizer.next_symbol_expect_eq('=')?;
             let value = self.next_constant()?;
             self.tokenizer.next_symbol_expect_eq(';')?;
-            Ok(Some(ProtobufOption { name, value }))
+            source.comments.trailing = self.last_token_trailing_comment();
+            self.end_source(&mut source);
+            Ok(Some(ProtobufOption {
+                name,
+                value,
+                source,
+            }))
         } else {
             Ok(None)
         }
@@ -525,7 +612,7 @@ impl<'a> Parser<'a> {
     // Fields
 
     // label = "required" | "optional" | "repeated"
-    fn next_label(&mut self, mode: MessageBodyParseMode) -> ParserResult<Rule> {
+    fn next_label(&mut self, mode: MessageBodyParseMode) -> ParserResult<Option<Rule>> {
         let map = &[
             ("optional", Rule::Optional),
             ("required", Rule::Required),
@@ -539,14 +626,14 @@ impl<'a> Parser<'a> {
                 }
 
                 *self = clone;
-                return Ok(value);
+                return Ok(Some(value));
             }
         }
 
         if mode.some_label_required() {
             Err(ParserError::LabelRequired)
         } else {
-            Ok(Rule::Optional)
+            Ok(None)
         }
     }
 
@@ -591,21 +678,42 @@ impl<'a> Parser<'a> {
     }
 
     // fieldOption = optionName "=" constant
-    fn next_field_option(&mut self) -> ParserResult<ProtobufOption> {
+    fn next_field_option(&mut self, source: &mut SourceInfo) -> ParserResult<ProtobufOption> {
+        let start = self.tokenizer.lookahead_loc();
         let name = self.next_option_name()?;
         self.tokenizer.next_symbol_expect_eq('=')?;
-        let value = self.next_constant()?;
-        Ok(ProtobufOption { name, value })
+        // Builtin pseudo-options are stored in `FieldDescriptorProto` fields
+        let field_number = match name {
+            ProtobufOptionName::Builtin(ref n) if n.get() == "default" => Some(7),
+            ProtobufOptionName::Builtin(ref n) if n.get() == "json_name" => Some(10),
+            _ => None,
+        };
+        let value = match field_number {
+            Some(field_number) => self.next_part(source, field_number, |p| p.next_constant())?,
+            None => self.next_constant()?,
+        };
+        if field_number == Some(10) {
+            // Like `protoc`, `json_name` has locations of both assignment and value
+            source.parts.push((10, self.span_from(start)));
+        }
+        Ok(ProtobufOption {
+            name,
+            value,
+            source: SourceInfo {
+                span: self.span_from(start),
+                ..SourceInfo::default()
+            },
+        })
     }
 
     // fieldOptions = fieldOption { ","  fieldOption }
-    fn next_field_options(&mut self) -> ParserResult<Vec<ProtobufOption>> {
+    fn next_field_options(&mut self, source: &mut SourceInfo) -> ParserResult<Vec<ProtobufOption>> {
         let mut options = Vec::new();
 
-        options.push(self.next_field_option()?);
+        options.push(self.next_field_option(source)?);
 
         while self.tokenizer.next_symbol_if_eq(',')? {
-            options.push(self.next_field_option()?);
+            options.push(self.next_field_option(source)?);
         }
 
         Ok(options)
@@ -615,25 +723,38 @@ impl<'a> Parser<'a> {
     // group = label "group" groupName "=" fieldNumber messageBody
     fn next_field(&mut self, mode: MessageBodyParseMode) -> ParserResult<WithLoc<Field>> {
         let loc = self.tokenizer.lookahead_loc();
+        let mut source = self.begin_source()?;
         let rule = if self.clone().tokenizer.next_ident_if_eq("map")? {
             if !mode.map_allowed() {
                 return Err(ParserError::MapFieldNotAllowed);
             }
-            Rule::Optional
+            None
         } else {
-            self.next_label(mode)?
+            let rule = self.next_label(mode)?;
+            if rule.is_some() {
+                source.parts.push((4, self.span_from(loc)));
+            }
+            rule
         };
+        let group_start = self.tokenizer.lookahead_loc();
         if self.tokenizer.next_ident_if_eq("group")? {
-            let name = self.next_group_name()?.to_owned();
+            source.parts.push((5, self.span_from(group_start)));
+            let name = self.next_part(&mut source, 1, |p| p.next_group_name())?;
             self.tokenizer.next_symbol_expect_eq('=')?;
-            let number = self.next_field_number()?;
+            let number = self.next_part(&mut source, 3, |p| p.next_field_number())?;
 
             let mode = match self.syntax {
                 Syntax::Proto2 => MessageBodyParseMode::MessageProto2,
                 Syntax::Proto3 => MessageBodyParseMode::MessageProto3,
             };
 
-            let MessageBody { fields, .. } = self.next_message_body(mode)?;
+            let MessageBody {
+                fields,
+                trailing_comment,
+                ..
+            } = self.next_message_body(mode)?;
+            source.comments.trailing = trailing_comment;
+            self.end_source(&mut source);
 
             let fields = fields
                 .into_iter()
@@ -652,29 +773,43 @@ impl<'a> Parser<'a> {
                 typ: FieldType::Group(Group { name, fields }),
                 number,
                 options: Vec::new(),
+                source,
             };
             Ok(WithLoc { t: field, loc })
         } else {
+            let type_start = self.tokenizer.lookahead_loc();
             let typ = self.next_field_type()?;
-            let name = self.tokenizer.next_ident()?.to_owned();
+            let type_field_number = match typ {
+                FieldType::MessageOrEnum(..) | FieldType::Map(..) => 6,
+                _ => 5,
+            };
+            source
+                .parts
+                .push((type_field_number, self.span_from(type_start)));
+            let name = self.next_part(&mut source, 1, |p| Ok(p.tokenizer.next_ident()?))?;
             self.tokenizer.next_symbol_expect_eq('=')?;
-            let number = self.next_field_number()?;
+            let number = self.next_part(&mut source, 3, |p| p.next_field_number())?;
 
             let mut options = Vec::new();
 
+            let options_start = self.tokenizer.lookahead_loc();
             if self.tokenizer.next_symbol_if_eq('[')? {
-                for o in self.next_field_options()? {
+                for o in self.next_field_options(&mut source)? {
                     options.push(o);
                 }
                 self.tokenizer.next_symbol_expect_eq(']')?;
+                source.parts.push((8, self.span_from(options_start)));
             }
             self.tokenizer.next_symbol_expect_eq(';')?;
+            source.comments.trailing = self.last_token_trailing_comment();
+            self.end_source(&mut source);
             let field = Field {
                 name,
                 rule,
                 typ,
                 number,
                 options,
+                source,
             };
             Ok(WithLoc { t: field, loc })
         }
@@ -683,11 +818,17 @@ impl<'a> Parser<'a> {
     // oneof = "oneof" oneofName "{" { oneofField | emptyStatement } "}"
     // oneofField = type fieldName "=" fieldNumber [ "[" fieldOptions "]" ] ";"
     fn next_oneof_opt(&mut self) -> ParserResult<Option<OneOf>> {
+        let mut source = self.begin_source()?;
         if self.tokenizer.next_ident_if_eq("oneof")? {
-            let name = self.tokenizer.next_ident()?.to_owned();
+            let name = self.next_part(&mut source, 1, |p| Ok(p.tokenizer.next_ident()?))?;
             let MessageBody {
-                fields, options, ..
+                fields,
+                options,
+                trailing_comment,
+                ..
             } = self.next_message_body(MessageBodyParseMode::Oneof)?;
+            source.comments.trailing = trailing_comment;
+            self.end_source(&mut source);
             let fields = fields
                 .into_iter()
                 .map(|fo| match fo.t {
@@ -699,6 +840,7 @@ impl<'a> Parser<'a> {
                 name,
                 fields,
                 options,
+                source,
             }))
         } else {
             Ok(None)
@@ -727,34 +869,66 @@ impl<'a> Parser<'a> {
     // Extensions
 
     // range =  intLit [ "to" ( intLit | "max" ) ]
-    fn next_range(&mut self) -> ParserResult<FieldNumberRange> {
-        let from = self.next_field_number()?;
+    fn next_range(&mut self, items: &mut Vec<SourceInfo>) -> ParserResult<FieldNumberRange> {
+        let mut item = SourceInfo::default();
+        item.span.start = self.tokenizer.lookahead_loc();
+        let from = self.next_part(&mut item, 1, |p| p.next_field_number())?;
         let to = if self.tokenizer.next_ident_if_eq("to")? {
-            if self.tokenizer.next_ident_if_eq("max")? {
-                0x20000000 - 1
-            } else {
-                self.next_field_number()?
-            }
+            self.next_part(&mut item, 2, |p| {
+                if p.tokenizer.next_ident_if_eq("max")? {
+                    Ok(0x20000000 - 1)
+                } else {
+                    p.next_field_number()
+                }
+            })?
         } else {
+            // Like `protoc`, end of single number range is located at the number
+            let start_span = item.parts[0].1;
+            item.parts.push((2, start_span));
             from
         };
+        self.end_source(&mut item);
+        items.push(item);
         Ok(FieldNumberRange { from, to })
     }
 
     // ranges = range { "," range }
-    fn next_ranges(&mut self) -> ParserResult<Vec<FieldNumberRange>> {
+    fn next_ranges(&mut self, items: &mut Vec<SourceInfo>) -> ParserResult<Vec<FieldNumberRange>> {
         let mut ranges = Vec::new();
-        ranges.push(self.next_range()?);
+        ranges.push(self.next_range(items)?);
         while self.tokenizer.next_symbol_if_eq(',')? {
-            ranges.push(self.next_range()?);
+            ranges.push(self.next_range(items)?);
         }
         Ok(ranges)
     }
 
+    /// End of `extensions` or `reserved` statement
+    fn end_ranges_statement(
+        &mut self,
+        field_number: i32,
+        mut source: SourceInfo,
+        items: Vec<SourceInfo>,
+    ) -> ParserResult<RangesStatement> {
+        self.tokenizer.next_symbol_expect_eq(';')?;
+        source.comments.trailing = self.last_token_trailing_comment();
+        self.end_source(&mut source);
+        Ok(RangesStatement {
+            field_number,
+            source,
+            items,
+        })
+    }
+
     // extensions = "extensions" ranges ";"
-    fn next_extensions_opt(&mut self) -> ParserResult<Option<Vec<FieldNumberRange>>> {
+    fn next_extensions_opt(
+        &mut self,
+    ) -> ParserResult<Option<(Vec<FieldNumberRange>, RangesStatement)>> {
+        let source = self.begin_source()?;
         if self.tokenizer.next_ident_if_eq("extensions")? {
-            Ok(Some(self.next_ranges()?))
+            let mut items = Vec::new();
+            let ranges = self.next_ranges(&mut items)?;
+            let statement = self.end_ranges_statement(5, source, items)?;
+            Ok(Some((ranges, statement)))
         } else {
             Ok(None)
         }
@@ -765,22 +939,34 @@ impl<'a> Parser<'a> {
     // Grammar is incorrect: https://github.com/google/protobuf/issues/4558
     // reserved = "reserved" ( ranges | fieldNames ) ";"
     // fieldNames = fieldName { "," fieldName }
-    fn next_reserved_opt(&mut self) -> ParserResult<Option<(Vec<FieldNumberRange>, Vec<String>)>> {
+    fn next_reserved_opt(
+        &mut self,
+    ) -> ParserResult<Option<(Vec<FieldNumberRange>, Vec<String>, RangesStatement)>> {
+        let source = self.begin_source()?;
         if self.tokenizer.next_ident_if_eq("reserved")? {
-            let (ranges, names) = if let &Token::StrLit(..) = self.tokenizer.lookahead_some()? {
-                let mut names = Vec::new();
-                names.push(self.tokenizer.next_str_lit()?.decode_utf8()?);
-                while self.tokenizer.next_symbol_if_eq(',')? {
-                    names.push(self.tokenizer.next_str_lit()?.decode_utf8()?);
-                }
-                (Vec::new(), names)
-            } else {
-                (self.next_ranges()?, Vec::new())
-            };
+            let mut items = Vec::new();
+            let (ranges, names, field_number) =
+                if let &Token::StrLit(..) = self.tokenizer.lookahead_some()? {
+                    let mut names = Vec::new();
+                    loop {
+                        let start = self.tokenizer.lookahead_loc();
+                        names.push(self.tokenizer.next_str_lit()?.decode_utf8()?);
+                        items.push(SourceInfo {
+                            span: self.span_from(start),
+                            ..SourceInfo::default()
+                        });
+                        if !self.tokenizer.next_symbol_if_eq(',')? {
+                            break;
+                        }
+                    }
+                    (Vec::new(), names, 10)
+                } else {
+                    (self.next_ranges(&mut items)?, Vec::new(), 9)
+                };
 
-            self.tokenizer.next_symbol_expect_eq(';')?;
+            let statement = self.end_ranges_statement(field_number, source, items)?;
 
-            Ok(Some((ranges, names)))
+            Ok(Some((ranges, names, statement)))
         } else {
             Ok(None)
         }
@@ -792,10 +978,18 @@ impl<'a> Parser<'a> {
 
     // enumValueOption = optionName "=" constant
     fn next_enum_value_option(&mut self) -> ParserResult<ProtobufOption> {
+        let start = self.tokenizer.lookahead_loc();
         let name = self.next_option_name()?;
         self.tokenizer.next_symbol_expect_eq('=')?;
         let value = self.next_constant()?;
-        Ok(ProtobufOption { name, value })
+        Ok(ProtobufOption {
+            name,
+            value,
+            source: SourceInfo {
+                span: self.span_from(start),
+                ..SourceInfo::default()
+            },
+        })
     }
 
     // https://github.com/google/protobuf/issues/4561
@@ -815,35 +1009,45 @@ impl<'a> Parser<'a> {
 
     // enumField = ident "=" intLit [ "[" enumValueOption { ","  enumValueOption } "]" ]";"
     fn next_enum_field(&mut self) -> ParserResult<EnumValue> {
-        let name = self.tokenizer.next_ident()?.to_owned();
+        let mut source = self.begin_source()?;
+        let name = self.next_part(&mut source, 1, |p| Ok(p.tokenizer.next_ident()?))?;
         self.tokenizer.next_symbol_expect_eq('=')?;
-        let number = self.next_enum_value()?;
+        let number = self.next_part(&mut source, 2, |p| p.next_enum_value())?;
         let mut options = Vec::new();
+        let options_start = self.tokenizer.lookahead_loc();
         if self.tokenizer.next_symbol_if_eq('[')? {
             options.push(self.next_enum_value_option()?);
             while self.tokenizer.next_symbol_if_eq(',')? {
                 options.push(self.next_enum_value_option()?);
             }
             self.tokenizer.next_symbol_expect_eq(']')?;
+            source.parts.push((3, self.span_from(options_start)));
         }
+        if self.tokenizer.next_symbol_if_eq(';')? {
+            source.comments.trailing = self.last_token_trailing_comment();
+        }
+        self.end_source(&mut source);
 
         Ok(EnumValue {
             name,
             number,
             options,
+            source,
         })
     }
 
     // enum = "enum" enumName enumBody
     // enumBody = "{" { option | enumField | emptyStatement } "}"
     fn next_enum_opt(&mut self) -> ParserResult<Option<Enumeration>> {
+        let mut source = self.begin_source()?;
         if self.tokenizer.next_ident_if_eq("enum")? {
-            let name = self.tokenizer.next_ident()?.to_owned();
+            let name = self.next_part(&mut source, 1, |p| Ok(p.tokenizer.next_ident()?))?;
 
             let mut values = Vec::new();
             let mut options = Vec::new();
 
             self.tokenizer.next_symbol_expect_eq('{')?;
+            source.comments.trailing = self.last_token_trailing_comment();
             while self.tokenizer.lookahead_if_symbol()? != Some('}') {
                 // emptyStatement
                 if self.tokenizer.next_symbol_if_eq(';')? {
@@ -858,10 +1062,12 @@ impl<'a> Parser<'a> {
                 values.push(self.next_enum_field()?);
             }
             self.tokenizer.next_symbol_expect_eq('}')?;
+            self.end_source(&mut source);
             Ok(Some(Enumeration {
                 name,
                 values,
                 options,
+                source,
             }))
         } else {
             Ok(None)
@@ -876,6 +1082,7 @@ impl<'a> Parser<'a> {
         self.tokenizer.next_symbol_expect_eq('{')?;
 
         let mut r = MessageBody::default();
+        r.trailing_comment = self.last_token_trailing_comment();
 
         while self.tokenizer.lookahead_if_symbol()? != Some('}') {
             let loc = self.tokenizer.lookahead_loc();
@@ -886,9 +1093,10 @@ impl<'a> Parser<'a> {
             }
 
             if mode.is_most_non_fields_allowed() {
-                if let Some((field_nums, field_names)) = self.next_reserved_opt()? {
+                if let Some((field_nums, field_names, statement)) = self.next_reserved_opt()? {
                     r.reserved_nums.extend(field_nums);
                     r.reserved_names.extend(field_names);
+                    r.range_statements.push(statement);
                     continue;
                 }
 
@@ -898,8 +1106,9 @@ impl<'a> Parser<'a> {
                     continue;
                 }
 
-                if let Some(extension_ranges) = self.next_extensions_opt()? {
+                if let Some((extension_ranges, statement)) = self.next_extensions_opt()? {
                     r.extension_ranges.extend(extension_ranges);
+                    r.range_statements.push(statement);
                     continue;
                 }
 
@@ -947,9 +1156,10 @@ impl<'a> Parser<'a> {
     // message = "message" messageName messageBody
     fn next_message_opt(&mut self) -> ParserResult<Option<WithLoc<Message>>> {
         let loc = self.tokenizer.lookahead_loc();
+        let mut source = self.begin_source()?;
 
         if self.tokenizer.next_ident_if_eq("message")? {
-            let name = self.tokenizer.next_ident()?.to_owned();
+            let name = self.next_part(&mut source, 1, |p| Ok(p.tokenizer.next_ident()?))?;
 
             let mode = match self.syntax {
                 Syntax::Proto2 => MessageBodyParseMode::MessageProto2,
@@ -965,7 +1175,11 @@ impl<'a> Parser<'a> {
                 options,
                 extensions,
                 extension_ranges,
+                range_statements,
+                trailing_comment,
             } = self.next_message_body(mode)?;
+            source.comments.trailing = trailing_comment;
+            self.end_source(&mut source);
 
             let message = Message {
                 name,
@@ -977,6 +1191,8 @@ impl<'a> Parser<'a> {
                 options,
                 extensions,
                 extension_ranges,
+                range_statements,
+                source,
             };
             Ok(Some(WithLoc { t: message, loc }))
         } else {
@@ -988,6 +1204,7 @@ impl<'a> Parser<'a> {
 
     // extend = "extend" messageType "{" {field | group | emptyStatement} "}"
     fn next_extend_opt(&mut self) -> ParserResult<Option<Vec<WithLoc<Extension>>>> {
+        let mut block = self.begin_source()?;
         let mut clone = self.clone();
         if clone.tokenizer.next_ident_if_eq("extend")? {
             // According to spec `extend` is only for `proto2`, but it is used in `proto3`
@@ -995,14 +1212,22 @@ impl<'a> Parser<'a> {
 
             *self = clone;
 
+            let extendee_start = self.tokenizer.lookahead_loc();
             let extendee = self.next_message_or_enum_type()?;
+            let extendee_span = self.span_from(extendee_start);
 
             let mode = match self.syntax {
                 Syntax::Proto2 => MessageBodyParseMode::ExtendProto2,
                 Syntax::Proto3 => MessageBodyParseMode::ExtendProto3,
             };
 
-            let MessageBody { fields, .. } = self.next_message_body(mode)?;
+            let MessageBody {
+                fields,
+                trailing_comment,
+                ..
+            } = self.next_message_body(mode)?;
+            block.comments.trailing = trailing_comment;
+            self.end_source(&mut block);
 
             // TODO: is oneof allowed in extend?
             let fields: Vec<WithLoc<Field>> = fields
@@ -1015,10 +1240,15 @@ impl<'a> Parser<'a> {
 
             let extensions = fields
                 .into_iter()
-                .map(|field| {
+                .map(|mut field| {
                     let extendee = extendee.clone();
                     let loc = field.loc;
-                    let extension = Extension { extendee, field };
+                    field.t.source.parts.push((2, extendee_span));
+                    let extension = Extension {
+                        extendee,
+                        field,
+                        block: block.clone(),
+                    };
                     WithLoc { t: extension, loc }
                 })
                 .collect();
@@ -1031,9 +1261,12 @@ impl<'a> Parser<'a> {
 
     // Service definition
 
-    fn next_options_or_colon(&mut self) -> ParserResult<Vec<ProtobufOption>> {
+    /// Options and trailing comment
+    fn next_options_or_colon(&mut self) -> ParserResult<(Vec<ProtobufOption>, Option<String>)> {
         let mut options = Vec::new();
+        let trailing_comment;
         if self.tokenizer.next_symbol_if_eq('{')? {
+            trailing_comment = self.last_token_trailing_comment();
             while self.tokenizer.lookahead_if_symbol()? != Some('}') {
                 if let Some(option) = self.next_option_opt()? {
                     options.push(option);
@@ -1049,23 +1282,27 @@ impl<'a> Parser<'a> {
             self.tokenizer.next_symbol_expect_eq('}')?;
         } else {
             self.tokenizer.next_symbol_expect_eq(';')?;
+            trailing_comment = self.last_token_trailing_comment();
         }
 
-        Ok(options)
+        Ok((options, trailing_comment))
     }
 
     // stream = "stream" streamName "(" messageType "," messageType ")"
     //        (( "{" { option | emptyStatement } "}") | ";" )
     fn next_stream_opt(&mut self) -> ParserResult<Option<Method>> {
         assert_eq!(Syntax::Proto2, self.syntax);
+        let mut source = self.begin_source()?;
         if self.tokenizer.next_ident_if_eq("stream")? {
-            let name = self.tokenizer.next_ident()?;
+            let name = self.next_part(&mut source, 1, |p| Ok(p.tokenizer.next_ident()?))?;
             self.tokenizer.next_symbol_expect_eq('(')?;
-            let input_type = self.next_message_or_enum_type()?;
+            let input_type = self.next_part(&mut source, 2, |p| p.next_message_or_enum_type())?;
             self.tokenizer.next_symbol_expect_eq(',')?;
-            let output_type = self.next_message_or_enum_type()?;
+            let output_type = self.next_part(&mut source, 3, |p| p.next_message_or_enum_type())?;
             self.tokenizer.next_symbol_expect_eq(')')?;
-            let options = self.next_options_or_colon()?;
+            let (options, trailing_comment) = self.next_options_or_colon()?;
+            source.comments.trailing = trailing_comment;
+            self.end_source(&mut source);
             Ok(Some(Method {
                 name,
                 input_type,
@@ -1073,28 +1310,46 @@ impl<'a> Parser<'a> {
                 client_streaming: true,
                 server_streaming: true,
                 options,
+                source,
             }))
         } else {
             Ok(None)
         }
     }
 
+    /// Optional `stream` keyword in `rpc` declaration
+    fn next_stream_keyword(
+        &mut self,
+        source: &mut SourceInfo,
+        field_number: i32,
+    ) -> ParserResult<bool> {
+        let start = self.tokenizer.lookahead_loc();
+        let stream = self.tokenizer.next_ident_if_eq("stream")?;
+        if stream {
+            source.parts.push((field_number, self.span_from(start)));
+        }
+        Ok(stream)
+    }
+
     // rpc = "rpc" rpcName "(" [ "stream" ] messageType ")"
     //     "returns" "(" [ "stream" ] messageType ")"
     //     (( "{" { option | emptyStatement } "}" ) | ";" )
     fn next_rpc_opt(&mut self) -> ParserResult<Option<Method>> {
+        let mut source = self.begin_source()?;
         if self.tokenizer.next_ident_if_eq("rpc")? {
-            let name = self.tokenizer.next_ident()?;
+            let name = self.next_part(&mut source, 1, |p| Ok(p.tokenizer.next_ident()?))?;
             self.tokenizer.next_symbol_expect_eq('(')?;
-            let client_streaming = self.tokenizer.next_ident_if_eq("stream")?;
-            let input_type = self.next_message_or_enum_type()?;
+            let client_streaming = self.next_stream_keyword(&mut source, 5)?;
+            let input_type = self.next_part(&mut source, 2, |p| p.next_message_or_enum_type())?;
             self.tokenizer.next_symbol_expect_eq(')')?;
             self.tokenizer.next_ident_expect_eq("returns")?;
             self.tokenizer.next_symbol_expect_eq('(')?;
-            let server_streaming = self.tokenizer.next_ident_if_eq("stream")?;
-            let output_type = self.next_message_or_enum_type()?;
+            let server_streaming = self.next_stream_keyword(&mut source, 6)?;
+            let output_type = self.next_part(&mut source, 3, |p| p.next_message_or_enum_type())?;
             self.tokenizer.next_symbol_expect_eq(')')?;
-            let options = self.next_options_or_colon()?;
+            let (options, trailing_comment) = self.next_options_or_colon()?;
+            source.comments.trailing = trailing_comment;
+            self.end_source(&mut source);
             Ok(Some(Method {
                 name,
                 input_type,
@@ -1102,6 +1357,7 @@ impl<'a> Parser<'a> {
                 client_streaming,
                 server_streaming,
                 options,
+                source,
             }))
         } else {
             Ok(None)
@@ -1115,12 +1371,14 @@ impl<'a> Parser<'a> {
     // service = "service" serviceName "{" { option | rpc | emptyStatement } "}"
     fn next_service_opt(&mut self) -> ParserResult<Option<WithLoc<Service>>> {
         let loc = self.tokenizer.lookahead_loc();
+        let mut source = self.begin_source()?;
 
         if self.tokenizer.next_ident_if_eq("service")? {
-            let name = self.tokenizer.next_ident()?;
+            let name = self.next_part(&mut source, 1, |p| Ok(p.tokenizer.next_ident()?))?;
             let mut methods = Vec::new();
             let mut options = Vec::new();
             self.tokenizer.next_symbol_expect_eq('{')?;
+            source.comments.trailing = self.last_token_trailing_comment();
             while self.tokenizer.lookahead_if_symbol()? != Some('}') {
                 if let Some(method) = self.next_rpc_opt()? {
                     methods.push(method);
@@ -1146,12 +1404,14 @@ impl<'a> Parser<'a> {
                 return Err(ParserError::IncorrectInput);
             }
             self.tokenizer.next_symbol_expect_eq('}')?;
+            self.end_source(&mut source);
             Ok(Some(WithLoc {
                 loc,
                 t: Service {
                     name,
                     methods,
                     options,
+                    source,
                 },
             }))
         } else {
@@ -1164,8 +1424,13 @@ impl<'a> Parser<'a> {
     // proto = syntax { import | package | option | topLevelDef | emptyStatement }
     // topLevelDef = message | enum | extend | service
     pub fn next_proto(&mut self) -> ParserResult<FileDescriptor> {
-        let syntax = self.next_syntax()?.unwrap_or(Syntax::Proto2);
+        let start = self.tokenizer.lookahead_loc();
+        let (syntax, syntax_source) = match self.next_syntax()? {
+            Some((syntax, source)) => (syntax, Some(source)),
+            None => (Syntax::Proto2, None),
+        };
         self.syntax = syntax;
+        let mut package_source = None;
 
         let mut imports = Vec::new();
         let mut package = ProtobufAbsolutePath::root();
@@ -1181,8 +1446,9 @@ impl<'a> Parser<'a> {
                 continue;
             }
 
-            if let Some(next_package) = self.next_package_opt()? {
+            if let Some((next_package, source)) = self.next_package_opt()? {
                 package = next_package;
+                package_source = Some(source);
                 continue;
             }
 
@@ -1227,6 +1493,9 @@ impl<'a> Parser<'a> {
             extensions,
             services,
             options,
+            span: self.span_from(start),
+            syntax_source,
+            package_source,
         })
     }
 }
@@ -1269,7 +1538,7 @@ mod test {
     fn test_syntax() {
         let msg = r#"  syntax = "proto3";  "#;
         let mess = parse_opt(msg, |p| p.next_syntax());
-        assert_eq!(Syntax::Proto3, mess);
+        assert_eq!(Syntax::Proto3, mess.0);
     }
 
     #[test]
@@ -1406,6 +1675,118 @@ mod test {
         assert_eq!(3, mess.t.oneofs_for_test()[0].fields.len());
     }
 
+    #[test]
+    fn test_comments() {
+        let msg = r#"// detached
+
+// Message A
+message A { // after brace
+    // Field a
+    optional int32 a = 1; // after field
+    oneof o {
+        // Variant b
+        string b = 2;
+    }
+    enum E {
+        // Value X
+        X = 1; // after value
+    }
+}"#;
+
+        let mess = parse_opt(msg, |p| p.next_message_opt());
+        assert_eq!(
+            Comments {
+                leading_detached: vec![" detached\n".to_owned()],
+                leading: Some(" Message A\n".to_owned()),
+                trailing: Some(" after brace\n".to_owned()),
+            },
+            mess.t.source.comments
+        );
+        let a = mess.t.field_by_name("a").unwrap();
+        assert_eq!(Some(" Field a\n"), a.source.comments.leading.as_deref());
+        assert_eq!(
+            Some(" after field\n"),
+            a.source.comments.trailing.as_deref()
+        );
+        let b = mess.t.field_by_name("b").unwrap();
+        assert_eq!(Some(" Variant b\n"), b.source.comments.leading.as_deref());
+        let x = &mess.t.enums[0].values[0];
+        assert_eq!(Some(" Value X\n"), x.source.comments.leading.as_deref());
+        assert_eq!(
+            Some(" after value\n"),
+            x.source.comments.trailing.as_deref()
+        );
+    }
+
+    #[test]
+    fn test_source_spans() {
+        let msg = r#"message A {
+    optional int32 a = 1;
+    repeated B b = 2;
+}"#;
+
+        let mess = parse_opt(msg, |p| p.next_message_opt());
+        let span = |start: (u32, u32), end: (u32, u32)| Span {
+            start: Loc {
+                line: start.0,
+                col: start.1,
+            },
+            end: Loc {
+                line: end.0,
+                col: end.1,
+            },
+        };
+        assert_eq!(span((1, 1), (4, 2)), mess.t.source.span);
+        assert_eq!(vec![(1, span((1, 9), (1, 10)))], mess.t.source.parts);
+
+        let a = mess.t.field_by_name("a").unwrap();
+        assert_eq!(span((2, 5), (2, 26)), a.source.span);
+        assert_eq!(
+            vec![
+                (4, span((2, 5), (2, 13))),
+                (5, span((2, 14), (2, 19))),
+                (1, span((2, 20), (2, 21))),
+                (3, span((2, 24), (2, 25))),
+            ],
+            a.source.parts
+        );
+
+        let b = mess.t.field_by_name("b").unwrap();
+        assert_eq!(
+            vec![
+                (4, span((3, 5), (3, 13))),
+                (6, span((3, 14), (3, 15))),
+                (1, span((3, 16), (3, 17))),
+                (3, span((3, 20), (3, 21))),
+            ],
+            b.source.parts
+        );
+    }
+
+    #[test]
+    fn test_proto3_optional() {
+        let msg = r#"syntax = "proto3";
+
+    message A {
+        optional int32 a = 1;
+        int32 b = 2;
+    }
+    "#;
+
+        let desc = parse(msg, |p| p.next_proto());
+        let fields = desc.messages[0].t.regular_fields_for_test();
+        assert_eq!(Some(Rule::Optional), fields[0].rule);
+        assert_eq!(None, fields[1].rule);
+
+        let msg = r#"syntax = "proto3";
+
+    message A {
+        required int32 a = 1;
+    }
+    "#;
+        assert!(Parser::new(msg).next_proto().is_err());
+    }
+
     #[test]
     fn test_reserved() {
         let msg = r#"message Sample {
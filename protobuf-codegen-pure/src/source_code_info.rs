@@ -0,0 +1,570 @@
+//! Build `SourceCodeInfo` for a converted file.
+//!
+//! Elements are matched with the converted descriptor by name,
+//! because conversion reorders messages and adds synthetic declarations.
+//!
+//! Options are recorded at the paths of their interpreted values, like `protoc` does.
+
+use std::collections::HashMap;
+
+use protobuf::descriptor::source_code_info::Location;
+use protobuf::descriptor::DescriptorProto;
+use protobuf::descriptor::EnumDescriptorProto;
+use protobuf::descriptor::EnumOptions;
+use protobuf::descriptor::EnumValueOptions;
+use protobuf::descriptor::FieldDescriptorProto;
+use protobuf::descriptor::FieldOptions;
+use protobuf::descriptor::FileDescriptorProto;
+use protobuf::descriptor::FileOptions;
+use protobuf::descriptor::MessageOptions;
+use protobuf::descriptor::MethodOptions;
+use protobuf::descriptor::OneofOptions;
+use protobuf::descriptor::ServiceDescriptorProto;
+use protobuf::descriptor::ServiceOptions;
+use protobuf::descriptor::SourceCodeInfo;
+use protobuf::text_format::lexer::Loc;
+use protobuf::Message;
+use protobuf_codegen::ProtobufAbsolutePath;
+use protobuf_codegen::ProtobufIdent;
+
+use crate::convert::ConvertResult;
+use crate::convert::Resolver;
+use crate::model;
+
+// Field numbers from `descriptor.proto`
+const FILE_PACKAGE: i32 = 2;
+const FILE_DEPENDENCY: i32 = 3;
+const FILE_MESSAGE_TYPE: i32 = 4;
+const FILE_ENUM_TYPE: i32 = 5;
+const FILE_SERVICE: i32 = 6;
+const FILE_EXTENSION: i32 = 7;
+const FILE_PUBLIC_DEPENDENCY: i32 = 10;
+const FILE_WEAK_DEPENDENCY: i32 = 11;
+const FILE_OPTIONS: i32 = 8;
+const FILE_SYNTAX: i32 = 12;
+const MESSAGE_NAME: i32 = 1;
+const MESSAGE_FIELD: i32 = 2;
+const MESSAGE_NESTED_TYPE: i32 = 3;
+const MESSAGE_ENUM_TYPE: i32 = 4;
+const MESSAGE_EXTENSION: i32 = 6;
+const MESSAGE_OPTIONS: i32 = 7;
+const MESSAGE_ONEOF_DECL: i32 = 8;
+const FIELD_OPTIONS: i32 = 8;
+const ONEOF_OPTIONS: i32 = 2;
+const ENUM_VALUE: i32 = 2;
+const ENUM_OPTIONS: i32 = 3;
+const ENUM_VALUE_OPTIONS: i32 = 3;
+const SERVICE_METHOD: i32 = 2;
+const SERVICE_OPTIONS: i32 = 3;
+const METHOD_OPTIONS: i32 = 4;
+
+fn index_by_name<'a, T, I>(items: I, name: &str, get_name: impl Fn(&T) -> &str) -> Option<i32>
+where
+    T: 'a,
+    I: IntoIterator<Item = &'a T>,
+{
+    items
+        .into_iter()
+        .position(|item| get_name(item) == name)
+        .map(|i| i as i32)
+}
+
+fn child_path(path: &[i32], field_number: i32, index: i32) -> Vec<i32> {
+    let mut path = path.to_vec();
+    path.extend_from_slice(&[field_number, index]);
+    path
+}
+
+fn field_path(path: &[i32], field_number: i32) -> Vec<i32> {
+    let mut path = path.to_vec();
+    path.push(field_number);
+    path
+}
+
+fn nested_scope(scope: &ProtobufAbsolutePath, name: &str) -> ProtobufAbsolutePath {
+    let mut scope = scope.clone();
+    scope.push_simple(ProtobufIdent::from(name));
+    scope
+}
+
+/// Span in `protoc` format: zero-based start line, start column,
+/// end line and end column; end line is omitted when it equals start line.
+fn span_to_proto(span: model::Span) -> Vec<i32> {
+    let start_line = span.start.line as i32 - 1;
+    let start_col = span.start.col as i32 - 1;
+    let end_line = span.end.line as i32 - 1;
+    let end_col = span.end.col as i32 - 1;
+    if start_line == end_line {
+        vec![start_line, start_col, end_col]
+    } else {
+        vec![start_line, start_col, end_line, end_col]
+    }
+}
+
+struct Builder<'a> {
+    resolver: &'a Resolver<'a>,
+    /// Locations along with start of the declaration they belong to
+    locations: Vec<(Loc, Location)>,
+    /// Number of values of repeated options by path, to index next value
+    repeated_option_counts: HashMap<Vec<i32>, i32>,
+}
+
+impl<'a> Builder<'a> {
+    fn add_span(
+        &mut self,
+        declaration_start: Loc,
+        path: Vec<i32>,
+        span: model::Span,
+        comments: &model::Comments,
+    ) {
+        let mut location = Location::new();
+        location.path = path;
+        location.span = span_to_proto(span);
+        if let Some(leading) = &comments.leading {
+            location.set_leading_comments(leading.clone());
+        }
+        if let Some(trailing) = &comments.trailing {
+            location.set_trailing_comments(trailing.clone());
+        }
+        location.leading_detached_comments = comments.leading_detached.clone();
+        self.locations.push((declaration_start, location));
+    }
+
+    /// Path of the option value, with index for repeated options.
+    fn option_path<M: Message>(
+        &mut self,
+        scope: &ProtobufAbsolutePath,
+        options_path: &[i32],
+        option: &model::ProtobufOption,
+    ) -> ConvertResult<Option<Vec<i32>>> {
+        let (fields, repeated) = match self.resolver.option_path::<M>(scope, option)? {
+            Some(p) => p,
+            None => return Ok(None),
+        };
+        let mut path = options_path.to_vec();
+        path.extend(fields);
+        if repeated {
+            let count = self.repeated_option_counts.entry(path.clone()).or_insert(0);
+            path.push(*count);
+            *count += 1;
+        }
+        Ok(Some(path))
+    }
+
+    /// Add location of a declaration followed by locations of its parts
+    /// and of its options in brackets, in order of their start like `protoc` does.
+    fn add_with_options<M: Message>(
+        &mut self,
+        path: Vec<i32>,
+        source: &model::SourceInfo,
+        scope: &ProtobufAbsolutePath,
+        options_field_number: i32,
+        options: &[model::ProtobufOption],
+    ) -> ConvertResult<()> {
+        let mut parts: Vec<_> = source
+            .parts
+            .iter()
+            .map(|&(field_number, span)| (field_path(&path, field_number), span))
+            .collect();
+        let options_path = field_path(&path, options_field_number);
+        for option in options {
+            if let Some(option_path) = self.option_path::<M>(scope, &options_path, option)? {
+                parts.push((option_path, option.source.span));
+            }
+        }
+        parts.sort_by_key(|&(_, span)| span.start);
+
+        let start = source.span.start;
+        self.add_span(start, path, source.span, &source.comments);
+        for (part_path, span) in parts {
+            self.add_span(start, part_path, span, &model::Comments::default());
+        }
+        Ok(())
+    }
+
+    /// Add location of a declaration followed by locations of its parts.
+    fn add(&mut self, path: Vec<i32>, source: &model::SourceInfo) {
+        let start = source.span.start;
+        self.add_span(start, path.clone(), source.span, &source.comments);
+        for &(field_number, span) in &source.parts {
+            self.add_span(
+                start,
+                field_path(&path, field_number),
+                span,
+                &model::Comments::default(),
+            );
+        }
+    }
+
+    /// Add locations of `option` statements: the statement itself
+    /// and the interpreted option with comments.
+    fn option_statements<M: Message>(
+        &mut self,
+        path: &[i32],
+        scope: &ProtobufAbsolutePath,
+        options_field_number: i32,
+        options: &[model::ProtobufOption],
+    ) -> ConvertResult<()> {
+        let options_path = field_path(path, options_field_number);
+        for option in options {
+            if let Some(option_path) = self.option_path::<M>(scope, &options_path, option)? {
+                let start = option.source.span.start;
+                self.add_span(
+                    start,
+                    options_path.clone(),
+                    option.source.span,
+                    &model::Comments::default(),
+                );
+                self.add_span(
+                    start,
+                    option_path,
+                    option.source.span,
+                    &option.source.comments,
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Add locations of `extensions` and `reserved` statements and their items,
+    /// items are indexed across all statements of the message.
+    fn range_statements(&mut self, path: &[i32], statements: &[model::RangesStatement]) {
+        let mut counts = HashMap::new();
+        for statement in statements {
+            let statement_path = field_path(path, statement.field_number);
+            let start = statement.source.span.start;
+            self.add_span(
+                start,
+                statement_path.clone(),
+                statement.source.span,
+                &statement.source.comments,
+            );
+            for item in &statement.items {
+                let count = counts.entry(statement.field_number).or_insert(0);
+                let item_path = field_path(&statement_path, *count);
+                *count += 1;
+                self.add_span(
+                    start,
+                    item_path.clone(),
+                    item.span,
+                    &model::Comments::default(),
+                );
+                for &(field_number, span) in &item.parts {
+                    self.add_span(
+                        start,
+                        field_path(&item_path, field_number),
+                        span,
+                        &model::Comments::default(),
+                    );
+                }
+            }
+        }
+    }
+
+    fn field(
+        &mut self,
+        path: Vec<i32>,
+        scope: &ProtobufAbsolutePath,
+        field: &model::Field,
+    ) -> ConvertResult<()> {
+        self.add_with_options::<FieldOptions>(
+            path,
+            &field.source,
+            scope,
+            FIELD_OPTIONS,
+            &field.options,
+        )
+    }
+
+    fn fields(
+        &mut self,
+        path: &[i32],
+        scope: &ProtobufAbsolutePath,
+        fields: &[&model::WithLoc<model::Field>],
+        output: &DescriptorProto,
+    ) -> ConvertResult<()> {
+        for field in fields {
+            if let Some(index) = index_by_name(&output.field, &field.t.name, |f| f.get_name()) {
+                self.field(child_path(path, MESSAGE_FIELD, index), scope, &field.t)?;
+            }
+            if let model::FieldType::Group(group) = &field.t.typ {
+                if let Some(index) =
+                    index_by_name(&output.nested_type, &group.name, |m| m.get_name())
+                {
+                    let path = child_path(path, MESSAGE_NESTED_TYPE, index);
+                    // Like `protoc`, group message spans the whole field declaration
+                    let group_source = model::SourceInfo {
+                        span: field.t.source.span,
+                        parts: field
+                            .t
+                            .source
+                            .parts
+                            .iter()
+                            .filter(|&&(n, _)| n == MESSAGE_NAME)
+                            .cloned()
+                            .collect(),
+                        comments: model::Comments::default(),
+                    };
+                    self.add(path.clone(), &group_source);
+                    let group_fields: Vec<_> = group.fields.iter().collect();
+                    self.fields(
+                        &path,
+                        scope,
+                        &group_fields,
+                        &output.nested_type[index as usize],
+                    )?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn extensions(
+        &mut self,
+        path: &[i32],
+        scope: &ProtobufAbsolutePath,
+        field_number: i32,
+        extensions: &[model::WithLoc<model::Extension>],
+        output: &[FieldDescriptorProto],
+    ) -> ConvertResult<()> {
+        let mut last_block_span = None;
+        for e in extensions {
+            // `extend` block location is recorded once for all its extensions
+            if last_block_span != Some(e.t.block.span) {
+                last_block_span = Some(e.t.block.span);
+                self.add(field_path(path, field_number), &e.t.block);
+            }
+            let field = &e.t.field.t;
+            if let Some(index) = index_by_name(output, &field.name, |f| f.get_name()) {
+                self.field(child_path(path, field_number, index), scope, field)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn message(
+        &mut self,
+        path: Vec<i32>,
+        scope: &ProtobufAbsolutePath,
+        input: &model::Message,
+        output: &DescriptorProto,
+    ) -> ConvertResult<()> {
+        let nested_scope = nested_scope(scope, &input.name);
+
+        self.add(path.clone(), &input.source);
+        self.option_statements::<MessageOptions>(&path, scope, MESSAGE_OPTIONS, &input.options)?;
+        self.range_statements(&path, &input.range_statements);
+        self.fields(
+            &path,
+            &nested_scope,
+            &input.regular_fields_including_in_oneofs(),
+            output,
+        )?;
+
+        for field in &input.fields {
+            if let model::FieldOrOneOf::OneOf(oneof) = &field.t {
+                if let Some(index) =
+                    index_by_name(&output.oneof_decl, &oneof.name, |o| o.get_name())
+                {
+                    let path = child_path(&path, MESSAGE_ONEOF_DECL, index);
+                    self.add(path.clone(), &oneof.source);
+                    self.option_statements::<OneofOptions>(
+                        &path,
+                        scope,
+                        ONEOF_OPTIONS,
+                        &oneof.options,
+                    )?;
+                }
+            }
+        }
+
+        for nested in &input.messages {
+            if let Some(index) =
+                index_by_name(&output.nested_type, &nested.t.name, |m| m.get_name())
+            {
+                self.message(
+                    child_path(&path, MESSAGE_NESTED_TYPE, index),
+                    &nested_scope,
+                    &nested.t,
+                    &output.nested_type[index as usize],
+                )?;
+            }
+        }
+
+        for e in &input.enums {
+            if let Some(index) = index_by_name(&output.enum_type, &e.name, |e| e.get_name()) {
+                self.enumeration(
+                    child_path(&path, MESSAGE_ENUM_TYPE, index),
+                    scope,
+                    e,
+                    &output.enum_type[index as usize],
+                )?;
+            }
+        }
+
+        self.extensions(
+            &path,
+            scope,
+            MESSAGE_EXTENSION,
+            &input.extensions,
+            &output.extension,
+        )
+    }
+
+    fn enumeration(
+        &mut self,
+        path: Vec<i32>,
+        scope: &ProtobufAbsolutePath,
+        input: &model::Enumeration,
+        output: &EnumDescriptorProto,
+    ) -> ConvertResult<()> {
+        self.add(path.clone(), &input.source);
+        self.option_statements::<EnumOptions>(&path, scope, ENUM_OPTIONS, &input.options)?;
+        for value in &input.values {
+            if let Some(index) = index_by_name(&output.value, &value.name, |v| v.get_name()) {
+                self.add_with_options::<EnumValueOptions>(
+                    child_path(&path, ENUM_VALUE, index),
+                    &value.source,
+                    scope,
+                    ENUM_VALUE_OPTIONS,
+                    &value.options,
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    fn service(
+        &mut self,
+        path: Vec<i32>,
+        scope: &ProtobufAbsolutePath,
+        input: &model::Service,
+        output: &ServiceDescriptorProto,
+    ) -> ConvertResult<()> {
+        self.add(path.clone(), &input.source);
+        self.option_statements::<ServiceOptions>(&path, scope, SERVICE_OPTIONS, &input.options)?;
+        for method in &input.methods {
+            if let Some(index) = index_by_name(&output.method, &method.name, |m| m.get_name()) {
+                let path = child_path(&path, SERVICE_METHOD, index);
+                self.add(path.clone(), &method.source);
+                self.option_statements::<MethodOptions>(
+                    &path,
+                    scope,
+                    METHOD_OPTIONS,
+                    &method.options,
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Locations and comments of all declarations of the file,
+/// in the order `protoc` records them.
+pub(crate) fn source_code_info(
+    resolver: &Resolver,
+    input: &model::FileDescriptor,
+    output: &FileDescriptorProto,
+) -> ConvertResult<SourceCodeInfo> {
+    let mut builder = Builder {
+        resolver,
+        locations: Vec::new(),
+        repeated_option_counts: HashMap::new(),
+    };
+    let scope = &input.package;
+
+    builder.add_span(
+        input.span.start,
+        Vec::new(),
+        input.span,
+        &model::Comments::default(),
+    );
+
+    if let Some(source) = &input.syntax_source {
+        builder.add(vec![FILE_SYNTAX], source);
+    }
+
+    if let Some(source) = &input.package_source {
+        builder.add(vec![FILE_PACKAGE], source);
+    }
+
+    builder.option_statements::<FileOptions>(&[], scope, FILE_OPTIONS, &input.options)?;
+
+    let mut public_count = 0;
+    let mut weak_count = 0;
+    for (i, import) in input.imports.iter().enumerate() {
+        builder.add(vec![FILE_DEPENDENCY, i as i32], &import.source);
+        if let Some(vis_span) = import.vis_span {
+            let path = match import.vis {
+                model::ImportVis::Public => {
+                    public_count += 1;
+                    vec![FILE_PUBLIC_DEPENDENCY, public_count - 1]
+                }
+                model::ImportVis::Weak => {
+                    weak_count += 1;
+                    vec![FILE_WEAK_DEPENDENCY, weak_count - 1]
+                }
+                model::ImportVis::Default => continue,
+            };
+            builder.add_span(
+                import.source.span.start,
+                path,
+                vis_span,
+                &model::Comments::default(),
+            );
+        }
+    }
+
+    for m in &input.messages {
+        if let Some(index) = index_by_name(&output.message_type, &m.t.name, |m| m.get_name()) {
+            builder.message(
+                vec![FILE_MESSAGE_TYPE, index],
+                scope,
+                &m.t,
+                &output.message_type[index as usize],
+            )?;
+        }
+    }
+
+    for e in &input.enums {
+        if let Some(index) = index_by_name(&output.enum_type, &e.name, |e| e.get_name()) {
+            builder.enumeration(
+                vec![FILE_ENUM_TYPE, index],
+                scope,
+                e,
+                &output.enum_type[index as usize],
+            )?;
+        }
+    }
+
+    for s in &input.services {
+        if let Some(index) = index_by_name(&output.service, &s.t.name, |s| s.get_name()) {
+            builder.service(
+                vec![FILE_SERVICE, index],
+                scope,
+                &s.t,
+                &output.service[index as usize],
+            )?;
+        }
+    }
+
+    builder.extensions(
+        &[],
+        scope,
+        FILE_EXTENSION,
+        &input.extensions,
+        &output.extension,
+    )?;
+
+    // Declarations are recorded in source order,
+    // each followed by its parts (stable sort keeps that order)
+    builder.locations.sort_by_key(|&(start, _)| start);
+
+    let mut source_code_info = SourceCodeInfo::new();
+    source_code_info.location = builder
+        .locations
+        .into_iter()
+        .map(|(_, location)| location)
+        .collect();
+    Ok(source_code_info)
+}
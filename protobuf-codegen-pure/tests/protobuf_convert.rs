@@ -0,0 +1,102 @@
+use std::fs;
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::Command;
+use std::process::Stdio;
+
+const PROTO: &str = r#"
+syntax = "proto2";
+
+package test;
+
+message Inner {
+    optional string s = 1;
+}
+
+message Outer {
+    required int32 a = 1;
+    repeated Inner inner = 2;
+    map<string, int32> m = 3;
+}
+"#;
+
+fn proto_dir() -> PathBuf {
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("protobuf_convert");
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("convert.proto"), PROTO).unwrap();
+    dir
+}
+
+fn run(args: &[&str], input: &[u8]) -> Result<Vec<u8>, String> {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_protobuf-convert"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(input).unwrap();
+    let output = child.wait_with_output().unwrap();
+    if output.status.success() {
+        Ok(output.stdout)
+    } else {
+        Err(String::from_utf8(output.stderr).unwrap())
+    }
+}
+
+fn convert(from: &str, to: &str, input: &[u8]) -> Result<Vec<u8>, String> {
+    let dir = proto_dir();
+    run(
+        &[
+            "-I",
+            dir.to_str().unwrap(),
+            "--proto",
+            dir.join("convert.proto").to_str().unwrap(),
+            "--type",
+            "test.Outer",
+            "--from",
+            from,
+            "--to",
+            to,
+        ],
+        input,
+    )
+}
+
+#[test]
+fn round_trip() {
+    let text = "a: 5\ninner {\n  s: \"x\"\n}\nm {\n  key: \"k\"\n  value: 3\n}\n";
+
+    let binary = convert("text", "binary", text.as_bytes()).unwrap();
+    assert_eq!(
+        &[0x08, 0x05, 0x12, 0x03, 0x0a, 0x01, b'x', 0x1a, 0x05, 0x0a, 0x01, b'k', 0x10, 0x03][..],
+        &binary[..]
+    );
+
+    let json = convert("binary", "json", &binary).unwrap();
+    assert_eq!(
+        "{\"a\": 5, \"inner\": [{\"s\": \"x\"}], \"m\": {\"k\": 3}}\n",
+        String::from_utf8(json.clone()).unwrap()
+    );
+
+    let text_again = convert("json", "text", &json).unwrap();
+    assert_eq!(text, String::from_utf8(text_again).unwrap());
+}
+
+#[test]
+fn decode_raw() {
+    let output = run(&["--decode-raw"], &[0x08, 0x96, 0x01, 0x12, 0x01, b'a']).unwrap();
+    assert_eq!("1: 150\n2: \"a\"\n", String::from_utf8(output).unwrap());
+}
+
+#[test]
+fn errors() {
+    let err = convert("text", "binary", b"inner {}").unwrap_err();
+    assert!(err.contains("missing a"), "{}", err);
+
+    let err = convert("text", "binary", b"unknown: 1").unwrap_err();
+    assert!(err.starts_with("error: "), "{}", err);
+
+    let err = run(&["--type", "test.Outer"], b"").unwrap_err();
+    assert!(err.contains("--from is required"), "{}", err);
+}
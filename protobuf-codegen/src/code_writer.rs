@@ -342,22 +342,34 @@ impl<'a> CodeWriter<'a> {
         info: Option<&protobuf::descriptor::SourceCodeInfo>,
         path: &[i32],
     ) {
-        let doc = info
+        let location = match info
             .map(|v| &v.location)
             .and_then(|ls| ls.iter().find(|l| l.path == path))
-            .map(|l| l.get_leading_comments());
-
-        let lines = doc
-            .iter()
-            .map(|doc| doc.lines())
-            .flatten()
-            .collect::<Vec<_>>();
-
-        // Skip comments with code blocks to avoid rustdoc trying to compile them.
-        if !lines.iter().any(|line| line.starts_with("    ")) {
-            for doc in &lines {
-                self.documentation(doc);
+        {
+            Some(location) => location,
+            None => return,
+        };
+
+        // Detached comments are not about this element, so they are not documentation
+        for (i, detached) in location.leading_detached_comments.iter().enumerate() {
+            if i != 0 {
+                self.comment("");
             }
+            for line in comment_lines(detached) {
+                self.comment(&line);
+            }
+        }
+
+        let leading = doc_comment_lines(location.get_leading_comments());
+        let trailing = doc_comment_lines(location.get_trailing_comments());
+        for line in &leading {
+            self.documentation(line);
+        }
+        if !leading.is_empty() && !trailing.is_empty() {
+            self.documentation("");
+        }
+        for line in &trailing {
+            self.documentation(line);
         }
     }
 
@@ -479,3 +491,104 @@ impl<'a> CodeWriter<'a> {
         self.write_line(&format!("{} => {},", cond.as_ref(), body.as_ref()));
     }
 }
+
+/// Lines of `.proto` comment without the space `protoc` leaves after `//`.
+fn comment_lines(comment: &str) -> Vec<&str> {
+    let lines: Vec<&str> = comment.lines().map(|l| l.trim_end()).collect();
+    let strip_space = lines.iter().all(|l| l.is_empty() || l.starts_with(' '));
+    let mut lines: Vec<&str> = lines
+        .into_iter()
+        .map(|l| {
+            if strip_space && !l.is_empty() {
+                &l[1..]
+            } else {
+                l
+            }
+        })
+        .collect();
+    while lines.last() == Some(&"") {
+        lines.pop();
+    }
+    while lines.first() == Some(&"") {
+        lines.remove(0);
+    }
+    lines
+}
+
+/// Convert `.proto` comment into rustdoc lines.
+///
+/// Comments are not written in Rust, so code blocks (fenced or indented)
+/// are emitted as `text` blocks, otherwise rustdoc would try to compile them.
+fn doc_comment_lines(comment: &str) -> Vec<String> {
+    let mut r = Vec::new();
+    let mut in_fence = false;
+    let mut in_indented_block = false;
+    let mut pending_blank_lines = 0;
+    for line in comment_lines(comment) {
+        let trimmed = line.trim_start();
+        if in_fence {
+            if trimmed.starts_with("```") {
+                in_fence = false;
+                r.push("```".to_owned());
+            } else {
+                r.push(line.to_owned());
+            }
+            continue;
+        }
+
+        if in_indented_block {
+            if line.is_empty() {
+                pending_blank_lines += 1;
+                continue;
+            }
+            if line.starts_with("    ") {
+                r.extend((0..pending_blank_lines).map(|_| String::new()));
+                pending_blank_lines = 0;
+                r.push(line[4..].to_owned());
+                continue;
+            }
+            in_indented_block = false;
+            r.push("```".to_owned());
+            r.extend((0..pending_blank_lines).map(|_| String::new()));
+            pending_blank_lines = 0;
+        }
+
+        if trimmed.starts_with("```") {
+            in_fence = true;
+            r.push("```text".to_owned());
+        } else if line.starts_with("    ") {
+            in_indented_block = true;
+            r.push("```text".to_owned());
+            r.push(line[4..].to_owned());
+        } else {
+            r.push(line.to_owned());
+        }
+    }
+    if in_fence || in_indented_block {
+        r.push("```".to_owned());
+    }
+    r
+}
+
+#[cfg(test)]
+mod test {
+    use super::doc_comment_lines;
+
+    #[test]
+    fn test_doc_comment_lines() {
+        assert_eq!(Vec::<String>::new(), doc_comment_lines(""));
+        assert_eq!(vec!["Foo", "", "bar"], doc_comment_lines(" Foo\n\n bar\n"));
+        assert_eq!(
+            vec!["Example:", "```text", "a = 1;", "", "b = 2;", "```", "", "End"],
+            doc_comment_lines(" Example:\n     a = 1;\n\n     b = 2;\n\n End\n")
+        );
+        assert_eq!(
+            vec!["```text", "    fn main() {}", "```"],
+            doc_comment_lines(" ```rust\n     fn main() {}\n ```\n")
+        );
+        assert_eq!(
+            vec!["```text", "unterminated", "```"],
+            doc_comment_lines("```\nunterminated\n")
+        );
+    }
+}
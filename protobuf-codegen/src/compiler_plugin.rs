@@ -1,6 +1,7 @@
 use protobuf::descriptor::FileDescriptorProto;
 use protobuf::plugin::*;
 use protobuf::Message;
+use protobuf::ProtobufEnum;
 use std::io::stdin;
 use std::io::stdout;
 use std::path::PathBuf;
@@ -41,5 +42,8 @@ where
             r
         })
         .collect();
+    resp.set_supported_features(
+        code_generator_response::Feature::FEATURE_PROTO3_OPTIONAL.value() as u64,
+    );
     resp.write_to_writer(&mut stdout()).unwrap();
 }
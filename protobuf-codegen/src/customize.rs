@@ -35,6 +35,13 @@ pub struct Customize {
     /// Used internally to generate protos bundled in protobuf crate
     /// like `descriptor.proto`
     pub inside_protobuf: Option<bool>,
+    /// Generate plain `E`/`Option<E>` for fields of closed enums
+    /// (enums defined in proto2 files).
+    ///
+    /// Unknown values of closed enums are stored in unknown fields
+    /// regardless of this option, when it is `false` fields are only
+    /// stored as `ProtobufEnumOrUnknown<E>`. Default is true.
+    pub closed_enums: Option<bool>,
 
     // When adding more options please keep in sync with `parse_from_parameter` below.
     /// Make sure `Customize` is always used with `..Default::default()`
@@ -87,6 +94,9 @@ impl Customize {
         if let Some(v) = that.inside_protobuf {
             self.inside_protobuf = Some(v);
         }
+        if let Some(v) = that.closed_enums {
+            self.closed_enums = Some(v);
+        }
     }
 
     /// Update unset fields of self with fields from other customize
@@ -135,6 +145,8 @@ impl Customize {
                 r.gen_mod_rs = Some(parse_bool(v)?);
             } else if n == "inside_protobuf" {
                 r.inside_protobuf = Some(parse_bool(v)?);
+            } else if n == "closed_enums" {
+                r.closed_enums = Some(parse_bool(v)?);
             } else {
                 return Err(CustomizeParseParameterError::UnknownOptionName(
                     n.to_owned(),
@@ -157,6 +169,7 @@ pub fn customize_from_rustproto_for_message(source: &MessageOptions) -> Customiz
     let lite_runtime = None;
     let gen_mod_rs = None;
     let inside_protobuf = None;
+    let closed_enums = None;
     Customize {
         expose_oneof,
         expose_fields,
@@ -169,6 +182,7 @@ pub fn customize_from_rustproto_for_message(source: &MessageOptions) -> Customiz
         lite_runtime,
         gen_mod_rs,
         inside_protobuf,
+        closed_enums,
         _future_options: (),
     }
 }
@@ -186,6 +200,7 @@ pub fn customize_from_rustproto_for_field(source: &FieldOptions) -> Customize {
     let lite_runtime = None;
     let gen_mod_rs = None;
     let inside_protobuf = None;
+    let closed_enums = None;
     Customize {
         expose_oneof,
         expose_fields,
@@ -198,6 +213,7 @@ pub fn customize_from_rustproto_for_field(source: &FieldOptions) -> Customize {
         lite_runtime,
         gen_mod_rs,
         inside_protobuf,
+        closed_enums,
         _future_options: (),
     }
 }
@@ -214,6 +230,7 @@ pub fn customize_from_rustproto_for_file(source: &FileOptions) -> Customize {
     let lite_runtime = rustproto::exts::lite_runtime_all.get(source);
     let gen_mod_rs = None;
     let inside_protobuf = None;
+    let closed_enums = None;
     Customize {
         expose_oneof,
         expose_fields,
@@ -226,6 +243,7 @@ pub fn customize_from_rustproto_for_file(source: &FileOptions) -> Customize {
         lite_runtime,
         inside_protobuf,
         gen_mod_rs,
+        closed_enums,
         _future_options: (),
     }
 }
@@ -172,8 +172,21 @@ impl<'a> EnumGen<'a> {
             "derive(::serde::Serialize, ::serde::Deserialize)",
         );
         let ref type_name = self.type_name;
+        static VALUE_NUMBER: protobuf::rt::LazyV2<i32> = protobuf::rt::LazyV2::INIT;
+        let value_number = *VALUE_NUMBER.get(|| {
+            protobuf::reflect::MessageDescriptor::for_type::<EnumDescriptorProto>()
+                .get_field_by_name("value")
+                .expect("`value` must exist")
+                .get_proto()
+                .get_number()
+        });
+
         w.expr_block(&format!("pub enum {}", type_name), |w| {
-            for value in self.values_all() {
+            for (id, value) in self.values_all().into_iter().enumerate() {
+                let mut path = self.path.to_vec();
+                path.extend_from_slice(&[value_number, id as i32]);
+                w.all_documentation(self.info, &path);
+
                 if self.allow_alias() {
                     w.write_line(&format!(
                         "{}, // {}",
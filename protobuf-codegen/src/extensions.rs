@@ -10,19 +10,24 @@ use crate::rust_name::RustIdentWithPath;
 use crate::rust_name::RustRelativePath;
 use crate::scope::RootScope;
 use protobuf::descriptor::*;
+use protobuf::reflect::ExtensionDescriptor;
 use protobuf::reflect::FileDescriptor;
 
 struct ExtGen<'a> {
     file: &'a FileDescriptor,
     root_scope: &'a RootScope<'a>,
-    field: &'a FieldDescriptorProto,
+    extension: &'a ExtensionDescriptor,
     customize: Customize,
 }
 
 impl<'a> ExtGen<'a> {
+    fn field(&self) -> &FieldDescriptorProto {
+        self.extension.get_proto()
+    }
+
     fn extendee_rust_name(&self) -> RustIdentWithPath {
         type_name_to_rust_relative(
-            &ProtobufAbsolutePath::from(self.field.get_extendee()),
+            &ProtobufAbsolutePath::from(self.field().get_extendee()),
             &FileAndMod {
                 file: self.file.proto().get_name().to_owned(),
                 relative_mod: RustRelativePath::from("exts"),
@@ -33,19 +38,19 @@ impl<'a> ExtGen<'a> {
     }
 
     fn repeated(&self) -> bool {
-        match self.field.get_label() {
+        match self.field().get_label() {
             field_descriptor_proto::Label::LABEL_REPEATED => true,
             field_descriptor_proto::Label::LABEL_OPTIONAL => false,
             field_descriptor_proto::Label::LABEL_REQUIRED => {
-                panic!("required ext field: {}", self.field.get_name())
+                panic!("required ext field: {}", self.field().get_name())
             }
         }
     }
 
     fn return_type_gen(&self) -> ProtobufTypeGen {
-        if self.field.has_type_name() {
+        if self.field().has_type_name() {
             let rust_name_relative = type_name_to_rust_relative(
-                &ProtobufAbsolutePath::from(self.field.get_type_name()),
+                &ProtobufAbsolutePath::from(self.field().get_type_name()),
                 &FileAndMod {
                     file: self.file.proto().get_name().to_owned(),
                     relative_mod: RustRelativePath::from("exts"),
@@ -53,7 +58,7 @@ impl<'a> ExtGen<'a> {
                 },
                 self.root_scope,
             );
-            match self.field.get_field_type() {
+            match self.field().get_field_type() {
                 field_descriptor_proto::Type::TYPE_MESSAGE => {
                     ProtobufTypeGen::Message(RustTypeMessage(rust_name_relative))
                 }
@@ -63,7 +68,7 @@ impl<'a> ExtGen<'a> {
                 t => panic!("unknown type: {:?}", t),
             }
         } else {
-            ProtobufTypeGen::Primitive(self.field.get_field_type(), PrimitiveTypeVariant::Default)
+            ProtobufTypeGen::Primitive(self.field().get_field_type(), PrimitiveTypeVariant::Default)
         }
     }
 
@@ -74,8 +79,13 @@ impl<'a> ExtGen<'a> {
             "ExtFieldOptional"
         };
         let field_type = format!("{}::ext::{}", protobuf_crate_path(&self.customize), suffix);
+        let packed = if self.repeated() {
+            format!(" packed: {},", self.extension.is_packed())
+        } else {
+            String::new()
+        };
         w.pub_const(
-            rust_field_name_for_protobuf_field_name(self.field.get_name()).get(),
+            rust_field_name_for_protobuf_field_name(self.field().get_name()).get(),
             &format!(
                 "{}<{}, {}>",
                 field_type,
@@ -83,9 +93,10 @@ impl<'a> ExtGen<'a> {
                 self.return_type_gen().rust_type(&self.customize),
             ),
             &format!(
-                "{} {{ field_number: {}, phantom: ::std::marker::PhantomData }}",
+                "{} {{ field_number: {},{} phantom: ::std::marker::PhantomData }}",
                 field_type,
-                self.field.get_number()
+                self.field().get_number(),
+                packed,
             ),
         );
     }
@@ -104,8 +115,8 @@ pub(crate) fn write_extensions(
     w.write_line("");
     w.write_line("/// Extension fields");
     w.pub_mod("exts", |w| {
-        for field in &file.proto().extension {
-            if field.get_field_type() == field_descriptor_proto::Type::TYPE_GROUP {
+        for extension in &file.extensions() {
+            if extension.get_proto().get_field_type() == field_descriptor_proto::Type::TYPE_GROUP {
                 continue;
             }
 
@@ -113,7 +124,7 @@ pub(crate) fn write_extensions(
             ExtGen {
                 file: file,
                 root_scope: root_scope,
-                field: field,
+                extension: extension,
                 customize: customize.clone(),
             }
             .write(w);
@@ -69,22 +69,24 @@ impl FieldGen<'_> {
         ]
     }
 
-    fn make_accessor_fns_has_get_set(&self) -> Vec<String> {
+    fn make_accessor_fns_has_get_set_clear(&self) -> Vec<String> {
         let message = self.proto_field.message.rust_name();
         vec![
             format!("{}::has_{}", message, self.rust_name),
             format!("{}::get_{}", message, self.rust_name),
             format!("{}::set_{}", message, self.rust_name),
+            format!("{}::{}", message, self.clear_field_func()),
         ]
     }
 
-    fn make_accessor_fns_has_get_mut_set(&self) -> Vec<String> {
+    fn make_accessor_fns_has_get_mut_set_clear(&self) -> Vec<String> {
         let message = self.proto_field.message.rust_name();
         vec![
             format!("{}::has_{}", message, self.rust_name),
             format!("{}::get_{}", message, self.rust_name),
             format!("{}::mut_{}", message, self.rust_name),
             format!("{}::set_{}", message, self.rust_name),
+            format!("{}::{}", message, self.clear_field_func()),
         ]
     }
 
@@ -150,6 +152,11 @@ impl FieldGen<'_> {
                 type_params: vec!["_".to_owned()],
                 callback_params: self.make_accessor_fns_lambda_get(),
             },
+            FieldElem::Enum(en) if en.plain => AccessorFn {
+                name: "make_option_get_copy_simpler_accessor".to_owned(),
+                type_params: vec!["_".to_owned()],
+                callback_params: self.make_accessor_fns_lambda_get(),
+            },
             FieldElem::Primitive(..) => AccessorFn {
                 name: "make_option_get_copy_simpler_accessor".to_owned(),
                 type_params: vec!["_".to_owned()],
@@ -183,7 +190,7 @@ impl FieldGen<'_> {
             return AccessorFn {
                 name: "make_oneof_copy_has_get_set_simpler_accessors".to_owned(),
                 type_params: vec![format!("_")],
-                callback_params: self.make_accessor_fns_has_get_set(),
+                callback_params: self.make_accessor_fns_has_get_set_clear(),
             };
         }
 
@@ -191,7 +198,7 @@ impl FieldGen<'_> {
             return AccessorFn {
                 name: "make_oneof_copy_has_get_set_simpler_accessors".to_owned(),
                 type_params: vec![format!("_")],
-                callback_params: self.make_accessor_fns_has_get_set(),
+                callback_params: self.make_accessor_fns_has_get_set_clear(),
             };
         }
 
@@ -199,7 +206,7 @@ impl FieldGen<'_> {
             return AccessorFn {
                 name: "make_oneof_message_has_get_mut_set_accessor".to_owned(),
                 type_params: vec![format!("{}", name)],
-                callback_params: self.make_accessor_fns_has_get_mut_set(),
+                callback_params: self.make_accessor_fns_has_get_mut_set_clear(),
             };
         }
 
@@ -207,7 +214,7 @@ impl FieldGen<'_> {
         AccessorFn {
             name: "make_oneof_deref_has_get_set_simpler_accessor".to_owned(),
             type_params: vec![format!("_")],
-            callback_params: self.make_accessor_fns_has_get_set(),
+            callback_params: self.make_accessor_fns_has_get_set_clear(),
         }
     }
 
@@ -353,6 +353,10 @@ pub struct EntryKeyValue<'a>(FieldElem<'a>, FieldElem<'a>);
 pub(crate) struct FieldElemEnum<'a> {
     /// Enum default value variant, either from proto or from enum definition
     default_value: EnumValueWithContext<'a>,
+    /// Enum is closed (defined in proto2 file): unknown values are stored in unknown fields
+    closed: bool,
+    /// Field is stored as plain enum rather than `ProtobufEnumOrUnknown`
+    plain: bool,
 }
 
 impl<'a> FieldElemEnum<'a> {
@@ -376,6 +380,14 @@ impl<'a> FieldElemEnum<'a> {
         )
     }
 
+    fn rust_storage_type(&self, reference: &FileAndMod) -> RustType {
+        if self.plain {
+            self.enum_rust_type(reference)
+        } else {
+            self.enum_or_unknown_rust_type(reference)
+        }
+    }
+
     fn default_value_rust_expr(&self, reference: &FileAndMod) -> RustIdentWithPath {
         self.rust_name_relative(reference)
             .to_path()
@@ -435,7 +447,7 @@ impl<'a> FieldElem<'a> {
             FieldElem::Primitive(.., PrimitiveTypeVariant::Carllerche) => unreachable!(),
             FieldElem::Group => RustType::Group,
             FieldElem::Message(ref m) => m.rust_type(reference),
-            FieldElem::Enum(ref en) => en.enum_or_unknown_rust_type(reference),
+            FieldElem::Enum(ref en) => en.rust_storage_type(reference),
         }
     }
 
@@ -452,6 +464,9 @@ impl<'a> FieldElem<'a> {
         match *self {
             FieldElem::Primitive(t, v) => ProtobufTypeGen::Primitive(t, v),
             FieldElem::Message(ref m) => ProtobufTypeGen::Message(m.rust_name_relative(reference)),
+            FieldElem::Enum(ref en) if en.plain => {
+                ProtobufTypeGen::Enum(en.rust_name_relative(reference))
+            }
             FieldElem::Enum(ref en) => {
                 ProtobufTypeGen::EnumOrUnknown(en.rust_name_relative(reference))
             }
@@ -465,6 +480,22 @@ impl<'a> FieldElem<'a> {
             .rust_type(&reference.customize)
     }
 
+    /// Enum field with unknown values stored in unknown fields
+    fn is_plain_enum(&self) -> bool {
+        match self {
+            FieldElem::Enum(en) => en.plain,
+            _ => false,
+        }
+    }
+
+    /// Map values are always stored as `ProtobufEnumOrUnknown`
+    fn into_enum_or_unknown(self) -> FieldElem<'a> {
+        match self {
+            FieldElem::Enum(en) => FieldElem::Enum(FieldElemEnum { plain: false, ..en }),
+            elem => elem,
+        }
+    }
+
     fn primitive_type_variant(&self) -> PrimitiveTypeVariant {
         match self {
             &FieldElem::Primitive(_, v) => v,
@@ -495,7 +526,8 @@ fn field_elem<'a>(
                     if let (true, Some((key, value))) = (parse_map, map_entry(&message)) {
                         Some(Box::new(EntryKeyValue(
                             field_elem(&key, root_scope, false, customize, current_file_path),
-                            field_elem(&value, root_scope, false, customize, current_file_path),
+                            field_elem(&value, root_scope, false, customize, current_file_path)
+                                .into_enum_or_unknown(),
                         )))
                     } else {
                         None
@@ -514,7 +546,13 @@ fn field_elem<'a>(
                 } else {
                     enum_with_scope.values()[0].clone()
                 };
-                FieldElem::Enum(FieldElemEnum { default_value })
+                let closed = enum_with_scope.scope.file_scope.syntax() == Syntax::PROTO2;
+                let plain = closed && customize.closed_enums.unwrap_or(true);
+                FieldElem::Enum(FieldElemEnum {
+                    default_value,
+                    closed,
+                    plain,
+                })
             }
             _ => panic!(
                 "unknown named type: {:?}",
@@ -606,7 +644,10 @@ impl<'a> FieldGen<'a> {
             .unwrap_or(default_generate_accessors)
             || field.is_oneof();
 
-        let default_generate_getter = generate_accessors || field_may_have_custom_default_value;
+        // Getter is also used in reflection of proto3 `optional` fields
+        let default_generate_getter = generate_accessors
+            || field_may_have_custom_default_value
+            || field.field.get_proto().get_proto3_optional();
         let generate_getter =
             customize.generate_getter.unwrap_or(default_generate_getter) || field.is_oneof();
 
@@ -627,12 +668,7 @@ impl<'a> FieldGen<'a> {
                 // regular repeated field
                 elem => FieldKind::Repeated(RepeatedField {
                     elem,
-                    packed: field
-                        .field
-                        .get_proto()
-                        .options
-                        .get_or_default()
-                        .get_packed(),
+                    packed: field.field.is_packed(),
                 }),
             }
         } else if let Some(oneof) = field.oneof() {
@@ -641,6 +677,7 @@ impl<'a> FieldGen<'a> {
             let flag = if field.message.scope.file_scope.syntax() == Syntax::PROTO3
                 && field.field.get_proto().get_field_type()
                     != field_descriptor_proto::Type::TYPE_MESSAGE
+                && !field.field.get_proto().get_proto3_optional()
             {
                 SingularFieldFlag::WithoutFlag
             } else {
@@ -960,6 +997,7 @@ impl<'a> FieldGen<'a> {
     pub fn reconstruct_def(&self) -> String {
         let prefix = match (self.proto_field.field.get_proto().get_label(), self.syntax) {
             (field_descriptor_proto::Label::LABEL_REPEATED, _) => "repeated ",
+            _ if self.proto_field.field.get_proto().get_proto3_optional() => "optional ",
             (_, Syntax::PROTO3) => "",
             (field_descriptor_proto::Label::LABEL_OPTIONAL, _) => "optional ",
             (field_descriptor_proto::Label::LABEL_REQUIRED, _) => "required ",
@@ -1021,9 +1059,14 @@ impl<'a> FieldGen<'a> {
                         &RustType::Ref(ref t) => (**t).clone(),
                         t => t.clone(),
                     };
+                    let fn_name = match param_type {
+                        RustType::Enum(..) => "enum_size",
+                        _ => "enum_or_unknown_size",
+                    };
                     format!(
-                        "{}::rt::enum_or_unknown_size({}, {})",
+                        "{}::rt::{}({}, {})",
                         protobuf_crate_path(&self.customize),
+                        fn_name,
                         self.proto_field.number(),
                         var_type.into_target(&param_type, var, &self.customize)
                     )
@@ -1167,11 +1210,15 @@ impl<'a> FieldGen<'a> {
         }
     }
 
+    pub fn write_documentation(&self, w: &mut CodeWriter) {
+        w.all_documentation(self.info, &self.path);
+    }
+
     pub fn write_struct_field(&self, w: &mut CodeWriter) {
         if self.proto_type == field_descriptor_proto::Type::TYPE_GROUP {
             w.comment(&format!("{}: <group>", &self.rust_name));
         } else {
-            w.all_documentation(self.info, &self.path);
+            self.write_documentation(w);
 
             self.write_serde_attr(w);
             let vis = self.visibility();
@@ -1504,7 +1551,9 @@ impl<'a> FieldGen<'a> {
 
     fn self_field_vec_packed_varint_data_size(&self) -> String {
         assert!(!self.is_fixed());
-        let fn_name = if self.is_enum() {
+        let fn_name = if self.elem().is_plain_enum() {
+            "vec_packed_enum_data_size".to_string()
+        } else if self.is_enum() {
             "vec_packed_enum_or_unknown_data_size".to_string()
         } else {
             let zigzag_suffix = if self.is_zigzag() { "_zigzag" } else { "" };
@@ -1541,7 +1590,9 @@ impl<'a> FieldGen<'a> {
     fn self_field_vec_packed_varint_size(&self) -> String {
         // zero is filtered outside
         assert!(!self.is_fixed());
-        let fn_name = if self.is_enum() {
+        let fn_name = if self.elem().is_plain_enum() {
+            "vec_packed_enum_size".to_string()
+        } else if self.is_enum() {
             "vec_packed_enum_or_unknown_size".to_string()
         } else {
             let zigzag_suffix = if self.is_zigzag() { "_zigzag" } else { "" };
@@ -1662,6 +1713,11 @@ impl<'a> FieldGen<'a> {
     fn write_merge_from_oneof(&self, o: &OneofField, wire_type_var: &str, w: &mut CodeWriter) {
         self.write_assert_wire_type(wire_type_var, w);
 
+        if let FieldElem::Enum(FieldElemEnum { closed: true, .. }) = o.elem {
+            self.write_merge_from_oneof_closed_enum(o, w);
+            return;
+        }
+
         let typed = RustValueTyped {
             value: format!(
                 "{}?",
@@ -1698,11 +1754,70 @@ impl<'a> FieldGen<'a> {
         )); // TODO: into_type
     }
 
+    // Write `merge_from` part for oneof field of closed enum type,
+    // unknown values are stored in unknown fields
+    fn write_merge_from_oneof_closed_enum(&self, o: &OneofField, w: &mut CodeWriter) {
+        w.write_line("let v = is.read_int32()?;");
+        w.match_block(
+            &format!(
+                "<{} as {}::ProtobufEnum>::from_i32(v)",
+                o.elem
+                    .rust_set_xxx_param_type(&self.get_file_and_mod())
+                    .to_code(&self.customize),
+                protobuf_crate_path(&self.customize),
+            ),
+            |w| {
+                let value = match o.elem {
+                    FieldElem::Enum(FieldElemEnum { plain: true, .. }) => "e".to_owned(),
+                    _ => format!(
+                        "{}::ProtobufEnumOrUnknown::new(e)",
+                        protobuf_crate_path(&self.customize)
+                    ),
+                };
+                w.case_expr(
+                    "::std::option::Option::Some(e)",
+                    &format!(
+                        "self.{} = ::std::option::Option::Some({}({}))",
+                        o.oneof_field_name,
+                        o.variant_path(
+                            &self
+                                .proto_field
+                                .message
+                                .scope
+                                .rust_path_to_file()
+                                .clone()
+                                .into_path()
+                        ),
+                        value,
+                    ),
+                );
+                w.case_expr(
+                    "::std::option::Option::None",
+                    &format!(
+                        "self.unknown_fields.add_varint({}, v as i64 as u64)",
+                        self.proto_field.number()
+                    ),
+                );
+            },
+        );
+    }
+
     // Write `merge_from` part for this map field
     fn write_merge_from_map(&self, w: &mut CodeWriter) {
         let &MapField {
             ref key, ref value, ..
         } = self.map();
+        if let FieldElem::Enum(en @ FieldElemEnum { closed: true, .. }) = value {
+            w.write_line(&format!(
+                "{}::rt::read_map_with_closed_enum_values_into::<{}, {}>(wire_type, is, &mut {}, {}, &mut self.unknown_fields)?;",
+                protobuf_crate_path(&self.customize),
+                key.lib_protobuf_type(&self.get_file_and_mod()),
+                en.rust_name_relative(&self.get_file_and_mod()),
+                self.self_field(),
+                self.proto_field.number(),
+            ));
+            return;
+        }
         w.write_line(&format!(
             "{}::rt::read_map_into::<{}, {}>(wire_type, is, &mut {})?;",
             protobuf_crate_path(&self.customize),
@@ -1723,6 +1838,26 @@ impl<'a> FieldGen<'a> {
             FieldElem::Message(..) => {
                 self.write_merge_from_field_message_string_bytes(w);
             }
+            FieldElem::Enum(FieldElemEnum {
+                closed: true,
+                plain,
+                ..
+            }) => {
+                let version = match s.flag {
+                    SingularFieldFlag::WithFlag { .. } => "proto2",
+                    SingularFieldFlag::WithoutFlag => "proto3",
+                };
+                let storage = if plain { "enum" } else { "enum_or_unknown" };
+                w.write_line(&format!(
+                    "{}::rt::read_{}_{}_with_unknown_fields_into({}, is, &mut self.{}, {}, &mut self.unknown_fields)?;",
+                    protobuf_crate_path(&self.customize),
+                    version,
+                    storage,
+                    wire_type_var,
+                    self.rust_name,
+                    self.proto_field.number(),
+                ));
+            }
             _ => {
                 self.write_assert_wire_type(wire_type_var, w);
                 let read_proc = format!(
@@ -1747,6 +1882,21 @@ impl<'a> FieldGen<'a> {
             | FieldElem::Primitive(field_descriptor_proto::Type::TYPE_BYTES, ..) => {
                 self.write_merge_from_field_message_string_bytes(w);
             }
+            FieldElem::Enum(FieldElemEnum {
+                closed: true,
+                plain,
+                ..
+            }) => {
+                let storage = if plain { "enum" } else { "enum_or_unknown" };
+                w.write_line(&format!(
+                    "{}::rt::read_repeated_{}_with_unknown_fields_into({}, is, &mut self.{}, {}, &mut self.unknown_fields)?",
+                    protobuf_crate_path(&self.customize),
+                    storage,
+                    wire_type_var,
+                    self.rust_name,
+                    self.proto_field.number(),
+                ));
+            }
             FieldElem::Enum(..) => {
                 w.write_line(&format!(
                     "{}::rt::read_repeated_enum_or_unknown_into({}, is, &mut self.{})?",
@@ -1962,13 +2112,23 @@ impl<'a> FieldGen<'a> {
     fn write_message_field_get_singular_enum(
         &self,
         flag: SingularFieldFlag,
-        _elem: &FieldElemEnum,
+        elem: &FieldElemEnum,
         w: &mut CodeWriter,
     ) {
         match flag {
+            SingularFieldFlag::WithoutFlag if elem.plain => {
+                w.write_line(&format!("self.{}", self.rust_name));
+            }
             SingularFieldFlag::WithoutFlag => {
                 w.write_line(&format!("self.{}.enum_value_or_default()", self.rust_name));
             }
+            SingularFieldFlag::WithFlag { .. } if elem.plain => {
+                w.write_line(&format!(
+                    "self.{}.unwrap_or({})",
+                    self.rust_name,
+                    self.get_xxx_default_value_rust()
+                ));
+            }
             SingularFieldFlag::WithFlag { .. } => {
                 w.match_expr(&self.self_field(), |w| {
                     let default_value = self.get_xxx_default_value_rust();
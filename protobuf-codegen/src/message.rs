@@ -141,10 +141,31 @@ impl<'a> MessageGen<'a> {
     }
 
     fn oneofs(&'a self) -> Vec<OneofGen<'a>> {
+        static ONEOF_DECL_NUMBER: protobuf::rt::LazyV2<i32> = protobuf::rt::LazyV2::INIT;
+        let oneof_decl_number = *ONEOF_DECL_NUMBER.get(|| {
+            protobuf::reflect::MessageDescriptor::for_type::<DescriptorProto>()
+                .get_field_by_name("oneof_decl")
+                .expect("`oneof_decl` must exist")
+                .get_proto()
+                .get_number()
+        });
+
         self.message
             .oneofs()
             .into_iter()
-            .map(|oneof| OneofGen::parse(self, oneof, &self.customize))
+            .map(|oneof| {
+                let index = self
+                    .message
+                    .message
+                    .get_proto()
+                    .oneof_decl
+                    .iter()
+                    .position(|o| o.get_name() == oneof.oneof.get_name())
+                    .expect("oneof must exist");
+                let mut path = self.path.to_vec();
+                path.extend_from_slice(&[oneof_decl_number, index as i32]);
+                OneofGen::parse(self, oneof, &self.customize, path, self.info)
+            })
             .collect()
     }
 
@@ -590,6 +611,7 @@ impl<'a> MessageGen<'a> {
             if !self.oneofs().is_empty() {
                 w.comment("message oneof groups");
                 for oneof in self.oneofs() {
+                    oneof.write_documentation(w);
                     let vis = match self.expose_oneof() {
                         true => Visibility::Public,
                         false => Visibility::Default,
@@ -674,12 +696,14 @@ impl<'a> MessageGen<'a> {
         let mod_name = message_name_to_nested_mod_name(&self.message.message.get_name());
 
         let oneofs = self.oneofs();
+        // Indices are kept to compute paths in source code info
         let nested_messages: Vec<_> = self
             .message
             .to_scope()
             .get_messages()
             .into_iter()
-            .filter(|nested| {
+            .enumerate()
+            .filter(|(_, nested)| {
                 // ignore map entries, because they are not used in map fields
                 !nested.is_map()
             })
@@ -711,9 +735,9 @@ impl<'a> MessageGen<'a> {
 
                 let mut path = self.path.to_vec();
                 path.extend(&[nested_type_number, 0]);
-                for (id, nested) in nested_messages.iter().enumerate() {
+                for (id, nested) in &nested_messages {
                     let len = path.len() - 1;
-                    path[len] = id as i32;
+                    path[len] = *id as i32;
 
                     if !first {
                         w.write_line("");
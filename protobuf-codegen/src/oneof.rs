@@ -20,6 +20,7 @@ use crate::scope::WithScope;
 use crate::serde;
 use crate::ProtobufAbsolutePath;
 use protobuf::descriptor::field_descriptor_proto;
+use protobuf::descriptor::SourceCodeInfo;
 use std::collections::HashSet;
 
 // oneof one { ... }
@@ -163,6 +164,8 @@ pub(crate) struct OneofGen<'a> {
     pub oneof: OneofWithContext<'a>,
     lite_runtime: bool,
     customize: Customize,
+    path: Vec<i32>,
+    info: Option<&'a SourceCodeInfo>,
 }
 
 impl<'a> OneofGen<'a> {
@@ -170,15 +173,23 @@ impl<'a> OneofGen<'a> {
         message: &'a MessageGen,
         oneof: OneofWithContext<'a>,
         customize: &Customize,
+        path: Vec<i32>,
+        info: Option<&'a SourceCodeInfo>,
     ) -> OneofGen<'a> {
         OneofGen {
             message,
             oneof,
             lite_runtime: message.lite_runtime,
             customize: customize.clone(),
+            path,
+            info,
         }
     }
 
+    pub fn write_documentation(&self, w: &mut CodeWriter) {
+        w.all_documentation(self.info, &self.path);
+    }
+
     pub fn type_name_relative(&self, source: &RustPath) -> RustIdentWithPath {
         make_path(source, &self.oneof.rust_name())
     }
@@ -236,6 +247,7 @@ impl<'a> OneofGen<'a> {
     }
 
     fn write_enum(&self, w: &mut CodeWriter) {
+        self.write_documentation(w);
         let derive = vec!["Clone", "PartialEq", "Debug"];
         w.derive(&derive);
         serde::write_serde_attr(
@@ -245,6 +257,7 @@ impl<'a> OneofGen<'a> {
         );
         w.pub_enum(&self.oneof.rust_name().ident.to_string(), |w| {
             for variant in self.variants_except_group() {
+                variant.field.write_documentation(w);
                 w.write_line(&format!(
                     "{}({}),",
                     variant.field.rust_name,
@@ -573,7 +573,7 @@ pub(crate) enum ProtobufTypeGen {
     Primitive(field_descriptor_proto::Type, PrimitiveTypeVariant),
     Message(RustTypeMessage),
     EnumOrUnknown(RustIdentWithPath),
-    _Enum(RustIdentWithPath),
+    Enum(RustIdentWithPath),
 }
 
 impl ProtobufTypeGen {
@@ -609,7 +609,7 @@ impl ProtobufTypeGen {
                 protobuf_crate_path(customize),
                 name
             ),
-            &ProtobufTypeGen::_Enum(ref name) => format!(
+            &ProtobufTypeGen::Enum(ref name) => format!(
                 "{}::reflect::types::ProtobufTypeEnum<{}>",
                 protobuf_crate_path(customize),
                 name
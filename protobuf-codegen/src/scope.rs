@@ -399,10 +399,6 @@ impl<'a> MessageWithScope<'a> {
             .collect()
     }
 
-    pub fn oneof_by_index(&self, index: u32) -> OneofWithContext<'a> {
-        self.oneofs().swap_remove(index as usize)
-    }
-
     pub fn mod_name(&self) -> RustIdent {
         message_name_to_nested_mod_name(self.message.get_name())
     }
@@ -418,7 +414,7 @@ impl<'a> MessageWithScope<'a> {
         if !self.to_scope().get_enums().is_empty() {
             return true;
         }
-        if self.message.oneofs().len() != 0 {
+        if self.message.oneofs().next().is_some() {
             return true;
         }
         false
@@ -524,18 +520,14 @@ pub(crate) struct FieldWithContext<'a> {
 
 impl<'a> FieldWithContext<'a> {
     pub fn is_oneof(&self) -> bool {
-        self.field.get_proto().has_oneof_index()
+        self.field.containing_oneof().is_some()
     }
 
     pub fn oneof(&self) -> Option<OneofWithContext<'a>> {
-        if self.is_oneof() {
-            Some(
-                self.message
-                    .oneof_by_index(self.field.get_proto().get_oneof_index() as u32),
-            )
-        } else {
-            None
-        }
+        self.field.containing_oneof().map(|oneof| OneofWithContext {
+            message: self.message.clone(),
+            oneof,
+        })
     }
 
     pub fn number(&self) -> u32 {
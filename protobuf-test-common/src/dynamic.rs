@@ -0,0 +1,25 @@
+use protobuf::reflect::DynamicMessage;
+use protobuf::reflect::FileDescriptor;
+use protobuf::reflect::MessageDescriptor;
+use protobuf::MessageDyn;
+
+/// Descriptor of message `name` in a dynamic copy of a generated `file`.
+///
+/// `file` may import `rustproto.proto`.
+pub fn dynamic_message_descriptor(file: &FileDescriptor, name: &str) -> MessageDescriptor {
+    let file = FileDescriptor::new_dynamic(
+        file.proto().clone(),
+        vec![protobuf::rustproto::file_descriptor()],
+    );
+    file.message_by_package_relative_name(name).unwrap()
+}
+
+/// Copy a message into a dynamic message of type `descriptor` through wire format.
+pub fn to_dynamic(message: &dyn MessageDyn, descriptor: &MessageDescriptor) -> DynamicMessage {
+    let mut dynamic = DynamicMessage::new(descriptor.clone());
+    let dynamic_dyn: &mut dyn MessageDyn = &mut dynamic;
+    dynamic_dyn
+        .merge_from_bytes_dyn(&message.write_to_bytes_dyn().unwrap())
+        .unwrap();
+    dynamic
+}
@@ -22,5 +22,8 @@ pub use json_tests::*;
 mod reflect_tests;
 pub use reflect_tests::*;
 
+mod dynamic;
+pub use dynamic::*;
+
 mod cargo;
 pub use cargo::*;
@@ -0,0 +1,99 @@
+use protobuf::descriptor::source_code_info::Location;
+
+use protobuf::Message;
+
+use super::test_comments_pb::*;
+
+fn location(path: &[i32]) -> Location {
+    file_descriptor()
+        .proto()
+        .source_code_info
+        .get_or_default()
+        .location
+        .iter()
+        .find(|l| l.path == path)
+        .expect("location not found")
+        .clone()
+}
+
+#[test]
+fn test_message_comments() {
+    let message = location(&[4, 0]);
+    assert!(message
+        .get_leading_comments()
+        .starts_with(" Message with comments.\n"));
+    assert_eq!(
+        " Trailing comment of message.\n",
+        message.get_trailing_comments()
+    );
+    assert_eq!(
+        vec![" This comment is detached from the message.\n".to_owned()],
+        message.leading_detached_comments
+    );
+
+    let field = location(&[4, 0, 2, 0]);
+    assert_eq!(" Leading comment of field.\n", field.get_leading_comments());
+    assert_eq!(
+        " Trailing comment of field.\n",
+        field.get_trailing_comments()
+    );
+
+    let oneof = location(&[4, 0, 8, 0]);
+    assert_eq!(" Block comment\n of oneof. ", oneof.get_leading_comments());
+
+    let variant = location(&[4, 0, 2, 1]);
+    assert_eq!(" Oneof variant.\n", variant.get_leading_comments());
+
+    // Nested message is declared after implicit map entry message
+    let nested = location(&[4, 0, 3, 1]);
+    assert_eq!(
+        " Nested message after map entry.\n",
+        nested.get_leading_comments()
+    );
+    assert_eq!(
+        "Nested",
+        CommentedMessage::descriptor_static()
+            .get_proto()
+            .nested_type[1]
+            .get_name()
+    );
+}
+
+#[test]
+fn test_enum_comments() {
+    assert_eq!(" Enum comment.\n", location(&[5, 0]).get_leading_comments());
+    let value = location(&[5, 0, 2, 0]);
+    assert_eq!(" Enum value comment.\n", value.get_leading_comments());
+    assert_eq!(
+        " Trailing comment of enum value.\n",
+        value.get_trailing_comments()
+    );
+}
+
+#[test]
+fn test_service_comments() {
+    assert_eq!(
+        " Service comment.\n",
+        location(&[6, 0]).get_leading_comments()
+    );
+    let method = location(&[6, 0, 2, 0]);
+    assert_eq!(" Method comment.\n", method.get_leading_comments());
+    assert_eq!(
+        " Trailing comment of method.\n",
+        method.get_trailing_comments()
+    );
+}
+
+#[test]
+fn test_spans() {
+    // Copies of this file in other test crates have extra header lines
+    let syntax = location(&[12]).span;
+    let l = syntax[0];
+    assert_eq!(vec![l, 0, 18], syntax);
+    assert_eq!(vec![l, 0, l + 43, 1], location(&[]).span);
+    assert_eq!(vec![l + 2, 0, 22], location(&[2]).span);
+    assert_eq!(vec![l + 16, 0, l + 31, 1], location(&[4, 0]).span);
+    assert_eq!(vec![l + 16, 8, 24], location(&[4, 0, 1]).span);
+    assert_eq!(vec![l + 42, 4, 58], location(&[6, 0, 2, 0]).span);
+    assert_eq!(vec![l + 42, 13, 29], location(&[6, 0, 2, 0, 2]).span);
+}
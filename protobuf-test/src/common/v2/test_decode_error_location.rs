@@ -0,0 +1,114 @@
+use protobuf::reflect::DynamicMessage;
+use protobuf::Message;
+use protobuf::MessageDyn;
+use protobuf::ProtobufError;
+use protobuf::WireError;
+use protobuf_test_common::dynamic_message_descriptor;
+
+use super::test_decode_error_location_pb::*;
+
+const INVALID_UTF8: &[u8] = &[0xff, 0xfe];
+
+fn item(sku: &str) -> Item {
+    let mut item = Item::new();
+    item.set_sku(sku.to_owned());
+    item
+}
+
+/// Serialize order, replacing `XX` placeholder with invalid UTF-8.
+fn serialize_with_invalid_utf8(order: &Order) -> (Vec<u8>, u64) {
+    let mut bytes = order.write_to_bytes().unwrap();
+    let pos = bytes.windows(2).position(|w| w == b"XX").unwrap();
+    bytes[pos..pos + 2].copy_from_slice(INVALID_UTF8);
+    (bytes, pos as u64 + 2)
+}
+
+fn assert_location(e: ProtobufError, offset: u64, path: &str) {
+    assert!(
+        matches!(e.wire_error(), Some(WireError::Utf8Error)),
+        "{:?}",
+        e
+    );
+    let location = e.decode_error_location().expect("location");
+    assert_eq!(offset, location.offset);
+    assert_eq!(path, location.path());
+    assert_eq!(
+        format!(
+            "invalid UTF-8 sequence at offset {} in field {}",
+            offset, path
+        ),
+        format!("{}", e)
+    );
+}
+
+/// Parse errors of generated and dynamic message.
+fn parse_errors(bytes: &[u8]) -> Vec<ProtobufError> {
+    let descriptor = dynamic_message_descriptor(&file_descriptor(), "Order");
+    let mut dynamic = DynamicMessage::new(descriptor);
+    let dynamic: &mut dyn MessageDyn = &mut dynamic;
+    vec![
+        Order::parse_from_bytes(bytes).unwrap_err(),
+        dynamic.merge_from_bytes_dyn(bytes).unwrap_err(),
+    ]
+}
+
+fn test_both(order: &Order, path: &str) {
+    let (bytes, offset) = serialize_with_invalid_utf8(order);
+    for e in parse_errors(&bytes) {
+        assert_location(e, offset, path);
+    }
+}
+
+#[test]
+fn test_top_level_field() {
+    let mut order = Order::new();
+    order.set_id("XX".to_owned());
+    test_both(&order, "id");
+}
+
+#[test]
+fn test_repeated_message() {
+    let mut order = Order::new();
+    order.set_id("order".to_owned());
+    for sku in &["a", "b", "c", "XX"] {
+        order.items.push(item(sku));
+    }
+    test_both(&order, "items[3].sku");
+}
+
+#[test]
+fn test_nested_message() {
+    let mut part = item("part");
+    part.set_part(item("XX"));
+    let mut order = Order::new();
+    order.items.push(item("a"));
+    order.items.push(part);
+    test_both(&order, "items[1].part.sku");
+}
+
+#[test]
+fn test_map() {
+    let mut order = Order::new();
+    order.items_by_sku.insert("a".to_owned(), item("XX"));
+    test_both(&order, "items_by_sku.value.sku");
+
+    let mut order = Order::new();
+    order.items_by_sku.insert("XX".to_owned(), item("a"));
+    test_both(&order, "items_by_sku.key");
+}
+
+#[test]
+fn test_incorrect_tag_after_field() {
+    // tag 0 after string field is not attributed to that field
+    for e in parse_errors(&[0x0a, 0x01, b'a', 0x00]) {
+        assert!(matches!(e.wire_error(), Some(WireError::IncorrectTag(0))));
+        let location = e.decode_error_location().expect("location");
+        assert_eq!(4, location.offset);
+        assert_eq!("", location.path());
+    }
+
+    for e in parse_errors(&[0x12, 0x04, 0x0a, 0x01, b'a', 0x00]) {
+        assert!(matches!(e.wire_error(), Some(WireError::IncorrectTag(0))));
+        assert_eq!("items[0]", e.decode_error_location().unwrap().path());
+    }
+}
@@ -0,0 +1,135 @@
+use protobuf::reflect::DynamicMessage;
+use protobuf::CodedInputStream;
+use protobuf::DecodeLimits;
+use protobuf::Message;
+use protobuf::MessageDyn;
+use protobuf::WireError;
+use protobuf_test_common::dynamic_message_descriptor;
+
+use super::test_decode_limits_pb::*;
+
+fn limited() -> Limited {
+    let mut m = Limited::new();
+    m.numbers = vec![1, 2, 3];
+    m.names.push("abcd".to_owned());
+    m.entries.insert(1, 10);
+    m.entries.insert(2, 20);
+    m.entries.insert(3, 30);
+    m.children.push(Limited::new());
+    m
+}
+
+/// Parse bytes as generated message, dynamic message
+/// and message with all fields unknown, return errors.
+fn parse_all(bytes: &[u8], limits: &DecodeLimits) -> Vec<ProtobufResult> {
+    let mut r = Vec::new();
+
+    let mut is = CodedInputStream::from_bytes(bytes);
+    is.set_decode_limits(limits.clone());
+    r.push(Limited::new().merge_from(&mut is));
+
+    let mut is = CodedInputStream::from_bytes(bytes);
+    is.set_decode_limits(limits.clone());
+    r.push(
+        DynamicMessage::new(dynamic_message_descriptor(&file_descriptor(), "Limited"))
+            .merge_from_dyn(&mut is),
+    );
+
+    let mut is = CodedInputStream::from_bytes(bytes);
+    is.set_decode_limits(limits.clone());
+    r.push(Empty::new().merge_from(&mut is));
+
+    let mut is = CodedInputStream::from_bytes(bytes);
+    is.set_decode_limits(limits.clone());
+    r.push(
+        DynamicMessage::new(dynamic_message_descriptor(&file_descriptor(), "Empty"))
+            .merge_from_dyn(&mut is),
+    );
+
+    r
+}
+
+type ProtobufResult = protobuf::ProtobufResult<()>;
+
+fn assert_all_ok(limits: DecodeLimits) {
+    let bytes = limited().write_to_bytes().unwrap();
+    for r in parse_all(&bytes, &limits) {
+        r.unwrap();
+    }
+}
+
+fn assert_all_fail(limits: DecodeLimits, check: fn(&WireError) -> bool) {
+    let bytes = limited().write_to_bytes().unwrap();
+    for r in parse_all(&bytes, &limits) {
+        match r {
+            Err(ref e) if e.wire_error().map_or(false, check) => {}
+            r => panic!("unexpected result: {:?}", r),
+        }
+    }
+}
+
+#[test]
+fn test_default_limits() {
+    assert_all_ok(DecodeLimits::default());
+}
+
+#[test]
+fn test_max_message_size() {
+    let size = limited().compute_size() as u64;
+    assert_all_ok(DecodeLimits {
+        max_message_size: Some(size),
+        ..Default::default()
+    });
+    assert_all_fail(
+        DecodeLimits {
+            max_message_size: Some(size - 1),
+            ..Default::default()
+        },
+        |e| matches!(e, WireError::MessageTooLarge),
+    );
+}
+
+#[test]
+fn test_max_bytes_len() {
+    assert_all_ok(DecodeLimits {
+        max_bytes_len: Some(16),
+        ..Default::default()
+    });
+    assert_all_fail(
+        DecodeLimits {
+            max_bytes_len: Some(3),
+            ..Default::default()
+        },
+        |e| matches!(e, WireError::BytesTooLong),
+    );
+}
+
+#[test]
+fn test_max_repeated_len() {
+    assert_all_ok(DecodeLimits {
+        max_repeated_len: Some(3),
+        ..Default::default()
+    });
+    assert_all_fail(
+        DecodeLimits {
+            max_repeated_len: Some(2),
+            ..Default::default()
+        },
+        |e| matches!(e, WireError::TooManyElements),
+    );
+}
+
+#[test]
+fn test_max_allocation() {
+    assert_all_ok(DecodeLimits {
+        max_allocation: Some(1 << 20),
+        ..Default::default()
+    });
+    assert_all_fail(
+        DecodeLimits {
+            max_allocation: Some(3),
+            ..Default::default()
+        },
+        |e| matches!(e, WireError::AllocationBudgetExceeded),
+    );
+}
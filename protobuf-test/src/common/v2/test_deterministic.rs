@@ -0,0 +1,63 @@
+use protobuf::Message;
+use protobuf::MessageDyn;
+use protobuf_test_common::dynamic_message_descriptor;
+use protobuf_test_common::hex::encode_hex;
+use protobuf_test_common::to_dynamic;
+
+use super::test_deterministic_pb::*;
+
+// numbers: {1: 10, 2: 20, 3: 30}, unknown fields 9 and 10
+const SORTED: &str = "0a 04 08 01 10 0a 0a 04 08 02 10 14 0a 04 08 03 10 1e 48 07 50 08";
+
+fn message() -> WithMaps {
+    let mut m = WithMaps::new();
+    for &k in &[3, 1, 2] {
+        m.numbers.insert(k, k * 10);
+    }
+    m.mut_unknown_fields().add_varint(10, 8);
+    m.mut_unknown_fields().add_varint(9, 7);
+    m
+}
+
+#[test]
+fn test_generated() {
+    let m = message();
+    assert_eq!(
+        SORTED,
+        encode_hex(&m.write_to_bytes_deterministic().unwrap())
+    );
+}
+
+#[test]
+fn test_dynamic() {
+    let descriptor = dynamic_message_descriptor(&file_descriptor(), "WithMaps");
+    let m = to_dynamic(&message(), &descriptor);
+    let m: &dyn MessageDyn = &m;
+    assert_eq!(
+        SORTED,
+        encode_hex(&m.write_to_bytes_deterministic_dyn().unwrap())
+    );
+}
+
+#[test]
+fn test_nested() {
+    let mut a = WithMaps::new();
+    let mut b = WithMaps::new();
+    for i in 0..20 {
+        a.nested.insert(format!("{}", i), message());
+        b.nested.insert(format!("{}", 19 - i), message());
+    }
+    let bytes = a.write_to_bytes_deterministic().unwrap();
+    assert_eq!(bytes, b.write_to_bytes_deterministic().unwrap());
+
+    // Entries are sorted by key, so output is concatenation of single entry messages
+    let mut keys: Vec<_> = a.nested.keys().cloned().collect();
+    keys.sort();
+    let mut expected = Vec::new();
+    for key in keys {
+        let mut single = WithMaps::new();
+        single.nested.insert(key, message());
+        expected.extend(single.write_to_bytes_deterministic().unwrap());
+    }
+    assert_eq!(encode_hex(&expected), encode_hex(&bytes));
+}
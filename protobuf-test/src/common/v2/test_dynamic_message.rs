@@ -0,0 +1,320 @@
+use protobuf::reflect::DynamicMessage;
+use protobuf::reflect::MessageDescriptor;
+use protobuf::reflect::ReflectFieldRef;
+use protobuf::reflect::ReflectValueBox;
+use protobuf::reflect::ReflectValueRef;
+use protobuf::reflect::RuntimeFieldType;
+use protobuf::reflect::RuntimeTypeBox;
+use protobuf::Message;
+use protobuf_test_common::*;
+
+use super::test_dynamic_message_pb::*;
+
+/// Test both generated and dynamic descriptors.
+fn descriptors() -> Vec<MessageDescriptor> {
+    vec![
+        ForDynamicMessageTest::descriptor_static(),
+        dynamic_message_descriptor(&file_descriptor(), "ForDynamicMessageTest"),
+    ]
+}
+
+fn singular<'a>(m: &'a DynamicMessage, name: &str) -> Option<ReflectValueRef<'a>> {
+    match m.get_field_by_name(name).unwrap() {
+        ReflectFieldRef::Optional(v) => v,
+        _ => panic!("not singular: {}", name),
+    }
+}
+
+fn nested_field<'a>(m: &'a DynamicNested, name: &str) -> Option<ReflectValueRef<'a>> {
+    DynamicNested::descriptor_static()
+        .get_field_by_name(name)
+        .unwrap()
+        .get_singular(m)
+}
+
+fn nested_descriptor(d: &MessageDescriptor, field: &str) -> MessageDescriptor {
+    match d.get_field_by_name(field).unwrap().runtime_field_type() {
+        RuntimeFieldType::Singular(RuntimeTypeBox::Message(m))
+        | RuntimeFieldType::Repeated(RuntimeTypeBox::Message(m))
+        | RuntimeFieldType::Map(_, RuntimeTypeBox::Message(m)) => m,
+        _ => panic!("not a message field: {}", field),
+    }
+}
+
+fn nested(d: &MessageDescriptor, field: &str, a: i32, b: &str) -> ReflectValueBox {
+    let mut m = DynamicMessage::new(nested_descriptor(d, field));
+    m.set_field_by_name("a", ReflectValueBox::I32(a));
+    m.set_field_by_name("b", ReflectValueBox::String(b.to_owned()));
+    ReflectValueBox::Message(Box::new(m))
+}
+
+fn enum_value(d: &MessageDescriptor, number: i32) -> ReflectValueBox {
+    match d
+        .get_field_by_name("enum_field")
+        .unwrap()
+        .singular_runtime_type()
+    {
+        RuntimeTypeBox::Enum(e) => ReflectValueBox::Enum(e, number),
+        t => panic!("not enum: {}", t),
+    }
+}
+
+fn filled(d: &MessageDescriptor) -> DynamicMessage {
+    let mut m = DynamicMessage::new(d.clone());
+    m.set_field_by_name("int32_field", ReflectValueBox::I32(-1));
+    m.set_field_by_name("sint32_field", ReflectValueBox::I32(-2));
+    m.set_field_by_number(3, ReflectValueBox::I64(-3));
+    m.set_field_by_name("double_field", ReflectValueBox::F64(4.5));
+    m.set_field_by_name("string_field", ReflectValueBox::String("five".to_owned()));
+    m.set_field_by_name("bytes_field", ReflectValueBox::Bytes(vec![6, 7]));
+    m.set_field_by_name("enum_field", enum_value(d, 2));
+    m.set_field_by_name("message_field", nested(d, "message_field", 8, "eight"));
+
+    let field = d.get_field_by_name("repeated_sint64_field").unwrap();
+    m.mut_repeated(&field).push(ReflectValueBox::I64(-9));
+    m.mut_repeated(&field).push(ReflectValueBox::I64(9));
+    let field = d
+        .get_field_by_name("repeated_packed_fixed32_field")
+        .unwrap();
+    m.mut_repeated(&field).push(ReflectValueBox::U32(10));
+    m.mut_repeated(&field).push(ReflectValueBox::U32(11));
+    let field = d.get_field_by_name("repeated_message_field").unwrap();
+    m.mut_repeated(&field)
+        .push(nested(d, "repeated_message_field", 12, "twelve"));
+
+    let field = d.get_field_by_name("map_field").unwrap();
+    m.mut_map(&field).insert(
+        ReflectValueBox::String("thirteen".to_owned()),
+        ReflectValueBox::I32(-13),
+    );
+    let field = d.get_field_by_name("map_message_field").unwrap();
+    m.mut_map(&field).insert(
+        ReflectValueBox::I32(14),
+        nested(d, "map_message_field", 14, "fourteen"),
+    );
+
+    m.set_field_by_name(
+        "oneof_string",
+        ReflectValueBox::String("fifteen".to_owned()),
+    );
+    m
+}
+
+#[test]
+fn test_to_generated_and_back() {
+    for d in descriptors() {
+        let dynamic = filled(&d);
+        let generated: ForDynamicMessageTest = dynamic.to_message().unwrap();
+
+        let g = ForDynamicMessageTest::descriptor_static();
+        let field = |name| g.get_field_by_name(name).unwrap().get_singular(&generated);
+        assert_eq!(Some(ReflectValueRef::I32(-1)), field("int32_field"));
+        assert_eq!(Some(ReflectValueRef::I32(-2)), field("sint32_field"));
+        assert_eq!(Some(ReflectValueRef::I64(-3)), field("sfixed64_field"));
+        assert_eq!(Some(ReflectValueRef::F64(4.5)), field("double_field"));
+        assert_eq!(Some(ReflectValueRef::String("five")), field("string_field"));
+        assert_eq!(Some(ReflectValueRef::Bytes(&[6, 7])), field("bytes_field"));
+        assert_eq!(
+            Some(ReflectValueRef::I32(8)),
+            nested_field(generated.message_field.get_or_default(), "a")
+        );
+        assert_eq!(vec![-9, 9], generated.repeated_sint64_field);
+        assert_eq!(vec![10, 11], generated.repeated_packed_fixed32_field);
+        assert_eq!(
+            Some(ReflectValueRef::String("twelve")),
+            nested_field(&generated.repeated_message_field[0], "b")
+        );
+        assert_eq!(Some(&-13), generated.map_field.get("thirteen"));
+        assert_eq!(
+            Some(ReflectValueRef::String("fourteen")),
+            nested_field(&generated.map_message_field[&14], "b")
+        );
+        assert_eq!("fifteen", generated.get_oneof_string());
+
+        assert_eq!(
+            generated.write_to_bytes().unwrap(),
+            dynamic.write_to_bytes().unwrap()
+        );
+
+        let back = DynamicMessage::from_message(d.clone(), &generated).unwrap();
+        assert_eq!(
+            Some(ReflectValueRef::I32(-2)),
+            singular(&back, "sint32_field")
+        );
+        assert_eq!(
+            Some(ReflectValueRef::Enum(
+                match enum_value(&d, 2) {
+                    ReflectValueBox::Enum(e, _) => e,
+                    _ => unreachable!(),
+                },
+                2
+            )),
+            singular(&back, "enum_field")
+        );
+        assert_eq!(
+            dynamic.write_to_bytes().unwrap(),
+            back.write_to_bytes().unwrap()
+        );
+
+        // Zero values are written in proto2 and skipped in proto3 by both implementations
+        let mut zeros = DynamicMessage::new(d.clone());
+        zeros.set_field_by_name("int32_field", ReflectValueBox::I32(0));
+        zeros.set_field_by_name("sint32_field", ReflectValueBox::I32(0));
+        zeros.set_field_by_name("sfixed64_field", ReflectValueBox::I64(0));
+        zeros.set_field_by_name("double_field", ReflectValueBox::F64(0.0));
+        zeros.set_field_by_name("string_field", ReflectValueBox::String(String::new()));
+        zeros.set_field_by_name("bytes_field", ReflectValueBox::Bytes(Vec::new()));
+        zeros.set_field_by_name("enum_field", enum_value(&d, 0));
+        let generated: ForDynamicMessageTest = zeros.to_message().unwrap();
+        assert_eq!(
+            generated.write_to_bytes().unwrap(),
+            zeros.write_to_bytes().unwrap()
+        );
+        let dynamic = to_dynamic(&generated, &d);
+        assert_eq!(
+            generated.write_to_bytes().unwrap(),
+            dynamic.write_to_bytes().unwrap()
+        );
+        let proto3 = d.file_descriptor().proto().get_syntax() == "proto3";
+        assert_eq!(!proto3, dynamic.has_field_by_name("int32_field"));
+    }
+}
+
+#[test]
+fn test_repeated_packed_by_syntax() {
+    for d in descriptors() {
+        let proto3 = d.file_descriptor().proto().get_syntax() == "proto3";
+        let packed = d
+            .get_field_by_name("repeated_packed_fixed32_field")
+            .unwrap();
+        assert!(packed.is_packed());
+        let field = d.get_field_by_name("repeated_sint64_field").unwrap();
+        assert_eq!(proto3, field.is_packed());
+
+        // Repeated scalars are packed in proto3 unless `[packed = false]` is specified
+        let mut m = DynamicMessage::new(d.clone());
+        m.mut_repeated(&field).push(ReflectValueBox::I64(1));
+        m.mut_repeated(&field).push(ReflectValueBox::I64(-1));
+        let expected = if proto3 {
+            vec![0x4a, 2, 2, 1]
+        } else {
+            vec![0x48, 2, 0x48, 1]
+        };
+        assert_eq!(expected, m.write_to_bytes().unwrap());
+
+        let generated: ForDynamicMessageTest = m.to_message().unwrap();
+        assert_eq!(expected, generated.write_to_bytes().unwrap());
+    }
+}
+
+#[test]
+fn test_get_set_has_clear() {
+    for d in descriptors() {
+        let mut m = DynamicMessage::new(d.clone());
+        assert!(!m.has_field_by_name("int32_field"));
+        assert_eq!(None, singular(&m, "int32_field"));
+        assert!(m.get_field_by_name("no_such_field").is_none());
+        assert!(m.get_field_by_number(1000).is_none());
+
+        m.set_field_by_number(1, ReflectValueBox::I32(10));
+        assert!(m.has_field_by_name("int32_field"));
+        assert!(m.has_field_by_number(1));
+        assert_eq!(Some(ReflectValueRef::I32(10)), singular(&m, "int32_field"));
+
+        // Regular reflection works too
+        let field = d.get_field_by_name("int32_field").unwrap();
+        assert_eq!(Some(ReflectValueRef::I32(10)), field.get_singular(&m));
+
+        let field = d.get_field_by_name("repeated_sint64_field").unwrap();
+        m.mut_repeated(&field).push(ReflectValueBox::I64(1));
+        assert!(m.has_field(&field));
+
+        let present: Vec<_> = m
+            .present_fields()
+            .map(|(f, _)| f.get_name().to_owned())
+            .collect();
+        assert_eq!(vec!["int32_field", "repeated_sint64_field"], present);
+
+        m.clear_field_by_name("int32_field");
+        assert!(!m.has_field_by_name("int32_field"));
+        m.clear_field_by_number(9);
+        assert_eq!(0, m.present_fields().count());
+    }
+}
+
+#[test]
+#[should_panic]
+fn test_set_wrong_type() {
+    let mut m = DynamicMessage::new(ForDynamicMessageTest::descriptor_static());
+    m.set_field_by_name("int32_field", ReflectValueBox::I64(10));
+}
+
+#[test]
+fn test_oneof() {
+    for d in descriptors() {
+        let oneof = d.oneofs().next().unwrap();
+        let mut m = DynamicMessage::new(d.clone());
+        assert!(m.which_oneof(&oneof).is_none());
+
+        m.set_field_by_name("oneof_string", ReflectValueBox::String("s".to_owned()));
+        assert_eq!("oneof_string", m.which_oneof(&oneof).unwrap().get_name());
+
+        let field = d.get_field_by_name("oneof_message").unwrap();
+        m.mut_message(&field);
+        assert_eq!("oneof_message", m.which_oneof(&oneof).unwrap().get_name());
+        assert!(!m.has_field_by_name("oneof_string"));
+
+        m.clear_oneof(&oneof);
+        assert!(m.which_oneof(&oneof).is_none());
+
+        // Last oneof field on the wire wins
+        let mut generated = ForDynamicMessageTest::new();
+        generated.set_oneof_string("s".to_owned());
+        let mut bytes = generated.write_to_bytes().unwrap();
+        generated.set_oneof_message(DynamicNested::new());
+        bytes.extend(generated.write_to_bytes().unwrap());
+        let mut m = DynamicMessage::new(d.clone());
+        m.merge_from_bytes(&bytes).unwrap();
+        assert_eq!("oneof_message", m.which_oneof(&oneof).unwrap().get_name());
+    }
+}
+
+#[test]
+fn test_merge() {
+    for d in descriptors() {
+        let mut a = DynamicMessage::new(d.clone());
+        a.set_field_by_name("int32_field", ReflectValueBox::I32(1));
+        a.set_field_by_name("string_field", ReflectValueBox::String("a".to_owned()));
+        a.set_field_by_name("message_field", nested(&d, "message_field", 1, "a"));
+        let field = d.get_field_by_name("repeated_sint64_field").unwrap();
+        a.mut_repeated(&field).push(ReflectValueBox::I64(1));
+
+        let mut b = DynamicMessage::new(d.clone());
+        b.set_field_by_name("string_field", ReflectValueBox::String("b".to_owned()));
+        let mut nested_b = DynamicMessage::new(nested_descriptor(&d, "message_field"));
+        nested_b.set_field_by_name("b", ReflectValueBox::String("b".to_owned()));
+        b.set_field_by_name(
+            "message_field",
+            ReflectValueBox::Message(Box::new(nested_b)),
+        );
+        b.mut_repeated(&field).push(ReflectValueBox::I64(2));
+
+        a.merge_from_dynamic(&b);
+
+        assert_eq!(Some(ReflectValueRef::I32(1)), singular(&a, "int32_field"));
+        assert_eq!(
+            Some(ReflectValueRef::String("b")),
+            singular(&a, "string_field")
+        );
+        let generated: ForDynamicMessageTest = a.to_message().unwrap();
+        assert_eq!(
+            Some(ReflectValueRef::I32(1)),
+            nested_field(generated.message_field.get_or_default(), "a")
+        );
+        assert_eq!(
+            Some(ReflectValueRef::String("b")),
+            nested_field(generated.message_field.get_or_default(), "b")
+        );
+        assert_eq!(vec![1, 2], generated.repeated_sint64_field);
+    }
+}
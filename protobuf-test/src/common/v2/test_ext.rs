@@ -1,4 +1,7 @@
+use protobuf::reflect::ExtensionDescriptor;
+use protobuf::reflect::ReflectValueBox;
 use protobuf::Message;
+use protobuf::ProtobufEnumOrUnknown;
 
 use super::test_ext_pb::*;
 
@@ -23,12 +26,260 @@ fn test_get() {
         "Hello world!",
         exts::string_field.get(message).unwrap_or_default()
     );
-    if false {
-        // TODO: only implemented in `protoc`-based codegen
-        assert_eq!(
-            TestEnum::RED,
-            exts::enum_field.get(message).unwrap_or_default().unwrap()
-        );
-        assert_eq!(22, exts::message_field.get(message).unwrap().get_n());
+    assert_eq!(
+        TestEnum::RED,
+        exts::enum_field.get(message).unwrap_or_default().unwrap()
+    );
+    assert_eq!(22, exts::message_field.get(message).unwrap().get_n());
+}
+
+#[test]
+fn test_optional_set_clear() {
+    let mut m = ExtendableMessage::new();
+    assert!(!exts::optional_int32_field.has(&m));
+    assert_eq!(None, exts::optional_int32_field.get(&m));
+
+    exts::optional_int32_field.set(&mut m, 10);
+    exts::optional_int32_field.set(&mut m, -20);
+    assert!(exts::optional_int32_field.has(&m));
+    assert_eq!(Some(-20), exts::optional_int32_field.get(&m));
+
+    let mut sub = SubM::new();
+    sub.set_n(30);
+    exts::optional_message_field.set(&mut m, sub);
+
+    let m = ExtendableMessage::parse_from_bytes(&m.write_to_bytes().unwrap()).unwrap();
+    assert_eq!(Some(-20), exts::optional_int32_field.get(&m));
+    assert_eq!(30, exts::optional_message_field.get(&m).unwrap().get_n());
+
+    let mut m = m;
+    exts::optional_int32_field.clear(&mut m);
+    assert!(!exts::optional_int32_field.has(&m));
+    assert!(exts::optional_message_field.has(&m));
+}
+
+#[test]
+fn test_repeated() {
+    let mut m = ExtendableMessage::new();
+    assert!(!exts::repeated_int32_field.has(&m));
+    assert_eq!(Vec::<i32>::new(), exts::repeated_int32_field.get(&m));
+
+    exts::repeated_int32_field.push(&mut m, 1);
+    exts::repeated_int32_field.push(&mut m, -2);
+    exts::repeated_string_field.set(&mut m, vec!["a".to_owned(), "b".to_owned()]);
+    exts::repeated_enum_field.set(&mut m, vec![TestEnum::RED.into(), TestEnum::BLUE.into()]);
+    let mut sub = SubM::new();
+    sub.set_n(3);
+    exts::repeated_message_field.push(&mut m, sub.clone());
+    exts::repeated_message_field.push(&mut m, SubM::new());
+
+    let m = ExtendableMessage::parse_from_bytes(&m.write_to_bytes().unwrap()).unwrap();
+    assert!(exts::repeated_int32_field.has(&m));
+    assert_eq!(vec![1, -2], exts::repeated_int32_field.get(&m));
+    assert_eq!(vec!["a", "b"], exts::repeated_string_field.get(&m));
+    assert_eq!(
+        vec![
+            ProtobufEnumOrUnknown::new(TestEnum::RED),
+            ProtobufEnumOrUnknown::new(TestEnum::BLUE)
+        ],
+        exts::repeated_enum_field.get(&m)
+    );
+    assert_eq!(vec![sub, SubM::new()], exts::repeated_message_field.get(&m));
+
+    let mut m = m;
+    exts::repeated_int32_field.clear(&mut m);
+    assert!(!exts::repeated_int32_field.has(&m));
+    assert_eq!(2, exts::repeated_string_field.get(&m).len());
+}
+
+#[test]
+fn test_repeated_packed() {
+    // field 104: non-packed value -2
+    // followed by packed values 1, -1, 2
+    let bytes = [0xc0, 0x06, 0x03, 0xc2, 0x06, 0x03, 0x02, 0x01, 0x04];
+    let m = ExtendableMessage::parse_from_bytes(&bytes).unwrap();
+    assert_eq!(
+        vec![-2, 1, -1, 2],
+        exts::repeated_packed_sint64_field.get(&m)
+    );
+}
+
+#[test]
+fn test_repeated_packed_write() {
+    let mut m = ExtendableMessage::new();
+    exts::repeated_packed_sint64_field.set(&mut m, vec![1, -1]);
+    exts::repeated_packed_sint64_field.push(&mut m, 2);
+    assert_eq!(
+        vec![0xc2, 0x06, 0x02, 0x02, 0x01, 0xc2, 0x06, 0x01, 0x04],
+        m.write_to_bytes().unwrap()
+    );
+    assert_eq!(vec![1, -1, 2], exts::repeated_packed_sint64_field.get(&m));
+
+    let mut m = ExtendableMessage::new();
+    extension_by_name("repeated_packed_sint64_field")
+        .set_repeated(
+            &mut m,
+            vec![ReflectValueBox::I64(1), ReflectValueBox::I64(-1)],
+        )
+        .unwrap();
+    assert_eq!(
+        vec![0xc2, 0x06, 0x02, 0x02, 0x01],
+        m.write_to_bytes().unwrap()
+    );
+}
+
+#[test]
+fn test_optional_message_merged() {
+    // field 102: `SubM { n: 1 }` followed by empty `SubM`
+    let bytes = [0xb2, 0x06, 0x02, 0x08, 0x01, 0xb2, 0x06, 0x00];
+    let m = ExtendableMessage::parse_from_bytes(&bytes).unwrap();
+    assert_eq!(1, exts::optional_message_field.get(&m).unwrap().get_n());
+}
+
+#[test]
+fn test_malformed() {
+    // field 101: length-delimited value of int32 field, truncated varint inside
+    let bytes = [0xaa, 0x06, 0x01, 0x80];
+    let m = ExtendableMessage::parse_from_bytes(&bytes).unwrap();
+    assert!(exts::optional_int32_field.has(&m));
+    assert_eq!(None, exts::optional_int32_field.get(&m));
+
+    // field 105: invalid UTF-8
+    let bytes = [0xca, 0x06, 0x01, 0xff];
+    let m = ExtendableMessage::parse_from_bytes(&bytes).unwrap();
+    assert_eq!(Vec::<String>::new(), exts::repeated_string_field.get(&m));
+}
+
+fn extension_by_name(name: &str) -> ExtensionDescriptor {
+    file_descriptor()
+        .extensions()
+        .into_iter()
+        .find(|e| e.get_name() == name)
+        .unwrap()
+}
+
+#[test]
+fn test_reflect_extensions() {
+    let extensions = file_descriptor().extensions();
+    assert!(extensions
+        .iter()
+        .any(|e| e.full_name() == "test_ext.int32_field"));
+    assert!(extensions
+        .iter()
+        .all(|e| e.get_name() != "nested_string_field"));
+
+    let int32_field = extension_by_name("int32_field");
+    assert_eq!(50003, int32_field.get_number());
+    assert_eq!(None, int32_field.containing_message());
+    assert_eq!(
+        protobuf::descriptor::MessageOptions::descriptor_static(),
+        int32_field.extendee()
+    );
+
+    let scope = ExtensionScope::descriptor_static();
+    let nested = scope.extensions();
+    assert_eq!(1, nested.len());
+    assert_eq!(
+        "test_ext.ExtensionScope.nested_string_field",
+        nested[0].full_name()
+    );
+    assert_eq!(Some(scope), nested[0].containing_message());
+    assert_eq!(ExtendableMessage::descriptor_static(), nested[0].extendee());
+
+    let extendable = ExtendableMessage::descriptor_static();
+    assert_eq!(vec![100..200], extendable.extension_ranges());
+    assert!(extendable.is_extension_number(199));
+    assert!(!extendable.is_extension_number(200));
+}
+
+#[test]
+fn test_reflect_get_options() {
+    let options = MyMessage::descriptor_static()
+        .get_proto()
+        .options
+        .get_or_default()
+        .clone();
+
+    assert_eq!(
+        Some(ReflectValueBox::I32(-3)),
+        extension_by_name("int32_field")
+            .get_singular(&options)
+            .unwrap()
+    );
+    assert_eq!(
+        Some(ReflectValueBox::String("Hello world!".to_owned())),
+        extension_by_name("string_field")
+            .get_singular(&options)
+            .unwrap()
+    );
+    match extension_by_name("enum_field")
+        .get_singular(&options)
+        .unwrap()
+    {
+        Some(ReflectValueBox::Enum(d, v)) => {
+            assert_eq!("RED", d.get_value_by_number(v).unwrap().get_name())
+        }
+        v => panic!("unexpected: {:?}", v),
+    }
+    match extension_by_name("message_field")
+        .get_singular(&options)
+        .unwrap()
+    {
+        Some(ReflectValueBox::Message(m)) => {
+            assert_eq!(22, m.downcast_ref::<SubM>().unwrap().get_n())
+        }
+        v => panic!("unexpected: {:?}", v),
     }
 }
+
+#[test]
+fn test_reflect_set() {
+    let mut m = ExtendableMessage::new();
+
+    let optional_int32_field = extension_by_name("optional_int32_field");
+    assert!(!optional_int32_field.has(&m));
+    optional_int32_field
+        .set_singular(&mut m, ReflectValueBox::I32(5))
+        .unwrap();
+    optional_int32_field
+        .set_singular(&mut m, ReflectValueBox::I32(6))
+        .unwrap();
+    assert!(optional_int32_field.has(&m));
+    assert_eq!(Some(6), exts::optional_int32_field.get(&m));
+
+    let repeated_string_field = extension_by_name("repeated_string_field");
+    repeated_string_field
+        .set_repeated(
+            &mut m,
+            vec![
+                ReflectValueBox::String("a".to_owned()),
+                ReflectValueBox::String("b".to_owned()),
+            ],
+        )
+        .unwrap();
+    repeated_string_field
+        .push_repeated(&mut m, ReflectValueBox::String("c".to_owned()))
+        .unwrap();
+    assert_eq!(vec!["a", "b", "c"], exts::repeated_string_field.get(&m));
+    assert_eq!(3, repeated_string_field.get_repeated(&m).unwrap().len());
+
+    let mut sub = SubM::new();
+    sub.set_n(7);
+    let optional_message_field = extension_by_name("optional_message_field");
+    optional_message_field
+        .set_singular(&mut m, ReflectValueBox::Message(Box::new(sub.clone())))
+        .unwrap();
+    assert_eq!(Some(sub), exts::optional_message_field.get(&m));
+
+    optional_int32_field.clear(&mut m);
+    assert!(!optional_int32_field.has(&m));
+    assert!(optional_message_field.has(&m));
+}
+
+#[test]
+#[should_panic(expected = "extension is not repeated")]
+fn test_reflect_set_repeated_empty_on_singular() {
+    let mut m = ExtendableMessage::new();
+    exts::optional_int32_field.set(&mut m, 1);
+    let _ = extension_by_name("optional_int32_field").set_repeated(&mut m, Vec::new());
+}
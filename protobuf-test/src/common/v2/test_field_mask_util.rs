@@ -0,0 +1,243 @@
+use protobuf::field_mask;
+use protobuf::field_mask::MergeOptions;
+use protobuf::well_known_types::FieldMask;
+use protobuf::Message;
+use protobuf::MessageDyn;
+use protobuf_test_common::dynamic_message_descriptor;
+use protobuf_test_common::to_dynamic;
+
+use super::test_field_mask_util_pb::*;
+
+fn mask(paths: &[&str]) -> FieldMask {
+    FieldMask {
+        paths: paths.iter().map(|p| (*p).to_owned()).collect(),
+        ..Default::default()
+    }
+}
+
+fn resource() -> Resource {
+    let mut child = Resource::new();
+    child.set_name("child".to_owned());
+    child.set_count(1);
+
+    let mut r = Resource::new();
+    r.set_name("name".to_owned());
+    r.set_count(2);
+    r.set_child(child);
+    r.mut_tags().push("a".to_owned());
+    r.mut_labels().insert("env".to_owned(), "prod".to_owned());
+    r.mut_labels().insert("team".to_owned(), "x".to_owned());
+    r.set_text("text".to_owned());
+    r
+}
+
+#[test]
+fn test_validate() {
+    let descriptor = Resource::descriptor_static();
+    for path in &[
+        "name",
+        "child",
+        "child.child.count",
+        "tags",
+        "labels",
+        "labels.env",
+        "labels.`a.b`",
+        "text",
+        "*",
+    ] {
+        assert_eq!(Ok(()), field_mask::validate(&mask(&[path]), &descriptor));
+    }
+
+    for path in &[
+        "unknown",
+        "",
+        "name.x",
+        "tags.x",
+        "child.",
+        "child.unknown",
+        "labels.env.x",
+    ] {
+        let e = field_mask::validate(&mask(&["name", path]), &descriptor).unwrap_err();
+        assert_eq!(*path, e.path);
+        assert_eq!("test_field_mask_util.Resource", e.message);
+    }
+}
+
+#[test]
+fn test_trim() {
+    let mut r = resource();
+    field_mask::trim(&mut r, &mask(&["name", "child.count", "labels.env"]));
+
+    let mut expected = Resource::new();
+    expected.set_name("name".to_owned());
+    expected.mut_child().set_count(1);
+    expected
+        .mut_labels()
+        .insert("env".to_owned(), "prod".to_owned());
+    assert_eq!(expected, r);
+
+    let mut r = resource();
+    field_mask::trim(&mut r, &mask(&["*"]));
+    assert_eq!(resource(), r);
+}
+
+#[test]
+fn test_merge_default_options() {
+    let mut src = Resource::new();
+    src.set_name("new".to_owned());
+    src.mut_child().set_count(5);
+    src.mut_tags().push("b".to_owned());
+    src.mut_labels().insert("env".to_owned(), "dev".to_owned());
+
+    let mut dst = resource();
+    field_mask::merge_with_mask(
+        &src,
+        &mut dst,
+        &mask(&["name", "count", "child", "tags", "labels", "text"]),
+        &MergeOptions::default(),
+    )
+    .unwrap();
+
+    let mut expected = resource();
+    expected.set_name("new".to_owned());
+    expected.mut_child().set_count(5);
+    expected.mut_tags().push("b".to_owned());
+    expected
+        .mut_labels()
+        .insert("env".to_owned(), "dev".to_owned());
+    assert_eq!(expected, dst);
+}
+
+#[test]
+fn test_merge_aip_161() {
+    let mut src = Resource::new();
+    src.set_name("new".to_owned());
+    src.mut_child().set_count(5);
+    src.mut_tags().push("b".to_owned());
+
+    let mut dst = resource();
+    field_mask::merge_with_mask(
+        &src,
+        &mut dst,
+        &mask(&["name", "count", "child", "tags", "labels", "text"]),
+        &MergeOptions::aip_161(),
+    )
+    .unwrap();
+
+    let mut expected = Resource::new();
+    expected.set_name("new".to_owned());
+    expected.mut_child().set_count(5);
+    expected.mut_tags().push("b".to_owned());
+    assert_eq!(expected, dst);
+}
+
+#[test]
+fn test_merge_nested_path() {
+    let mut dst = resource();
+    field_mask::merge_with_mask(
+        &Resource::new(),
+        &mut dst,
+        &mask(&["child.name"]),
+        &MergeOptions::aip_161(),
+    )
+    .unwrap();
+
+    let mut expected = resource();
+    expected.mut_child().clear_name();
+    assert_eq!(expected, dst);
+}
+
+#[test]
+fn test_merge_map_keys() {
+    let mut src = Resource::new();
+    src.mut_labels().insert("env".to_owned(), "dev".to_owned());
+    src.mut_labels().insert("other".to_owned(), "y".to_owned());
+    let paths = mask(&["labels.env", "labels.team"]);
+
+    let mut dst = resource();
+    field_mask::merge_with_mask(&src, &mut dst, &paths, &MergeOptions::default()).unwrap();
+    assert_eq!(2, dst.get_labels().len());
+    assert_eq!("dev", dst.get_labels()["env"]);
+    assert_eq!("x", dst.get_labels()["team"]);
+
+    let mut dst = resource();
+    field_mask::merge_with_mask(&src, &mut dst, &paths, &MergeOptions::aip_161()).unwrap();
+    assert_eq!(1, dst.get_labels().len());
+    assert_eq!("dev", dst.get_labels()["env"]);
+}
+
+#[test]
+fn test_merge_oneof() {
+    let mut src = Resource::new();
+    src.set_number(3);
+
+    let mut dst = resource();
+    field_mask::merge_with_mask(&src, &mut dst, &mask(&["number"]), &MergeOptions::aip_161())
+        .unwrap();
+    assert!(!dst.has_text());
+    assert_eq!(3, dst.get_number());
+
+    let mut dst = resource();
+    field_mask::merge_with_mask(&src, &mut dst, &mask(&["text"]), &MergeOptions::aip_161())
+        .unwrap();
+    assert!(!dst.has_text());
+    assert!(!dst.has_number());
+    assert_eq!("name", dst.get_name());
+}
+
+#[test]
+fn test_clear_oneof_field() {
+    let descriptor = Resource::descriptor_static();
+    let number = descriptor.get_field_by_name("number").unwrap();
+    let text = descriptor.get_field_by_name("text").unwrap();
+
+    // clearing unset field keeps other field of the oneof
+    let mut r = resource();
+    number.clear_field(&mut r);
+    assert_eq!("text", r.get_text());
+
+    text.clear_field(&mut r);
+    assert!(!r.has_text());
+    assert_eq!("name", r.get_name());
+}
+
+#[test]
+fn test_merge_invalid_mask() {
+    let mut dst = resource();
+    let e = field_mask::merge_with_mask(
+        &Resource::new(),
+        &mut dst,
+        &mask(&["name", "nome"]),
+        &MergeOptions::aip_161(),
+    )
+    .unwrap_err();
+    assert_eq!("nome", e.path);
+    assert_eq!(resource(), dst);
+}
+
+#[test]
+fn test_dynamic() {
+    let descriptor = dynamic_message_descriptor(&file_descriptor(), "Resource");
+
+    let mut src = Resource::new();
+    src.set_name("new".to_owned());
+    let dynamic_src = to_dynamic(&src, &descriptor);
+    let mut dynamic_dst = to_dynamic(&resource(), &descriptor);
+    let dynamic_dst: &mut dyn MessageDyn = &mut dynamic_dst;
+
+    let paths = mask(&["name", "text", "labels.team", "child.count"]);
+    field_mask::merge_with_mask(&dynamic_src, dynamic_dst, &paths, &MergeOptions::aip_161())
+        .unwrap();
+    field_mask::trim(dynamic_dst, &mask(&["name", "labels", "child"]));
+
+    let mut expected = Resource::new();
+    expected.set_name("new".to_owned());
+    expected
+        .mut_labels()
+        .insert("env".to_owned(), "prod".to_owned());
+    expected.mut_child().set_name("child".to_owned());
+    assert_eq!(
+        expected,
+        Resource::parse_from_bytes(&dynamic_dst.write_to_bytes_dyn().unwrap()).unwrap()
+    );
+}
@@ -1,7 +1,10 @@
+use protobuf::json;
+use protobuf::reflect::TypeRegistry;
 use protobuf::well_known_types::*;
 
 use protobuf_test_common::*;
 
+use super::test_fmt_json_well_known_pb::file_descriptor;
 use super::test_fmt_json_well_known_pb::*;
 
 #[test]
@@ -131,7 +134,55 @@ fn test_wrappers() {
 fn test_any() {
     let mut m = TestFmtJsonWellKnownTypes::new();
     m.any_value.mut_or_default();
-    // TODO
+    test_json_print_parse_message("{\"anyValue\": {}}", &m);
+
+    let mut registry = TypeRegistry::new();
+    registry.add_file(&file_descriptor());
+    let print_options = json::PrintOptions {
+        type_registry: registry.clone(),
+        ..Default::default()
+    };
+    let parse_options = json::ParseOptions {
+        type_registry: registry,
+        ..Default::default()
+    };
+
+    let mut inner = TestFmtJsonWellKnownTypes::new();
+    inner.mut_int32_value().value = 17;
+    let mut m = TestFmtJsonWellKnownTypes::new();
+    m.set_any_value(Any::pack(&inner).unwrap());
+    let json = "{\"anyValue\": {\"@type\": \"type.googleapis.com/TestFmtJsonWellKnownTypes\", \"int32Value\": 17}}";
+    assert_eq!(
+        json,
+        json::print_to_string_with_options(&m, &print_options).unwrap()
+    );
+    let parsed: TestFmtJsonWellKnownTypes =
+        json::parse_from_str_with_options(json, &parse_options).unwrap();
+    assert_eq!(m, parsed);
+
+    // `@type` is not required to be the first key
+    let parsed: TestFmtJsonWellKnownTypes = json::parse_from_str_with_options(
+        "{\"anyValue\": {\"int32Value\": 17, \"@type\": \"type.googleapis.com/TestFmtJsonWellKnownTypes\"}}",
+        &parse_options,
+    )
+    .unwrap();
+    assert_eq!(m, parsed);
+
+    // Well-known types do not need to be registered and use `value` key
+    let mut d = Duration::new();
+    d.seconds = 3;
+    let mut m = TestFmtJsonWellKnownTypes::new();
+    m.set_any_value(Any::pack(&d).unwrap());
+    test_json_print_parse_message(
+        "{\"anyValue\": {\"@type\": \"type.googleapis.com/google.protobuf.Duration\", \"value\": \"3.000000000s\"}}",
+        &m,
+    );
+
+    // Unregistered type
+    let mut m = TestFmtJsonWellKnownTypes::new();
+    m.set_any_value(Any::pack(&inner).unwrap());
+    assert!(json::print_to_string(&m).is_err());
+    assert!(json::parse_from_str::<TestFmtJsonWellKnownTypes>(json).is_err());
 }
 
 #[test]
@@ -3,7 +3,11 @@ use protobuf::Message;
 use protobuf_test_common::*;
 
 use super::test_fmt_text_format_pb::*;
+use protobuf::reflect::ReflectValueRef;
+use protobuf::reflect::TypeRegistry;
+use protobuf::text_format;
 use protobuf::text_format::print_to_string;
+use protobuf::well_known_types::Any;
 
 #[test]
 fn test_show() {
@@ -284,3 +288,316 @@ fn test_string_escaped() {
         &*format!("{:?}", m)
     );
 }
+
+fn type_registry() -> TypeRegistry {
+    let mut type_registry = TypeRegistry::new();
+    type_registry.add_file(&file_descriptor());
+    type_registry
+}
+
+fn parse_options() -> text_format::ParseOptions {
+    text_format::ParseOptions {
+        type_registry: type_registry(),
+        ..Default::default()
+    }
+}
+
+fn print_options() -> text_format::PrintOptions {
+    text_format::PrintOptions {
+        type_registry: type_registry(),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_extensions() {
+    let text = "value: 1 \
+        [test_fmt_text_format.int32_ext]: 10 \
+        [test_fmt_text_format.sint64_ext]: -20 \
+        [test_fmt_text_format.double_ext]: 1.5 \
+        [test_fmt_text_format.string_ext]: \"a\" \
+        [test_fmt_text_format.string_ext]: \"b\" \
+        [test_fmt_text_format.enum_ext]: LIGHT \
+        [test_fmt_text_format.message_ext] {value: 30}";
+    let m: TestExtendable =
+        text_format::parse_from_str_with_options(text, &parse_options()).unwrap();
+    assert_eq!(1, m.get_value());
+    assert_eq!(Some(10), exts::int32_ext.get(&m));
+    assert_eq!(Some(-20), exts::sint64_ext.get(&m));
+    assert_eq!(Some(1.5), exts::double_ext.get(&m));
+
+    assert_eq!(
+        text,
+        text_format::print_to_string_with_options(&m, &print_options())
+    );
+
+    // Extensions are printed as unknown fields when not in registry
+    assert!(print_to_string(&m).starts_with("value: 1 100: 10 101: 39 "));
+}
+
+#[test]
+fn test_extensions_errors() {
+    let e = text_format::parse_from_str::<TestExtendable>("[test_fmt_text_format.int32_ext]: 10")
+        .unwrap_err();
+    assert_eq!(
+        "1:32: UnknownExtension(\"test_fmt_text_format.int32_ext\")",
+        e.to_string()
+    );
+
+    // Extension of different message
+    assert!(text_format::parse_from_str_with_options::<TestMessage>(
+        "[test_fmt_text_format.int32_ext]: 10",
+        &parse_options()
+    )
+    .is_err());
+}
+
+#[test]
+fn test_any_expanded() {
+    let text = "any_field {[type.googleapis.com/test_fmt_text_format.TestMessage] {value: 23}}";
+    let m: TestAnyField = text_format::parse_from_str_with_options(text, &parse_options()).unwrap();
+    let any = m.get_any_field();
+    assert_eq!(
+        "type.googleapis.com/test_fmt_text_format.TestMessage",
+        any.type_url
+    );
+    assert_eq!(
+        23,
+        any.unpack::<TestMessage>().unwrap().unwrap().get_value()
+    );
+
+    assert_eq!(
+        text,
+        text_format::print_to_string_with_options(&m, &print_options())
+    );
+
+    // Type URL cannot be resolved without registry
+    assert_eq!(
+        "any_field {type_url: \"type.googleapis.com/test_fmt_text_format.TestMessage\" value: \"P\\027\"}",
+        print_to_string(&m)
+    );
+    assert!(text_format::parse_from_str::<TestAnyField>(text).is_err());
+
+    // Expanded syntax is only allowed in `Any`
+    assert!(text_format::parse_from_str_with_options::<TestAnyField>(
+        "[type.googleapis.com/test_fmt_text_format.TestMessage] {value: 23}",
+        &parse_options()
+    )
+    .is_err());
+
+    // Well-known types are resolved without registration
+    let mut any = Any::new();
+    any.type_url = "type.googleapis.com/google.protobuf.Duration".to_owned();
+    let mut m = TestAnyField::new();
+    m.set_any_field(any);
+    let text = "any_field {[type.googleapis.com/google.protobuf.Duration] {}}";
+    assert_eq!(
+        text,
+        text_format::print_to_string_with_options(&m, &text_format::PrintOptions::default())
+    );
+}
+
+/// Parse text in alternative syntax and compare with canonical text.
+fn test_parse_same(text: &str, canonical: &str) {
+    let parsed: TestTypes = text_format::parse_from_str(text).expect(text);
+    let expected: TestTypes = text_format::parse_from_str(canonical).expect(canonical);
+    assert_eq!(expected, parsed, "{:?}", text);
+}
+
+#[test]
+fn test_parse_list() {
+    test_parse_same(
+        "int32_repeated: [1, -2, 3]",
+        "int32_repeated: 1 int32_repeated: -2 int32_repeated: 3",
+    );
+    test_parse_same("int32_repeated: []", "");
+    test_parse_same(
+        "test_message_repeated [{value: 1}, <value: 2>]",
+        "test_message_repeated {value: 1} test_message_repeated {value: 2}",
+    );
+    test_parse_same(
+        "test_enum_repeated: [DARK, 2]",
+        "test_enum_repeated: DARK test_enum_repeated: LIGHT",
+    );
+    test_parse_same(
+        "int32_map_field: [{key: 1 value: 2}, {key: 3 value: 4}]",
+        "int32_map_field {key: 1 value: 2} int32_map_field {key: 3 value: 4}",
+    );
+    // Lists are only allowed for repeated fields
+    assert!(text_format::parse_from_str::<TestTypes>("int32_singular: [1]").is_err());
+}
+
+#[test]
+fn test_parse_message_delimiters() {
+    test_parse_same(
+        "test_message_singular <value: 1>",
+        "test_message_singular {value: 1}",
+    );
+    test_parse_same(
+        "test_message_singular: {value: 1}",
+        "test_message_singular {value: 1}",
+    );
+    test_parse_same(
+        "message_map_field <key: \"a\" value <value: 1>>",
+        "message_map_field {key: \"a\" value {value: 1}}",
+    );
+    // Colon is required for scalar fields
+    assert!(text_format::parse_from_str::<TestTypes>("int32_singular 1").is_err());
+}
+
+#[test]
+fn test_parse_separators() {
+    test_parse_same(
+        "int32_singular: 1; uint32_singular: 2, test_message_singular {value: 3;};",
+        "int32_singular: 1 uint32_singular: 2 test_message_singular {value: 3}",
+    );
+}
+
+#[test]
+fn test_parse_scalar_forms() {
+    test_parse_same("test_enum_singular: 1", "test_enum_singular: DARK");
+    test_parse_same(
+        "bool_singular: t bool_repeated: [True, f, False, 1, 0]",
+        "bool_singular: true bool_repeated: [true, false, false, true, false]",
+    );
+    test_parse_same(
+        "float_singular: 1.5f double_singular: 2F",
+        "float_singular: 1.5 double_singular: 2",
+    );
+    test_parse_same(
+        "double_repeated: [-Infinity, INF]",
+        "double_repeated: [-inf, inf]",
+    );
+    test_parse_same(
+        "string_singular: \"ab\" 'c' \"d\"",
+        "string_singular: \"abcd\"",
+    );
+    test_parse_same(
+        "bytes_singular: \"\\xc3\" \"\\xa9\"",
+        "bytes_singular: \"\\303\\251\"",
+    );
+    test_parse_same("uint32_singular: 0x1F", "uint32_singular: 31");
+
+    let m: TestTypes = text_format::parse_from_str("double_singular: nan").unwrap();
+    assert!(m.get_double_singular().is_nan());
+}
+
+#[test]
+fn test_parse_unknown_enum_number() {
+    let r = text_format::parse_from_str::<TestTypes>("test_enum_singular: 17");
+    // Only open enums accept numbers without declared value
+    if file_descriptor().proto().get_syntax() == "proto3" {
+        let m = r.unwrap();
+        let field = TestTypes::descriptor_static()
+            .get_field_by_name("test_enum_singular")
+            .unwrap();
+        match field.get_singular_field_or_default(&m) {
+            ReflectValueRef::Enum(_, value) => assert_eq!(17, value),
+            _ => panic!("expecting enum"),
+        }
+    } else {
+        assert!(r.is_err());
+    }
+}
+
+#[test]
+fn test_parse_ignore_unknown_fields() {
+    let text = "int32_singular: 1 \
+        unknown_a: [1, -2.5, \"s\" \"t\", ident] \
+        unknown_b {x: \"y\" z <w: -1.5> v [{}, {}]}; \
+        [test_fmt_text_format.unknown_ext]: 3, \
+        uint32_singular: 2";
+    assert!(text_format::parse_from_str::<TestTypes>(text).is_err());
+
+    let parse_options = text_format::ParseOptions {
+        ignore_unknown_fields: true,
+        ..Default::default()
+    };
+    let m: TestTypes = text_format::parse_from_str_with_options(text, &parse_options).unwrap();
+    assert_eq!(
+        text_format::parse_from_str::<TestTypes>("int32_singular: 1 uint32_singular: 2").unwrap(),
+        m
+    );
+}
+
+#[test]
+fn test_parse_recursion_limit() {
+    let parse_options = text_format::ParseOptions {
+        recursion_limit: 1,
+        ..Default::default()
+    };
+    assert!(text_format::parse_from_str_with_options::<TestTypesList>(
+        "ts {int32_singular: 1}",
+        &parse_options
+    )
+    .is_ok());
+    let e = text_format::parse_from_str_with_options::<TestTypesList>(
+        "ts {test_message_singular {}}",
+        &parse_options,
+    )
+    .unwrap_err();
+    assert_eq!("1:27: RecursionLimitExceeded", e.to_string());
+}
+
+#[test]
+fn test_print_options() {
+    let mut m = TestTypes::new();
+    m.set_int32_singular(1);
+    m.mut_test_message_singular().set_value(2);
+    m.set_string_singular("день".to_owned());
+
+    let print_options = text_format::PrintOptions {
+        single_line: false,
+        indent_width: 4,
+        utf8: true,
+        ..Default::default()
+    };
+    assert_eq!(
+        "int32_singular: 1\nstring_singular: \"день\"\ntest_message_singular {\n    value: 2\n}\n",
+        text_format::print_to_string_with_options(&m, &print_options)
+    );
+}
+
+#[test]
+fn test_print_options_unknown_fields() {
+    let mut m = TestMessage::new();
+    m.mut_unknown_fields().add_varint(20, 3);
+    // Nested message `10: 7`
+    m.mut_unknown_fields()
+        .add_length_delimited(21, vec![0x50, 0x07]);
+
+    assert_eq!("20: 3 21: \"P\\007\"", print_to_string(&m));
+
+    let print_options = text_format::PrintOptions {
+        decode_unknown_messages: true,
+        ..Default::default()
+    };
+    assert_eq!(
+        "20: 3 21 {10: 7}",
+        text_format::print_to_string_with_options(&m, &print_options)
+    );
+
+    let print_options = text_format::PrintOptions {
+        skip_unknown_fields: true,
+        ..Default::default()
+    };
+    assert_eq!(
+        "",
+        text_format::print_to_string_with_options(&m, &print_options)
+    );
+}
+
+#[test]
+fn test_print_options_expand_any() {
+    let text = "any_field {[type.googleapis.com/test_fmt_text_format.TestMessage] {value: 23}}";
+    let m: TestAnyField = text_format::parse_from_str_with_options(text, &parse_options()).unwrap();
+
+    let print_options = text_format::PrintOptions {
+        expand_any: false,
+        ..print_options()
+    };
+    assert_eq!(
+        "any_field {type_url: \"type.googleapis.com/test_fmt_text_format.TestMessage\" value: \"P\\027\"}",
+        text_format::print_to_string_with_options(&m, &print_options)
+    );
+}
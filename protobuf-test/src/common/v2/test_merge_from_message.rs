@@ -0,0 +1,157 @@
+use protobuf::Message;
+use protobuf::MessageDyn;
+use protobuf_test_common::dynamic_message_descriptor;
+use protobuf_test_common::to_dynamic;
+
+use super::test_merge_from_message_pb::*;
+
+fn inner(name: &str, value: i32) -> Inner {
+    let mut inner = Inner::new();
+    inner.set_name(name.to_owned());
+    inner.set_value(value);
+    inner.mut_tags().push(name.to_owned());
+    inner
+}
+
+fn dst() -> Outer {
+    let mut m = Outer::new();
+    m.set_id(1);
+    m.set_name("dst".to_owned());
+    m.set_kind(Kind::SMALL);
+    m.set_inner(inner("a", 1));
+    m.mut_numbers().push(1);
+    m.mut_items().push(inner("x", 1));
+    m.mut_by_name().insert("a".to_owned(), inner("a", 1));
+    m.mut_by_name().insert("b".to_owned(), inner("b", 2));
+    m.set_nested(inner("n", 1));
+    m.mut_unknown_fields().add_varint(100, 1);
+    m
+}
+
+fn src() -> Outer {
+    let mut m = Outer::new();
+    m.set_id(2);
+    m.set_data(b"data".to_vec());
+    m.mut_inner().set_value(5);
+    m.mut_inner().mut_tags().push("b".to_owned());
+    m.mut_numbers().extend(vec![2, 3]);
+    m.mut_items().push(inner("y", 2));
+    m.mut_by_name().insert("b".to_owned(), inner("c", 3));
+    m.mut_by_name().insert("d".to_owned(), inner("d", 4));
+    m.mut_nested().set_value(7);
+    m.mut_unknown_fields().add_varint(100, 2);
+    m.mut_unknown_fields().add_fixed32(101, 3);
+    m
+}
+
+fn expected_merge() -> Outer {
+    let mut m = dst();
+    m.set_id(2);
+    m.set_data(b"data".to_vec());
+    m.mut_inner().set_value(5);
+    m.mut_inner().mut_tags().push("b".to_owned());
+    m.mut_numbers().extend(vec![2, 3]);
+    m.mut_items().push(inner("y", 2));
+    m.mut_by_name().insert("b".to_owned(), inner("c", 3));
+    m.mut_by_name().insert("d".to_owned(), inner("d", 4));
+    m.mut_nested().set_value(7);
+    m.mut_unknown_fields().add_varint(100, 2);
+    m.mut_unknown_fields().add_fixed32(101, 3);
+    m
+}
+
+#[test]
+fn test_merge() {
+    let mut merged = dst();
+    merged.merge_from_message(&src());
+
+    assert_eq!(2, merged.get_id());
+    assert_eq!("dst", merged.get_name());
+    assert_eq!(b"data", merged.get_data());
+    assert_eq!(Kind::SMALL, merged.get_kind());
+    assert_eq!("a", merged.get_inner().get_name());
+    assert_eq!(5, merged.get_inner().get_value());
+    assert_eq!(&["a", "b"], merged.get_inner().get_tags());
+    assert_eq!(&[1, 2, 3], merged.get_numbers());
+    assert_eq!(2, merged.get_items().len());
+    assert_eq!(3, merged.get_by_name().len());
+    assert_eq!(&inner("c", 3), &merged.get_by_name()["b"]);
+    assert_eq!("n", merged.get_nested().get_name());
+    assert_eq!(7, merged.get_nested().get_value());
+    assert_eq!(
+        vec![1, 2],
+        merged.get_unknown_fields().get(100).unwrap().varint.clone()
+    );
+
+    assert_eq!(expected_merge(), merged);
+}
+
+#[test]
+fn test_merge_oneof() {
+    let mut merged = dst();
+    let mut text = Outer::new();
+    text.set_text("text".to_owned());
+    merged.merge_from_message(&text);
+    assert_eq!("text", merged.get_text());
+    assert!(!merged.has_nested());
+
+    let mut nested = Outer::new();
+    nested.set_nested(inner("n", 2));
+    merged.merge_from_message(&nested);
+    assert!(!merged.has_text());
+    assert_eq!(&inner("n", 2), merged.get_nested());
+}
+
+#[test]
+fn test_merge_empty() {
+    let mut merged = dst();
+    merged.merge_from_message(&Outer::new());
+    assert_eq!(dst(), merged);
+
+    let mut merged = Outer::new();
+    merged.merge_from_message(&src());
+    assert_eq!(src(), merged);
+}
+
+#[test]
+fn test_merge_unknown_fields_order() {
+    let mut src = Outer::new();
+    src.mut_unknown_fields().add_varint(101, 1);
+    src.mut_unknown_fields().add_varint(100, 2);
+    src.mut_unknown_fields().add_varint(101, 3);
+
+    let mut merged = Outer::new();
+    merged.merge_from_message(&src);
+    let numbers: Vec<u32> = merged
+        .get_unknown_fields()
+        .iter_values()
+        .map(|(number, _)| number)
+        .collect();
+    assert_eq!(vec![101, 100, 101], numbers);
+    assert_eq!(
+        src.write_to_bytes().unwrap(),
+        merged.write_to_bytes().unwrap()
+    );
+}
+
+#[test]
+#[should_panic]
+fn test_merge_different_types() {
+    let mut merged = dst();
+    let merged: &mut dyn MessageDyn = &mut merged;
+    merged.merge_from_message_dyn(&Inner::new());
+}
+
+#[test]
+fn test_dynamic() {
+    let descriptor = dynamic_message_descriptor(&file_descriptor(), "Outer");
+
+    let mut merged = to_dynamic(&dst(), &descriptor);
+    let merged: &mut dyn MessageDyn = &mut merged;
+    merged.merge_from_message_dyn(&to_dynamic(&src(), &descriptor));
+
+    assert_eq!(
+        expected_merge(),
+        Outer::parse_from_bytes(&merged.write_to_bytes_dyn().unwrap()).unwrap()
+    );
+}
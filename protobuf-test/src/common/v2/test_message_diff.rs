@@ -0,0 +1,171 @@
+use protobuf::reflect::diff_messages;
+use protobuf::reflect::DiffKind;
+use protobuf::reflect::DiffOptions;
+use protobuf::reflect::ReflectEqMode;
+use protobuf::Message;
+
+use super::test_message_diff_pb::*;
+
+fn item(id: &str, count: i32) -> Item {
+    let mut item = Item::new();
+    item.set_id(id.to_owned());
+    item.set_count(count);
+    item
+}
+
+fn diff(left: &Order, right: &Order) -> String {
+    diff_messages(left, right, &DiffOptions::default()).print_to_string()
+}
+
+#[test]
+fn test_equal() {
+    let mut order = Order::new();
+    order.set_name("order".to_owned());
+    order.mut_items().push(item("a", 1));
+    assert!(diff_messages(&order, &order.clone(), &DiffOptions::default()).is_empty());
+}
+
+#[test]
+fn test_singular() {
+    let mut left = Order::new();
+    left.set_name("a".to_owned());
+    left.set_color(Color::RED);
+
+    let mut right = Order::new();
+    right.set_color(Color::GREEN);
+    right.set_price(1.5);
+
+    let d = diff_messages(&left, &right, &DiffOptions::default());
+    let kinds: Vec<DiffKind> = d.diffs.iter().map(|d| d.kind).collect();
+    assert_eq!(
+        vec![DiffKind::Removed, DiffKind::Changed, DiffKind::Added],
+        kinds
+    );
+    assert_eq!(
+        "removed: name: \"a\"\n\
+         changed: color: RED -> GREEN\n\
+         added: price: 1.5\n",
+        format!("{}", d)
+    );
+}
+
+#[test]
+fn test_repeated() {
+    let mut left = Order::new();
+    left.mut_items().push(item("a", 1));
+    left.mut_items().push(item("b", 2));
+
+    let mut right = Order::new();
+    right.mut_items().push(item("b", 2));
+
+    assert_eq!(
+        "changed: items[0].id: \"a\" -> \"b\"\n\
+         changed: items[0].count: 1 -> 2\n\
+         removed: items[1]: { id: \"b\" count: 2 }\n",
+        diff(&left, &right)
+    );
+
+    let mut options = DiffOptions::default();
+    options.compare_repeated_by_key(
+        &Order::descriptor_static()
+            .get_field_by_name("items")
+            .unwrap(),
+        &Item::descriptor_static().get_field_by_name("id").unwrap(),
+    );
+    assert_eq!(
+        "removed: items[id=\"a\"]: { id: \"a\" count: 1 }\n",
+        diff_messages(&left, &right, &options).print_to_string()
+    );
+}
+
+#[test]
+fn test_map() {
+    let mut left = Order::new();
+    left.mut_by_id().insert("a".to_owned(), item("a", 1));
+    left.mut_by_id().insert("b".to_owned(), item("b", 2));
+    left.mut_labels().insert(1, "x".to_owned());
+    left.mut_labels().insert(10, "y".to_owned());
+
+    let mut right = Order::new();
+    right.mut_by_id().insert("b".to_owned(), item("b", 3));
+    right.mut_by_id().insert("c".to_owned(), item("c", 4));
+    right.mut_labels().insert(2, "x".to_owned());
+    right.mut_labels().insert(10, "z".to_owned());
+
+    assert_eq!(
+        "removed: by_id[\"a\"]: { id: \"a\" count: 1 }\n\
+         changed: by_id[\"b\"].count: 2 -> 3\n\
+         added: by_id[\"c\"]: { id: \"c\" count: 4 }\n\
+         removed: labels[1]: \"x\"\n\
+         added: labels[2]: \"x\"\n\
+         changed: labels[10]: \"y\" -> \"z\"\n",
+        diff(&left, &right)
+    );
+}
+
+#[test]
+fn test_nan() {
+    let mut order = Order::new();
+    order.set_price(f64::NAN);
+
+    assert_eq!("changed: price: NaN -> NaN\n", diff(&order, &order.clone()));
+
+    let options = DiffOptions {
+        eq_mode: ReflectEqMode::nan_equal(),
+        ..Default::default()
+    };
+    assert!(diff_messages(&order, &order.clone(), &options).is_empty());
+}
+
+#[test]
+fn test_unknown_fields() {
+    let mut left = Order::new();
+    left.mut_unknown_fields().add_varint(100, 1);
+    left.mut_items().push(item("a", 1));
+    left.mut_items()[0].mut_unknown_fields().add_fixed32(50, 7);
+
+    let mut right = Order::new();
+    right.mut_unknown_fields().add_varint(100, 2);
+    right
+        .mut_unknown_fields()
+        .add_length_delimited(101, b"x".to_vec());
+    right.mut_items().push(item("a", 1));
+
+    assert_eq!(
+        "removed: items[0].50: 7\n\
+         changed: 100: 1 -> 2\n\
+         added: 101: \"x\"\n",
+        diff(&left, &right)
+    );
+
+    let mut options = DiffOptions::default();
+    options.eq_mode.ignore_unknown_fields = true;
+    assert!(diff_messages(&left, &right, &options).is_empty());
+}
+
+#[test]
+fn test_eq_mode() {
+    let mut left = Order::new();
+    left.set_name("".to_owned());
+    left.mut_items().push(item("a", 1));
+    left.mut_items().push(item("b", 2));
+
+    let mut right = Order::new();
+    right.set_color(Color::UNKNOWN);
+    right.mut_items().push(item("b", 3));
+    right.mut_items().push(item("a", 4));
+    right.mut_items().push(item("c", 5));
+
+    let mut options = DiffOptions::default();
+    options.eq_mode.ignore_fields.push("items.count".to_owned());
+    options.eq_mode.default_equals_unset = true;
+    options.eq_mode.unordered_repeated = true;
+    assert_eq!(
+        "added: items[2]: { id: \"c\" count: 5 }\n",
+        diff_messages(&left, &right, &options).print_to_string()
+    );
+
+    right.mut_items().pop();
+    assert!(diff_messages(&left, &right, &options).is_empty());
+    assert!(left.reflect_eq(&right, &options.eq_mode));
+}
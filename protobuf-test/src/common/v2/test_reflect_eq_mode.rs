@@ -0,0 +1,138 @@
+use protobuf::reflect::ReflectEqMode;
+use protobuf::Message;
+
+use super::test_reflect_eq_mode_pb::*;
+
+fn point(id: &str, x: f64, y: f32) -> Point {
+    let mut point = Point::new();
+    point.set_id(id.to_owned());
+    point.set_x(x);
+    point.set_y(y);
+    point
+}
+
+fn shape() -> Shape {
+    let mut shape = Shape::new();
+    shape.set_name("shape".to_owned());
+    shape.set_version(3);
+    shape.set_center(point("c", 0.5, 0.5));
+    shape.mut_points().push(point("a", 1.0, 2.0));
+    shape.mut_points().push(point("b", 3.0, 4.0));
+    shape.mut_tags().extend(vec![1, 2, 2, 3]);
+    shape
+        .mut_named()
+        .insert("a".to_owned(), point("a", 1.0, 2.0));
+    shape
+}
+
+#[test]
+fn test_default_mode() {
+    let a = shape();
+    let mut b = shape();
+    assert!(a.reflect_eq(&b, &ReflectEqMode::default()));
+
+    b.mut_points()[1].set_x(3.5);
+    assert!(!a.reflect_eq(&b, &ReflectEqMode::default()));
+    assert_eq!(a == b, a.reflect_eq(&b, &ReflectEqMode::default()));
+}
+
+#[test]
+fn test_ignore_fields() {
+    let a = shape();
+    let mut b = shape();
+    b.set_version(4);
+    b.mut_center().set_id("other".to_owned());
+    b.mut_points()[0].set_id("other".to_owned());
+    b.mut_named()
+        .get_mut("a")
+        .unwrap()
+        .set_id("other".to_owned());
+
+    let mut mode = ReflectEqMode::default();
+    mode.ignore_fields.push("version".to_owned());
+    mode.ignore_fields.push("center.id".to_owned());
+    assert!(!a.reflect_eq(&b, &mode));
+
+    mode.ignore_fields.push("points.id".to_owned());
+    mode.ignore_fields.push("named.id".to_owned());
+    assert!(a.reflect_eq(&b, &mode));
+
+    // paths are relative to the compared message
+    mode.ignore_fields = vec!["id".to_owned()];
+    assert!(a.get_center().reflect_eq(b.get_center(), &mode));
+    assert!(!a.reflect_eq(&b, &mode));
+}
+
+#[test]
+fn test_float_tolerance() {
+    let a = shape();
+    let mut b = shape();
+    b.mut_center().set_x(0.5 + 1e-7);
+    b.mut_points()[0].set_y(2.0 + 1e-5);
+    assert!(!a.reflect_eq(&b, &ReflectEqMode::default()));
+
+    let mut mode = ReflectEqMode::default();
+    mode.float_absolute_tolerance = 1e-4;
+    assert!(a.reflect_eq(&b, &mode));
+
+    let mut mode = ReflectEqMode::default();
+    mode.float_relative_tolerance = 1e-6;
+    assert!(!a.reflect_eq(&b, &mode));
+    mode.float_relative_tolerance = 1e-4;
+    assert!(a.reflect_eq(&b, &mode));
+
+    b.mut_center().set_x(f64::NAN);
+    assert!(!a.reflect_eq(&b, &mode));
+}
+
+#[test]
+fn test_unordered_repeated() {
+    let a = shape();
+    let mut b = shape();
+    b.mut_points().reverse();
+    b.mut_tags().clear();
+    b.mut_tags().extend(vec![2, 3, 2, 1]);
+    assert!(!a.reflect_eq(&b, &ReflectEqMode::default()));
+
+    let mut mode = ReflectEqMode::default();
+    mode.unordered_repeated = true;
+    assert!(a.reflect_eq(&b, &mode));
+
+    // multiset, not set
+    b.mut_tags().clear();
+    b.mut_tags().extend(vec![1, 2, 3, 3]);
+    assert!(!a.reflect_eq(&b, &mode));
+}
+
+#[test]
+fn test_default_equals_unset() {
+    let mut a = shape();
+    a.clear_version();
+    a.clear_center();
+    let mut b = shape();
+    b.set_version(0);
+    b.set_center(Point::new());
+
+    let mut mode = ReflectEqMode::default();
+    mode.default_equals_unset = true;
+    assert!(a.reflect_eq(&b, &mode));
+
+    b.mut_center().set_id(String::new());
+    assert!(a.reflect_eq(&b, &mode));
+
+    b.set_version(1);
+    assert!(!a.reflect_eq(&b, &mode));
+}
+
+#[test]
+fn test_unknown_fields() {
+    let a = shape();
+    let mut b = shape();
+    b.mut_center().mut_unknown_fields().add_varint(100, 1);
+    assert!(a != b);
+    assert!(!a.reflect_eq(&b, &ReflectEqMode::default()));
+
+    let mut mode = ReflectEqMode::default();
+    mode.ignore_unknown_fields = true;
+    assert!(a.reflect_eq(&b, &mode));
+}
@@ -0,0 +1,154 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashSet;
+use std::hash::Hasher;
+
+use protobuf::reflect::ReflectEqMode;
+use protobuf::reflect::ReflectHashKey;
+use protobuf::Message;
+use protobuf::MessageDyn;
+use protobuf_test_common::dynamic_message_descriptor;
+use protobuf_test_common::to_dynamic;
+
+use super::test_reflect_hash_pb::*;
+
+fn entry(key: &str, weight: f64) -> Entry {
+    let mut entry = Entry::new();
+    entry.set_key(key.to_owned());
+    entry.set_weight(weight);
+    entry
+}
+
+fn record() -> Record {
+    let mut r = Record::new();
+    r.set_name("record".to_owned());
+    r.set_version(1);
+    r.set_score(0.5);
+    r.set_color(Color::RED);
+    r.set_entry(entry("e", 1.0));
+    r.mut_entries().push(entry("a", 1.0));
+    r.mut_entries().push(entry("b", 2.0));
+    for i in 0..20 {
+        r.mut_counts().insert(format!("k{}", i), i);
+        r.mut_by_id().insert(i, entry(&format!("k{}", i), i as f64));
+    }
+    r
+}
+
+fn hash(m: &dyn MessageDyn, mode: &ReflectEqMode) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    m.reflect_hash_dyn(&mut hasher, mode);
+    hasher.finish()
+}
+
+/// Check that `reflect_eq` and `reflect_hash` agree.
+fn assert_eq_and_hash(a: &Record, b: &Record, mode: &ReflectEqMode) {
+    assert!(a.reflect_eq(b, mode));
+    assert_eq!(hash(a, mode), hash(b, mode));
+}
+
+#[test]
+fn test_equal() {
+    let mode = ReflectEqMode::default();
+    assert_eq_and_hash(&record(), &record(), &mode);
+    assert_eq_and_hash(&Record::new(), &Record::new(), &mode);
+
+    let mut changed = record();
+    changed.mut_by_id().get_mut(&3).unwrap().set_weight(4.0);
+    assert_ne!(hash(&record(), &mode), hash(&changed, &mode));
+}
+
+#[test]
+fn test_map_order() {
+    let mut a = Record::new();
+    let mut b = Record::new();
+    for i in 0..100 {
+        a.mut_counts().insert(format!("k{}", i), i);
+        b.mut_counts().insert(format!("k{}", 99 - i), 99 - i);
+    }
+    assert_eq_and_hash(&a, &b, &ReflectEqMode::default());
+}
+
+#[test]
+fn test_floats() {
+    let mut a = record();
+    a.mut_entry().set_weight(f64::NAN);
+    let b = a.clone();
+    assert!(!a.reflect_eq(&b, &ReflectEqMode::default()));
+    assert_eq_and_hash(&a, &b, &ReflectEqMode::nan_equal());
+
+    let mut b = a.clone();
+    b.set_score(0.51);
+    let mut mode = ReflectEqMode::nan_equal();
+    mode.float_absolute_tolerance = 0.1;
+    assert_eq_and_hash(&a, &b, &mode);
+}
+
+#[test]
+fn test_mode_options() {
+    let a = record();
+
+    let mut b = record();
+    b.mut_entries().reverse();
+    let mut mode = ReflectEqMode::default();
+    mode.unordered_repeated = true;
+    assert_eq_and_hash(&a, &b, &mode);
+
+    let mut b = record();
+    b.set_version(2);
+    b.mut_entries()[1].set_key("c".to_owned());
+    let mut mode = ReflectEqMode::default();
+    mode.ignore_fields.push("version".to_owned());
+    mode.ignore_fields.push("entries.key".to_owned());
+    assert_eq_and_hash(&a, &b, &mode);
+
+    let mut a = Record::new();
+    a.set_name(String::new());
+    a.set_entry(Entry::new());
+    let mut mode = ReflectEqMode::default();
+    mode.default_equals_unset = true;
+    assert_eq_and_hash(&a, &Record::new(), &mode);
+}
+
+#[test]
+fn test_unknown_fields() {
+    let a = record();
+    let mut b = record();
+    b.mut_entry().mut_unknown_fields().add_varint(100, 1);
+    let mode = ReflectEqMode::default();
+    assert!(!a.reflect_eq(&b, &mode));
+    assert_ne!(hash(&a, &mode), hash(&b, &mode));
+
+    let mut mode = ReflectEqMode::default();
+    mode.ignore_unknown_fields = true;
+    assert_eq_and_hash(&a, &b, &mode);
+}
+
+#[test]
+fn test_hash_key() {
+    let mut set = HashSet::new();
+    assert!(set.insert(ReflectHashKey(record())));
+    assert!(!set.insert(ReflectHashKey(record())));
+
+    let mut nan = record();
+    nan.set_score(f32::NAN);
+    assert!(set.insert(ReflectHashKey(nan.clone())));
+    assert!(!set.insert(ReflectHashKey(nan)));
+    assert_eq!(2, set.len());
+}
+
+#[test]
+fn test_dynamic() {
+    let descriptor = dynamic_message_descriptor(&file_descriptor(), "Record");
+
+    let mode = ReflectEqMode::default();
+    assert_eq!(
+        hash(&record(), &mode),
+        hash(&to_dynamic(&record(), &descriptor), &mode)
+    );
+
+    let mut set = HashSet::new();
+    let mut m = to_dynamic(&Record::new(), &descriptor);
+    m.set_field_by_name("name", "x".to_owned().into());
+    assert!(set.insert(ReflectHashKey(m.clone())));
+    assert!(!set.insert(ReflectHashKey(m)));
+}
@@ -1,3 +1,6 @@
+use protobuf::Message;
+
+use super::test_basic_pb;
 use super::test_service_pb::*;
 
 #[test]
@@ -8,3 +11,60 @@ fn test_service() {
     let _ = Request::new();
     let _ = Response::new();
 }
+
+#[test]
+fn test_service_descriptor() {
+    let services = file_descriptor().services();
+    assert_eq!(1, services.len());
+    let service = &services[0];
+    assert_eq!("Service", service.get_name());
+    assert_eq!("service.Service", service.full_name());
+    assert_eq!(
+        Some(service.clone()),
+        file_descriptor().service_by_package_relative_name("Service")
+    );
+
+    let names: Vec<_> = service
+        .methods()
+        .iter()
+        .map(|m| m.get_name().to_owned())
+        .collect();
+    assert_eq!(
+        vec![
+            "ShortForm",
+            "LongForm",
+            "DottyShortForm",
+            "DottyLongForm",
+            "ClientStreaming",
+            "ServerStreaming",
+        ],
+        names
+    );
+
+    let short_form = service.method_by_name("ShortForm").unwrap();
+    assert_eq!(Request::descriptor_static(), short_form.input_type());
+    assert_eq!(Response::descriptor_static(), short_form.output_type());
+    assert!(!short_form.is_client_streaming());
+    assert!(!short_form.is_server_streaming());
+    assert_eq!(service, short_form.service_descriptor());
+
+    let dotty = service.method_by_name("DottyShortForm").unwrap();
+    assert_eq!(
+        test_basic_pb::Test1::descriptor_static(),
+        dotty.input_type()
+    );
+    assert_eq!(
+        test_basic_pb::Test2::descriptor_static(),
+        dotty.output_type()
+    );
+
+    let client_streaming = service.method_by_name("ClientStreaming").unwrap();
+    assert!(client_streaming.is_client_streaming());
+    assert!(!client_streaming.is_server_streaming());
+
+    let server_streaming = service.method_by_name("ServerStreaming").unwrap();
+    assert!(!server_streaming.is_client_streaming());
+    assert!(server_streaming.is_server_streaming());
+
+    assert!(service.method_by_name("Nonexistent").is_none());
+}
@@ -0,0 +1,78 @@
+use protobuf::reflect::DynamicMessage;
+use protobuf::Message;
+use protobuf::MessageDyn;
+use protobuf::UnknownValueRef;
+use protobuf_test_common::dynamic_message_descriptor;
+use protobuf_test_common::hex::decode_hex;
+use protobuf_test_common::hex::encode_hex;
+
+use super::test_unknown_fields_order_pb::*;
+
+// known field 1, then unknown fields 5, 3, 5, 3, 4, 5 with different wire types
+const INTERLEAVED: &str =
+    "08 07 28 01 1d 01 00 00 00 2a 01 61 18 02 21 01 00 00 00 00 00 00 00 28 03";
+
+fn check_round_trip(m: &mut dyn MessageDyn) {
+    m.merge_from_bytes_dyn(&decode_hex(INTERLEAVED)).unwrap();
+
+    let unknown_fields = m.get_unknown_fields_dyn();
+    let numbers: Vec<u32> = unknown_fields.iter().map(|(n, _)| n).collect();
+    assert_eq!(vec![5, 3, 4], numbers);
+    let numbers: Vec<u32> = unknown_fields.iter_values().map(|(n, _)| n).collect();
+    assert_eq!(vec![5, 3, 5, 3, 4, 5], numbers);
+    assert_eq!(vec![1, 3], unknown_fields.get(5).unwrap().varint);
+    match unknown_fields.iter_values().nth(2).unwrap().1 {
+        UnknownValueRef::LengthDelimited(v) => assert_eq!(b"a", v),
+        _ => panic!("expecting length-delimited"),
+    }
+
+    assert_eq!(INTERLEAVED, encode_hex(&m.write_to_bytes_dyn().unwrap()));
+}
+
+#[test]
+fn test_generated() {
+    check_round_trip(&mut MessageWithOneField::new());
+}
+
+#[test]
+fn test_dynamic() {
+    let descriptor = dynamic_message_descriptor(&file_descriptor(), "MessageWithOneField");
+    check_round_trip(&mut DynamicMessage::new(descriptor));
+}
+
+// unknown 5, known 1, unknown 3, nested 2 { unknown 3, known 1, unknown 4 }, unknown 6
+const INTERLEAVED_WITH_KNOWN: &str = "28 01 08 07 18 02 12 06 18 03 08 08 20 04 30 05";
+
+fn check_preserving_order(m: &mut dyn MessageDyn) {
+    m.merge_from_bytes_dyn(&decode_hex(INTERLEAVED_WITH_KNOWN))
+        .unwrap();
+    assert_eq!(
+        INTERLEAVED_WITH_KNOWN,
+        encode_hex(&m.write_to_bytes_preserving_order_dyn().unwrap())
+    );
+}
+
+#[test]
+fn test_interleaved_with_known() {
+    check_preserving_order(&mut MessageWithNested::new());
+    let descriptor = dynamic_message_descriptor(&file_descriptor(), "MessageWithNested");
+    check_preserving_order(&mut DynamicMessage::new(descriptor));
+}
+
+#[test]
+fn test_added_unknown_written_last() {
+    let mut m = MessageWithOneField::parse_from_bytes(&decode_hex("18 02 08 07")).unwrap();
+    m.mut_unknown_fields().add_varint(4, 3);
+    assert_eq!(
+        "18 02 08 07 20 03",
+        encode_hex(&m.write_to_bytes_preserving_order().unwrap())
+    );
+}
+
+#[test]
+fn test_eq_ignores_order() {
+    let a = MessageWithOneField::parse_from_bytes(&decode_hex("28 01 18 02")).unwrap();
+    let b = MessageWithOneField::parse_from_bytes(&decode_hex("18 02 28 01")).unwrap();
+    assert_eq!(a, b);
+    assert_ne!(a.write_to_bytes().unwrap(), b.write_to_bytes().unwrap());
+}
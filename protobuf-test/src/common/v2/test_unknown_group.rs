@@ -0,0 +1,51 @@
+use protobuf::reflect::DynamicMessage;
+use protobuf::Message;
+use protobuf::MessageDyn;
+use protobuf::UnknownValueRef;
+use protobuf_test_common::dynamic_message_descriptor;
+use protobuf_test_common::hex::decode_hex;
+use protobuf_test_common::hex::encode_hex;
+
+use super::test_unknown_group_pb::*;
+
+// field 1 = 1, group 2 { 1: 5, group 3 { 1: 1 } }
+// group content is not canonical: tag of `1: 5` is encoded with two bytes
+const WITH_GROUP: &str = "08 01 13 88 00 05 1b 08 01 1c 14";
+const GROUP_CONTENT: &str = "88 00 05 1b 08 01 1c";
+
+fn check_round_trip(m: &mut dyn MessageDyn) {
+    m.merge_from_bytes_dyn(&decode_hex(WITH_GROUP)).unwrap();
+
+    let values: Vec<_> = m.get_unknown_fields_dyn().get(2).unwrap().iter().collect();
+    assert_eq!(1, values.len());
+    match values[0] {
+        UnknownValueRef::Group(content) => assert_eq!(GROUP_CONTENT, encode_hex(content)),
+        _ => panic!("expecting group"),
+    }
+
+    assert_eq!(WITH_GROUP, encode_hex(&m.write_to_bytes_dyn().unwrap()));
+}
+
+#[test]
+fn test_generated() {
+    check_round_trip(&mut UnknownGroupContainer::new());
+}
+
+#[test]
+fn test_dynamic() {
+    let descriptor = dynamic_message_descriptor(&file_descriptor(), "UnknownGroupContainer");
+    check_round_trip(&mut DynamicMessage::new(descriptor));
+}
+
+#[test]
+fn test_end_group_mismatch() {
+    // group 2 closed with end group 3
+    let bytes = decode_hex("13 08 05 1c");
+    assert!(UnknownGroupContainer::parse_from_bytes(&bytes).is_err());
+}
+
+#[test]
+fn test_unterminated_group() {
+    let bytes = decode_hex("13 08 05");
+    assert!(UnknownGroupContainer::parse_from_bytes(&bytes).is_err());
+}
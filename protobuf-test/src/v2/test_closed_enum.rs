@@ -0,0 +1,143 @@
+use protobuf::descriptor::field_descriptor_proto;
+use protobuf::descriptor::FieldDescriptorProto;
+use protobuf::reflect::DynamicMessage;
+use protobuf::reflect::ReflectValueRef;
+use protobuf::Message;
+use protobuf::MessageDyn;
+
+use protobuf_test_common::dynamic_message_descriptor;
+use protobuf_test_common::hex::decode_hex;
+use protobuf_test_common::hex::encode_hex;
+
+use super::test_closed_enum_pb::*;
+
+fn unknown_varints(m: &dyn MessageDyn, number: u32) -> Vec<u64> {
+    m.get_unknown_fields_dyn()
+        .get(number)
+        .map(|v| v.varint.clone())
+        .unwrap_or_default()
+}
+
+#[test]
+fn test_unknown_singular() {
+    let m = WithClosedEnum::parse_from_bytes(&decode_hex("08 05")).unwrap();
+    assert!(!m.has_color());
+    assert_eq!(Color::RED, m.get_color());
+    assert_eq!(vec![5], unknown_varints(&m, 1));
+    assert_eq!("08 05", encode_hex(&m.write_to_bytes().unwrap()));
+
+    let m = WithClosedEnum::parse_from_bytes(&decode_hex("08 02 08 05")).unwrap();
+    assert_eq!(Color::GREEN, m.get_color());
+    assert_eq!(vec![5], unknown_varints(&m, 1));
+}
+
+#[test]
+fn test_unknown_repeated() {
+    let m = WithClosedEnum::parse_from_bytes(&decode_hex("10 01 10 05 10 02")).unwrap();
+    assert_eq!(vec![Color::RED, Color::GREEN], m.colors);
+    assert_eq!(vec![5], unknown_varints(&m, 2));
+    assert_eq!(
+        "10 01 10 02 10 05",
+        encode_hex(&m.write_to_bytes().unwrap())
+    );
+}
+
+#[test]
+fn test_unknown_packed() {
+    let m = WithClosedEnum::parse_from_bytes(&decode_hex("1a 03 01 05 02")).unwrap();
+    assert_eq!(vec![Color::RED, Color::GREEN], m.packed_colors);
+    assert_eq!(vec![5], unknown_varints(&m, 3));
+    assert_eq!(
+        "1a 02 01 02 18 05",
+        encode_hex(&m.write_to_bytes().unwrap())
+    );
+}
+
+#[test]
+fn test_unknown_oneof() {
+    let m = WithClosedEnum::parse_from_bytes(&decode_hex("2a 01 61 20 07")).unwrap();
+    assert_eq!("a", m.get_one_string());
+    assert_eq!(vec![7], unknown_varints(&m, 4));
+    assert_eq!("2a 01 61 20 07", encode_hex(&m.write_to_bytes().unwrap()));
+}
+
+fn unknown_length_delimited(m: &dyn MessageDyn, number: u32) -> Vec<String> {
+    m.get_unknown_fields_dyn()
+        .get(number)
+        .map(|v| v.length_delimited.iter().map(|b| encode_hex(b)).collect())
+        .unwrap_or_default()
+}
+
+#[test]
+fn test_unknown_map_value() {
+    let m = WithClosedEnum::parse_from_bytes(&decode_hex("32 04 08 01 10 05 32 04 08 02 10 02"))
+        .unwrap();
+    assert_eq!(1, m.color_map.len());
+    assert_eq!(Some(Color::GREEN), m.color_map[&2].enum_value().ok());
+    assert_eq!(vec!["08 01 10 05"], unknown_length_delimited(&m, 6));
+    assert_eq!(
+        "32 04 08 02 10 02 32 04 08 01 10 05",
+        encode_hex(&m.write_to_bytes().unwrap())
+    );
+}
+
+#[test]
+fn test_unknown_map_value_entry_reencoded() {
+    // Only the rejected entry is stored, without its unknown field 3
+    let m = WithClosedEnum::parse_from_bytes(&decode_hex("32 06 08 01 10 05 18 07")).unwrap();
+    assert_eq!(0, m.color_map.len());
+    assert_eq!(vec!["08 01 10 05"], unknown_length_delimited(&m, 6));
+}
+
+#[test]
+fn test_map_entry_error_location() {
+    // Key of the second entry is encoded as fixed32
+    let e = WithClosedEnum::parse_from_bytes(&decode_hex("32 04 08 01 10 02 32 05 0d 01 00 00 00"))
+        .unwrap_err();
+    let location = e.decode_error_location().expect("location");
+    assert_eq!(9, location.offset);
+    assert_eq!("color_map.key", location.path());
+}
+
+#[test]
+fn test_enum_or_unknown_storage() {
+    // `descriptor.proto` is generated with `closed_enums=false`,
+    // but unknown values of its proto2 enums still go to unknown fields
+    let f = FieldDescriptorProto::parse_from_bytes(&decode_hex("20 09 20 03")).unwrap();
+    assert_eq!(field_descriptor_proto::Label::LABEL_REPEATED, f.get_label());
+    assert_eq!(vec![9], unknown_varints(&f, 4));
+}
+
+#[test]
+fn test_dynamic_message() {
+    let bytes = decode_hex("08 05 10 01 10 06 1a 03 01 07 02 2a 01 61 20 08 32 04 08 01 10 09");
+
+    let descriptor = dynamic_message_descriptor(&file_descriptor(), "WithClosedEnum");
+    let mut m = DynamicMessage::new(descriptor);
+    m.merge_from_bytes(&bytes).unwrap();
+
+    let descriptor = m.descriptor_dyn();
+    let color = descriptor.get_field_by_name("color").unwrap();
+    assert_eq!(None, color.get_singular(&m));
+    let colors = descriptor.get_field_by_name("colors").unwrap();
+    assert_eq!(1, colors.get_repeated(&m).len());
+    let packed_colors = descriptor.get_field_by_name("packed_colors").unwrap();
+    assert_eq!(2, packed_colors.get_repeated(&m).len());
+    let color_map = descriptor.get_field_by_name("color_map").unwrap();
+    assert_eq!(0, color_map.get_map(&m).len());
+    let one_string = descriptor.get_field_by_name("one_string").unwrap();
+    assert_eq!(
+        Some(ReflectValueRef::String("a")),
+        one_string.get_singular(&m)
+    );
+
+    assert_eq!(vec![5], unknown_varints(&m, 1));
+    assert_eq!(vec![6], unknown_varints(&m, 2));
+    assert_eq!(vec![7], unknown_varints(&m, 3));
+    assert_eq!(vec![8], unknown_varints(&m, 4));
+    assert_eq!(vec!["08 01 10 09"], unknown_length_delimited(&m, 6));
+
+    let generated = WithClosedEnum::parse_from_bytes(&bytes).unwrap();
+    let reparsed = WithClosedEnum::parse_from_bytes(&m.write_to_bytes().unwrap()).unwrap();
+    assert_eq!(generated, reparsed);
+}
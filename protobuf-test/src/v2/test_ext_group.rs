@@ -0,0 +1,96 @@
+use protobuf::reflect::ExtensionDescriptor;
+use protobuf::reflect::ReflectValueBox;
+use protobuf::Message;
+
+use super::test_ext_group_pb::*;
+
+fn extension_by_name(name: &str) -> ExtensionDescriptor {
+    file_descriptor()
+        .extensions()
+        .into_iter()
+        .find(|e| e.get_name() == name)
+        .unwrap()
+}
+
+fn optional_group(a: i32) -> OptionalGroup {
+    let mut group = OptionalGroup::new();
+    group.set_a(a);
+    group
+}
+
+#[test]
+fn test_singular() {
+    let optional_group_ext = extension_by_name("optionalgroup");
+
+    let mut m = Extendable::new();
+    optional_group_ext
+        .set_singular(
+            &mut m,
+            ReflectValueBox::Message(Box::new(optional_group(5))),
+        )
+        .unwrap();
+    // start group 101, field 1 = 5, end group 101
+    let bytes = m.write_to_bytes().unwrap();
+    assert_eq!(&[0xab, 0x06, 0x08, 0x05, 0xac, 0x06][..], &bytes[..]);
+
+    let m = Extendable::parse_from_bytes(&bytes).unwrap();
+    match optional_group_ext.get_singular(&m).unwrap() {
+        Some(ReflectValueBox::Message(g)) => {
+            assert_eq!(
+                &optional_group(5),
+                g.downcast_ref::<OptionalGroup>().unwrap()
+            )
+        }
+        v => panic!("unexpected: {:?}", v),
+    }
+}
+
+#[test]
+fn test_singular_merged() {
+    // two occurrences of group 101: {a: 5} and {s: "x"}
+    let bytes = [
+        0xab, 0x06, 0x08, 0x05, 0xac, 0x06, 0xab, 0x06, 0x12, 0x01, b'x', 0xac, 0x06,
+    ];
+    let m = Extendable::parse_from_bytes(&bytes).unwrap();
+    let mut expected = optional_group(5);
+    expected.set_s("x".to_owned());
+    match extension_by_name("optionalgroup").get_singular(&m).unwrap() {
+        Some(ReflectValueBox::Message(g)) => {
+            assert_eq!(&expected, g.downcast_ref::<OptionalGroup>().unwrap())
+        }
+        v => panic!("unexpected: {:?}", v),
+    }
+}
+
+#[test]
+fn test_repeated() {
+    let repeated_group_ext = extension_by_name("repeatedgroup");
+
+    let mut m = Extendable::new();
+    for b in 1..=2 {
+        let mut group = RepeatedGroup::new();
+        group.set_b(b);
+        repeated_group_ext
+            .push_repeated(&mut m, ReflectValueBox::Message(Box::new(group)))
+            .unwrap();
+    }
+
+    let m = Extendable::parse_from_bytes(&m.write_to_bytes().unwrap()).unwrap();
+    let values = repeated_group_ext.get_repeated(&m).unwrap();
+    let values: Vec<i32> = values
+        .into_iter()
+        .map(|v| match v {
+            ReflectValueBox::Message(g) => g.downcast_ref::<RepeatedGroup>().unwrap().get_b(),
+            v => panic!("unexpected: {:?}", v),
+        })
+        .collect();
+    assert_eq!(vec![1, 2], values);
+}
+
+#[test]
+fn test_wrong_wire_type() {
+    // field 101 as length-delimited instead of group
+    let bytes = [0xaa, 0x06, 0x02, 0x08, 0x05];
+    let m = Extendable::parse_from_bytes(&bytes).unwrap();
+    assert!(extension_by_name("optionalgroup").get_singular(&m).is_err());
+}
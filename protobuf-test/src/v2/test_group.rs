@@ -0,0 +1,27 @@
+use protobuf::reflect::DynamicMessage;
+use protobuf::reflect::ReflectValueRef;
+use protobuf::text_format;
+use protobuf_test_common::dynamic_message_descriptor;
+
+use super::test_group_pb::*;
+
+#[test]
+fn test_text_format_group_by_type_name() {
+    let descriptor = dynamic_message_descriptor(&file_descriptor(), "MessageWithGroup");
+    let mut m = DynamicMessage::new(descriptor.clone());
+    text_format::merge_from_str(
+        &mut m,
+        "aaa: \"x\" Identifier { iii: 1 } Identifier < sss: \"s\" >",
+    )
+    .unwrap();
+
+    let field = descriptor.get_field_by_name("identifier").unwrap();
+    let groups = field.get_repeated(&m);
+    assert_eq!(2, groups.len());
+    let group = match groups.get(0) {
+        ReflectValueRef::Message(m) => m,
+        _ => panic!("expecting message"),
+    };
+    let iii = group.descriptor_dyn().get_field_by_name("iii").unwrap();
+    assert_eq!(Some(ReflectValueRef::I32(1)), iii.get_singular(&*group));
+}
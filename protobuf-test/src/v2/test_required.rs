@@ -1,5 +1,7 @@
 use super::test_required_pb::*;
+use protobuf::CodedOutputStream;
 use protobuf::Message;
+use protobuf::ProtobufError;
 
 #[test]
 fn test_write_missing_required() {
@@ -20,3 +22,92 @@ fn test_is_initialized_is_recursive() {
     m.inner.as_mut().unwrap().set_b(false);
     assert!(m.is_initialized());
 }
+
+#[test]
+fn test_text_format_allow_partial() {
+    assert!(protobuf::text_format::parse_from_str::<TestRequiredOuter>("inner {}").is_err());
+
+    let parse_options = protobuf::text_format::ParseOptions {
+        allow_partial: true,
+        ..Default::default()
+    };
+    let m: TestRequiredOuter =
+        protobuf::text_format::parse_from_str_with_options("inner {}", &parse_options).unwrap();
+    assert!(!m.is_initialized());
+}
+
+fn tree() -> TestRequiredTree {
+    let mut inner = TestRequired::new();
+    inner.set_b(true);
+    let mut complete = TestRequiredOuter::new();
+    complete.inner = Some(inner).into();
+
+    let mut m = TestRequiredTree::new();
+    m.outers.push(complete);
+    m.outers.push(TestRequiredOuter::new());
+    let mut outer = TestRequiredOuter::new();
+    outer.inner = Some(TestRequired::new()).into();
+    m.outers.push(outer);
+    m.by_name.insert("x".to_owned(), TestRequired::new());
+    m.single = Some(TestRequired::new()).into();
+    m
+}
+
+const TREE_MISSING: &[&str] = &[
+    "outers[1].inner",
+    "outers[2].inner.b",
+    "by_name[\"x\"].b",
+    "single.b",
+];
+
+#[test]
+fn test_find_missing_required_fields() {
+    assert_eq!(
+        TREE_MISSING,
+        &protobuf::reflect::find_missing_required_fields(&tree())[..]
+    );
+    assert!(protobuf::reflect::find_missing_required_fields(&TestRequiredTree::new()).is_empty());
+}
+
+#[test]
+fn test_check_initialized_reports_paths() {
+    match tree().check_initialized() {
+        Err(ProtobufError::MessageNotInitialized {
+            message,
+            missing_fields,
+        }) => {
+            assert_eq!("TestRequiredTree", message);
+            assert_eq!(TREE_MISSING, &missing_fields[..]);
+        }
+        r => panic!("{:?}", r),
+    }
+}
+
+#[test]
+fn test_parse_reports_nested_paths() {
+    let m = tree();
+    m.compute_size();
+    let mut bytes = Vec::new();
+    let mut os = CodedOutputStream::vec(&mut bytes);
+    m.write_to_with_cached_sizes(&mut os).unwrap();
+    os.flush().unwrap();
+    drop(os);
+    match TestRequiredTree::parse_from_bytes(&bytes) {
+        Err(ProtobufError::MessageNotInitialized { missing_fields, .. }) => {
+            assert_eq!(TREE_MISSING, &missing_fields[..]);
+        }
+        r => panic!("{:?}", r),
+    }
+}
+
+#[test]
+fn test_text_format_reports_paths() {
+    let e = protobuf::text_format::parse_from_str::<TestRequiredOuter>("inner {}").unwrap_err();
+    assert!(format!("{}", e).contains("inner.b"), "{}", e);
+}
+
+#[test]
+fn test_json_reports_paths() {
+    let e = protobuf::json::parse_from_str::<TestRequiredOuter>("{\"inner\": {}}").unwrap_err();
+    assert!(format!("{}", e).contains("inner.b"), "{}", e);
+}
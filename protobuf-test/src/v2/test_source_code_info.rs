@@ -0,0 +1,177 @@
+use protobuf::descriptor::source_code_info::Location;
+
+use super::test_source_code_info_pb::*;
+
+fn locations() -> Vec<Location> {
+    file_descriptor()
+        .proto()
+        .source_code_info
+        .get_or_default()
+        .location
+        .clone()
+}
+
+/// Paths and spans of locations with given path prefix,
+/// with line numbers relative to `syntax` statement.
+fn spans(prefix: &[i32]) -> Vec<(Vec<i32>, Vec<i32>)> {
+    let locations = locations();
+    // Copies of this file in other test crates have extra header lines
+    let l = locations.iter().find(|l| l.path == [12]).unwrap().span[0];
+    locations
+        .into_iter()
+        .filter(|loc| loc.path.starts_with(prefix))
+        .map(|loc| {
+            let mut span = loc.span;
+            span[0] -= l;
+            if span.len() == 4 {
+                span[2] -= l;
+            }
+            (loc.path, span)
+        })
+        .collect()
+}
+
+fn location(path: &[i32]) -> Location {
+    locations()
+        .into_iter()
+        .find(|l| l.path == path)
+        .expect("location not found")
+}
+
+#[test]
+fn test_file_option() {
+    assert_eq!(
+        vec![(vec![8], vec![7, 0, 46]), (vec![8, 1], vec![7, 0, 46])],
+        spans(&[8])
+    );
+    let option = location(&[8, 1]);
+    assert_eq!(" Comment of file option.\n", option.get_leading_comments());
+    assert_eq!(
+        " Trailing comment of file option.\n",
+        option.get_trailing_comments()
+    );
+    assert!(!location(&[8]).has_leading_comments());
+}
+
+#[test]
+fn test_extendee() {
+    assert_eq!(
+        vec![
+            (vec![7, 0], vec![15, 4, 32]),
+            (vec![7, 0, 2], vec![14, 7, 35]),
+            (vec![7, 0, 4], vec![15, 4, 12]),
+            (vec![7, 0, 5], vec![15, 13, 18]),
+            (vec![7, 0, 1], vec![15, 19, 23]),
+            (vec![7, 0, 3], vec![15, 26, 31]),
+        ],
+        spans(&[7, 0])
+    );
+}
+
+#[test]
+fn test_message_option_and_ranges() {
+    assert_eq!(
+        vec![
+            (vec![4, 1, 7], vec![20, 4, 29]),
+            (vec![4, 1, 7, 3], vec![20, 4, 29]),
+        ],
+        spans(&[4, 1, 7])
+    );
+    assert_eq!(
+        vec![
+            (vec![4, 1, 9], vec![22, 4, 28]),
+            (vec![4, 1, 9, 0], vec![22, 13, 14]),
+            (vec![4, 1, 9, 0, 1], vec![22, 13, 14]),
+            (vec![4, 1, 9, 0, 2], vec![22, 13, 14]),
+            (vec![4, 1, 9, 1], vec![22, 16, 18]),
+            (vec![4, 1, 9, 1, 1], vec![22, 16, 18]),
+            (vec![4, 1, 9, 1, 2], vec![22, 16, 18]),
+            (vec![4, 1, 9, 2], vec![22, 20, 27]),
+            (vec![4, 1, 9, 2, 1], vec![22, 20, 21]),
+            (vec![4, 1, 9, 2, 2], vec![22, 25, 27]),
+        ],
+        spans(&[4, 1, 9])
+    );
+    assert_eq!(
+        " Comment of reserved.\n",
+        location(&[4, 1, 9]).get_leading_comments()
+    );
+    assert_eq!(
+        vec![
+            (vec![4, 1, 10], vec![23, 4, 26]),
+            (vec![4, 1, 10, 0], vec![23, 13, 18]),
+            (vec![4, 1, 10, 1], vec![23, 20, 25]),
+        ],
+        spans(&[4, 1, 10])
+    );
+    assert_eq!(
+        vec![
+            (vec![4, 1, 5], vec![24, 4, 38]),
+            (vec![4, 1, 5, 0], vec![24, 15, 25]),
+            (vec![4, 1, 5, 0, 1], vec![24, 15, 18]),
+            (vec![4, 1, 5, 0, 2], vec![24, 22, 25]),
+            (vec![4, 1, 5, 1], vec![24, 27, 37]),
+            (vec![4, 1, 5, 1, 1], vec![24, 27, 30]),
+            (vec![4, 1, 5, 1, 2], vec![24, 34, 37]),
+            (vec![4, 1, 5], vec![25, 4, 20]),
+            (vec![4, 1, 5, 2], vec![25, 15, 19]),
+            (vec![4, 1, 5, 2, 1], vec![25, 15, 19]),
+            (vec![4, 1, 5, 2, 2], vec![25, 15, 19]),
+        ],
+        spans(&[4, 1, 5])
+    );
+}
+
+#[test]
+fn test_field_options() {
+    assert_eq!(
+        vec![
+            (vec![4, 1, 2, 0], vec![27, 4, 87]),
+            (vec![4, 1, 2, 0, 4], vec![27, 4, 12]),
+            (vec![4, 1, 2, 0, 5], vec![27, 13, 18]),
+            (vec![4, 1, 2, 0, 1], vec![27, 19, 20]),
+            (vec![4, 1, 2, 0, 3], vec![27, 23, 24]),
+            (vec![4, 1, 2, 0, 8], vec![27, 25, 86]),
+            (vec![4, 1, 2, 0, 8, 3], vec![27, 26, 43]),
+            (vec![4, 1, 2, 0, 8, 50001, 0], vec![27, 45, 55]),
+            (vec![4, 1, 2, 0, 10], vec![27, 57, 73]),
+            (vec![4, 1, 2, 0, 10], vec![27, 69, 73]),
+            (vec![4, 1, 2, 0, 8, 50001, 1], vec![27, 75, 85]),
+        ],
+        spans(&[4, 1, 2, 0])
+    );
+    assert_eq!(
+        vec![
+            (vec![4, 1, 2, 1], vec![28, 4, 57]),
+            (vec![4, 1, 2, 1, 4], vec![28, 4, 12]),
+            (vec![4, 1, 2, 1, 5], vec![28, 13, 18]),
+            (vec![4, 1, 2, 1, 1], vec![28, 19, 20]),
+            (vec![4, 1, 2, 1, 3], vec![28, 23, 24]),
+            (vec![4, 1, 2, 1, 8], vec![28, 25, 56]),
+            (vec![4, 1, 2, 1, 8, 50002, 2], vec![28, 26, 42]),
+            (vec![4, 1, 2, 1, 7], vec![28, 54, 55]),
+        ],
+        spans(&[4, 1, 2, 1])
+    );
+}
+
+#[test]
+fn test_enum_options() {
+    assert_eq!(
+        vec![
+            (vec![5, 0, 3], vec![32, 4, 30]),
+            (vec![5, 0, 3, 2], vec![32, 4, 30]),
+        ],
+        spans(&[5, 0, 3])
+    );
+    assert_eq!(
+        vec![
+            (vec![5, 0, 2, 0], vec![33, 4, 34]),
+            (vec![5, 0, 2, 0, 1], vec![33, 4, 9]),
+            (vec![5, 0, 2, 0, 2], vec![33, 12, 13]),
+            (vec![5, 0, 2, 0, 3], vec![33, 14, 33]),
+            (vec![5, 0, 2, 0, 3, 1], vec![33, 15, 32]),
+        ],
+        spans(&[5, 0, 2, 0])
+    );
+}
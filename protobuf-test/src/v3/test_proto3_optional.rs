@@ -0,0 +1,99 @@
+use protobuf::json;
+use protobuf::text_format;
+use protobuf::Message;
+use protobuf_test_common::*;
+
+use super::test_proto3_optional_pb::*;
+
+#[test]
+fn test_serialize_zero() {
+    let mut m = TestProto3Optional::new();
+    test_serialize_deserialize("", &m);
+
+    m.iii = Some(0);
+    m.sss = Some(String::new());
+    m.eee = Some(Proto3OptionalEnum::ZERO.into());
+    test_serialize_deserialize("08 00 12 00 18 00", &m);
+}
+
+#[test]
+fn test_oneofs() {
+    let descriptor = TestProto3Optional::descriptor_static();
+    let oneofs: Vec<_> = descriptor
+        .oneofs()
+        .map(|o| o.get_name().to_owned())
+        .collect();
+    assert_eq!(vec!["real"], oneofs);
+    assert_eq!(1, descriptor.oneofs().len());
+
+    let all_oneofs: Vec<_> = descriptor
+        .all_oneofs()
+        .map(|o| o.get_name().to_owned())
+        .collect();
+    assert_eq!(
+        vec!["real", "_iii", "_sss", "_eee", "_mmm", "X_conflict"],
+        all_oneofs
+    );
+
+    let iii = descriptor.get_field_by_name("iii").unwrap();
+    assert!(iii.containing_oneof().is_none());
+    let synthetic = iii.containing_oneof_including_synthetic().unwrap();
+    assert!(synthetic.is_synthetic());
+    assert_eq!(
+        vec!["iii"],
+        synthetic
+            .fields()
+            .map(|f| f.get_name().to_owned())
+            .collect::<Vec<_>>()
+    );
+
+    let real_a = descriptor.get_field_by_name("real_a").unwrap();
+    assert!(!real_a.containing_oneof().unwrap().is_synthetic());
+}
+
+#[test]
+fn test_reflect_presence() {
+    let field = TestProto3Optional::descriptor_static()
+        .get_field_by_name("iii")
+        .unwrap();
+    let mut m = TestProto3Optional::new();
+    assert!(!field.has_field(&m));
+    m.iii = Some(0);
+    assert!(field.has_field(&m));
+}
+
+#[test]
+fn test_dynamic_descriptor() {
+    let descriptor = dynamic_message_descriptor(&file_descriptor(), "TestProto3Optional");
+    assert_eq!(1, descriptor.oneofs().count());
+    assert!(descriptor
+        .get_field_by_name("sss")
+        .unwrap()
+        .get_proto()
+        .get_proto3_optional());
+}
+
+#[test]
+fn test_json() {
+    let mut m = TestProto3Optional::new();
+    m.iii = Some(0);
+    assert_eq!("{\"iii\": 0}", json::print_to_string(&m).unwrap());
+    assert_eq!(m, json::parse_from_str("{\"iii\": 0}").unwrap());
+
+    let print_options = json::PrintOptions {
+        always_output_default_values: true,
+        ..Default::default()
+    };
+    let json =
+        json::print_to_string_with_options(&TestProto3Optional::new(), &print_options).unwrap();
+    assert!(json.contains("\"plain\": 0"));
+    assert!(!json.contains("iii"));
+}
+
+#[test]
+fn test_text_format() {
+    let mut m = TestProto3Optional::new();
+    m.iii = Some(0);
+    assert_eq!("iii: 0", text_format::print_to_string(&m));
+    assert_eq!(m, text_format::parse_from_str("iii: 0").unwrap());
+}
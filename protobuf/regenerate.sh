@@ -37,7 +37,7 @@ esac
 "$PROTOC" \
     --plugin=protoc-gen-rust="$where_am_i/target/debug/protoc-gen-rust$exe_suffix" \
     --rust_out tmp-generated \
-    --rust_opt 'serde_derive=true serde_derive_cfg=serde inside_protobuf=true' \
+    --rust_opt 'serde_derive=true serde_derive_cfg=serde inside_protobuf=true closed_enums=false' \
     -I../proto \
     -I../protoc-bin-vendored/include \
     ../protoc-bin-vendored/include/google/protobuf/*.proto \
@@ -44,11 +44,39 @@ const DEFAULT_RECURSION_LIMIT: u32 = 100;
 // Max allocated vec when reading length-delimited from unknown input stream
 pub(crate) const READ_RAW_BYTES_MAX_ALLOC: usize = 10_000_000;
 
+/// Limits applied while decoding, useful when input is untrusted.
+///
+/// All limits are disabled by default.
+/// Violations are reported as distinct [`WireError`] variants.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DecodeLimits {
+    /// Maximum number of bytes read from the stream.
+    pub max_message_size: Option<u64>,
+    /// Maximum length of a single `string`, `bytes`
+    /// or length-delimited unknown field.
+    pub max_bytes_len: Option<u64>,
+    /// Maximum number of elements in a single repeated field or map
+    /// (including unknown values with the same field number).
+    pub max_repeated_len: Option<u64>,
+    /// Maximum total number of bytes allocated for decoded
+    /// strings, bytes and repeated or map elements.
+    pub max_allocation: Option<u64>,
+}
+
 /// Buffered read with handy utilities.
 pub struct CodedInputStream<'a> {
     source: BufReadIter<'a>,
     recursion_level: u32,
     recursion_limit: u32,
+    decode_limits: DecodeLimits,
+    allocated: u64,
+    // number of the field of the last read tag, used in error location
+    last_field_number: u32,
+    // number of the last field of the current message which was not stored
+    // in unknown fields, used to remember positions of unknown fields
+    last_known_field_number: u32,
+    // value of the last read tag was stored in unknown fields
+    last_field_unknown: bool,
 }
 
 impl<'a> CodedInputStream<'a> {
@@ -86,6 +114,11 @@ impl<'a> CodedInputStream<'a> {
             source: source,
             recursion_level: 0,
             recursion_limit: DEFAULT_RECURSION_LIMIT,
+            decode_limits: DecodeLimits::default(),
+            allocated: 0,
+            last_field_number: 0,
+            last_known_field_number: 0,
+            last_field_unknown: false,
         }
     }
 
@@ -94,6 +127,84 @@ impl<'a> CodedInputStream<'a> {
         self.recursion_limit = limit;
     }
 
+    /// Set decoding limits.
+    pub fn set_decode_limits(&mut self, limits: DecodeLimits) {
+        self.decode_limits = limits;
+    }
+
+    /// Currently configured decoding limits.
+    pub fn decode_limits(&self) -> &DecodeLimits {
+        &self.decode_limits
+    }
+
+    /// Check that `len` more bytes can be read without exceeding message size limit.
+    #[inline]
+    fn check_message_size(&self, len: u64) -> ProtobufResult<()> {
+        if let Some(max) = self.decode_limits.max_message_size {
+            if self.pos().saturating_add(len) > max {
+                return Err(ProtobufError::WireError(WireError::MessageTooLarge));
+            }
+        }
+        Ok(())
+    }
+
+    /// Account `size` bytes in allocation budget.
+    #[inline]
+    fn allocate(&mut self, size: u64) -> ProtobufResult<()> {
+        self.allocated = self.allocated.saturating_add(size);
+        if let Some(max) = self.decode_limits.max_allocation {
+            if self.allocated > max {
+                return Err(ProtobufError::WireError(
+                    WireError::AllocationBudgetExceeded,
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Check length-delimited field of `len` bytes can be read.
+    #[inline]
+    fn check_bytes_len(&mut self, len: u64) -> ProtobufResult<()> {
+        if let Some(max) = self.decode_limits.max_bytes_len {
+            if len > max {
+                return Err(ProtobufError::WireError(WireError::BytesTooLong));
+            }
+        }
+        self.check_message_size(len)?;
+        self.allocate(len)
+    }
+
+    /// Check one more element of `size` bytes can be added
+    /// to repeated field or map which already contains `len` elements.
+    #[inline]
+    pub(crate) fn check_repeated_element(&mut self, len: usize, size: usize) -> ProtobufResult<()> {
+        if let Some(max) = self.decode_limits.max_repeated_len {
+            if len as u64 >= max {
+                return Err(ProtobufError::WireError(WireError::TooManyElements));
+            }
+        }
+        self.allocate(size as u64)
+    }
+
+    /// Do not preallocate more elements than decode limits allow.
+    fn cap_reserve<T>(&self, reserve: usize) -> usize {
+        let mut reserve = reserve as u64;
+        if let Some(max) = self.decode_limits.max_repeated_len {
+            reserve = reserve.min(max);
+        }
+        if let Some(max) = self.decode_limits.max_allocation {
+            let size = mem::size_of::<T>().max(1) as u64;
+            reserve = reserve.min(max.saturating_sub(self.allocated) / size);
+        }
+        reserve as usize
+    }
+
+    /// Check one more element can be pushed to `target`.
+    #[inline]
+    pub(crate) fn check_repeated_push<T>(&mut self, target: &[T]) -> ProtobufResult<()> {
+        self.check_repeated_element(target.len(), mem::size_of::<T>())
+    }
+
     #[inline]
     pub(crate) fn incr_recursion(&mut self) -> ProtobufResult<()> {
         if self.recursion_level >= self.recursion_limit {
@@ -130,6 +241,7 @@ impl<'a> CodedInputStream<'a> {
     /// constructed with `Bytes` parameter.
     #[cfg(feature = "bytes")]
     fn read_raw_callerche_bytes(&mut self, count: usize) -> ProtobufResult<Bytes> {
+        self.check_bytes_len(count as u64)?;
         self.source.read_exact_bytes(count)
     }
 
@@ -141,6 +253,7 @@ impl<'a> CodedInputStream<'a> {
 
     /// Push new limit, return previous limit.
     pub fn push_limit(&mut self, limit: u64) -> ProtobufResult<u64> {
+        self.check_message_size(limit)?;
         self.source.push_limit(limit)
     }
 
@@ -152,6 +265,7 @@ impl<'a> CodedInputStream<'a> {
     /// Are we at EOF?
     #[inline(always)]
     pub fn eof(&mut self) -> ProtobufResult<bool> {
+        self.check_message_size(0)?;
         self.source.eof()
     }
 
@@ -264,9 +378,18 @@ impl<'a> CodedInputStream<'a> {
     /// Read tag
     #[inline]
     pub fn read_tag(&mut self) -> ProtobufResult<wire_format::Tag> {
+        if !self.last_field_unknown {
+            self.last_known_field_number = self.last_field_number;
+        }
+        self.last_field_unknown = false;
+        // do not attribute tag read errors to the previous field
+        self.last_field_number = 0;
         let v = self.read_raw_varint32()?;
         match wire_format::Tag::new(v) {
-            Some(tag) => Ok(tag),
+            Some(tag) => {
+                self.last_field_number = tag.field_number();
+                Ok(tag)
+            }
             None => Err(ProtobufError::WireError(WireError::IncorrectTag(v))),
         }
     }
@@ -374,10 +497,11 @@ impl<'a> CodedInputStream<'a> {
             READ_RAW_BYTES_MAX_ALLOC / (T::ENCODED_SIZE as usize)
         };
 
-        target.reserve(reserve);
+        target.reserve(self.cap_reserve::<T::ProtobufValue>(reserve));
 
         let old_limit = self.push_limit(len_bytes)?;
         while !self.eof()? {
+            self.check_repeated_push(target)?;
             target.push(T::read(self)?);
         }
         self.pop_limit(old_limit);
@@ -398,10 +522,11 @@ impl<'a> CodedInputStream<'a> {
             READ_RAW_BYTES_MAX_ALLOC
         };
 
-        target.reserve(reserve);
+        target.reserve(self.cap_reserve::<T::ProtobufValue>(reserve));
 
         let old_limit = self.push_limit(len_bytes)?;
         while !self.eof()? {
+            self.check_repeated_push(target)?;
             target.push(T::read(self)?);
         }
         self.pop_limit(old_limit);
@@ -509,6 +634,8 @@ impl<'a> CodedInputStream<'a> {
     }
 
     /// Read `UnknownValue`
+    ///
+    /// Group end tag must match the start group tag read last.
     pub fn read_unknown(
         &mut self,
         wire_type: wire_format::WireType,
@@ -524,12 +651,93 @@ impl<'a> CodedInputStream<'a> {
                 self.read_raw_bytes(len)
                     .map(|v| UnknownValue::LengthDelimited(v))
             }
+            wire_format::WireTypeStartGroup => {
+                let field_number = self.last_field_number;
+                self.read_group(field_number).map(UnknownValue::Group)
+            }
             _ => Err(ProtobufError::WireError(WireError::UnexpectedWireType(
                 wire_type,
             ))),
         }
     }
 
+    /// Read group content after start group tag, consuming the end group tag.
+    ///
+    /// End group tag must have the same `field_number` as the start group tag.
+    /// Content is returned as is, without start and end group tags.
+    pub(crate) fn read_group(&mut self, field_number: u32) -> ProtobufResult<Vec<u8>> {
+        let mut r = Vec::new();
+        let end_tag_pos = self.copy_group(field_number, &mut r)?;
+        r.truncate(end_tag_pos);
+        Ok(r)
+    }
+
+    /// Copy raw group content including the end group tag,
+    /// return position of the end group tag in `target`.
+    fn copy_group(&mut self, field_number: u32, target: &mut Vec<u8>) -> ProtobufResult<usize> {
+        self.incr_recursion()?;
+        let res = self.copy_group_fields(field_number, target);
+        self.decr_recursion();
+        res
+    }
+
+    fn copy_group_fields(
+        &mut self,
+        field_number: u32,
+        target: &mut Vec<u8>,
+    ) -> ProtobufResult<usize> {
+        loop {
+            let tag_pos = target.len();
+            let tag = self.copy_raw_varint(target)? as u32;
+            let (number, wire_type) = match wire_format::Tag::new(tag) {
+                Some(tag) => tag.unpack(),
+                None => return Err(ProtobufError::WireError(WireError::IncorrectTag(tag))),
+            };
+            match wire_type {
+                wire_format::WireTypeEndGroup if number == field_number => return Ok(tag_pos),
+                wire_format::WireTypeEndGroup => {
+                    return Err(ProtobufError::WireError(WireError::UnexpectedWireType(
+                        wire_type,
+                    )))
+                }
+                wire_format::WireTypeStartGroup => {
+                    self.copy_group(number, target)?;
+                }
+                wire_format::WireTypeVarint => {
+                    self.copy_raw_varint(target)?;
+                }
+                wire_format::WireTypeFixed64 => {
+                    target.extend_from_slice(&self.read_raw_little_endian64()?.to_le_bytes());
+                }
+                wire_format::WireTypeFixed32 => {
+                    target.extend_from_slice(&self.read_raw_little_endian32()?.to_le_bytes());
+                }
+                wire_format::WireTypeLengthDelimited => {
+                    let len = self.copy_raw_varint(target)? as u32;
+                    target.extend_from_slice(&self.read_raw_bytes(len)?);
+                }
+            }
+        }
+    }
+
+    /// Read varint appending its bytes to `target` as is,
+    /// so non-canonical encoding is preserved.
+    fn copy_raw_varint(&mut self, target: &mut Vec<u8>) -> ProtobufResult<u64> {
+        let mut r: u64 = 0;
+        for i in 0..10 {
+            let b = self.read_raw_byte()?;
+            if i == 9 && (b & 0x7f) > 1 {
+                return Err(ProtobufError::WireError(WireError::IncorrectVarint));
+            }
+            target.push(b);
+            r |= ((b & 0x7f) as u64) << (i * 7);
+            if b < 0x80 {
+                return Ok(r);
+            }
+        }
+        Err(ProtobufError::WireError(WireError::IncorrectVarint))
+    }
+
     /// Skip field
     pub fn skip_field(&mut self, wire_type: wire_format::WireType) -> ProtobufResult<()> {
         self.read_unknown(wire_type).map(|_| ())
@@ -538,6 +746,7 @@ impl<'a> CodedInputStream<'a> {
     /// Read raw bytes into the supplied vector.  The vector will be resized as needed and
     /// overwritten.
     pub fn read_raw_bytes_into(&mut self, count: u32, target: &mut Vec<u8>) -> ProtobufResult<()> {
+        self.check_bytes_len(count as u64)?;
         self.source.read_exact_to_vec(count as usize, target)
     }
 
@@ -606,11 +815,60 @@ impl<'a> CodedInputStream<'a> {
 
     /// Read message, do not check if message is initialized
     pub fn merge_message<M: Message>(&mut self, message: &mut M) -> ProtobufResult<()> {
-        let len = self.read_raw_varint64()?;
-        let old_limit = self.push_limit(len)?;
-        message.merge_from(self)?;
-        self.pop_limit(old_limit);
-        Ok(())
+        self.nested(|is| {
+            let len = is.read_raw_varint64()?;
+            let old_limit = is.push_limit(len)?;
+            message.merge_from(is)?;
+            is.pop_limit(old_limit);
+            Ok(())
+        })
+    }
+
+    /// Read nested value of the field of the last read tag.
+    ///
+    /// On error, location with path through this field is attached.
+    pub(crate) fn nested<R>(
+        &mut self,
+        read: impl FnOnce(&mut Self) -> ProtobufResult<R>,
+    ) -> ProtobufResult<R> {
+        let field_number = self.last_field_number;
+        let last_known_field_number = self.last_known_field_number;
+        self.last_field_number = 0;
+        self.last_known_field_number = 0;
+        self.last_field_unknown = false;
+        let r = read(self);
+        let r = r.map_err(|e| {
+            e.with_location(self.pos(), self.last_field_number)
+                .with_parent_field(field_number)
+        });
+        self.last_field_number = field_number;
+        self.last_known_field_number = last_known_field_number;
+        self.last_field_unknown = false;
+        r
+    }
+
+    /// Mark the value of the last read tag as stored in unknown fields.
+    ///
+    /// Return the number of the last field of the current message
+    /// read before that tag which was not stored in unknown fields,
+    /// or zero if there is no such field.
+    pub(crate) fn unknown_field_position(&mut self) -> u32 {
+        self.last_field_unknown = true;
+        self.last_known_field_number
+    }
+
+    /// Merge message from the stream, attaching location with field names
+    /// to decoding errors.
+    pub(crate) fn merge_from_located<M: Message>(&mut self, message: &mut M) -> ProtobufResult<()> {
+        self.last_field_number = 0;
+        self.last_known_field_number = 0;
+        self.last_field_unknown = false;
+        match message.merge_from(self) {
+            Ok(()) => Ok(()),
+            Err(e) => Err(e
+                .with_location(self.pos(), self.last_field_number)
+                .with_field_names(&message.descriptor_by_instance())),
+        }
     }
 
     /// Read message
@@ -648,9 +906,14 @@ mod test {
 
     use crate::error::ProtobufError;
     use crate::error::ProtobufResult;
+    use crate::error::WireError;
     use crate::hex::decode_hex;
 
+    use crate::unknown::UnknownValue;
+    use crate::wire_format::WireType;
+
     use super::CodedInputStream;
+    use super::DecodeLimits;
     use super::READ_RAW_BYTES_MAX_ALLOC;
 
     fn test_read_partial<F>(hex: &str, mut callback: F)
@@ -830,6 +1093,55 @@ mod test {
         });
     }
 
+    #[test]
+    fn test_input_stream_read_unknown_group() {
+        // group 3 content is consumed with nested group 4 and end group tag
+        test_read("1b 08 01 23 08 02 24 1c", |reader| {
+            assert_eq!(
+                (3, WireType::WireTypeStartGroup),
+                reader.read_tag_unpack().unwrap()
+            );
+            match reader.read_unknown(WireType::WireTypeStartGroup).unwrap() {
+                UnknownValue::Group(content) => {
+                    assert_eq!(decode_hex("08 01 23 08 02 24"), content)
+                }
+                _ => panic!("expecting group"),
+            }
+        });
+        // nested end group tag number mismatch
+        test_read_partial("1b 23 08 02 2c 1c", |reader| {
+            reader.read_tag_unpack().unwrap();
+            assert!(reader.read_unknown(WireType::WireTypeStartGroup).is_err());
+        });
+        // end group tag number mismatch
+        test_read_partial("1b 08 01 24", |reader| {
+            reader.read_tag_unpack().unwrap();
+            assert!(reader.read_unknown(WireType::WireTypeStartGroup).is_err());
+        });
+        // non-canonical varints are copied as is
+        test_read("1b 88 00 81 00 1c", |reader| {
+            reader.read_tag_unpack().unwrap();
+            match reader.read_unknown(WireType::WireTypeStartGroup).unwrap() {
+                UnknownValue::Group(content) => assert_eq!(decode_hex("88 00 81 00"), content),
+                _ => panic!("expecting group"),
+            }
+        });
+    }
+
+    #[test]
+    fn test_input_stream_read_unknown_group_recursion_limit() {
+        test_read_partial("1b 23 23 23 24 24 24 1c", |reader| {
+            reader.read_tag_unpack().unwrap();
+            reader.set_recursion_limit(3);
+            assert!(reader.read_unknown(WireType::WireTypeStartGroup).is_err());
+        });
+        test_read("1b 23 23 24 24 1c", |reader| {
+            reader.read_tag_unpack().unwrap();
+            reader.set_recursion_limit(3);
+            reader.read_unknown(WireType::WireTypeStartGroup).unwrap();
+        });
+    }
+
     #[test]
     fn test_input_stream_read_raw_bytes_into_huge() {
         let mut v = Vec::new();
@@ -856,4 +1168,91 @@ mod test {
 
         assert!(is.eof().expect("eof"));
     }
+
+    fn test_read_with_limits<F>(hex: &str, limits: DecodeLimits, callback: F) -> WireError
+    where
+        F: FnOnce(&mut CodedInputStream) -> ProtobufResult<()>,
+    {
+        let d = decode_hex(hex);
+        let mut is = CodedInputStream::from_bytes(&d);
+        is.set_decode_limits(limits);
+        match callback(&mut is) {
+            Err(ProtobufError::WireError(e)) => e,
+            r => panic!("expecting wire error, got {:?}", r),
+        }
+    }
+
+    #[test]
+    fn test_input_stream_decode_limits_bytes_len() {
+        let limits = DecodeLimits {
+            max_bytes_len: Some(2),
+            ..Default::default()
+        };
+        test_read("02 61 62", |is| {
+            is.set_decode_limits(limits.clone());
+            assert_eq!("ab", is.read_string().unwrap());
+        });
+        let e = test_read_with_limits("03 61 62 63", limits.clone(), |is| {
+            is.read_string().map(|_| ())
+        });
+        assert!(matches!(e, WireError::BytesTooLong), "{:?}", e);
+        let e = test_read_with_limits("03 61 62 63", limits, |is| {
+            is.read_unknown(WireType::WireTypeLengthDelimited)
+                .map(|_| ())
+        });
+        assert!(matches!(e, WireError::BytesTooLong), "{:?}", e);
+    }
+
+    #[test]
+    fn test_input_stream_decode_limits_message_size() {
+        let limits = DecodeLimits {
+            max_message_size: Some(3),
+            ..Default::default()
+        };
+        let e = test_read_with_limits("08 01 08 02", limits.clone(), |is| {
+            while !is.eof()? {
+                is.read_tag()?;
+                is.read_int32()?;
+            }
+            Ok(())
+        });
+        assert!(matches!(e, WireError::MessageTooLarge), "{:?}", e);
+        // length is checked before reading the data
+        let e = test_read_with_limits("0a 05", limits, |is| {
+            is.read_tag()?;
+            is.read_bytes().map(|_| ())
+        });
+        assert!(matches!(e, WireError::MessageTooLarge), "{:?}", e);
+    }
+
+    #[test]
+    fn test_input_stream_decode_limits_repeated_len() {
+        let limits = DecodeLimits {
+            max_repeated_len: Some(2),
+            ..Default::default()
+        };
+        test_read("02 01 02", |is| {
+            is.set_decode_limits(limits.clone());
+            let mut v = Vec::new();
+            is.read_repeated_packed_int32_into(&mut v).unwrap();
+            assert_eq!(vec![1, 2], v);
+        });
+        let e = test_read_with_limits("03 01 02 03", limits, |is| {
+            is.read_repeated_packed_int32_into(&mut Vec::new())
+        });
+        assert!(matches!(e, WireError::TooManyElements), "{:?}", e);
+    }
+
+    #[test]
+    fn test_input_stream_decode_limits_allocation() {
+        let limits = DecodeLimits {
+            max_allocation: Some(5),
+            ..Default::default()
+        };
+        let e = test_read_with_limits("03 61 62 63 03 61 62 63", limits, |is| {
+            is.read_bytes()?;
+            is.read_bytes().map(|_| ())
+        });
+        assert!(matches!(e, WireError::AllocationBudgetExceeded), "{:?}", e);
+    }
 }
@@ -60,6 +60,8 @@ pub struct CodedOutputStream<'a> {
     buffer: &'a mut [u8],
     // within buffer
     position: usize,
+    // sort map entries and unknown fields
+    deterministic: bool,
 }
 
 impl<'a> CodedOutputStream<'a> {
@@ -80,6 +82,7 @@ impl<'a> CodedOutputStream<'a> {
             target: OutputTarget::Write(writer, buffer_storage),
             buffer: buffer,
             position: 0,
+            deterministic: false,
         }
     }
 
@@ -91,6 +94,7 @@ impl<'a> CodedOutputStream<'a> {
             target: OutputTarget::Bytes,
             buffer: bytes,
             position: 0,
+            deterministic: false,
         }
     }
 
@@ -100,9 +104,25 @@ impl<'a> CodedOutputStream<'a> {
             target: OutputTarget::Vec(vec),
             buffer: &mut [],
             position: 0,
+            deterministic: false,
         }
     }
 
+    /// Enable or disable deterministic serialization.
+    ///
+    /// In deterministic mode map entries are written sorted by key,
+    /// and unknown fields are written sorted by field number,
+    /// so equal messages are serialized to equal bytes
+    /// by the same version of the library.
+    pub fn set_deterministic(&mut self, deterministic: bool) {
+        self.deterministic = deterministic;
+    }
+
+    /// Is deterministic serialization enabled.
+    pub fn is_deterministic(&self) -> bool {
+        self.deterministic
+    }
+
     /// Check if EOF is reached.
     ///
     /// # Panics
@@ -355,12 +375,15 @@ impl<'a> CodedOutputStream<'a> {
     }
 
     /// Write unknown value
+    ///
+    /// For groups only group content is written, without end group tag.
     pub fn write_unknown_no_tag(&mut self, unknown: UnknownValueRef) -> ProtobufResult<()> {
         match unknown {
             UnknownValueRef::Fixed64(fixed64) => self.write_raw_little_endian64(fixed64),
             UnknownValueRef::Fixed32(fixed32) => self.write_raw_little_endian32(fixed32),
             UnknownValueRef::Varint(varint) => self.write_raw_varint64(varint),
             UnknownValueRef::LengthDelimited(bytes) => self.write_bytes_no_tag(bytes),
+            UnknownValueRef::Group(bytes) => self.write_raw_bytes(bytes),
         }
     }
 
@@ -474,30 +497,26 @@ impl<'a> CodedOutputStream<'a> {
         field_number: u32,
         value: UnknownValueRef,
     ) -> ProtobufResult<()> {
+        let group = value.wire_type() == wire_format::WireTypeStartGroup;
         self.write_tag(field_number, value.wire_type())?;
         self.write_unknown_no_tag(value)?;
+        if group {
+            self.write_tag(field_number, wire_format::WireTypeEndGroup)?;
+        }
         Ok(())
     }
 
-    /// Write unknown fields
+    /// Write unknown fields in the order they were added,
+    /// or sorted by field number in deterministic mode
     pub fn write_unknown_fields(&mut self, fields: &UnknownFields) -> ProtobufResult<()> {
-        for (number, values) in fields {
-            for value in values {
+        if self.deterministic {
+            let mut values: Vec<_> = fields.iter_values().collect();
+            values.sort_by_key(|(number, _)| *number);
+            for (number, value) in values {
                 self.write_unknown(number, value)?;
             }
-        }
-        Ok(())
-    }
-
-    /// Write unknown fields sorting them by name
-    pub(crate) fn write_unknown_fields_sorted(
-        &mut self,
-        fields: &UnknownFields,
-    ) -> ProtobufResult<()> {
-        let mut fields: Vec<_> = fields.iter().collect();
-        fields.sort_by_key(|(n, _)| *n);
-        for (number, values) in fields {
-            for value in values {
+        } else {
+            for (number, value) in fields.iter_values() {
                 self.write_unknown(number, value)?;
             }
         }
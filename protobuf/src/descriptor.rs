@@ -1999,16 +1999,10 @@ impl crate::Message for FieldDescriptorProto {
                     self.number = ::std::option::Option::Some(is.read_int32()?);
                 },
                 4 => {
-                    if wire_type != crate::wire_format::WireTypeVarint {
-                        return ::std::result::Result::Err(crate::rt::unexpected_wire_type(wire_type));
-                    }
-                    self.label = ::std::option::Option::Some(is.read_enum_or_unknown()?);
+                    crate::rt::read_proto2_enum_or_unknown_with_unknown_fields_into(wire_type, is, &mut self.label, 4, &mut self.unknown_fields)?;
                 },
                 5 => {
-                    if wire_type != crate::wire_format::WireTypeVarint {
-                        return ::std::result::Result::Err(crate::rt::unexpected_wire_type(wire_type));
-                    }
-                    self.field_type = ::std::option::Option::Some(is.read_enum_or_unknown()?);
+                    crate::rt::read_proto2_enum_or_unknown_with_unknown_fields_into(wire_type, is, &mut self.field_type, 5, &mut self.unknown_fields)?;
                 },
                 6 => {
                     if wire_type != crate::wire_format::WireTypeLengthDelimited {
@@ -4669,10 +4663,7 @@ impl crate::Message for FileOptions {
                     self.java_string_check_utf8 = ::std::option::Option::Some(is.read_bool()?);
                 },
                 9 => {
-                    if wire_type != crate::wire_format::WireTypeVarint {
-                        return ::std::result::Result::Err(crate::rt::unexpected_wire_type(wire_type));
-                    }
-                    self.optimize_for = ::std::option::Option::Some(is.read_enum_or_unknown()?);
+                    crate::rt::read_proto2_enum_or_unknown_with_unknown_fields_into(wire_type, is, &mut self.optimize_for, 9, &mut self.unknown_fields)?;
                 },
                 11 => {
                     if wire_type != crate::wire_format::WireTypeLengthDelimited {
@@ -5622,10 +5613,7 @@ impl crate::Message for FieldOptions {
             let (field_number, wire_type) = is.read_tag_unpack()?;
             match field_number {
                 1 => {
-                    if wire_type != crate::wire_format::WireTypeVarint {
-                        return ::std::result::Result::Err(crate::rt::unexpected_wire_type(wire_type));
-                    }
-                    self.ctype = ::std::option::Option::Some(is.read_enum_or_unknown()?);
+                    crate::rt::read_proto2_enum_or_unknown_with_unknown_fields_into(wire_type, is, &mut self.ctype, 1, &mut self.unknown_fields)?;
                 },
                 2 => {
                     if wire_type != crate::wire_format::WireTypeVarint {
@@ -5634,10 +5622,7 @@ impl crate::Message for FieldOptions {
                     self.packed = ::std::option::Option::Some(is.read_bool()?);
                 },
                 6 => {
-                    if wire_type != crate::wire_format::WireTypeVarint {
-                        return ::std::result::Result::Err(crate::rt::unexpected_wire_type(wire_type));
-                    }
-                    self.jstype = ::std::option::Option::Some(is.read_enum_or_unknown()?);
+                    crate::rt::read_proto2_enum_or_unknown_with_unknown_fields_into(wire_type, is, &mut self.jstype, 6, &mut self.unknown_fields)?;
                 },
                 5 => {
                     if wire_type != crate::wire_format::WireTypeVarint {
@@ -6712,10 +6697,7 @@ impl crate::Message for MethodOptions {
                     self.deprecated = ::std::option::Option::Some(is.read_bool()?);
                 },
                 34 => {
-                    if wire_type != crate::wire_format::WireTypeVarint {
-                        return ::std::result::Result::Err(crate::rt::unexpected_wire_type(wire_type));
-                    }
-                    self.idempotency_level = ::std::option::Option::Some(is.read_enum_or_unknown()?);
+                    crate::rt::read_proto2_enum_or_unknown_with_unknown_fields_into(wire_type, is, &mut self.idempotency_level, 34, &mut self.unknown_fields)?;
                 },
                 999 => {
                     crate::rt::read_repeated_message_into_vec(wire_type, is, &mut self.uninterpreted_option)?;
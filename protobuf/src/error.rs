@@ -3,6 +3,9 @@ use std::fmt;
 use std::io;
 use std::str;
 
+use crate::reflect::MessageDescriptor;
+use crate::reflect::RuntimeFieldType;
+use crate::reflect::RuntimeTypeBox;
 use crate::wire_format::WireType;
 
 /// `Result` alias for `ProtobufError`
@@ -25,6 +28,14 @@ pub enum WireError {
     // not really possible
     LimitOverflow,
     LimitIncrease,
+    /// Message is larger than `DecodeLimits::max_message_size`.
+    MessageTooLarge,
+    /// String or bytes field is longer than `DecodeLimits::max_bytes_len`.
+    BytesTooLong,
+    /// Repeated field or map has more than `DecodeLimits::max_repeated_len` elements.
+    TooManyElements,
+    /// Decoding allocated more than `DecodeLimits::max_allocation` bytes.
+    AllocationBudgetExceeded,
 }
 
 impl fmt::Display for WireError {
@@ -43,10 +54,88 @@ impl fmt::Display for WireError {
             WireError::LimitIncrease => {
                 write!(f, "new limit must be not greater than current limit")
             }
+            WireError::MessageTooLarge => write!(f, "message is too large"),
+            WireError::BytesTooLong => write!(f, "string or bytes field is too long"),
+            WireError::TooManyElements => write!(f, "too many repeated field elements"),
+            WireError::AllocationBudgetExceeded => write!(f, "allocation budget exceeded"),
         }
     }
 }
 
+/// Where in the input decoding error was detected.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DecodeErrorLocation {
+    /// Byte offset in the input stream.
+    pub offset: u64,
+    // field numbers with index in repeated field, innermost first
+    field_numbers: Vec<(u32, Option<usize>)>,
+    // descriptor of the outermost message, used to resolve field names
+    descriptor: Option<MessageDescriptor>,
+}
+
+impl DecodeErrorLocation {
+    /// Path to the field being decoded, like `order.items[3].sku`.
+    ///
+    /// Field numbers are used for fields not found in descriptors.
+    pub fn path(&self) -> String {
+        let mut path = String::new();
+        self.write_path(&mut path).unwrap();
+        path
+    }
+
+    fn write_path(&self, w: &mut dyn fmt::Write) -> fmt::Result {
+        enum Level {
+            Message(MessageDescriptor),
+            MapEntry(RuntimeTypeBox),
+            Unknown,
+        }
+
+        let mut level = match &self.descriptor {
+            Some(d) => Level::Message(d.clone()),
+            None => Level::Unknown,
+        };
+        for (i, &(number, index)) in self.field_numbers.iter().rev().enumerate() {
+            if i != 0 {
+                w.write_char('.')?;
+            }
+            level = match level {
+                Level::Message(d) => match d.get_field_by_number(number) {
+                    Some(field) => {
+                        w.write_str(field.get_name())?;
+                        match field.runtime_field_type() {
+                            RuntimeFieldType::Singular(RuntimeTypeBox::Message(m))
+                            | RuntimeFieldType::Repeated(RuntimeTypeBox::Message(m)) => {
+                                Level::Message(m)
+                            }
+                            RuntimeFieldType::Map(_, v) => Level::MapEntry(v),
+                            _ => Level::Unknown,
+                        }
+                    }
+                    None => {
+                        write!(w, "{}", number)?;
+                        Level::Unknown
+                    }
+                },
+                Level::MapEntry(v) if number == 1 || number == 2 => {
+                    w.write_str(if number == 1 { "key" } else { "value" })?;
+                    match v {
+                        RuntimeTypeBox::Message(m) if number == 2 => Level::Message(m),
+                        _ => Level::Unknown,
+                    }
+                }
+                Level::MapEntry(..) | Level::Unknown => {
+                    write!(w, "{}", number)?;
+                    Level::Unknown
+                }
+            };
+            if let Some(index) = index {
+                write!(w, "[{}]", index)?;
+            }
+        }
+        Ok(())
+    }
+}
+
 /// Generic protobuf error
 #[derive(Debug)]
 pub enum ProtobufError {
@@ -54,20 +143,48 @@ pub enum ProtobufError {
     IoError(io::Error),
     /// Malformed input
     WireError(WireError),
+    /// Malformed input, with location of the error.
+    ///
+    /// Message parsing functions return this variant rather than `WireError`,
+    /// use [`ProtobufError::wire_error`] to match both.
+    WireErrorAt(WireError, Box<DecodeErrorLocation>),
     /// Protocol contains a string which is not valid UTF-8 string
     Utf8(str::Utf8Error),
     /// Not all required fields of message set.
-    MessageNotInitialized(String),
+    MessageNotInitialized {
+        /// Message name.
+        message: String,
+        /// Paths of missing required fields,
+        /// see [`find_missing_required_fields`](crate::reflect::find_missing_required_fields).
+        missing_fields: Vec<String>,
+    },
 }
 
 impl fmt::Display for ProtobufError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
             // not sure that cause should be included in message
-            &ProtobufError::IoError(ref e) => write!(f, "IO error: {}", e),
-            &ProtobufError::WireError(ref e) => fmt::Display::fmt(e, f),
-            &ProtobufError::Utf8(ref e) => write!(f, "{}", e),
-            &ProtobufError::MessageNotInitialized { .. } => write!(f, "not all message fields set"),
+            ProtobufError::IoError(e) => write!(f, "IO error: {}", e),
+            ProtobufError::WireError(e) => fmt::Display::fmt(e, f),
+            ProtobufError::WireErrorAt(e, location) => {
+                write!(f, "{} at offset {}", e, location.offset)?;
+                if !location.field_numbers.is_empty() {
+                    write!(f, " in field ")?;
+                    location.write_path(f)?;
+                }
+                Ok(())
+            }
+            ProtobufError::Utf8(e) => write!(f, "{}", e),
+            ProtobufError::MessageNotInitialized {
+                message,
+                missing_fields,
+            } => {
+                write!(f, "not all message fields set in {}", message)?;
+                if !missing_fields.is_empty() {
+                    write!(f, ": missing {}", missing_fields.join(", "))?;
+                }
+                Ok(())
+            }
         }
     }
 }
@@ -78,11 +195,78 @@ impl Error for ProtobufError {
             &ProtobufError::IoError(ref e) => Some(e),
             &ProtobufError::Utf8(ref e) => Some(e),
             &ProtobufError::WireError(..) => None,
+            &ProtobufError::WireErrorAt(..) => None,
             &ProtobufError::MessageNotInitialized { .. } => None,
         }
     }
 }
 
+impl ProtobufError {
+    /// Wire error regardless of whether it has location.
+    pub fn wire_error(&self) -> Option<&WireError> {
+        match self {
+            ProtobufError::WireError(e) | ProtobufError::WireErrorAt(e, _) => Some(e),
+            _ => None,
+        }
+    }
+
+    /// Location of decoding error.
+    pub fn decode_error_location(&self) -> Option<&DecodeErrorLocation> {
+        match self {
+            ProtobufError::WireErrorAt(_, location) => Some(location),
+            _ => None,
+        }
+    }
+
+    /// Attach location to wire error.
+    ///
+    /// `last_field_number` is the innermost field being read (or zero).
+    pub(crate) fn with_location(self, offset: u64, last_field_number: u32) -> ProtobufError {
+        match self {
+            ProtobufError::WireError(e) => {
+                let mut location = DecodeErrorLocation {
+                    offset,
+                    field_numbers: Vec::new(),
+                    descriptor: None,
+                };
+                if last_field_number != 0 {
+                    location.field_numbers.push((last_field_number, None));
+                }
+                ProtobufError::WireErrorAt(e, Box::new(location))
+            }
+            e => e,
+        }
+    }
+
+    /// Prepend field to the path of located error.
+    pub(crate) fn with_parent_field(mut self, field_number: u32) -> ProtobufError {
+        if let ProtobufError::WireErrorAt(_, ref mut location) = self {
+            if field_number != 0 {
+                location.field_numbers.push((field_number, None));
+            }
+        }
+        self
+    }
+
+    /// Set repeated field index of the outermost field of error path.
+    pub(crate) fn with_repeated_index(mut self, index: usize) -> ProtobufError {
+        if let ProtobufError::WireErrorAt(_, ref mut location) = self {
+            if let Some(last) = location.field_numbers.last_mut() {
+                last.1 = Some(index);
+            }
+        }
+        self
+    }
+
+    /// Resolve field names in error path using the outermost message descriptor.
+    pub(crate) fn with_field_names(mut self, descriptor: &MessageDescriptor) -> ProtobufError {
+        if let ProtobufError::WireErrorAt(_, ref mut location) = self {
+            location.descriptor = Some(descriptor.clone());
+        }
+        self
+    }
+}
+
 impl From<io::Error> for ProtobufError {
     fn from(err: io::Error) -> Self {
         ProtobufError::IoError(err)
@@ -102,11 +286,34 @@ impl From<ProtobufError> for io::Error {
             ProtobufError::WireError(e) => {
                 io::Error::new(io::ErrorKind::InvalidData, ProtobufError::WireError(e))
             }
-            ProtobufError::MessageNotInitialized(message) => io::Error::new(
-                io::ErrorKind::InvalidInput,
-                ProtobufError::MessageNotInitialized(message),
-            ),
+            e @ ProtobufError::WireErrorAt(..) => io::Error::new(io::ErrorKind::InvalidData, e),
+            e @ ProtobufError::MessageNotInitialized { .. } => {
+                io::Error::new(io::ErrorKind::InvalidInput, e)
+            }
             e => io::Error::new(io::ErrorKind::Other, Box::new(e)),
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn message_not_initialized_display() {
+        let e = ProtobufError::MessageNotInitialized {
+            message: "M".to_owned(),
+            missing_fields: vec!["a".to_owned(), "b.c".to_owned()],
+        };
+        assert_eq!(
+            "not all message fields set in M: missing a, b.c",
+            e.to_string()
+        );
+
+        let e = ProtobufError::MessageNotInitialized {
+            message: "M".to_owned(),
+            missing_fields: Vec::new(),
+        };
+        assert_eq!("not all message fields set in M", e.to_string());
+    }
+}
@@ -8,6 +8,12 @@ use std::marker::PhantomData;
 
 use crate::message::Message;
 use crate::reflect::types::ProtobufType;
+use crate::reflect::wire;
+use crate::reflect::ProtobufValue;
+use crate::reflect::ReflectValueBox;
+use crate::reflect::ReflectValueRef;
+use crate::unknown::UnknownValue;
+use crate::unknown::UnknownValues;
 
 /// Optional ext field
 ///
@@ -28,26 +34,113 @@ pub struct ExtFieldRepeated<M: Message, T: ProtobufType> {
     /// Extension field number
     #[doc(hidden)]
     pub field_number: u32,
-    /// Extension field number
+    /// Is extension field packed
+    #[doc(hidden)]
+    pub packed: bool,
+    /// Marker
     #[doc(hidden)]
     pub phantom: PhantomData<(M, T)>,
 }
 
+fn get_values<T: ProtobufType>(values: &UnknownValues) -> Option<Vec<ReflectValueBox>> {
+    wire::values_from_unknown(T::FIELD_TYPE, &T::ProtobufValue::runtime_type_box(), values).ok()
+}
+
+fn from_value_box<T: ProtobufType>(value: ReflectValueBox) -> T::ProtobufValue {
+    T::ProtobufValue::from_value_box(value).expect("decoded value of extension type")
+}
+
+fn to_unknown<T: ProtobufType>(value: &T::ProtobufValue) -> UnknownValue {
+    wire::value_to_unknown(T::FIELD_TYPE, &T::ProtobufValue::as_ref(value)).expect("write to vec")
+}
+
+fn to_unknown_packed<T: ProtobufType>(values: &[T::ProtobufValue]) -> UnknownValue {
+    let values: Vec<ReflectValueRef> = values.iter().map(T::ProtobufValue::as_ref).collect();
+    wire::values_to_unknown_packed(T::FIELD_TYPE, &values).expect("write to vec")
+}
+
 impl<M: Message, T: ProtobufType> ExtFieldOptional<M, T> {
     /// Get a copy of value from a message.
     ///
     /// Extension data is stored in [`UnknownFields`](crate::UnknownFields).
+    /// If the field is encoded several times, the last value is returned,
+    /// except for messages, which are merged.
+    ///
+    /// `None` is returned if the extension is not set,
+    /// or if extension data cannot be decoded.
     pub fn get(&self, m: &M) -> Option<T::ProtobufValue> {
-        m.get_unknown_fields()
-            .get(self.field_number)
-            .and_then(T::get_from_unknown)
+        let values = m.get_unknown_fields().get(self.field_number)?;
+        wire::merge_singular(get_values::<T>(values)?).map(from_value_box::<T>)
+    }
+
+    /// Check if the extension is set in a message.
+    pub fn has(&self, m: &M) -> bool {
+        m.get_unknown_fields().get(self.field_number).is_some()
+    }
+
+    /// Set the extension value, replacing previous value.
+    pub fn set(&self, m: &mut M, value: T::ProtobufValue) {
+        let unknown_fields = m.mut_unknown_fields();
+        unknown_fields.remove(self.field_number);
+        unknown_fields.add_value(self.field_number, to_unknown::<T>(&value));
+    }
+
+    /// Remove the extension from a message.
+    pub fn clear(&self, m: &mut M) {
+        m.mut_unknown_fields().remove(self.field_number);
     }
 }
 
 impl<M: Message, T: ProtobufType> ExtFieldRepeated<M, T> {
-    /// Get a copy of value from a message (**not implemented**).
-    pub fn get(&self, _m: &M) -> Vec<T::ProtobufValue> {
-        // TODO
-        unimplemented!()
+    /// Get a copy of values from a message.
+    ///
+    /// Both packed and non-packed encodings are accepted.
+    ///
+    /// Empty vec is returned if extension data cannot be decoded.
+    pub fn get(&self, m: &M) -> Vec<T::ProtobufValue> {
+        m.get_unknown_fields()
+            .get(self.field_number)
+            .and_then(get_values::<T>)
+            .unwrap_or_default()
+            .into_iter()
+            .map(from_value_box::<T>)
+            .collect()
+    }
+
+    /// Check if the extension has at least one value in a message.
+    pub fn has(&self, m: &M) -> bool {
+        m.get_unknown_fields().get(self.field_number).is_some()
+    }
+
+    /// Append a value to the extension.
+    ///
+    /// Value of packed extension is appended as a separate packed chunk.
+    pub fn push(&self, m: &mut M, value: T::ProtobufValue) {
+        let value = if self.packed {
+            to_unknown_packed::<T>(&[value])
+        } else {
+            to_unknown::<T>(&value)
+        };
+        m.mut_unknown_fields().add_value(self.field_number, value);
+    }
+
+    /// Replace all extension values.
+    ///
+    /// Values are written using packed encoding if the extension is declared packed.
+    pub fn set(&self, m: &mut M, values: Vec<T::ProtobufValue>) {
+        self.clear(m);
+        if self.packed && !values.is_empty() {
+            m.mut_unknown_fields()
+                .add_value(self.field_number, to_unknown_packed::<T>(&values));
+            return;
+        }
+        for value in values {
+            self.push(m, value);
+        }
+    }
+
+    /// Remove all extension values from a message.
+    pub fn clear(&self, m: &mut M) {
+        m.mut_unknown_fields().remove(self.field_number);
     }
 }
@@ -0,0 +1,593 @@
+//! Operations on [`FieldMask`] using reflection.
+//!
+//! Paths are sequences of field names separated by dot, e. g. `a.b.c`.
+//! All path components except the last must be singular message fields.
+//!
+//! As described in [AIP-161](https://google.aip.dev/161):
+//!
+//! * the last component can be a map key after a map field, e. g. `labels.env`;
+//!   keys which are not identifiers must be quoted with backticks,
+//!   e. g. ``labels.`a.b` ``, backtick is escaped by doubling it
+//! * single `*` path means all fields
+//!
+//! ```
+//! use protobuf::field_mask;
+//! use protobuf::well_known_types::FieldMask;
+//!
+//! let a = FieldMask { paths: vec!["a.b".to_owned(), "c".to_owned()], ..Default::default() };
+//! let b = FieldMask { paths: vec!["a".to_owned()], ..Default::default() };
+//! assert_eq!(vec!["a", "c"], field_mask::union(&a, &b).paths);
+//! assert_eq!(vec!["a.b"], field_mask::intersect(&a, &b).paths);
+//! ```
+
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt;
+
+use crate::clear::Clear;
+use crate::reflect::map::map_key_to_string;
+use crate::reflect::FieldDescriptor;
+use crate::reflect::MessageDescriptor;
+use crate::reflect::ReflectFieldRef;
+use crate::reflect::ReflectValueBox;
+use crate::reflect::ReflectValueRef;
+use crate::reflect::RuntimeFieldType;
+use crate::reflect::RuntimeTypeBox;
+use crate::well_known_types::FieldMask;
+use crate::MessageDyn;
+
+const WILDCARD: &str = "*";
+
+/// Field mask path is not valid for the message type.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidPathError {
+    /// Full name of the message type.
+    pub message: String,
+    /// The invalid path.
+    pub path: String,
+}
+
+impl fmt::Display for InvalidPathError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "field mask path `{}` is not valid for message {}",
+            self.path, self.message
+        )
+    }
+}
+
+impl Error for InvalidPathError {}
+
+/// Options for [`merge_with_mask`].
+///
+/// By default fields are merged like [`Message::merge_from`](crate::Message::merge_from)
+/// does: message fields are merged recursively, repeated fields are appended,
+/// and fields unset in the source are left unchanged in the destination.
+#[derive(Debug, Clone, Default)]
+pub struct MergeOptions {
+    /// Replace message fields in destination instead of merging them.
+    pub replace_message_fields: bool,
+    /// Replace repeated and map fields in destination instead of appending to them,
+    /// and remove map entries selected by key which are absent in source.
+    pub replace_repeated_fields: bool,
+    /// Clear singular non-message fields in destination if they are unset in source.
+    pub replace_primitive_fields: bool,
+}
+
+impl MergeOptions {
+    /// Update semantics of [AIP-161](https://google.aip.dev/161):
+    /// every field in the mask is replaced by the field from the source,
+    /// and cleared if it is unset in the source.
+    pub fn aip_161() -> MergeOptions {
+        MergeOptions {
+            replace_message_fields: true,
+            replace_repeated_fields: true,
+            replace_primitive_fields: true,
+        }
+    }
+}
+
+/// Parsed field mask: paths merged into a tree.
+#[derive(Debug, Clone, Default)]
+struct PathTree {
+    /// Path ends here, so all nested paths are selected.
+    leaf: bool,
+    children: BTreeMap<String, PathTree>,
+}
+
+impl PathTree {
+    /// Build a tree from mask paths; paths which cannot be parsed are dropped.
+    fn from_mask(mask: &FieldMask) -> PathTree {
+        let mut tree = PathTree::default();
+        for path in &mask.paths {
+            if path == WILDCARD {
+                tree.leaf = true;
+                tree.children.clear();
+            } else if let Some(components) = split_path(path) {
+                tree.insert(&components);
+            }
+        }
+        tree
+    }
+
+    fn insert(&mut self, components: &[String]) {
+        let mut node = self;
+        for component in components {
+            if node.leaf {
+                return;
+            }
+            node = node.children.entry(component.clone()).or_default();
+        }
+        node.leaf = true;
+        node.children.clear();
+    }
+
+    fn merge(&mut self, other: &PathTree) {
+        if self.leaf {
+            return;
+        }
+        if other.leaf {
+            *self = other.clone();
+            return;
+        }
+        for (name, child) in &other.children {
+            self.children.entry(name.clone()).or_default().merge(child);
+        }
+    }
+
+    fn intersect(&self, other: &PathTree) -> PathTree {
+        if self.leaf {
+            return other.clone();
+        }
+        if other.leaf {
+            return self.clone();
+        }
+        let mut r = PathTree::default();
+        for (name, child) in &self.children {
+            if let Some(other_child) = other.children.get(name) {
+                let child = child.intersect(other_child);
+                if child.leaf || !child.children.is_empty() {
+                    r.children.insert(name.clone(), child);
+                }
+            }
+        }
+        r
+    }
+
+    fn to_mask(&self) -> FieldMask {
+        let mut paths = Vec::new();
+        if self.leaf {
+            paths.push(WILDCARD.to_owned());
+        } else {
+            self.collect_paths("", &mut paths);
+        }
+        FieldMask {
+            paths,
+            ..Default::default()
+        }
+    }
+
+    fn collect_paths(&self, prefix: &str, paths: &mut Vec<String>) {
+        for (name, child) in &self.children {
+            let path = format!("{}{}", prefix, quote_component(name));
+            if child.leaf {
+                paths.push(path);
+            } else {
+                child.collect_paths(&format!("{}.", path), paths);
+            }
+        }
+    }
+}
+
+/// Split path into components, unquoting backtick-quoted components.
+fn split_path(path: &str) -> Option<Vec<String>> {
+    let mut components = Vec::new();
+    let mut chars = path.chars().peekable();
+    loop {
+        let mut component = String::new();
+        if chars.peek() == Some(&'`') {
+            chars.next();
+            loop {
+                match chars.next()? {
+                    '`' if chars.peek() == Some(&'`') => {
+                        chars.next();
+                        component.push('`');
+                    }
+                    '`' => break,
+                    c => component.push(c),
+                }
+            }
+            match chars.next() {
+                None => {
+                    components.push(component);
+                    return Some(components);
+                }
+                Some('.') => {}
+                Some(_) => return None,
+            }
+        } else {
+            loop {
+                match chars.next() {
+                    None => {
+                        if component.is_empty() {
+                            return None;
+                        }
+                        components.push(component);
+                        return Some(components);
+                    }
+                    Some('.') => break,
+                    Some(c) => component.push(c),
+                }
+            }
+            if component.is_empty() {
+                return None;
+            }
+        }
+        components.push(component);
+    }
+}
+
+fn quote_component(component: &str) -> String {
+    let plain = !component.is_empty()
+        && component
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+    if plain {
+        component.to_owned()
+    } else {
+        format!("`{}`", component.replace('`', "``"))
+    }
+}
+
+fn is_valid_map_key(key_type: &RuntimeTypeBox, component: &str) -> bool {
+    match key_type {
+        RuntimeTypeBox::U32 => component.parse::<u32>().is_ok(),
+        RuntimeTypeBox::U64 => component.parse::<u64>().is_ok(),
+        RuntimeTypeBox::I32 => component.parse::<i32>().is_ok(),
+        RuntimeTypeBox::I64 => component.parse::<i64>().is_ok(),
+        RuntimeTypeBox::Bool => component == "true" || component == "false",
+        RuntimeTypeBox::String => true,
+        _ => false,
+    }
+}
+
+fn is_valid_path(descriptor: &MessageDescriptor, path: &str) -> bool {
+    if path == WILDCARD {
+        return true;
+    }
+    let components = match split_path(path) {
+        Some(components) => components,
+        None => return false,
+    };
+    let mut descriptor = descriptor.clone();
+    let mut components = components.iter();
+    while let Some(component) = components.next() {
+        let field = match descriptor.get_field_by_name(component) {
+            Some(field) => field,
+            None => return false,
+        };
+        let rest = components.as_slice();
+        if rest.is_empty() {
+            return true;
+        }
+        match field.runtime_field_type() {
+            RuntimeFieldType::Singular(RuntimeTypeBox::Message(m)) => descriptor = m,
+            RuntimeFieldType::Map(key_type, _) => {
+                return rest.len() == 1 && is_valid_map_key(&key_type, &rest[0]);
+            }
+            _ => return false,
+        }
+    }
+    true
+}
+
+/// Check that all paths of the mask refer to fields of given message type.
+///
+/// Returns the first invalid path as error.
+pub fn validate(mask: &FieldMask, descriptor: &MessageDescriptor) -> Result<(), InvalidPathError> {
+    for path in &mask.paths {
+        if !is_valid_path(descriptor, path) {
+            return Err(InvalidPathError {
+                message: descriptor.full_name().to_owned(),
+                path: path.clone(),
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Canonical form of the mask: paths are sorted, duplicates and paths
+/// covered by other paths (e. g. `a.b` when `a` is present) are removed.
+///
+/// Paths which cannot be parsed are dropped.
+pub fn normalize(mask: &FieldMask) -> FieldMask {
+    PathTree::from_mask(mask).to_mask()
+}
+
+/// Mask which selects fields selected by any of the masks, normalized.
+pub fn union(a: &FieldMask, b: &FieldMask) -> FieldMask {
+    let mut tree = PathTree::from_mask(a);
+    tree.merge(&PathTree::from_mask(b));
+    tree.to_mask()
+}
+
+/// Mask which selects fields selected by both masks, normalized.
+pub fn intersect(a: &FieldMask, b: &FieldMask) -> FieldMask {
+    PathTree::from_mask(a)
+        .intersect(&PathTree::from_mask(b))
+        .to_mask()
+}
+
+/// Clear all fields of the message not selected by the mask.
+///
+/// Unknown fields are cleared too unless the mask is `*`.
+/// Paths not valid for the message type are ignored.
+pub fn trim(message: &mut dyn MessageDyn, mask: &FieldMask) {
+    trim_message(message, &PathTree::from_mask(mask));
+}
+
+fn trim_message(message: &mut dyn MessageDyn, tree: &PathTree) {
+    if tree.leaf {
+        return;
+    }
+    for field in message.descriptor_dyn().fields() {
+        match tree.children.get(field.get_name()) {
+            None => field.clear_field(message),
+            Some(child) if child.leaf => {}
+            Some(child) => trim_field(message, &field, child),
+        }
+    }
+    message.mut_unknown_fields_dyn().clear();
+}
+
+fn trim_field(message: &mut dyn MessageDyn, field: &FieldDescriptor, tree: &PathTree) {
+    match field.runtime_field_type() {
+        RuntimeFieldType::Singular(RuntimeTypeBox::Message(..)) if field.has_field(message) => {
+            trim_message(field.mut_message(message), tree);
+        }
+        RuntimeFieldType::Map(..) => {
+            let retained: Vec<(ReflectValueBox, ReflectValueBox)> = field
+                .get_map(message)
+                .into_iter()
+                .filter(|(k, _)| match map_key_to_string(k, false) {
+                    Some(k) => tree.children.contains_key(&k),
+                    None => false,
+                })
+                .map(|(k, v)| (k.to_box(), v.to_box()))
+                .collect();
+            let mut map = field.mut_map(message);
+            map.clear();
+            for (k, v) in retained {
+                map.insert(k, v);
+            }
+        }
+        // nested path is not valid or message is not set, keep the field
+        _ => {}
+    }
+}
+
+/// Merge fields selected by the mask from `src` into `dst`.
+///
+/// Use [`MergeOptions::aip_161`] for the update semantics of
+/// [AIP-161](https://google.aip.dev/161).
+///
+/// Returns error without modifying `dst` if the mask is not valid for the message type.
+///
+/// # Panics
+///
+/// If messages are of different types.
+pub fn merge_with_mask(
+    src: &dyn MessageDyn,
+    dst: &mut dyn MessageDyn,
+    mask: &FieldMask,
+    options: &MergeOptions,
+) -> Result<(), InvalidPathError> {
+    let descriptor = src.descriptor_dyn();
+    assert_eq!(descriptor, dst.descriptor_dyn());
+    validate(mask, &descriptor)?;
+    merge_message(src, dst, &PathTree::from_mask(mask), options);
+    Ok(())
+}
+
+fn merge_message(
+    src: &dyn MessageDyn,
+    dst: &mut dyn MessageDyn,
+    tree: &PathTree,
+    options: &MergeOptions,
+) {
+    for field in src.descriptor_dyn().fields() {
+        if tree.leaf {
+            merge_field(src, dst, &field, options);
+        } else if let Some(child) = tree.children.get(field.get_name()) {
+            if child.leaf {
+                merge_field(src, dst, &field, options);
+            } else {
+                merge_nested(src, dst, &field, child, options);
+            }
+        }
+    }
+}
+
+/// Merge the whole field.
+fn merge_field(
+    src: &dyn MessageDyn,
+    dst: &mut dyn MessageDyn,
+    field: &FieldDescriptor,
+    options: &MergeOptions,
+) {
+    match field.get_reflect(src) {
+        ReflectFieldRef::Optional(Some(ReflectValueRef::Message(m))) => {
+            if options.replace_message_fields {
+                field.set_singular_field(dst, ReflectValueBox::Message(m.clone_box()));
+            } else {
+                field.merge_message(dst, &*m);
+            }
+        }
+        ReflectFieldRef::Optional(Some(v)) => {
+            field.set_singular_field(dst, v.to_box());
+        }
+        ReflectFieldRef::Optional(None) => {
+            let is_message = matches!(
+                field.runtime_field_type(),
+                RuntimeFieldType::Singular(RuntimeTypeBox::Message(..))
+            );
+            let replace = match is_message {
+                true => options.replace_message_fields,
+                false => options.replace_primitive_fields,
+            };
+            if replace {
+                field.clear_field(dst);
+            }
+        }
+        ReflectFieldRef::Repeated(repeated) => {
+            let values: Vec<ReflectValueBox> = repeated.into_iter().map(|v| v.to_box()).collect();
+            let mut dst_repeated = field.mut_repeated(dst);
+            if options.replace_repeated_fields {
+                dst_repeated.clear();
+            }
+            for v in values {
+                dst_repeated.push(v);
+            }
+        }
+        ReflectFieldRef::Map(map) => {
+            let entries: Vec<(ReflectValueBox, ReflectValueBox)> = map
+                .into_iter()
+                .map(|(k, v)| (k.to_box(), v.to_box()))
+                .collect();
+            let mut dst_map = field.mut_map(dst);
+            if options.replace_repeated_fields {
+                dst_map.clear();
+            }
+            for (k, v) in entries {
+                dst_map.insert(k, v);
+            }
+        }
+    }
+}
+
+/// Merge subfields of message field or entries of map field.
+fn merge_nested(
+    src: &dyn MessageDyn,
+    dst: &mut dyn MessageDyn,
+    field: &FieldDescriptor,
+    tree: &PathTree,
+    options: &MergeOptions,
+) {
+    match field.runtime_field_type() {
+        RuntimeFieldType::Singular(RuntimeTypeBox::Message(..)) => {
+            if !field.has_field(src) && !field.has_field(dst) {
+                return;
+            }
+            // unset source field is treated as empty message
+            merge_message(
+                &*field.get_message(src),
+                field.mut_message(dst),
+                tree,
+                options,
+            );
+        }
+        RuntimeFieldType::Map(..) => {
+            let src_map = field.get_map(src);
+            let mut updated = Vec::new();
+            for key in tree.children.keys() {
+                let entry = src_map
+                    .into_iter()
+                    .find(|(k, _)| map_key_to_string(k, false).as_ref() == Some(key));
+                if let Some((k, v)) = entry {
+                    updated.push((key, Some((k.to_box(), v.to_box()))));
+                } else if options.replace_repeated_fields {
+                    updated.push((key, None));
+                }
+            }
+
+            let removed: Vec<&String> = updated
+                .iter()
+                .filter(|(_, entry)| entry.is_none())
+                .map(|(key, _)| *key)
+                .collect();
+            let retained: Vec<(ReflectValueBox, ReflectValueBox)> = match removed.is_empty() {
+                true => Vec::new(),
+                false => field
+                    .get_map(dst)
+                    .into_iter()
+                    .filter(|(k, _)| match map_key_to_string(k, false) {
+                        Some(k) => !removed.contains(&&k),
+                        None => true,
+                    })
+                    .map(|(k, v)| (k.to_box(), v.to_box()))
+                    .collect(),
+            };
+
+            let mut dst_map = field.mut_map(dst);
+            if !removed.is_empty() {
+                dst_map.clear();
+                for (k, v) in retained {
+                    dst_map.insert(k, v);
+                }
+            }
+            for (_, entry) in updated {
+                if let Some((k, v)) = entry {
+                    dst_map.insert(k, v);
+                }
+            }
+        }
+        // not reachable for validated mask
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::intersect;
+    use super::normalize;
+    use super::split_path;
+    use super::union;
+    use crate::well_known_types::FieldMask;
+
+    fn mask(paths: &[&str]) -> FieldMask {
+        FieldMask {
+            paths: paths.iter().map(|p| (*p).to_owned()).collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn split() {
+        assert_eq!(Some(vec!["a".to_owned()]), split_path("a"));
+        assert_eq!(
+            Some(vec!["a".to_owned(), "b.c".to_owned(), "d`".to_owned()]),
+            split_path("a.`b.c`.`d```")
+        );
+        assert_eq!(None, split_path(""));
+        assert_eq!(None, split_path("a..b"));
+        assert_eq!(None, split_path("a."));
+        assert_eq!(None, split_path("a.`b"));
+        assert_eq!(None, split_path("`a`b"));
+    }
+
+    #[test]
+    fn normalize_paths() {
+        assert_eq!(
+            mask(&["a", "b.c", "b.d"]),
+            normalize(&mask(&["b.d", "a.x", "b.c", "a", "b.c"]))
+        );
+        assert_eq!(mask(&["*"]), normalize(&mask(&["a", "*"])));
+        assert_eq!(
+            mask(&["m.`a.b`", "m.k"]),
+            normalize(&mask(&["m.k", "m.`a.b`"]))
+        );
+        assert_eq!(mask(&[]), normalize(&mask(&["", "a..b"])));
+    }
+
+    #[test]
+    fn union_intersect() {
+        let a = mask(&["a.b", "c", "d.e"]);
+        let b = mask(&["a", "d.f", "g"]);
+        assert_eq!(mask(&["a", "c", "d.e", "d.f", "g"]), union(&a, &b));
+        assert_eq!(mask(&["a.b"]), intersect(&a, &b));
+        assert_eq!(a, intersect(&a, &mask(&["*"])));
+        assert_eq!(mask(&["*"]), union(&a, &mask(&["*"])));
+        assert_eq!(mask(&[]), intersect(&a, &mask(&[])));
+    }
+}
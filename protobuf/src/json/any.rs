@@ -0,0 +1,35 @@
+//! Utilities for JSON representation of `google.protobuf.Any`
+
+use crate::reflect::MessageDescriptor;
+
+/// Key in JSON object which contains type URL of `Any` message.
+pub(crate) const ANY_TYPE_URL_KEY: &str = "@type";
+/// Key in JSON object which contains well-known type embedded into `Any`.
+pub(crate) const ANY_VALUE_KEY: &str = "value";
+
+/// Well-known types which are not represented in JSON as JSON objects.
+///
+/// When such type is embedded into `Any`, the value is stored
+/// in `"value"` key of `Any` JSON object.
+const WELL_KNOWN_TYPES_WITH_SPECIAL_JSON: &[&str] = &[
+    "google.protobuf.Any",
+    "google.protobuf.BoolValue",
+    "google.protobuf.BytesValue",
+    "google.protobuf.DoubleValue",
+    "google.protobuf.Duration",
+    "google.protobuf.FieldMask",
+    "google.protobuf.FloatValue",
+    "google.protobuf.Int32Value",
+    "google.protobuf.Int64Value",
+    "google.protobuf.ListValue",
+    "google.protobuf.StringValue",
+    "google.protobuf.Struct",
+    "google.protobuf.Timestamp",
+    "google.protobuf.UInt32Value",
+    "google.protobuf.UInt64Value",
+    "google.protobuf.Value",
+];
+
+pub(crate) fn is_well_known_type_with_special_json(descriptor: &MessageDescriptor) -> bool {
+    WELL_KNOWN_TYPES_WITH_SPECIAL_JSON.contains(&descriptor.full_name())
+}
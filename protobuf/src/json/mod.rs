@@ -1,6 +1,7 @@
 //! JSON printer and parser which tries to follow
 //! [protobuf conventions](https://developers.google.com/protocol-buffers/docs/proto3#json)
 
+mod any;
 mod base64;
 mod float;
 mod json_name;
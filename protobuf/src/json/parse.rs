@@ -8,6 +8,9 @@ use std::fmt;
 use super::base64;
 
 use crate::enums::ProtobufEnum;
+use crate::json::any::is_well_known_type_with_special_json;
+use crate::json::any::ANY_TYPE_URL_KEY;
+use crate::json::any::ANY_VALUE_KEY;
 use crate::json::base64::FromBase64Error;
 use crate::message::Message;
 use crate::reflect::EnumDescriptor;
@@ -17,6 +20,7 @@ use crate::reflect::MessageDescriptor;
 use crate::reflect::ReflectValueBox;
 use crate::reflect::RuntimeFieldType;
 use crate::reflect::RuntimeTypeBox;
+use crate::reflect::TypeRegistry;
 use crate::text_format::lexer::Lexer;
 use crate::text_format::lexer::LexerError;
 use crate::text_format::lexer::Loc;
@@ -49,6 +53,7 @@ use crate::well_known_types::Timestamp;
 use crate::well_known_types::UInt32Value;
 use crate::well_known_types::UInt64Value;
 use crate::well_known_types::Value;
+use crate::ProtobufError;
 
 #[derive(Debug)]
 enum ParseErrorWithoutLocInner {
@@ -66,8 +71,10 @@ enum ParseErrorWithoutLocInner {
     ExpectingStrOrInt,
     ExpectingNumber,
     UnexpectedToken,
-    AnyParsingIsNotImplemented,
-    MessageNotInitialized,
+    AnyTypeUrlMissing,
+    AnyUnknownType(String),
+    AnyEncode(ProtobufError),
+    MessageNotInitialized(Vec<String>),
 }
 
 /// JSON parse error.
@@ -99,11 +106,21 @@ impl fmt::Display for ParseErrorWithoutLoc {
             }
             ParseErrorWithoutLocInner::ExpectingNumber => write!(f, "expecting number"),
             ParseErrorWithoutLocInner::UnexpectedToken => write!(f, "unexpected token"),
-            ParseErrorWithoutLocInner::AnyParsingIsNotImplemented => {
-                write!(f, "Any parsing is not implemented")
+            ParseErrorWithoutLocInner::AnyTypeUrlMissing => {
+                write!(f, "missing {} in Any", ANY_TYPE_URL_KEY)
             }
-            ParseErrorWithoutLocInner::MessageNotInitialized => {
-                write!(f, "Message not initialized")
+            ParseErrorWithoutLocInner::AnyUnknownType(t) => {
+                write!(f, "cannot resolve Any type URL: {}", t)
+            }
+            ParseErrorWithoutLocInner::AnyEncode(e) => {
+                write!(f, "failed to encode Any value: {}", e)
+            }
+            ParseErrorWithoutLocInner::MessageNotInitialized(missing) => {
+                write!(f, "Message not initialized")?;
+                if !missing.is_empty() {
+                    write!(f, ", missing required fields: {}", missing.join(", "))?;
+                }
+                Ok(())
             }
         }
     }
@@ -162,7 +179,7 @@ type ParseResult<A> = Result<A, ParseError>;
 #[derive(Clone)]
 struct Parser<'a> {
     tokenizer: Tokenizer<'a>,
-    parse_options: ParseOptions,
+    parse_options: &'a ParseOptions,
 }
 
 trait FromJsonNumber: PartialEq + Sized {
@@ -635,6 +652,23 @@ impl<'a> Parser<'a> {
         }
     }
 
+    fn merge_field_by_name(
+        &mut self,
+        message: &mut dyn MessageDyn,
+        descriptor: &MessageDescriptor,
+        field_name: String,
+    ) -> ParseResultWithoutLoc<()> {
+        // Proto3 JSON parsers are required to accept both
+        // the converted `lowerCamelCase` name and the proto field name.
+        match descriptor.get_field_by_name_or_json_name(&field_name) {
+            Some(field) => self.merge_field(message, &field),
+            None if self.parse_options.ignore_unknown_fields => self.skip_json_value(),
+            None => Err(ParseErrorWithoutLoc(
+                ParseErrorWithoutLocInner::UnknownFieldName(field_name),
+            )),
+        }
+    }
+
     fn merge_inner(&mut self, message: &mut dyn MessageDyn) -> ParseResultWithoutLoc<()> {
         if let Some(duration) = message.downcast_mut() {
             return self.merge_wk_duration(duration);
@@ -711,23 +745,8 @@ impl<'a> Parser<'a> {
             first = false;
 
             let field_name = self.read_string()?;
-            // Proto3 JSON parsers are required to accept both
-            // the converted `lowerCamelCase` name and the proto field name.
-            match descriptor.get_field_by_name_or_json_name(&field_name) {
-                Some(field) => {
-                    self.tokenizer.next_symbol_expect_eq(':')?;
-                    self.merge_field(message, &field)?;
-                }
-                None if self.parse_options.ignore_unknown_fields => {
-                    self.tokenizer.next_symbol_expect_eq(':')?;
-                    self.skip_json_value()?;
-                }
-                None => {
-                    return Err(ParseErrorWithoutLoc(
-                        ParseErrorWithoutLocInner::UnknownFieldName(field_name),
-                    ))
-                }
-            };
+            self.tokenizer.next_symbol_expect_eq(':')?;
+            self.merge_field_by_name(message, &descriptor, field_name)?;
         }
         Ok(())
     }
@@ -857,10 +876,89 @@ impl<'a> Parser<'a> {
         Ok(())
     }
 
-    fn merge_wk_any(&mut self, _value: &mut Any) -> ParseResultWithoutLoc<()> {
-        Err(ParseErrorWithoutLoc(
-            ParseErrorWithoutLocInner::AnyParsingIsNotImplemented,
-        ))
+    /// Find `@type` value in JSON object without consuming the object.
+    ///
+    /// `@type` is not required to be the first key of the object.
+    fn lookahead_any_type_url(&self) -> ParseResultWithoutLoc<Option<String>> {
+        let mut parser = self.clone();
+        let mut type_url = None;
+        parser.read_map(
+            |_, k| Ok(k),
+            |s, k| {
+                if k == ANY_TYPE_URL_KEY {
+                    type_url = Some(s.read_string()?);
+                    Ok(())
+                } else {
+                    s.skip_json_value()
+                }
+            },
+        )?;
+        Ok(type_url)
+    }
+
+    fn merge_wk_any(&mut self, any: &mut Any) -> ParseResultWithoutLoc<()> {
+        let type_url = match self.lookahead_any_type_url()? {
+            Some(type_url) => type_url,
+            None => {
+                // `{}` is an empty `Any`
+                self.tokenizer.next_symbol_expect_eq('{')?;
+                if self.tokenizer.next_symbol_if_eq('}')? {
+                    return Ok(());
+                }
+                return Err(ParseErrorWithoutLoc(
+                    ParseErrorWithoutLocInner::AnyTypeUrlMissing,
+                ));
+            }
+        };
+
+        let descriptor = match self
+            .parse_options
+            .type_registry
+            .find_message_by_type_url(&type_url)
+        {
+            Some(descriptor) => descriptor,
+            None => {
+                return Err(ParseErrorWithoutLoc(
+                    ParseErrorWithoutLocInner::AnyUnknownType(type_url),
+                ))
+            }
+        };
+        let special_json = is_well_known_type_with_special_json(&descriptor);
+
+        let mut message = descriptor.new_instance();
+
+        self.tokenizer.next_symbol_expect_eq('{')?;
+        let mut first = true;
+        while !self.tokenizer.next_symbol_if_eq('}')? {
+            if !first {
+                self.tokenizer.next_symbol_expect_eq(',')?;
+            }
+            first = false;
+
+            let field_name = self.read_string()?;
+            self.tokenizer.next_symbol_expect_eq(':')?;
+            if field_name == ANY_TYPE_URL_KEY {
+                self.read_string()?;
+            } else if special_json {
+                if field_name == ANY_VALUE_KEY {
+                    self.merge_inner(&mut *message)?;
+                } else if self.parse_options.ignore_unknown_fields {
+                    self.skip_json_value()?;
+                } else {
+                    return Err(ParseErrorWithoutLoc(
+                        ParseErrorWithoutLocInner::UnknownFieldName(field_name),
+                    ));
+                }
+            } else {
+                self.merge_field_by_name(&mut *message, &descriptor, field_name)?;
+            }
+        }
+
+        any.type_url = type_url;
+        any.value = message
+            .write_to_bytes_dyn()
+            .map_err(|e| ParseErrorWithoutLoc(ParseErrorWithoutLocInner::AnyEncode(e)))?;
+        Ok(())
     }
 
     fn read_wk_value(&mut self) -> ParseResultWithoutLoc<Value> {
@@ -898,6 +996,10 @@ pub struct ParseOptions {
     /// When `true` fields with unknown names are ignored.
     /// When `false` parser returns an error on unknown field.
     pub ignore_unknown_fields: bool,
+    /// Types used to parse `google.protobuf.Any` messages.
+    ///
+    /// Parsing `Any` fails if its type URL cannot be resolved.
+    pub type_registry: TypeRegistry,
     /// Prevent initializing `ParseOptions` enumerating all field.
     pub _future_options: (),
 }
@@ -910,7 +1012,7 @@ pub fn merge_from_str_with_options(
 ) -> ParseResult<()> {
     let mut parser = Parser {
         tokenizer: Tokenizer::new(json, ParserLanguage::Json),
-        parse_options: parse_options.clone(),
+        parse_options,
     };
     parser.merge(message)
 }
@@ -928,9 +1030,13 @@ pub fn parse_dynamic_from_str_with_options(
 ) -> ParseResult<Box<dyn MessageDyn>> {
     let mut m = d.new_instance();
     merge_from_str_with_options(&mut *m, json, parse_options)?;
-    if let Err(_) = m.check_initialized_dyn() {
+    if let Err(ProtobufError::MessageNotInitialized { missing_fields, .. }) =
+        m.check_initialized_dyn()
+    {
         return Err(ParseError {
-            error: ParseErrorWithoutLoc(ParseErrorWithoutLocInner::MessageNotInitialized),
+            error: ParseErrorWithoutLoc(ParseErrorWithoutLocInner::MessageNotInitialized(
+                missing_fields,
+            )),
             loc: Loc::start(),
         });
     }
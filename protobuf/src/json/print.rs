@@ -1,3 +1,6 @@
+use crate::json::any::is_well_known_type_with_special_json;
+use crate::json::any::ANY_TYPE_URL_KEY;
+use crate::json::any::ANY_VALUE_KEY;
 use crate::json::base64;
 use crate::json::float;
 use crate::reflect::EnumDescriptor;
@@ -37,11 +40,14 @@ use crate::message_dyn::MessageDyn;
 use crate::reflect::EnumValueDescriptor;
 use crate::reflect::RuntimeFieldType;
 use crate::reflect::RuntimeTypeBox;
+use crate::reflect::TypeRegistry;
+use crate::ProtobufError;
 
 #[derive(Debug)]
 enum PrintErrorInner {
     Fmt(fmt::Error),
-    AnyPrintingIsNotImplemented,
+    AnyUnknownType(String),
+    AnyDecode(ProtobufError),
     TimestampNegativeNanos,
 }
 
@@ -49,6 +55,21 @@ enum PrintErrorInner {
 #[derive(Debug)]
 pub struct PrintError(PrintErrorInner);
 
+impl fmt::Display for PrintError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.0 {
+            PrintErrorInner::Fmt(e) => write!(f, "{}", e),
+            PrintErrorInner::AnyUnknownType(t) => {
+                write!(f, "cannot resolve Any type URL: {}", t)
+            }
+            PrintErrorInner::AnyDecode(e) => write!(f, "failed to decode Any value: {}", e),
+            PrintErrorInner::TimestampNegativeNanos => write!(f, "timestamp with negative nanos"),
+        }
+    }
+}
+
+impl std::error::Error for PrintError {}
+
 impl From<fmt::Error> for PrintError {
     fn from(e: fmt::Error) -> Self {
         PrintError(PrintErrorInner::Fmt(e))
@@ -57,9 +78,9 @@ impl From<fmt::Error> for PrintError {
 
 pub type PrintResult<T> = Result<T, PrintError>;
 
-struct Printer {
+struct Printer<'a> {
     buf: String,
-    print_options: PrintOptions,
+    print_options: &'a PrintOptions,
 }
 
 trait PrintableToJson {
@@ -242,8 +263,41 @@ impl PrintableToJson for FieldMask {
 }
 
 impl PrintableToJson for Any {
-    fn print_to_json(&self, _w: &mut Printer) -> PrintResult<()> {
-        Err(PrintError(PrintErrorInner::AnyPrintingIsNotImplemented))
+    fn print_to_json(&self, w: &mut Printer) -> PrintResult<()> {
+        if self.type_url.is_empty() && self.value.is_empty() {
+            return Ok(write!(w.buf, "{{}}")?);
+        }
+
+        let descriptor = match w
+            .print_options
+            .type_registry
+            .find_message_by_type_url(&self.type_url)
+        {
+            Some(descriptor) => descriptor,
+            None => {
+                return Err(PrintError(PrintErrorInner::AnyUnknownType(
+                    self.type_url.clone(),
+                )))
+            }
+        };
+
+        let mut message = descriptor.new_instance();
+        message
+            .merge_from_bytes_dyn(&self.value)
+            .map_err(|e| PrintError(PrintErrorInner::AnyDecode(e)))?;
+        let message = MessageRef::from(&*message);
+
+        write!(w.buf, "{{\"{}\": ", ANY_TYPE_URL_KEY)?;
+        w.print_printable(&self.type_url)?;
+        if is_well_known_type_with_special_json(&descriptor) {
+            write!(w.buf, ", \"{}\": ", ANY_VALUE_KEY)?;
+            w.print_message(&message)?;
+        } else {
+            let mut first = false;
+            w.print_message_fields(&message, &mut first)?;
+        }
+        write!(w.buf, "}}")?;
+        Ok(())
     }
 }
 
@@ -336,7 +390,7 @@ impl<'a, O: ObjectKey> ObjectKey for &'a O {
     }
 }
 
-impl Printer {
+impl<'a> Printer<'a> {
     fn print_comma_but_first(&mut self, first: &mut bool) -> fmt::Result {
         if *first {
             *first = false;
@@ -459,10 +513,16 @@ impl Printer {
     }
 
     fn print_regular_message(&mut self, message: &MessageRef) -> Result<(), PrintError> {
-        let descriptor = message.descriptor_dyn();
-
         write!(self.buf, "{{")?;
         let mut first = true;
+        self.print_message_fields(message, &mut first)?;
+        write!(self.buf, "}}")?;
+        Ok(())
+    }
+
+    fn print_message_fields(&mut self, message: &MessageRef, first: &mut bool) -> PrintResult<()> {
+        let descriptor = message.descriptor_dyn();
+
         for field in descriptor.fields() {
             let json_field_name = if self.print_options.proto_field_name {
                 field.get_name()
@@ -487,34 +547,33 @@ impl Printer {
 
                         if !is_message && !is_oneof {
                             let v = field.get_singular_field_or_default(&**message);
-                            self.print_comma_but_first(&mut first)?;
+                            self.print_comma_but_first(first)?;
                             write!(self.buf, "\"{}\": ", json_field_name)?;
                             self.print_printable(&v)?;
                         }
                     }
                 }
                 ReflectFieldRef::Optional(Some(v)) => {
-                    self.print_comma_but_first(&mut first)?;
+                    self.print_comma_but_first(first)?;
                     write!(self.buf, "\"{}\": ", json_field_name)?;
                     self.print_printable(&v)?;
                 }
                 ReflectFieldRef::Repeated(v) => {
                     if !v.is_empty() {
-                        self.print_comma_but_first(&mut first)?;
+                        self.print_comma_but_first(first)?;
                         write!(self.buf, "\"{}\": ", json_field_name)?;
                         self.print_repeated(&v)?;
                     }
                 }
                 ReflectFieldRef::Map(v) => {
                     if !v.is_empty() {
-                        self.print_comma_but_first(&mut first)?;
+                        self.print_comma_but_first(first)?;
                         write!(self.buf, "\"{}\": ", json_field_name)?;
                         self.print_map(&v)?;
                     }
                 }
             }
         }
-        write!(self.buf, "}}")?;
         Ok(())
     }
 
@@ -553,6 +612,10 @@ pub struct PrintOptions {
     pub proto_field_name: bool,
     /// Output field default values.
     pub always_output_default_values: bool,
+    /// Types used to print `google.protobuf.Any` messages.
+    ///
+    /// Printing `Any` fails if its type URL cannot be resolved.
+    pub type_registry: TypeRegistry,
     /// Prevent initializing `PrintOptions` enumerating all field.
     pub _future_options: (),
 }
@@ -564,7 +627,7 @@ pub fn print_to_string_with_options(
 ) -> PrintResult<String> {
     let mut printer = Printer {
         buf: String::new(),
-        print_options: print_options.clone(),
+        print_options,
     };
     printer.print_message(&MessageRef::from(message))?;
     Ok(printer.buf)
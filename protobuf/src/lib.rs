@@ -17,6 +17,7 @@ extern crate serde;
 extern crate serde_derive;
 pub use crate::clear::Clear;
 pub use crate::coded_input_stream::CodedInputStream;
+pub use crate::coded_input_stream::DecodeLimits;
 pub use crate::coded_output_stream::CodedOutputStream;
 pub use crate::enums::ProtobufEnum;
 pub use crate::enums::ProtobufEnumOrUnknown;
@@ -26,6 +27,7 @@ pub use crate::message_field::MessageField;
 pub use crate::oneof::Oneof;
 pub use crate::unknown::UnknownFields;
 pub use crate::unknown::UnknownFieldsIter;
+pub use crate::unknown::UnknownFieldsValuesIter;
 pub use crate::unknown::UnknownValue;
 pub use crate::unknown::UnknownValueRef;
 pub use crate::unknown::UnknownValues;
@@ -35,6 +37,8 @@ pub mod wire_format;
 pub use crate::chars::Chars;
 pub use crate::error::ProtobufError;
 pub use crate::error::ProtobufResult;
+pub use crate::error::DecodeErrorLocation;
+pub use crate::error::WireError;
 
 // generated
 pub mod descriptor;
@@ -48,12 +52,14 @@ mod coded_output_stream;
 mod enums;
 mod error;
 pub mod ext;
+pub mod field_mask;
 pub mod json;
 mod lazy_v2;
 mod message;
 mod message_dyn;
 mod message_field;
 mod oneof;
+pub mod raw_message;
 pub mod reflect;
 pub mod rt;
 pub mod text_format;
@@ -1,4 +1,5 @@
 use std::fmt;
+use std::hash::Hasher;
 use std::io::Read;
 use std::io::Write;
 
@@ -12,6 +13,7 @@ use crate::coded_output_stream::WithCodedOutputStream;
 use crate::error::ProtobufError;
 use crate::error::ProtobufResult;
 use crate::message_dyn::MessageDyn;
+use crate::reflect::find_missing_required_fields;
 use crate::reflect::reflect_eq::ReflectEqMode;
 use crate::reflect::MessageDescriptor;
 use crate::unknown::UnknownFields;
@@ -54,7 +56,7 @@ pub trait Message: fmt::Debug + Clear + Send + Sync + Sized + 'static {
     /// Parse message from stream.
     fn parse_from(is: &mut CodedInputStream) -> ProtobufResult<Self> {
         let mut r: Self = Message::new();
-        r.merge_from(is)?;
+        is.merge_from_located(&mut r)?;
         r.check_initialized()?;
         Ok(r)
     }
@@ -109,7 +111,7 @@ pub trait Message: fmt::Debug + Clear + Send + Sync + Sized + 'static {
     /// Update this message object with fields read from given stream.
     fn merge_from_bytes(&mut self, bytes: &[u8]) -> ProtobufResult<()> {
         let mut is = CodedInputStream::from_bytes(bytes);
-        self.merge_from(&mut is)
+        is.merge_from_located(self)
     }
 
     /// Parse message from reader.
@@ -151,9 +153,10 @@ pub trait Message: fmt::Debug + Clear + Send + Sync + Sized + 'static {
     /// Check if all required fields of this object are initialized.
     fn check_initialized(&self) -> ProtobufResult<()> {
         if !self.is_initialized() {
-            Err(ProtobufError::MessageNotInitialized(
-                self.descriptor_by_instance().name().to_owned(),
-            ))
+            Err(ProtobufError::MessageNotInitialized {
+                message: self.descriptor_by_instance().name().to_owned(),
+                missing_fields: find_missing_required_fields(self),
+            })
         } else {
             Ok(())
         }
@@ -190,6 +193,28 @@ pub trait Message: fmt::Debug + Clear + Send + Sync + Sized + 'static {
         Ok(v)
     }
 
+    /// Write the message to bytes vec using deterministic serialization:
+    /// map entries are sorted by key and unknown fields by field number.
+    ///
+    /// See [`CodedOutputStream::set_deterministic`].
+    fn write_to_bytes_deterministic(&self) -> ProtobufResult<Vec<u8>> {
+        (self as &dyn MessageDyn).write_to_new_vec(true)
+    }
+
+    /// Write the message to bytes vec placing each parsed unknown value
+    /// right after the known field which preceded it in the parsed input.
+    ///
+    /// Known fields are written in field number order and map entries are sorted by key,
+    /// unknown values added after parsing are written last.
+    /// So a message parsed from the output of this function, or from input
+    /// serialized in field number order with canonical encoding and
+    /// at most one map entry per map field, is written byte for byte as parsed.
+    ///
+    /// This function uses reflection, so it is slower than [`write_to_bytes`](Message::write_to_bytes).
+    fn write_to_bytes_preserving_order(&self) -> ProtobufResult<Vec<u8>> {
+        (self as &dyn MessageDyn).write_to_bytes_preserving_order_dyn()
+    }
+
     /// Write the message to the writer, prepend the message with message length
     /// encoded as varint.
     fn write_length_delimited_to_writer(&self, w: &mut dyn Write) -> ProtobufResult<()> {
@@ -237,4 +262,23 @@ pub trait Message: fmt::Debug + Clear + Send + Sync + Sized + 'static {
     fn reflect_eq(&self, other: &Self, mode: &ReflectEqMode) -> bool {
         MessageDyn::reflect_eq_dyn(self, other, mode)
     }
+
+    /// Reflective hash, consistent with [`reflect_eq`](Message::reflect_eq)
+    /// with the same `mode`.
+    ///
+    /// # See also
+    ///
+    /// [`dyn Message::reflect_hash_dyn()`], `dyn` version of this function.
+    fn reflect_hash(&self, state: &mut dyn Hasher, mode: &ReflectEqMode) {
+        <dyn MessageDyn>::reflect_hash_dyn(self, state, mode)
+    }
+
+    /// Merge fields of `other` message into this message.
+    ///
+    /// # See also
+    ///
+    /// [`dyn Message::merge_from_message_dyn()`], `dyn` version of this function.
+    fn merge_from_message(&mut self, other: &Self) {
+        <dyn MessageDyn>::merge_from_message_dyn(self, other)
+    }
 }
@@ -1,4 +1,7 @@
 use crate::coded_output_stream::WithCodedOutputStream;
+use crate::reflect::find_missing_required_fields;
+use crate::reflect::reflect_hash;
+use crate::reflect::wire;
 use crate::reflect::MessageDescriptor;
 use crate::reflect::ReflectEqMode;
 use crate::CodedInputStream;
@@ -10,6 +13,7 @@ use crate::UnknownFields;
 use std::any::Any;
 use std::any::TypeId;
 use std::fmt;
+use std::hash::Hasher;
 use std::io::Write;
 
 /// Dynamic-dispatch version of [`Message`].
@@ -26,6 +30,9 @@ pub trait MessageDyn: Any + fmt::Debug + Send + Sync + 'static {
     /// Compute (and cache) the message size.
     fn compute_size_dyn(&self) -> u32;
 
+    /// Get size previously computed by `compute_size_dyn`.
+    fn get_cached_size_dyn(&self) -> u32;
+
     /// True iff all required fields are initialized.
     /// Always returns `true` for protobuf 3.
     fn is_initialized_dyn(&self) -> bool;
@@ -42,7 +49,7 @@ impl<M: Message> MessageDyn for M {
     }
 
     fn merge_from_dyn(&mut self, is: &mut CodedInputStream) -> ProtobufResult<()> {
-        self.merge_from(is)
+        is.merge_from_located(self)
     }
 
     fn write_to_with_cached_sizes_dyn(&self, os: &mut CodedOutputStream) -> ProtobufResult<()> {
@@ -53,6 +60,10 @@ impl<M: Message> MessageDyn for M {
         self.compute_size()
     }
 
+    fn get_cached_size_dyn(&self) -> u32 {
+        self.get_cached_size()
+    }
+
     fn is_initialized_dyn(&self) -> bool {
         self.is_initialized()
     }
@@ -70,9 +81,10 @@ impl dyn MessageDyn {
     /// Check if all required fields of this object are initialized.
     pub fn check_initialized_dyn(&self) -> ProtobufResult<()> {
         if !self.is_initialized_dyn() {
-            Err(ProtobufError::MessageNotInitialized(
-                self.descriptor_dyn().name().to_owned(),
-            ))
+            Err(ProtobufError::MessageNotInitialized {
+                message: self.descriptor_dyn().name().to_owned(),
+                missing_fields: find_missing_required_fields(self),
+            })
         } else {
             Ok(())
         }
@@ -138,6 +150,39 @@ impl dyn MessageDyn {
         Ok(v)
     }
 
+    /// Write the message to bytes vec using deterministic serialization.
+    ///
+    /// See [`Message::write_to_bytes_deterministic`].
+    pub fn write_to_bytes_deterministic_dyn(&self) -> ProtobufResult<Vec<u8>> {
+        self.write_to_new_vec(true)
+    }
+
+    /// Write the message to bytes vec with unknown fields placed where they were parsed.
+    ///
+    /// See [`Message::write_to_bytes_preserving_order`].
+    pub fn write_to_bytes_preserving_order_dyn(&self) -> ProtobufResult<Vec<u8>> {
+        self.check_initialized_dyn()?;
+
+        let mut v = Vec::new();
+        (&mut v).with_coded_output_stream(|os| wire::write_preserving_order(self, os))?;
+        Ok(v)
+    }
+
+    /// Write the message to a new vec of exact size.
+    pub(crate) fn write_to_new_vec(&self, deterministic: bool) -> ProtobufResult<Vec<u8>> {
+        self.check_initialized_dyn()?;
+
+        let size = self.compute_size_dyn() as usize;
+        let mut v = Vec::with_capacity(size);
+        {
+            let mut os = CodedOutputStream::vec(&mut v);
+            os.set_deterministic(deterministic);
+            self.write_to_with_cached_sizes_dyn(&mut os)?;
+            os.flush()?;
+        }
+        Ok(v)
+    }
+
     /// Write the message to the stream prepending the message with message length
     /// encoded as varint.
     pub fn write_length_delimited_to_dyn(&self, os: &mut CodedOutputStream) -> ProtobufResult<()> {
@@ -223,6 +268,31 @@ impl dyn MessageDyn {
         self.descriptor_dyn().clone_message(self)
     }
 
+    /// Reflectively hash the message.
+    ///
+    /// Messages equal by [`reflect_eq_dyn`](Self::reflect_eq_dyn)
+    /// with the same `mode` have equal hashes: map entry order is ignored,
+    /// `NaN`s are hashed equally and unknown fields are hashed unless ignored.
+    /// Floats are not hashed at all when the mode has float tolerance.
+    pub fn reflect_hash_dyn(&self, state: &mut dyn Hasher, mode: &ReflectEqMode) {
+        reflect_hash::reflect_hash_message(self, state, mode)
+    }
+
+    /// Merge fields of `other` message into this message.
+    ///
+    /// Set singular fields overwrite, repeated fields are appended,
+    /// map entries are inserted, message fields are merged recursively
+    /// and unknown fields are concatenated, which is the same as
+    /// parsing serialized `other` into this message.
+    ///
+    /// # Panics
+    ///
+    /// If messages have different types.
+    pub fn merge_from_message_dyn(&mut self, other: &dyn MessageDyn) {
+        let descriptor = self.descriptor_dyn();
+        descriptor.merge_from_message(self, other)
+    }
+
     /// Reflectively compare the messages.
     pub fn reflect_eq_dyn(&self, other: &dyn MessageDyn, mode: &ReflectEqMode) -> bool {
         MessageDescriptor::reflect_eq_maybe_unrelated(self, other, mode)
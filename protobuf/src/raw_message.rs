@@ -0,0 +1,283 @@
+//! Decode messages without schema, like `protoc --decode_raw`.
+//!
+//! ```
+//! let raw = protobuf::raw_message::decode_raw(&[0x08, 0x96, 0x01, 0x12, 0x01, 0x61]).unwrap();
+//! assert_eq!("1: 150\n2: \"a\"\n", raw.print_to_string());
+//! ```
+
+use std::fmt;
+use std::str;
+
+use crate::coded_input_stream::CodedInputStream;
+use crate::error::ProtobufResult;
+use crate::rt;
+use crate::text_format::quote_bytes_to;
+use crate::unknown::UnknownFields;
+use crate::unknown::UnknownValueRef;
+
+// Do not try to decode length-delimited values as messages deeper than that.
+const MAX_DEPTH: u32 = 100;
+
+/// Message decoded without schema.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct RawMessage {
+    /// Fields in the order they are found in the input.
+    pub fields: Vec<RawField>,
+}
+
+/// Field of message decoded without schema.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RawField {
+    /// Field number.
+    pub number: u32,
+    /// Field value.
+    pub value: RawValue,
+}
+
+/// Value of field decoded without schema.
+///
+/// Length-delimited values are decoded speculatively:
+/// as a message if they can be parsed as message,
+/// otherwise as a string if they are valid UTF-8,
+/// otherwise as bytes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RawValue {
+    /// Varint value.
+    Varint(u64),
+    /// 32-bit fixed value.
+    Fixed32(u32),
+    /// 64-bit fixed value.
+    Fixed64(u64),
+    /// Length-delimited value which can be parsed as a message.
+    Message(RawMessage),
+    /// Length-delimited value which is valid UTF-8.
+    String(String),
+    /// Other length-delimited value.
+    Bytes(Vec<u8>),
+    /// Group.
+    Group(RawMessage),
+}
+
+/// Decode a message without schema.
+///
+/// Returns error if the input is not a valid sequence of fields.
+pub fn decode_raw(bytes: &[u8]) -> ProtobufResult<RawMessage> {
+    let unknown_fields = parse_unknown_fields(bytes)?;
+    Ok(RawMessage::from_unknown_fields(&unknown_fields))
+}
+
+fn parse_unknown_fields(bytes: &[u8]) -> ProtobufResult<UnknownFields> {
+    let mut is = CodedInputStream::from_bytes(bytes);
+    let mut unknown_fields = UnknownFields::new();
+    while !is.eof()? {
+        let (number, wire_type) = is.read_tag_unpack()?;
+        rt::read_unknown_or_skip_group(number, wire_type, &mut is, &mut unknown_fields)?;
+    }
+    Ok(unknown_fields)
+}
+
+impl RawMessage {
+    /// Build a tree from unknown fields,
+    /// decoding nested messages and groups.
+    pub fn from_unknown_fields(unknown_fields: &UnknownFields) -> RawMessage {
+        RawMessage::from_unknown_fields_depth(unknown_fields, 0)
+    }
+
+    fn from_unknown_fields_depth(unknown_fields: &UnknownFields, depth: u32) -> RawMessage {
+        RawMessage {
+            fields: unknown_fields
+                .iter_values()
+                .map(|(number, value)| RawField {
+                    number,
+                    value: RawValue::from_unknown_value(value, depth),
+                })
+                .collect(),
+        }
+    }
+
+    /// Print in text format, one field per line.
+    pub fn print_to_string(&self) -> String {
+        let mut buf = String::new();
+        self.print_to(0, &mut buf);
+        buf
+    }
+
+    fn print_to(&self, indent: usize, buf: &mut String) {
+        for field in &self.fields {
+            for _ in 0..indent {
+                buf.push_str("  ");
+            }
+            buf.push_str(&field.number.to_string());
+            match &field.value {
+                RawValue::Varint(v) => buf.push_str(&format!(": {}\n", v)),
+                RawValue::Fixed32(v) => buf.push_str(&format!(": 0x{:08x}\n", v)),
+                RawValue::Fixed64(v) => buf.push_str(&format!(": 0x{:016x}\n", v)),
+                RawValue::String(s) => {
+                    buf.push_str(": \"");
+                    quote_bytes_to(s.as_bytes(), buf);
+                    buf.push_str("\"\n");
+                }
+                RawValue::Bytes(b) => {
+                    buf.push_str(": \"");
+                    quote_bytes_to(b, buf);
+                    buf.push_str("\"\n");
+                }
+                RawValue::Message(m) | RawValue::Group(m) => {
+                    buf.push_str(" {\n");
+                    m.print_to(indent + 1, buf);
+                    for _ in 0..indent {
+                        buf.push_str("  ");
+                    }
+                    buf.push_str("}\n");
+                }
+            }
+        }
+    }
+}
+
+impl fmt::Display for RawMessage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.print_to_string())
+    }
+}
+
+impl RawValue {
+    fn from_unknown_value(value: UnknownValueRef, depth: u32) -> RawValue {
+        let fields = decode_unknown_message(&value, depth);
+        match value {
+            UnknownValueRef::Varint(v) => RawValue::Varint(v),
+            UnknownValueRef::Fixed32(v) => RawValue::Fixed32(v),
+            UnknownValueRef::Fixed64(v) => RawValue::Fixed64(v),
+            UnknownValueRef::LengthDelimited(bytes) => match fields {
+                Some(fields) => {
+                    RawValue::Message(RawMessage::from_unknown_fields_depth(&fields, depth + 1))
+                }
+                None => match str::from_utf8(bytes) {
+                    Ok(s) => RawValue::String(s.to_owned()),
+                    Err(_) => RawValue::Bytes(bytes.to_vec()),
+                },
+            },
+            UnknownValueRef::Group(bytes) => match fields {
+                Some(fields) => {
+                    RawValue::Group(RawMessage::from_unknown_fields_depth(&fields, depth + 1))
+                }
+                None => RawValue::Bytes(bytes.to_vec()),
+            },
+        }
+    }
+}
+
+/// Speculatively parse length-delimited or group value found
+/// at given nesting depth as a message.
+///
+/// Empty length-delimited values are not considered messages.
+pub(crate) fn decode_unknown_message(value: &UnknownValueRef, depth: u32) -> Option<UnknownFields> {
+    match value {
+        UnknownValueRef::LengthDelimited(bytes) if !bytes.is_empty() && depth < MAX_DEPTH => {
+            parse_unknown_fields(bytes).ok()
+        }
+        // Group content is validated when it is read
+        UnknownValueRef::Group(bytes) => parse_unknown_fields(bytes).ok(),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::decode_raw;
+    use super::RawField;
+    use super::RawMessage;
+    use super::RawValue;
+    use super::MAX_DEPTH;
+    use crate::hex::decode_hex;
+
+    fn decode_raw_hex(hex: &str) -> RawMessage {
+        decode_raw(&decode_hex(hex)).unwrap()
+    }
+
+    #[test]
+    fn scalars() {
+        let m = decode_raw_hex("08 96 01 15 2a 00 00 00 19 2a 00 00 00 00 00 00 00");
+        assert_eq!(
+            vec![
+                RawField {
+                    number: 1,
+                    value: RawValue::Varint(150)
+                },
+                RawField {
+                    number: 2,
+                    value: RawValue::Fixed32(42)
+                },
+                RawField {
+                    number: 3,
+                    value: RawValue::Fixed64(42)
+                },
+            ],
+            m.fields
+        );
+        assert_eq!(
+            "1: 150\n2: 0x0000002a\n3: 0x000000000000002a\n",
+            m.print_to_string()
+        );
+    }
+
+    #[test]
+    fn length_delimited() {
+        // nested message, string, bytes, empty
+        let m = decode_raw_hex("0a 02 08 01 12 05 68 65 6c 6c 6f 1a 02 ff 00 22 00");
+        assert_eq!(
+            "1 {\n  1: 1\n}\n2: \"hello\"\n3: \"\\377\\000\"\n4: \"\"\n",
+            m.print_to_string()
+        );
+    }
+
+    #[test]
+    fn group() {
+        let m = decode_raw_hex("0b 10 02 1b 08 03 1c 0c");
+        assert_eq!(
+            "1 {\n  2: 2\n  3 {\n    1: 3\n  }\n}\n",
+            m.print_to_string()
+        );
+        assert!(matches!(m.fields[0].value, RawValue::Group(..)));
+    }
+
+    #[test]
+    fn field_order() {
+        let m = decode_raw_hex("10 01 08 02 10 03");
+        assert_eq!("2: 1\n1: 2\n2: 3\n", m.print_to_string());
+    }
+
+    #[test]
+    fn max_depth() {
+        let mut bytes = vec![0x08, 0x01];
+        for _ in 0..MAX_DEPTH + 10 {
+            let mut outer = vec![0x0a];
+            let mut len = bytes.len();
+            while len >= 0x80 {
+                outer.push((len as u8) | 0x80);
+                len >>= 7;
+            }
+            outer.push(len as u8);
+            outer.extend_from_slice(&bytes);
+            bytes = outer;
+        }
+
+        let mut m = decode_raw(&bytes).unwrap();
+        let mut depth = 0;
+        loop {
+            match m.fields.pop().unwrap().value {
+                RawValue::Message(nested) => m = nested,
+                RawValue::Bytes(..) | RawValue::String(..) => break,
+                value => panic!("unexpected value: {:?}", value),
+            }
+            depth += 1;
+        }
+        assert_eq!(MAX_DEPTH, depth);
+    }
+
+    #[test]
+    fn malformed() {
+        assert!(decode_raw(&decode_hex("0a 05 01")).is_err());
+        assert!(decode_raw(&decode_hex("0c")).is_err());
+    }
+}
@@ -23,6 +23,7 @@ trait OptionLike<T> {
     fn as_option_ref(&self) -> Option<&T>;
     fn as_option_mut(&mut self) -> Option<&mut T>;
     fn set_value(&mut self, value: T);
+    fn clear_value(&mut self);
 }
 
 impl<T> OptionLike<T> for Option<T> {
@@ -37,6 +38,10 @@ impl<T> OptionLike<T> for Option<T> {
     fn set_value(&mut self, value: T) {
         *self = Some(value);
     }
+
+    fn clear_value(&mut self) {
+        *self = None;
+    }
 }
 
 impl<T> OptionLike<T> for MessageField<T> {
@@ -51,6 +56,10 @@ impl<T> OptionLike<T> for MessageField<T> {
     fn set_value(&mut self, value: T) {
         *self = MessageField::some(value);
     }
+
+    fn clear_value(&mut self) {
+        *self = MessageField::none();
+    }
 }
 
 /// This trait should not be used directly, use `FieldDescriptor` instead
@@ -59,6 +68,7 @@ pub(crate) trait SingularFieldAccessor: Send + Sync + 'static {
     fn get_field_or_default<'a>(&self, m: &'a dyn MessageDyn) -> ReflectValueRef<'a>;
     fn mut_field_or_default<'a>(&self, m: &'a mut dyn MessageDyn) -> ReflectValueMut<'a>;
     fn set_field(&self, m: &mut dyn MessageDyn, value: ReflectValueBox);
+    fn clear_field(&self, m: &mut dyn MessageDyn);
 }
 
 pub(crate) struct SingularFieldAccessorHolder {
@@ -85,6 +95,7 @@ trait MutOrDefaultImpl<M>: Send + Sync + 'static {
 
 trait SetImpl<M>: Send + Sync + 'static {
     fn set_singular_field(&self, m: &mut M, value: ReflectValueBox);
+    fn clear_singular_field(&self, m: &mut M);
 }
 
 struct MutOrDefaultUnmplemented<M>
@@ -160,6 +171,11 @@ where
         let m = m.downcast_mut().unwrap();
         self.set_impl.set_singular_field(m, value)
     }
+
+    fn clear_field(&self, m: &mut dyn MessageDyn) {
+        let m = m.downcast_mut().unwrap();
+        self.set_impl.clear_singular_field(m)
+    }
 }
 
 struct GetOptionImplFieldPointer<M, V>
@@ -432,6 +448,7 @@ where
     V: ProtobufValue,
 {
     set_field: for<'a> fn(&'a mut M, V),
+    clear_field: for<'a> fn(&'a mut M),
 }
 
 impl<M, V> SetImpl<M> for SetImplFieldPointer<M, V>
@@ -442,6 +459,10 @@ where
     fn set_singular_field(&self, m: &mut M, value: ReflectValueBox) {
         V::set_from_value_box((self.mut_field)(m), value);
     }
+
+    fn clear_singular_field(&self, m: &mut M) {
+        *(self.mut_field)(m) = V::default();
+    }
 }
 
 impl<M, V, O> SetImpl<M> for SetImplOptionFieldPointer<M, V, O>
@@ -453,6 +474,10 @@ where
     fn set_singular_field(&self, m: &mut M, value: ReflectValueBox) {
         (self.mut_field)(m).set_value(V::from_value_box(value).expect("wrong type"));
     }
+
+    fn clear_singular_field(&self, m: &mut M) {
+        (self.mut_field)(m).clear_value();
+    }
 }
 
 impl<M, V> SetImpl<M> for SetImplSetField<M, V>
@@ -464,6 +489,10 @@ where
         let value = value.downcast::<V>().expect("message");
         (self.set_field)(m, value)
     }
+
+    fn clear_singular_field(&self, m: &mut M) {
+        (self.clear_field)(m)
+    }
 }
 
 /// Make accessor for `SingularPtrField`
@@ -0,0 +1,647 @@
+//! Structured differences between messages.
+
+use std::cmp::Ordering;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::fmt;
+use std::fmt::Write;
+use std::hash::Hasher;
+
+use crate::reflect::map::map_key_to_string;
+use crate::reflect::FieldDescriptor;
+use crate::reflect::ReflectEq;
+use crate::reflect::ReflectEqMode;
+use crate::reflect::ReflectFieldRef;
+use crate::reflect::ReflectHash;
+use crate::reflect::ReflectMapRef;
+use crate::reflect::ReflectRepeatedRef;
+use crate::reflect::ReflectValueRef;
+use crate::reflect::RuntimeFieldType;
+use crate::reflect::RuntimeTypeBox;
+use crate::text_format;
+use crate::text_format::quote_bytes_to;
+use crate::unknown::UnknownFields;
+use crate::unknown::UnknownValueRef;
+use crate::MessageDyn;
+
+/// Kind of [`FieldDiff`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiffKind {
+    /// Value is present only in the right message.
+    Added,
+    /// Value is present only in the left message.
+    Removed,
+    /// Values are present in both messages, but differ.
+    Changed,
+}
+
+/// Single difference between two messages.
+#[derive(Debug, Clone)]
+pub struct FieldDiff<'a> {
+    /// Path to the value like `a.b[2].c`.
+    ///
+    /// Map entries are referenced by key like `m["key"]`,
+    /// elements of repeated fields compared by key like `items[id=10]`,
+    /// unknown fields by number like `a.15`.
+    pub path: String,
+    /// Kind of the difference.
+    pub kind: DiffKind,
+    /// Value in the left message, `None` for added value.
+    pub left: Option<ReflectValueRef<'a>>,
+    /// Value in the right message, `None` for removed value.
+    pub right: Option<ReflectValueRef<'a>>,
+}
+
+/// Options for [`diff_messages`].
+#[derive(Default)]
+pub struct DiffOptions {
+    /// How values are compared.
+    ///
+    /// All options of the mode are honored: ignored fields and unknown fields
+    /// (with [`ReflectEqMode::ignore_unknown_fields`]) are not reported,
+    /// unset fields equal to defaults are not reported
+    /// with [`ReflectEqMode::default_equals_unset`],
+    /// and with [`ReflectEqMode::unordered_repeated`] elements of repeated fields
+    /// are matched regardless of order, unmatched elements are reported
+    /// as removed or added at their indices.
+    pub eq_mode: ReflectEqMode,
+    /// Pairs of repeated message field and key field of the element message,
+    /// see [`DiffOptions::compare_repeated_by_key`].
+    pub repeated_keys: Vec<(FieldDescriptor, FieldDescriptor)>,
+}
+
+impl DiffOptions {
+    /// Compare elements of repeated message field by the value of given key field
+    /// of the element rather than by index.
+    ///
+    /// # Panics
+    ///
+    /// If `field` is not a repeated message field or `key` is not
+    /// a singular field of the element message.
+    pub fn compare_repeated_by_key(&mut self, field: &FieldDescriptor, key: &FieldDescriptor) {
+        match field.runtime_field_type() {
+            RuntimeFieldType::Repeated(RuntimeTypeBox::Message(m)) => {
+                assert_eq!(m, key.message_descriptor, "key field of different message");
+            }
+            _ => panic!("not a repeated message field: {}", field),
+        }
+        assert!(key.is_singular(), "key field must be singular: {}", key);
+        self.repeated_keys.push((field.clone(), key.clone()));
+    }
+
+    fn repeated_key(&self, field: &FieldDescriptor) -> Option<&FieldDescriptor> {
+        self.repeated_keys
+            .iter()
+            .find(|(f, _)| f == field)
+            .map(|(_, k)| k)
+    }
+}
+
+/// Differences between two messages.
+#[derive(Debug, Clone, Default)]
+pub struct MessageDiff<'a> {
+    /// Differences in field order.
+    pub diffs: Vec<FieldDiff<'a>>,
+}
+
+impl<'a> MessageDiff<'a> {
+    /// Messages are equal.
+    pub fn is_empty(&self) -> bool {
+        self.diffs.is_empty()
+    }
+
+    /// Print differences one per line, like:
+    ///
+    /// ```text
+    /// changed: name: "a" -> "b"
+    /// added: items[1]: { id: 10 }
+    /// ```
+    pub fn print_to_string(&self) -> String {
+        let mut buf = String::new();
+        for diff in &self.diffs {
+            match (diff.kind, &diff.left, &diff.right) {
+                (DiffKind::Added, _, Some(right)) => {
+                    write!(buf, "added: {}: ", diff.path).unwrap();
+                    print_value(right, &mut buf);
+                }
+                (DiffKind::Removed, Some(left), _) => {
+                    write!(buf, "removed: {}: ", diff.path).unwrap();
+                    print_value(left, &mut buf);
+                }
+                (DiffKind::Changed, Some(left), Some(right)) => {
+                    write!(buf, "changed: {}: ", diff.path).unwrap();
+                    print_value(left, &mut buf);
+                    buf.push_str(" -> ");
+                    print_value(right, &mut buf);
+                }
+                _ => write!(buf, "{:?}: {}", diff.kind, diff.path).unwrap(),
+            }
+            buf.push('\n');
+        }
+        buf
+    }
+}
+
+impl<'a> fmt::Display for MessageDiff<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.print_to_string())
+    }
+}
+
+/// Print value in text format.
+fn print_value(value: &ReflectValueRef, buf: &mut String) {
+    match value {
+        ReflectValueRef::Message(m) => {
+            let text = text_format::print_to_string(&**m);
+            if text.is_empty() {
+                buf.push_str("{}");
+            } else {
+                write!(buf, "{{ {} }}", text).unwrap();
+            }
+        }
+        ReflectValueRef::Enum(d, v) => match d.get_value_by_number(*v) {
+            Some(e) => buf.push_str(e.get_name()),
+            None => write!(buf, "{}", v).unwrap(),
+        },
+        ReflectValueRef::String(s) => {
+            buf.push('"');
+            quote_bytes_to(s.as_bytes(), buf);
+            buf.push('"');
+        }
+        ReflectValueRef::Bytes(b) => {
+            buf.push('"');
+            quote_bytes_to(b, buf);
+            buf.push('"');
+        }
+        ReflectValueRef::U32(v) => write!(buf, "{}", v).unwrap(),
+        ReflectValueRef::U64(v) => write!(buf, "{}", v).unwrap(),
+        ReflectValueRef::I32(v) => write!(buf, "{}", v).unwrap(),
+        ReflectValueRef::I64(v) => write!(buf, "{}", v).unwrap(),
+        ReflectValueRef::F32(v) => write!(buf, "{}", v).unwrap(),
+        ReflectValueRef::F64(v) => write!(buf, "{}", v).unwrap(),
+        ReflectValueRef::Bool(v) => write!(buf, "{}", v).unwrap(),
+    }
+}
+
+fn value_to_string(value: &ReflectValueRef) -> String {
+    let mut buf = String::new();
+    print_value(value, &mut buf);
+    buf
+}
+
+/// Compare two messages of the same type field by field.
+///
+/// Singular fields are compared by value, nested messages recursively,
+/// repeated fields by index (or by key, see [`DiffOptions::compare_repeated_by_key`]),
+/// map fields by key, unknown fields by field number and index.
+///
+/// # Panics
+///
+/// If messages are of different types.
+pub fn diff_messages<'a>(
+    left: &'a dyn MessageDyn,
+    right: &'a dyn MessageDyn,
+    options: &DiffOptions,
+) -> MessageDiff<'a> {
+    assert_eq!(left.descriptor_dyn(), right.descriptor_dyn());
+    let mut differ = Differ {
+        options,
+        field_path: String::new(),
+        diffs: Vec::new(),
+    };
+    differ.diff_message(left, right, "");
+    MessageDiff {
+        diffs: differ.diffs,
+    }
+}
+
+struct Differ<'a, 'o> {
+    options: &'o DiffOptions,
+    /// Path of the current field for [`ReflectEqMode::ignore_fields`],
+    /// field names only, without indices and keys.
+    field_path: String,
+    diffs: Vec<FieldDiff<'a>>,
+}
+
+impl<'a, 'o> Differ<'a, 'o> {
+    fn push(
+        &mut self,
+        path: String,
+        left: Option<ReflectValueRef<'a>>,
+        right: Option<ReflectValueRef<'a>>,
+    ) {
+        let kind = match (&left, &right) {
+            (None, Some(..)) => DiffKind::Added,
+            (Some(..), None) => DiffKind::Removed,
+            _ => DiffKind::Changed,
+        };
+        self.diffs.push(FieldDiff {
+            path,
+            kind,
+            left,
+            right,
+        });
+    }
+
+    fn diff_message(&mut self, left: &'a dyn MessageDyn, right: &'a dyn MessageDyn, prefix: &str) {
+        for field in left.descriptor_dyn().fields() {
+            let len = self.field_path.len();
+            if len != 0 {
+                self.field_path.push('.');
+            }
+            self.field_path.push_str(field.get_name());
+
+            if !self.options.eq_mode.is_field_ignored(&self.field_path) {
+                let path = format!("{}{}", prefix, field.get_name());
+                match (field.get_reflect(left), field.get_reflect(right)) {
+                    (ReflectFieldRef::Optional(l), ReflectFieldRef::Optional(r)) => {
+                        // value of the unset field, for `default_equals_unset`
+                        let default = match (&l, &r) {
+                            (None, Some(..)) if self.options.eq_mode.default_equals_unset => {
+                                Some(field.get_singular_field_or_default(left))
+                            }
+                            (Some(..), None) if self.options.eq_mode.default_equals_unset => {
+                                Some(field.get_singular_field_or_default(right))
+                            }
+                            _ => None,
+                        };
+                        self.diff_optional(path, l, r, default)
+                    }
+                    (ReflectFieldRef::Repeated(l), ReflectFieldRef::Repeated(r)) => {
+                        match self.options.repeated_key(&field) {
+                            Some(key) => self.diff_repeated_by_key(&path, l, r, key),
+                            None if self.options.eq_mode.unordered_repeated => {
+                                self.diff_repeated_unordered(&path, l, r)
+                            }
+                            None => self.diff_repeated(&path, l, r),
+                        }
+                    }
+                    (ReflectFieldRef::Map(l), ReflectFieldRef::Map(r)) => {
+                        self.diff_map(&path, l, r)
+                    }
+                    _ => unreachable!("same field of the same message type"),
+                }
+            }
+
+            self.field_path.truncate(len);
+        }
+
+        if !self.options.eq_mode.ignore_unknown_fields {
+            self.diff_unknown(
+                left.get_unknown_fields_dyn(),
+                right.get_unknown_fields_dyn(),
+                prefix,
+            );
+        }
+    }
+
+    fn diff_optional(
+        &mut self,
+        path: String,
+        left: Option<ReflectValueRef<'a>>,
+        right: Option<ReflectValueRef<'a>>,
+        default: Option<ReflectValueRef<'a>>,
+    ) {
+        match (left, right, default) {
+            (None, None, _) => {}
+            (Some(l), Some(r), _) => self.diff_values(path, l, r),
+            (Some(v), None, Some(d)) | (None, Some(v), Some(d)) if self.value_eq(&v, &d) => {}
+            (l, r, _) => self.push(path, l, r),
+        }
+    }
+
+    /// Values are equal according to `eq_mode` at the current field path.
+    fn value_eq(&mut self, left: &ReflectValueRef, right: &ReflectValueRef) -> bool {
+        left.reflect_eq_at(right, &self.options.eq_mode, &mut self.field_path)
+    }
+
+    fn diff_values(&mut self, path: String, left: ReflectValueRef<'a>, right: ReflectValueRef<'a>) {
+        if let (ReflectValueRef::Message(l), ReflectValueRef::Message(r)) = (&left, &right) {
+            if let (Some(l), Some(r)) = (l.as_message_dyn(), r.as_message_dyn()) {
+                if l.descriptor_dyn() == r.descriptor_dyn() {
+                    self.diff_message(l, r, &format!("{}.", path));
+                    return;
+                }
+            }
+        }
+        if !self.value_eq(&left, &right) {
+            self.push(path, Some(left), Some(right));
+        }
+    }
+
+    fn diff_repeated(
+        &mut self,
+        path: &str,
+        left: ReflectRepeatedRef<'a>,
+        right: ReflectRepeatedRef<'a>,
+    ) {
+        let mut left = left.into_iter();
+        let mut right = right.into_iter();
+        for i in 0.. {
+            let path = format!("{}[{}]", path, i);
+            match (left.next(), right.next()) {
+                (None, None) => break,
+                (Some(l), Some(r)) => self.diff_values(path, l, r),
+                (l, r) => self.push(path, l, r),
+            }
+        }
+    }
+
+    fn diff_repeated_unordered(
+        &mut self,
+        path: &str,
+        left: ReflectRepeatedRef<'a>,
+        right: ReflectRepeatedRef<'a>,
+    ) {
+        let right: Vec<ReflectValueRef<'a>> = right.into_iter().collect();
+        let mut right_matched = vec![false; right.len()];
+
+        for (i, l) in left.into_iter().enumerate() {
+            let found =
+                (0..right.len()).find(|&j| !right_matched[j] && self.value_eq(&l, &right[j]));
+            match found {
+                Some(j) => right_matched[j] = true,
+                None => self.push(format!("{}[{}]", path, i), Some(l), None),
+            }
+        }
+
+        for (j, (r, matched)) in right.into_iter().zip(right_matched).enumerate() {
+            if !matched {
+                self.push(format!("{}[{}]", path, j), None, Some(r));
+            }
+        }
+    }
+
+    fn diff_repeated_by_key(
+        &mut self,
+        path: &str,
+        left: ReflectRepeatedRef<'a>,
+        right: ReflectRepeatedRef<'a>,
+        key: &FieldDescriptor,
+    ) {
+        let key_path = |value: &Option<ReflectValueRef>| -> String {
+            let value = match value {
+                Some(value) => key_to_string(value),
+                None => "?".to_owned(),
+            };
+            format!("{}[{}={}]", path, key.get_name(), value)
+        };
+        let eq_mode = &self.options.eq_mode;
+        let key_hash = |value: &ReflectValueRef| {
+            let mut hasher = DefaultHasher::new();
+            value.reflect_hash(&mut hasher, eq_mode);
+            hasher.finish()
+        };
+
+        let right: Vec<(Option<ReflectValueRef<'a>>, ReflectValueRef<'a>)> = right
+            .into_iter()
+            .map(|v| (element_key(&v, key), v))
+            .collect();
+        let mut right_matched = vec![false; right.len()];
+        // indices of right elements by key hash, in order
+        let mut right_by_key: HashMap<u64, Vec<usize>> = HashMap::new();
+        for (i, (r_key, _)) in right.iter().enumerate() {
+            if let Some(r_key) = r_key {
+                right_by_key.entry(key_hash(r_key)).or_default().push(i);
+            }
+        }
+
+        for l in left {
+            let l_key = element_key(&l, key);
+            let found = l_key.as_ref().and_then(|l_key| {
+                let candidates = right_by_key.get_mut(&key_hash(l_key))?;
+                let pos = candidates.iter().position(|&i| match &right[i].0 {
+                    Some(r_key) => r_key.reflect_eq(l_key, eq_mode),
+                    None => false,
+                })?;
+                Some(candidates.remove(pos))
+            });
+            match found {
+                Some(i) => {
+                    right_matched[i] = true;
+                    self.diff_values(key_path(&l_key), l, right[i].1.clone());
+                }
+                None => self.push(key_path(&l_key), Some(l), None),
+            }
+        }
+
+        for ((r_key, r), matched) in right.into_iter().zip(right_matched) {
+            if !matched {
+                self.push(key_path(&r_key), None, Some(r));
+            }
+        }
+    }
+
+    fn diff_map(&mut self, path: &str, left: ReflectMapRef<'a>, right: ReflectMapRef<'a>) {
+        let sorted_entries = |map: &ReflectMapRef<'a>| {
+            let mut entries: Vec<(ReflectValueRef<'a>, ReflectValueRef<'a>)> =
+                map.into_iter().collect();
+            entries.sort_by(|a, b| compare_map_keys(&a.0, &b.0));
+            entries
+        };
+        let mut left = sorted_entries(&left).into_iter().peekable();
+        let mut right = sorted_entries(&right).into_iter().peekable();
+
+        loop {
+            let ordering = match (left.peek(), right.peek()) {
+                (None, None) => break,
+                (Some(..), None) => Ordering::Less,
+                (None, Some(..)) => Ordering::Greater,
+                (Some((l, _)), Some((r, _))) => compare_map_keys(l, r),
+            };
+            match ordering {
+                Ordering::Less => {
+                    let (k, v) = left.next().unwrap();
+                    self.push(map_entry_path(path, &k), Some(v), None);
+                }
+                Ordering::Greater => {
+                    let (k, v) = right.next().unwrap();
+                    self.push(map_entry_path(path, &k), None, Some(v));
+                }
+                Ordering::Equal => {
+                    let (k, l) = left.next().unwrap();
+                    let (_, r) = right.next().unwrap();
+                    self.diff_values(map_entry_path(path, &k), l, r);
+                }
+            }
+        }
+    }
+
+    fn diff_unknown(&mut self, left: &'a UnknownFields, right: &'a UnknownFields, prefix: &str) {
+        let mut numbers = BTreeMap::new();
+        for (number, value) in left.iter_values() {
+            numbers
+                .entry(number)
+                .or_insert_with(|| (Vec::new(), Vec::new()))
+                .0
+                .push(value);
+        }
+        for (number, value) in right.iter_values() {
+            numbers
+                .entry(number)
+                .or_insert_with(|| (Vec::new(), Vec::new()))
+                .1
+                .push(value);
+        }
+
+        for (number, (left, right)) in numbers {
+            let indexed = left.len() > 1 || right.len() > 1;
+            let mut left = left.into_iter();
+            let mut right = right.into_iter();
+            for i in 0.. {
+                let path = match indexed {
+                    true => format!("{}{}[{}]", prefix, number, i),
+                    false => format!("{}{}", prefix, number),
+                };
+                match (left.next(), right.next()) {
+                    (None, None) => break,
+                    (Some(l), Some(r)) => {
+                        if !unknown_value_eq(&l, &r) {
+                            self.push(path, Some(unknown_to_value(l)), Some(unknown_to_value(r)));
+                        }
+                    }
+                    (l, r) => self.push(path, l.map(unknown_to_value), r.map(unknown_to_value)),
+                }
+            }
+        }
+    }
+}
+
+/// Value of key field of repeated field element.
+fn element_key<'a>(
+    element: &ReflectValueRef<'a>,
+    key: &FieldDescriptor,
+) -> Option<ReflectValueRef<'a>> {
+    match element {
+        ReflectValueRef::Message(m) => m
+            .as_message_dyn()
+            .map(|m| key.get_singular_field_or_default(m)),
+        _ => None,
+    }
+}
+
+/// Key of map entry or of repeated field element as it is written in path.
+fn key_to_string(key: &ReflectValueRef) -> String {
+    match map_key_to_string(key, true) {
+        Some(key) => key,
+        None => value_to_string(key),
+    }
+}
+
+fn map_entry_path(path: &str, key: &ReflectValueRef) -> String {
+    format!("{}[{}]", path, key_to_string(key))
+}
+
+fn compare_map_keys(a: &ReflectValueRef, b: &ReflectValueRef) -> Ordering {
+    match (a, b) {
+        (ReflectValueRef::U32(a), ReflectValueRef::U32(b)) => a.cmp(b),
+        (ReflectValueRef::U64(a), ReflectValueRef::U64(b)) => a.cmp(b),
+        (ReflectValueRef::I32(a), ReflectValueRef::I32(b)) => a.cmp(b),
+        (ReflectValueRef::I64(a), ReflectValueRef::I64(b)) => a.cmp(b),
+        (ReflectValueRef::Bool(a), ReflectValueRef::Bool(b)) => a.cmp(b),
+        (ReflectValueRef::String(a), ReflectValueRef::String(b)) => a.cmp(b),
+        // not valid map key types
+        _ => Ordering::Equal,
+    }
+}
+
+fn unknown_value_eq(a: &UnknownValueRef, b: &UnknownValueRef) -> bool {
+    match (a, b) {
+        (UnknownValueRef::Fixed32(a), UnknownValueRef::Fixed32(b)) => a == b,
+        (UnknownValueRef::Fixed64(a), UnknownValueRef::Fixed64(b)) => a == b,
+        (UnknownValueRef::Varint(a), UnknownValueRef::Varint(b)) => a == b,
+        (UnknownValueRef::LengthDelimited(a), UnknownValueRef::LengthDelimited(b)) => a == b,
+        (UnknownValueRef::Group(a), UnknownValueRef::Group(b)) => a == b,
+        _ => false,
+    }
+}
+
+fn unknown_to_value(value: UnknownValueRef) -> ReflectValueRef {
+    match value {
+        UnknownValueRef::Fixed32(v) => ReflectValueRef::U32(v),
+        UnknownValueRef::Fixed64(v) => ReflectValueRef::U64(v),
+        UnknownValueRef::Varint(v) => ReflectValueRef::U64(v),
+        UnknownValueRef::LengthDelimited(v) => ReflectValueRef::Bytes(v),
+        UnknownValueRef::Group(v) => ReflectValueRef::Bytes(v),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::diff_messages;
+    use super::DiffKind;
+    use super::DiffOptions;
+    use crate::descriptor::DescriptorProto;
+    use crate::descriptor::FieldDescriptorProto;
+    use crate::reflect::ReflectValueRef;
+    use crate::Message;
+
+    #[test]
+    fn nested_and_repeated() {
+        let mut left = DescriptorProto::new();
+        left.set_name("a".to_owned());
+        left.field.push(FieldDescriptorProto::new());
+        left.field[0].set_name("x".to_owned());
+
+        let mut right = left.clone();
+        right.set_name("b".to_owned());
+        right.field[0].set_number(1);
+        right.field.push(FieldDescriptorProto::new());
+
+        let diff = diff_messages(&left, &right, &DiffOptions::default());
+        let kinds: Vec<(&str, DiffKind)> =
+            diff.diffs.iter().map(|d| (&d.path[..], d.kind)).collect();
+        assert_eq!(
+            vec![
+                ("name", DiffKind::Changed),
+                ("field[0].number", DiffKind::Added),
+                ("field[1]", DiffKind::Added),
+            ],
+            kinds
+        );
+        assert!(matches!(
+            diff.diffs[0].left,
+            Some(ReflectValueRef::String("a"))
+        ));
+        assert_eq!(
+            "changed: name: \"a\" -> \"b\"\n\
+             added: field[0].number: 1\n\
+             added: field[1]: {}\n",
+            diff.print_to_string()
+        );
+
+        assert!(diff_messages(&left, &left.clone(), &DiffOptions::default()).is_empty());
+    }
+
+    #[test]
+    fn repeated_by_key() {
+        let field = |name: &str, number: i32| {
+            let mut f = FieldDescriptorProto::new();
+            f.set_name(name.to_owned());
+            f.set_number(number);
+            f
+        };
+        let mut left = DescriptorProto::new();
+        left.field.push(field("a", 1));
+        left.field.push(field("b", 2));
+        let mut right = DescriptorProto::new();
+        right.field.push(field("c", 3));
+        right.field.push(field("b", 4));
+
+        let descriptor = DescriptorProto::descriptor_static();
+        let mut options = DiffOptions::default();
+        options.compare_repeated_by_key(
+            &descriptor.get_field_by_name("field").unwrap(),
+            &FieldDescriptorProto::descriptor_static()
+                .get_field_by_name("name")
+                .unwrap(),
+        );
+        assert_eq!(
+            "removed: field[name=\"a\"]: { name: \"a\" number: 1 }\n\
+             changed: field[name=\"b\"].number: 2 -> 4\n\
+             added: field[name=\"c\"]: { name: \"c\" number: 3 }\n",
+            diff_messages(&left, &right, &options).print_to_string()
+        );
+    }
+}
@@ -1,14 +1,24 @@
-use crate::{CodedInputStream, CodedOutputStream, ProtobufResult, reflect::map::ReflectMap, wire_format::WireType};
+use crate::descriptor::field_descriptor_proto::Type;
+use crate::reflect::map::ReflectMap;
 use crate::reflect::map::ReflectMapIter;
 use crate::reflect::map::ReflectMapIterTrait;
 use crate::reflect::runtime_types::RuntimeType;
+use crate::reflect::wire;
 use crate::reflect::ProtobufValue;
 use crate::reflect::ReflectValueBox;
 use crate::reflect::ReflectValueRef;
 use crate::reflect::RuntimeTypeBox;
+use crate::rt;
+use crate::unknown::UnknownFields;
+use crate::wire_format::WireType;
+use crate::CodedInputStream;
+use crate::CodedOutputStream;
+use crate::ProtobufResult;
+use std::cmp::Ordering;
 use std::collections::hash_map;
 use std::collections::HashMap;
 use std::hash::Hash;
+use std::mem;
 
 #[derive(Debug, Clone)]
 enum Maps {
@@ -66,6 +76,19 @@ impl Maps {
     }
 }
 
+/// Compare keys of the same map.
+pub(crate) fn cmp_keys(a: &ReflectValueRef, b: &ReflectValueRef) -> Ordering {
+    match (a, b) {
+        (ReflectValueRef::U32(a), ReflectValueRef::U32(b)) => a.cmp(b),
+        (ReflectValueRef::I32(a), ReflectValueRef::I32(b)) => a.cmp(b),
+        (ReflectValueRef::U64(a), ReflectValueRef::U64(b)) => a.cmp(b),
+        (ReflectValueRef::I64(a), ReflectValueRef::I64(b)) => a.cmp(b),
+        (ReflectValueRef::Bool(a), ReflectValueRef::Bool(b)) => a.cmp(b),
+        (ReflectValueRef::String(a), ReflectValueRef::String(b)) => a.cmp(b),
+        (a, b) => panic!("keys of different types: {:?}, {:?}", a, b),
+    }
+}
+
 #[derive(Debug, Clone)]
 pub(crate) struct DynamicMap {
     value: RuntimeTypeBox,
@@ -88,58 +111,128 @@ impl DynamicMap {
         }
     }
 
-    pub fn compute_size(&self, field_number: u32) -> u32 {
+    fn entry_size(
+        k: &ReflectValueRef,
+        v: &ReflectValueRef,
+        key_type: Type,
+        value_type: Type,
+    ) -> u32 {
+        wire::compute_size(1, key_type, k) + wire::compute_size(2, value_type, v)
+    }
+
+    pub fn compute_size(&self, field_number: u32, key_type: Type, value_type: Type) -> u32 {
         self.reflect_iter()
             .map(|(k, v)| {
-                let entry_len = crate::rt::tag_size(1) + k.to_box().compute_size() + crate::rt::tag_size(2) + v.to_box().compute_size();
-                crate::rt::tag_size(field_number) + crate::rt::compute_raw_varint32_size(entry_len) + entry_len
+                let entry_size = Self::entry_size(&k, &v, key_type, value_type);
+                rt::tag_size(field_number) + rt::compute_raw_varint32_size(entry_size) + entry_size
             })
             .sum()
     }
 
-    pub fn write_to_with_cached_sizes(&self, os: &mut CodedOutputStream, field_number: u32) -> ProtobufResult<()> {
-        self.reflect_iter()
-            .try_for_each(|(k, v)| {
-                os.write_tag(field_number, WireType::WireTypeLengthDelimited)?;
-                let entry_len = crate::rt::tag_size(1) + k.to_box().compute_size() + crate::rt::tag_size(2) + v.to_box().compute_size();
-                os.write_raw_varint32(entry_len)?;
+    pub fn write_to_with_cached_sizes(
+        &self,
+        os: &mut CodedOutputStream,
+        field_number: u32,
+        key_type: Type,
+        value_type: Type,
+    ) -> ProtobufResult<()> {
+        if os.is_deterministic() {
+            let mut entries: Vec<_> = self.reflect_iter().collect();
+            entries.sort_by(|a, b| cmp_keys(&a.0, &b.0));
+            for (k, v) in entries {
+                Self::write_entry(os, field_number, &k, &v, key_type, value_type)?;
+            }
+        } else {
+            for (k, v) in self.reflect_iter() {
+                Self::write_entry(os, field_number, &k, &v, key_type, value_type)?;
+            }
+        }
+        Ok(())
+    }
 
-                k.to_box().write_to_with_cached_sizes(os, 1)?;
-                v.to_box().write_to_with_cached_sizes(os, 2)
-            })
+    fn write_entry(
+        os: &mut CodedOutputStream,
+        field_number: u32,
+        k: &ReflectValueRef,
+        v: &ReflectValueRef,
+        key_type: Type,
+        value_type: Type,
+    ) -> ProtobufResult<()> {
+        os.write_tag(field_number, WireType::WireTypeLengthDelimited)?;
+        os.write_raw_varint32(Self::entry_size(k, v, key_type, value_type))?;
+        wire::write(1, key_type, k, os)?;
+        wire::write(2, value_type, v, os)
     }
 
-    pub fn merge_from(&mut self, is: &mut CodedInputStream) -> ProtobufResult<()> {
-        let len = is.read_raw_varint64()?;
-        let old_limit = is.push_limit(len)?;
+    pub fn merge_from(
+        &mut self,
+        is: &mut CodedInputStream,
+        key_type: Type,
+        value_type: Type,
+        wire_type: WireType,
+        field_number: u32,
+        unknown_fields: &mut UnknownFields,
+    ) -> ProtobufResult<()> {
+        if wire_type != WireType::WireTypeLengthDelimited {
+            return Err(rt::unexpected_wire_type(wire_type));
+        }
+
+        is.check_repeated_element(ReflectMap::len(self), 2 * mem::size_of::<ReflectValueBox>())?;
+
+        if value_type == Type::TYPE_ENUM {
+            // Like generated code, entry with unknown value of closed enum
+            // is stored in unknown fields as is.
+            let bytes = is.read_bytes()?;
+            let (key, value) = self.read_entry(
+                &mut CodedInputStream::from_bytes(&bytes),
+                key_type,
+                value_type,
+            )?;
+            match wire::unknown_closed_enum_value(&value) {
+                Some(..) => unknown_fields.add_length_delimited(field_number, bytes),
+                None => self.insert(key, value),
+            }
+            return Ok(());
+        }
 
-        let mut key = None;
-        let mut value = None;
+        let (key, value) = is.nested(|is| {
+            let len = is.read_raw_varint64()?;
+            let old_limit = is.push_limit(len)?;
+            let entry = self.read_entry(is, key_type, value_type)?;
+            is.pop_limit(old_limit);
+            Ok(entry)
+        })?;
+
+        self.insert(key, value);
+        Ok(())
+    }
+
+    /// Read entry fields until the end of the stream (or current limit).
+    fn read_entry(
+        &self,
+        is: &mut CodedInputStream,
+        key_type: Type,
+        value_type: Type,
+    ) -> ProtobufResult<(ReflectValueBox, ReflectValueBox)> {
+        let mut key = self.key_type().default_value_ref().to_box();
+        let mut value = self.value_type().default_value_ref().to_box();
 
         while !is.eof()? {
             let (field_number, wire_type) = is.read_tag_unpack()?;
 
             match field_number {
-                1 => {
-                    let mut keyb = self.key_type().default_value_ref().to_box();
-                    keyb.merge_from(is)?;
-                    key = Some(keyb)
-                },
-                2 => {
-                    let mut valueb = self.value_type().default_value_ref().to_box();
-                    valueb.merge_from(is)?;
-                    value = Some(valueb)
-                },
+                1 if wire_type == wire::wire_type(key_type) => {
+                    key = wire::read(key_type, &self.key_type(), is)?;
+                }
+                2 if wire_type == wire::wire_type(value_type) => {
+                    wire::merge(value_type, &mut value, is)?;
+                }
                 _ => {
-                    is.read_unknown(wire_type)?;
+                    is.skip_field(wire_type)?;
                 }
             }
         }
-
-        self.insert(key.unwrap(), value.unwrap());
-
-        is.pop_limit(old_limit);
-        Ok(())
+        Ok((key, value))
     }
 }
 
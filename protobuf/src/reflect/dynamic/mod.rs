@@ -1,4 +1,6 @@
 use crate::cached_size::CachedSize;
+use crate::descriptor::field_descriptor_proto;
+use crate::descriptor::field_descriptor_proto::Type;
 use crate::message_dyn::MessageDyn;
 use crate::reflect::dynamic::map::DynamicMap;
 use crate::reflect::dynamic::optional::DynamicOptional;
@@ -8,12 +10,14 @@ use crate::reflect::repeated::ReflectRepeated;
 use crate::reflect::value::value_ref::ReflectValueMut;
 use crate::reflect::FieldDescriptor;
 use crate::reflect::MessageDescriptor;
+use crate::reflect::OneofDescriptor;
 use crate::reflect::ReflectFieldRef;
 use crate::reflect::ReflectMapMut;
 use crate::reflect::ReflectMapRef;
 use crate::reflect::ReflectRepeatedMut;
 use crate::reflect::ReflectRepeatedRef;
 use crate::reflect::ReflectValueBox;
+use crate::reflect::ReflectValueRef;
 use crate::reflect::RuntimeFieldType;
 use crate::Clear;
 use crate::CodedInputStream;
@@ -28,65 +32,153 @@ pub(crate) mod repeated;
 
 #[derive(Debug, Clone)]
 enum DynamicFieldValue {
-    Singular(DynamicOptional),
-    Repeated(DynamicRepeated),
-    Map(DynamicMap),
+    Singular(DynamicOptional, Type),
+    /// Values, element type and `packed` flag.
+    Repeated(DynamicRepeated, Type, bool),
+    /// Values, key type and value type.
+    Map(DynamicMap, Type, Type),
 }
 
 impl DynamicFieldValue {
-    fn as_ref(&self) -> ReflectFieldRef {
+    fn as_ref<'a>(&'a self) -> ReflectFieldRef<'a> {
         match self {
-            DynamicFieldValue::Singular(v) => ReflectFieldRef::Optional(v.get()),
-            DynamicFieldValue::Repeated(r) => ReflectFieldRef::Repeated(ReflectRepeatedRef::new(r)),
-            DynamicFieldValue::Map(m) => ReflectFieldRef::Map(ReflectMapRef::new(m)),
+            DynamicFieldValue::Singular(v, _) => ReflectFieldRef::Optional(v.get()),
+            DynamicFieldValue::Repeated(r, ..) => {
+                ReflectFieldRef::Repeated(ReflectRepeatedRef::new(r))
+            }
+            DynamicFieldValue::Map(m, ..) => ReflectFieldRef::Map(ReflectMapRef::new(m)),
+        }
+    }
+
+    fn is_set(&self) -> bool {
+        match self {
+            DynamicFieldValue::Singular(o, _) => o.get().is_some(),
+            DynamicFieldValue::Repeated(r, ..) => r.len() != 0,
+            DynamicFieldValue::Map(m, ..) => !m.is_empty(),
         }
     }
 
     fn clear(&mut self) {
         match self {
-            DynamicFieldValue::Singular(o) => o.clear(),
-            DynamicFieldValue::Repeated(r) => r.clear(),
-            DynamicFieldValue::Map(m) => m.clear(),
+            DynamicFieldValue::Singular(o, _) => o.clear(),
+            DynamicFieldValue::Repeated(r, ..) => r.clear(),
+            DynamicFieldValue::Map(m, ..) => m.clear(),
         }
     }
 
     fn compute_size(&self, field_number: u32) -> u32 {
         match self {
-            DynamicFieldValue::Singular(o) => o.compute_size(field_number),
-            DynamicFieldValue::Repeated(r) => r.compute_size(field_number),
-            DynamicFieldValue::Map(m) => m.compute_size(field_number),
+            DynamicFieldValue::Singular(o, t) => o.compute_size(field_number, *t),
+            DynamicFieldValue::Repeated(r, t, packed) => r.compute_size(field_number, *t, *packed),
+            DynamicFieldValue::Map(m, k, v) => m.compute_size(field_number, *k, *v),
         }
     }
-    
-    fn write_to_with_cached_sizes(&self, os: &mut CodedOutputStream, field_number: u32) -> ProtobufResult<()> {
+
+    fn write_to_with_cached_sizes(
+        &self,
+        os: &mut CodedOutputStream,
+        field_number: u32,
+    ) -> ProtobufResult<()> {
         match self {
-            DynamicFieldValue::Singular(o) => o.write_to_with_cached_sizes(os, field_number),
-            DynamicFieldValue::Repeated(r) => r.write_to_with_cached_sizes(os, field_number),
-            DynamicFieldValue::Map(m) => m.write_to_with_cached_sizes(os, field_number),
+            DynamicFieldValue::Singular(o, t) => o.write_to_with_cached_sizes(os, field_number, *t),
+            DynamicFieldValue::Repeated(r, t, packed) => {
+                r.write_to_with_cached_sizes(os, field_number, *t, *packed)
+            }
+            DynamicFieldValue::Map(m, k, v) => {
+                m.write_to_with_cached_sizes(os, field_number, *k, *v)
+            }
         }
     }
 
-    fn merge_from(&mut self, is: &mut CodedInputStream) -> ProtobufResult<()> {
+    fn merge_from(
+        &mut self,
+        is: &mut CodedInputStream,
+        wire_type: crate::wire_format::WireType,
+        field_number: u32,
+        unknown_fields: &mut UnknownFields,
+    ) -> ProtobufResult<()> {
+        match self {
+            DynamicFieldValue::Singular(o, t) => {
+                o.merge_from(is, *t, wire_type, field_number, unknown_fields)
+            }
+            DynamicFieldValue::Repeated(r, t, _) => {
+                r.merge_from(is, *t, wire_type, field_number, unknown_fields)
+            }
+            DynamicFieldValue::Map(m, k, v) => {
+                m.merge_from(is, *k, *v, wire_type, field_number, unknown_fields)
+            }
+        }
+    }
+
+    fn is_initialized(&self) -> bool {
+        fn value_is_initialized(v: ReflectValueRef) -> bool {
+            match v {
+                ReflectValueRef::Message(m) => m.is_initialized_dyn(),
+                _ => true,
+            }
+        }
         match self {
-            DynamicFieldValue::Singular(o) => o.merge_from(is),
-            DynamicFieldValue::Repeated(r) => r.merge_from(is),
-            DynamicFieldValue::Map(m) => m.merge_from(is),
+            DynamicFieldValue::Singular(o, _) => o.get().into_iter().all(value_is_initialized),
+            DynamicFieldValue::Repeated(r, ..) => r
+                .values()
+                .iter()
+                .all(|v| value_is_initialized(v.as_value_ref())),
+            DynamicFieldValue::Map(m, ..) => m.reflect_iter().all(|(_, v)| value_is_initialized(v)),
         }
     }
 }
 
 impl DynamicFieldValue {
     fn default_for_field(field: &FieldDescriptor) -> DynamicFieldValue {
+        let proto = field.get_proto();
         match field.runtime_field_type() {
-            RuntimeFieldType::Singular(s) => DynamicFieldValue::Singular(DynamicOptional::none(s)),
-            RuntimeFieldType::Repeated(r) => DynamicFieldValue::Repeated(DynamicRepeated::new(r)),
-            RuntimeFieldType::Map(k, v) => DynamicFieldValue::Map(DynamicMap::new(k, v)),
+            RuntimeFieldType::Singular(s) => {
+                // Like generated code, zero values of proto3 fields without
+                // `optional` are not written and are reported as unset.
+                // Proto3 `optional` fields are members of synthetic oneofs.
+                let implicit_presence = field
+                    .message_descriptor
+                    .file_descriptor_proto()
+                    .get_syntax()
+                    == "proto3"
+                    && !proto.has_oneof_index()
+                    && proto.get_field_type() != Type::TYPE_MESSAGE;
+                DynamicFieldValue::Singular(
+                    DynamicOptional::none(s, implicit_presence),
+                    proto.get_field_type(),
+                )
+            }
+            RuntimeFieldType::Repeated(r) => DynamicFieldValue::Repeated(
+                DynamicRepeated::new(r),
+                proto.get_field_type(),
+                field.is_packed(),
+            ),
+            RuntimeFieldType::Map(k, v) => {
+                let (key_type, value_type) = field.map_entry_types();
+                DynamicFieldValue::Map(DynamicMap::new(k, v), key_type, value_type)
+            }
         }
     }
 }
 
+/// Message whose type is known only at runtime.
+///
+/// Fields are accessed by [`FieldDescriptor`], name or number,
+/// and the message can be serialized and parsed like generated messages.
+///
+/// ```
+/// # use protobuf::reflect::DynamicMessage;
+/// # use protobuf::reflect::ReflectValueBox;
+/// # use protobuf::Message;
+/// # use protobuf::descriptor::FileDescriptorProto;
+/// let mut message = DynamicMessage::new(FileDescriptorProto::descriptor_static());
+/// message.set_field_by_name("name", ReflectValueBox::String("foo.proto".to_owned()));
+///
+/// let generated: FileDescriptorProto = message.to_message().unwrap();
+/// assert_eq!("foo.proto", generated.get_name());
+/// ```
 #[derive(Debug, Clone)]
-pub(crate) struct DynamicMessage {
+pub struct DynamicMessage {
     pub(crate) descriptor: MessageDescriptor,
     fields: Box<[(u32, DynamicFieldValue)]>,
     unknown_fields: UnknownFields,
@@ -94,43 +186,180 @@ pub(crate) struct DynamicMessage {
 }
 
 impl DynamicMessage {
-    pub(crate) fn new(descriptor: MessageDescriptor) -> DynamicMessage {
+    /// Create an empty message of given type.
+    ///
+    /// Descriptor can be a descriptor of a generated message,
+    /// in that case the result is still stored dynamically.
+    ///
+    /// # Panics
+    ///
+    /// If descriptor is a map entry descriptor.
+    pub fn new(descriptor: MessageDescriptor) -> DynamicMessage {
+        assert!(
+            !descriptor.is_map_entry(),
+            "message is map entry: {}",
+            descriptor
+        );
+        let fields = descriptor
+            .fields()
+            .map(|f| (f.get_number(), DynamicFieldValue::default_for_field(&f)))
+            .collect();
         DynamicMessage {
             descriptor,
-            fields: Vec::new().into_boxed_slice(),
+            fields,
             unknown_fields: UnknownFields::new(),
             cached_size: CachedSize::new(),
         }
     }
 
-    fn init_fields(&mut self) {
-        if self.fields.is_empty() {
-            self.fields = self
-                .descriptor
-                .fields()
-                .map(|f| (f.get_number(), DynamicFieldValue::default_for_field(&f)))
-                .collect();
-        }
+    /// Convert a message of the same type to dynamic message.
+    ///
+    /// Conversion is done through the wire format, so `message` can be
+    /// either generated or dynamic message.
+    ///
+    /// # Panics
+    ///
+    /// If message type has different name than `descriptor`.
+    pub fn from_message(
+        descriptor: MessageDescriptor,
+        message: &dyn MessageDyn,
+    ) -> ProtobufResult<DynamicMessage> {
+        assert_eq!(
+            descriptor.full_name(),
+            message.descriptor_dyn().full_name(),
+            "message types differ"
+        );
+        let mut r = DynamicMessage::new(descriptor);
+        r.merge_from_bytes(&write_to_vec_unchecked(message)?)?;
+        Ok(r)
     }
 
-    pub(crate) fn get_reflect<'a>(&'a self, field: &FieldDescriptor) -> ReflectFieldRef<'a> {
+    /// Convert this message to a message of the same type.
+    ///
+    /// Conversion is done through the wire format.
+    ///
+    /// # Panics
+    ///
+    /// If `M` type has different name than this message type.
+    pub fn to_message<M: Message>(&self) -> ProtobufResult<M> {
+        let mut r = M::new();
+        assert_eq!(
+            self.descriptor.full_name(),
+            r.descriptor_by_instance().full_name(),
+            "message types differ"
+        );
+        r.merge_from_bytes(&write_to_vec_unchecked(self)?)?;
+        Ok(r)
+    }
+
+    /// Descriptor of this message.
+    pub fn descriptor(&self) -> &MessageDescriptor {
+        &self.descriptor
+    }
+
+    fn field_by_name(&self, name: &str) -> FieldDescriptor {
+        self.descriptor
+            .get_field_by_name(name)
+            .unwrap_or_else(|| panic!("field not found: {}.{}", self.descriptor, name))
+    }
+
+    fn field_by_number(&self, number: u32) -> FieldDescriptor {
+        self.descriptor
+            .get_field_by_number(number)
+            .unwrap_or_else(|| panic!("field not found: {}.{}", self.descriptor, number))
+    }
+
+    /// Get field of any type.
+    ///
+    /// # Panics
+    ///
+    /// If field belongs to a different message type.
+    pub fn get_reflect<'a>(&'a self, field: &FieldDescriptor) -> ReflectFieldRef<'a> {
+        assert_eq!(self.descriptor, field.message_descriptor);
+        self.fields[field.index].1.as_ref()
+    }
+
+    /// Get field by name, `None` if there's no such field.
+    pub fn get_field_by_name<'a>(&'a self, name: &str) -> Option<ReflectFieldRef<'a>> {
+        let field = self.descriptor.get_field_by_name(name)?;
+        Some(self.fields[field.index].1.as_ref())
+    }
+
+    /// Get field by number, `None` if there's no such field.
+    pub fn get_field_by_number<'a>(&'a self, number: u32) -> Option<ReflectFieldRef<'a>> {
+        let field = self.descriptor.get_field_by_number(number)?;
+        Some(self.fields[field.index].1.as_ref())
+    }
+
+    /// Check if field is set.
+    ///
+    /// For repeated and map fields returns `true` if the collection is not empty.
+    ///
+    /// # Panics
+    ///
+    /// If field belongs to a different message type.
+    pub fn has_field(&self, field: &FieldDescriptor) -> bool {
         assert_eq!(self.descriptor, field.message_descriptor);
-        if self.fields.is_empty() {
-            ReflectFieldRef::default_for_field(field)
-        } else {
-            self.fields[field.index].1.as_ref()
+        self.fields[field.index].1.is_set()
+    }
+
+    /// Check if field is set by field name, `false` if there's no such field.
+    pub fn has_field_by_name(&self, name: &str) -> bool {
+        match self.descriptor.get_field_by_name(name) {
+            Some(field) => self.has_field(&field),
+            None => false,
         }
     }
 
-    pub fn clear_field(&mut self, field: &FieldDescriptor) {
-        assert_eq!(field.message_descriptor, self.descriptor);
-        if self.fields.is_empty() {
-            return;
+    /// Check if field is set by field number, `false` if there's no such field.
+    pub fn has_field_by_number(&self, number: u32) -> bool {
+        match self.descriptor.get_field_by_number(number) {
+            Some(field) => self.has_field(&field),
+            None => false,
         }
+    }
+
+    /// Iterate over fields which are set, in the order of declaration.
+    pub fn present_fields<'a>(
+        &'a self,
+    ) -> impl Iterator<Item = (FieldDescriptor, ReflectFieldRef<'a>)> + 'a {
+        self.descriptor
+            .fields()
+            .zip(self.fields.iter())
+            .filter(|(_, (_, value))| value.is_set())
+            .map(|(field, (_, value))| (field, value.as_ref()))
+    }
 
+    /// Clear field.
+    ///
+    /// # Panics
+    ///
+    /// If field belongs to a different message type.
+    pub fn clear_field(&mut self, field: &FieldDescriptor) {
+        assert_eq!(field.message_descriptor, self.descriptor);
         self.fields[field.index].1.clear();
     }
 
+    /// Clear field by name.
+    ///
+    /// # Panics
+    ///
+    /// If there's no such field.
+    pub fn clear_field_by_name(&mut self, name: &str) {
+        let field = self.field_by_name(name);
+        self.clear_field(&field);
+    }
+
+    /// Clear field by number.
+    ///
+    /// # Panics
+    ///
+    /// If there's no such field.
+    pub fn clear_field_by_number(&mut self, number: u32) {
+        let field = self.field_by_number(number);
+        self.clear_field(&field);
+    }
+
     fn clear_oneof_group_fields_except(&mut self, field: &FieldDescriptor) {
         if let Some(oneof) = field.containing_oneof() {
             for next in oneof.fields() {
@@ -142,65 +371,186 @@ impl DynamicMessage {
         }
     }
 
+    /// Field of the oneof which is currently set.
+    ///
+    /// # Panics
+    ///
+    /// If oneof belongs to a different message type.
+    pub fn which_oneof(&self, oneof: &OneofDescriptor) -> Option<FieldDescriptor> {
+        oneof.fields().find(|f| self.has_field(f))
+    }
+
+    /// Clear all fields of the oneof.
+    ///
+    /// # Panics
+    ///
+    /// If oneof belongs to a different message type.
+    pub fn clear_oneof(&mut self, oneof: &OneofDescriptor) {
+        for field in oneof.fields() {
+            self.clear_field(&field);
+        }
+    }
+
     pub(crate) fn mut_singular_field_or_default<'a>(
         &'a mut self,
         field: &FieldDescriptor,
     ) -> ReflectValueMut<'a> {
         assert_eq!(field.message_descriptor, self.descriptor);
-        self.init_fields();
         self.clear_oneof_group_fields_except(field);
-        // TODO: reset oneof group fields
         match &mut self.fields[field.index].1 {
-            DynamicFieldValue::Singular(f) => f.mut_or_default(),
+            DynamicFieldValue::Singular(f, _) => f.mut_or_default(),
             _ => panic!("Not a singular field"),
         }
     }
 
-    pub(crate) fn mut_repeated<'a>(
-        &'a mut self,
-        field: &FieldDescriptor,
-    ) -> ReflectRepeatedMut<'a> {
+    /// Get a mutable reference to a message field,
+    /// initializing field with an empty message if unset.
+    ///
+    /// Other fields of the same oneof are cleared.
+    ///
+    /// # Panics
+    ///
+    /// If field belongs to a different message type or field is not a singular message.
+    pub fn mut_message<'a>(&'a mut self, field: &FieldDescriptor) -> &'a mut dyn MessageDyn {
+        match self.mut_singular_field_or_default(field) {
+            ReflectValueMut::Message(m) => m,
+        }
+    }
+
+    /// Get a mutable reference to a repeated field.
+    ///
+    /// # Panics
+    ///
+    /// If field belongs to a different message type or field is not repeated.
+    pub fn mut_repeated<'a>(&'a mut self, field: &FieldDescriptor) -> ReflectRepeatedMut<'a> {
         assert_eq!(self.descriptor, field.message_descriptor);
-        self.init_fields();
-        // TODO: reset oneof group fields
         match &mut self.fields[field.index].1 {
-            DynamicFieldValue::Repeated(r) => ReflectRepeatedMut::new(r),
+            DynamicFieldValue::Repeated(r, ..) => ReflectRepeatedMut::new(r),
             _ => panic!("Not a repeated field: {}", field),
         }
     }
 
-    pub(crate) fn mut_map<'a>(&'a mut self, field: &FieldDescriptor) -> ReflectMapMut<'a> {
+    /// Get a mutable reference to a map field.
+    ///
+    /// # Panics
+    ///
+    /// If field belongs to a different message type or field is not map.
+    pub fn mut_map<'a>(&'a mut self, field: &FieldDescriptor) -> ReflectMapMut<'a> {
         assert_eq!(field.message_descriptor, self.descriptor);
-        self.init_fields();
-        // TODO: reset oneof group fields
         match &mut self.fields[field.index].1 {
-            DynamicFieldValue::Map(m) => ReflectMapMut::new(m),
+            DynamicFieldValue::Map(m, ..) => ReflectMapMut::new(m),
             _ => panic!("Not a map field: {}", field),
         }
     }
 
-    pub(crate) fn set_field(&mut self, field: &FieldDescriptor, value: ReflectValueBox) {
+    /// Set singular field.
+    ///
+    /// Other fields of the same oneof are cleared.
+    ///
+    /// # Panics
+    ///
+    /// If field belongs to a different message type,
+    /// field is not singular or value is of different type.
+    pub fn set_field(&mut self, field: &FieldDescriptor, value: ReflectValueBox) {
         assert_eq!(field.message_descriptor, self.descriptor);
-        self.init_fields();
-        // TODO: reset oneof group fields
+        self.clear_oneof_group_fields_except(field);
         match &mut self.fields[field.index].1 {
-            DynamicFieldValue::Singular(s) => s.set(value),
+            DynamicFieldValue::Singular(s, _) => s.set(value),
             _ => panic!("Not a singular field: {}", field),
         }
     }
 
-    pub fn downcast_ref(message: &dyn MessageDyn) -> &DynamicMessage {
-        MessageDyn::downcast_ref(message).unwrap()
+    /// Set singular field by name.
+    ///
+    /// # Panics
+    ///
+    /// If there's no such field, field is not singular or value is of different type.
+    pub fn set_field_by_name(&mut self, name: &str, value: ReflectValueBox) {
+        let field = self.field_by_name(name);
+        self.set_field(&field, value);
     }
 
-    pub fn downcast_mut(message: &mut dyn MessageDyn) -> &mut DynamicMessage {
-        MessageDyn::downcast_mut(message).unwrap()
+    /// Set singular field by number.
+    ///
+    /// # Panics
+    ///
+    /// If there's no such field, field is not singular or value is of different type.
+    pub fn set_field_by_number(&mut self, number: u32, value: ReflectValueBox) {
+        let field = self.field_by_number(number);
+        self.set_field(&field, value);
+    }
+
+    /// Merge fields of another message of the same type into this message.
+    ///
+    /// Set singular fields overwrite fields of this message, except
+    /// message fields which are merged recursively, repeated fields
+    /// are appended, map entries are inserted and unknown fields are concatenated.
+    ///
+    /// # Panics
+    ///
+    /// If messages have different types.
+    pub fn merge_from_dynamic(&mut self, other: &DynamicMessage) {
+        assert_eq!(self.descriptor, other.descriptor);
+        for (field, (_, value)) in other.descriptor.fields().zip(other.fields.iter()) {
+            if !value.is_set() {
+                continue;
+            }
+            match value {
+                DynamicFieldValue::Singular(o, _) => {
+                    let value = o.get().unwrap();
+                    match value {
+                        ReflectValueRef::Message(m) => {
+                            self.mut_message(&field).merge_from_message_dyn(&*m);
+                        }
+                        value => self.set_field(&field, value.to_box()),
+                    }
+                }
+                DynamicFieldValue::Repeated(r, ..) => {
+                    let mut target = self.mut_repeated(&field);
+                    for v in r.values() {
+                        target.push(v.clone());
+                    }
+                }
+                DynamicFieldValue::Map(m, ..) => {
+                    let mut target = self.mut_map(&field);
+                    for (k, v) in m.reflect_iter() {
+                        target.insert(k.to_box(), v.to_box());
+                    }
+                }
+            }
+        }
+        for (number, value) in other.unknown_fields.iter_values() {
+            self.unknown_fields.add_value(number, value.to_value());
+        }
+    }
+
+    pub(crate) fn downcast_ref(message: &dyn MessageDyn) -> &DynamicMessage {
+        message.downcast_ref().unwrap()
+    }
+
+    pub(crate) fn downcast_mut(message: &mut dyn MessageDyn) -> &mut DynamicMessage {
+        message.downcast_mut().unwrap()
     }
 }
 
+/// Serialize message without checking required fields are set.
+fn write_to_vec_unchecked(message: &dyn MessageDyn) -> ProtobufResult<Vec<u8>> {
+    let size = message.compute_size_dyn();
+    let mut v = Vec::with_capacity(size as usize);
+    {
+        let mut os = CodedOutputStream::vec(&mut v);
+        message.write_to_with_cached_sizes_dyn(&mut os)?;
+        os.flush()?;
+    }
+    Ok(v)
+}
+
 impl Clear for DynamicMessage {
     fn clear(&mut self) {
-        unimplemented!()
+        for (_, field) in self.fields.iter_mut() {
+            field.clear();
+        }
+        self.unknown_fields.clear();
     }
 }
 
@@ -210,23 +560,51 @@ impl Message for DynamicMessage {
     }
 
     fn is_initialized(&self) -> bool {
+        for (field, (_, value)) in self.descriptor.fields().zip(self.fields.iter()) {
+            if field.get_proto().get_label() == field_descriptor_proto::Label::LABEL_REQUIRED
+                && !value.is_set()
+            {
+                return false;
+            }
+            if !value.is_initialized() {
+                return false;
+            }
+        }
         true
     }
 
     fn merge_from(&mut self, is: &mut CodedInputStream) -> ProtobufResult<()> {
-        self.init_fields();
-
-        'fields: while !is.eof()? {
+        while !is.eof()? {
             let (field_number, wire_type) = is.read_tag_unpack()?;
 
-            for &mut (n, ref mut field) in self.fields.iter_mut() {
-                if field_number == n {
-                    field.merge_from(is)?;
-                    continue 'fields;
+            match self.fields.iter().position(|(n, _)| *n == field_number) {
+                Some(index) => {
+                    self.fields[index].1.merge_from(
+                        is,
+                        wire_type,
+                        field_number,
+                        &mut self.unknown_fields,
+                    )?;
+                    // Unknown value of closed enum does not change oneof
+                    let is_set = match &self.fields[index].1 {
+                        DynamicFieldValue::Singular(o, _) => o.get().is_some(),
+                        _ => true,
+                    };
+                    if is_set && self.descriptor.get_proto().field[index].has_oneof_index() {
+                        let field = FieldDescriptor {
+                            message_descriptor: self.descriptor.clone(),
+                            index,
+                        };
+                        self.clear_oneof_group_fields_except(&field);
+                    }
                 }
+                None => crate::rt::read_unknown_or_skip_group(
+                    field_number,
+                    wire_type,
+                    is,
+                    &mut self.unknown_fields,
+                )?,
             }
-
-            self.unknown_fields.add_value(field_number, is.read_unknown(wire_type)?);
         }
         Ok(())
     }
@@ -275,3 +653,24 @@ impl Message for DynamicMessage {
         panic!("There's no default instance for dynamic message")
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::well_known_types::Int32Value;
+
+    #[test]
+    fn proto3_zero_is_not_set() {
+        let mut message = DynamicMessage::new(Int32Value::descriptor_static());
+        message.set_field_by_name("value", ReflectValueBox::I32(0));
+        assert!(!message.has_field_by_name("value"));
+        assert_eq!(0, message.present_fields().count());
+        assert_eq!(Vec::<u8>::new(), message.write_to_bytes().unwrap());
+        let dynamic: &dyn MessageDyn = &message;
+        assert!(dynamic.reflect_eq_dyn(&Int32Value::new(), &Default::default()));
+
+        message.set_field_by_name("value", ReflectValueBox::I32(1));
+        assert!(message.has_field_by_name("value"));
+        assert_eq!(vec![0x08, 0x01], message.write_to_bytes().unwrap());
+    }
+}
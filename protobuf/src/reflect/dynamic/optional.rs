@@ -1,17 +1,32 @@
-use crate::{CodedInputStream, CodedOutputStream, ProtobufResult, reflect::value::value_ref::ReflectValueMut};
+use crate::descriptor::field_descriptor_proto::Type;
+use crate::reflect::value::value_ref::ReflectValueMut;
+use crate::reflect::wire;
 use crate::reflect::ReflectValueBox;
 use crate::reflect::ReflectValueRef;
 use crate::reflect::RuntimeTypeBox;
+use crate::rt;
+use crate::wire_format::WireType;
+use crate::CodedInputStream;
+use crate::CodedOutputStream;
+use crate::ProtobufResult;
+use crate::UnknownFields;
 
 #[derive(Debug, Clone)]
 pub(crate) struct DynamicOptional {
     elem: RuntimeTypeBox,
     value: Option<ReflectValueBox>,
+    /// Field does not track presence (proto3 field without `optional`),
+    /// so default value is the same as no value.
+    implicit_presence: bool,
 }
 
 impl DynamicOptional {
-    pub fn none(elem: RuntimeTypeBox) -> DynamicOptional {
-        DynamicOptional { elem, value: None }
+    pub fn none(elem: RuntimeTypeBox, implicit_presence: bool) -> DynamicOptional {
+        DynamicOptional {
+            elem,
+            value: None,
+            implicit_presence,
+        }
     }
 
     pub fn mut_or_default(&mut self) -> ReflectValueMut {
@@ -26,7 +41,11 @@ impl DynamicOptional {
     }
 
     pub fn get(&self) -> Option<ReflectValueRef> {
-        self.value.as_ref().map(ReflectValueBox::as_value_ref)
+        let value = self.value.as_ref()?.as_value_ref();
+        if self.implicit_presence && !value.is_non_zero() {
+            return None;
+        }
+        Some(value)
     }
 
     pub fn set(&mut self, value: ReflectValueBox) {
@@ -34,26 +53,50 @@ impl DynamicOptional {
         self.value = Some(value);
     }
 
-    pub fn compute_size(&self, field_number: u32) -> u32 {
-        if let Some(value) = &self.value {
-            crate::rt::tag_size(field_number) + value.compute_size()
-        } else {
-            0
+    pub fn compute_size(&self, field_number: u32, t: Type) -> u32 {
+        match self.get() {
+            Some(value) => wire::compute_size(field_number, t, &value),
+            None => 0,
         }
     }
 
-    pub fn write_to_with_cached_sizes(&self, os: &mut CodedOutputStream, field_number: u32) -> ProtobufResult<()> {
-        if let Some(value) = &self.value {
-            value.write_to_with_cached_sizes(os, field_number)
-        } else {
-            Ok(())
+    pub fn write_to_with_cached_sizes(
+        &self,
+        os: &mut CodedOutputStream,
+        field_number: u32,
+        t: Type,
+    ) -> ProtobufResult<()> {
+        match self.get() {
+            Some(value) => wire::write(field_number, t, &value, os),
+            None => Ok(()),
         }
     }
 
-    pub fn merge_from(&mut self, is: &mut CodedInputStream) -> ProtobufResult<()> {
-        let mut value = self.elem.default_value_ref().to_box();
-        value.merge_from(is)?;
-        self.value = Some(value);
-        Ok(())
+    pub fn merge_from(
+        &mut self,
+        is: &mut CodedInputStream,
+        t: Type,
+        wire_type: WireType,
+        field_number: u32,
+        unknown_fields: &mut UnknownFields,
+    ) -> ProtobufResult<()> {
+        if wire_type != wire::wire_type(t) {
+            return Err(rt::unexpected_wire_type(wire_type));
+        }
+        if t == Type::TYPE_ENUM {
+            let value = wire::read(t, &self.elem, is)?;
+            match wire::unknown_closed_enum_value(&value) {
+                Some(v) => unknown_fields.add_varint(field_number, v as i64 as u64),
+                None => self.value = Some(value),
+            }
+            return Ok(());
+        }
+        match &mut self.value {
+            Some(value) => wire::merge(t, value, is),
+            None => {
+                self.value = Some(wire::read(t, &self.elem, is)?);
+                Ok(())
+            }
+        }
     }
 }
@@ -1,8 +1,16 @@
-use crate::{CodedInputStream, CodedOutputStream, ProtobufResult, reflect::repeated::ReflectRepeated};
+use crate::descriptor::field_descriptor_proto::Type;
+use crate::reflect::repeated::ReflectRepeated;
 use crate::reflect::repeated::ReflectRepeatedIter;
+use crate::reflect::wire;
 use crate::reflect::ReflectValueBox;
 use crate::reflect::ReflectValueRef;
 use crate::reflect::RuntimeTypeBox;
+use crate::rt;
+use crate::wire_format::WireType;
+use crate::CodedInputStream;
+use crate::CodedOutputStream;
+use crate::ProtobufResult;
+use crate::UnknownFields;
 
 #[derive(Debug, Clone)]
 pub(crate) struct DynamicRepeated {
@@ -12,7 +20,7 @@ pub(crate) struct DynamicRepeated {
 
 impl ReflectRepeated for DynamicRepeated {
     fn reflect_iter(&self) -> ReflectRepeatedIter {
-        unimplemented!()
+        ReflectRepeatedIter::new_boxes(&self.vec)
     }
 
     fn len(&self) -> usize {
@@ -49,25 +57,88 @@ impl DynamicRepeated {
             vec: Vec::new(),
         }
     }
-    
-    pub fn compute_size(&self, field_number: u32) -> u32 { 
-        self.vec.iter().map(|v| {
-            crate::rt::tag_size(field_number) + v.compute_size()
-        }).sum()
+
+    pub(crate) fn values(&self) -> &[ReflectValueBox] {
+        &self.vec
     }
 
-    pub fn write_to_with_cached_sizes(&self, os: &mut CodedOutputStream, field_number: u32) -> ProtobufResult<()> {
-        self.vec.iter().try_for_each(|v| {
-            v.write_to_with_cached_sizes(os, field_number)
-        })
+    fn packed_data_size(&self, t: Type) -> u32 {
+        self.vec
+            .iter()
+            .map(|v| wire::compute_size_no_tag(t, &v.as_value_ref()))
+            .sum()
     }
 
-    pub fn merge_from(&mut self, is: &mut CodedInputStream) -> ProtobufResult<()> {
-        let mut value = self.elem.default_value_ref().to_box();
-        value.merge_from(is)?;
+    pub fn compute_size(&self, field_number: u32, t: Type, packed: bool) -> u32 {
+        if packed {
+            if self.vec.is_empty() {
+                return 0;
+            }
+            let data_size = self.packed_data_size(t);
+            rt::tag_size(field_number) + rt::compute_raw_varint32_size(data_size) + data_size
+        } else {
+            self.vec
+                .iter()
+                .map(|v| wire::compute_size(field_number, t, &v.as_value_ref()))
+                .sum()
+        }
+    }
 
-        self.vec.push(value);
+    pub fn write_to_with_cached_sizes(
+        &self,
+        os: &mut CodedOutputStream,
+        field_number: u32,
+        t: Type,
+        packed: bool,
+    ) -> ProtobufResult<()> {
+        if packed {
+            if self.vec.is_empty() {
+                return Ok(());
+            }
+            os.write_tag(field_number, WireType::WireTypeLengthDelimited)?;
+            os.write_raw_varint32(self.packed_data_size(t))?;
+            for v in &self.vec {
+                wire::write_no_tag(t, &v.as_value_ref(), os)?;
+            }
+            Ok(())
+        } else {
+            for v in &self.vec {
+                wire::write(field_number, t, &v.as_value_ref(), os)?;
+            }
+            Ok(())
+        }
+    }
 
+    /// Read one value, or several values if they are packed.
+    pub fn merge_from(
+        &mut self,
+        is: &mut CodedInputStream,
+        t: Type,
+        wire_type: WireType,
+        field_number: u32,
+        unknown_fields: &mut UnknownFields,
+    ) -> ProtobufResult<()> {
+        let len = self.vec.len();
+        if wire_type == WireType::WireTypeLengthDelimited && wire::is_packable(t) {
+            wire::read_packed(t, &self.elem, is, &mut self.vec)?;
+        } else if wire_type == wire::wire_type(t) {
+            is.check_repeated_push(&self.vec)?;
+            let value = wire::read(t, &self.elem, is).map_err(|e| match t {
+                Type::TYPE_MESSAGE => e.with_repeated_index(self.vec.len()),
+                _ => e,
+            })?;
+            self.vec.push(value);
+        } else {
+            return Err(rt::unexpected_wire_type(wire_type));
+        }
+        if t == Type::TYPE_ENUM {
+            for value in self.vec.split_off(len) {
+                match wire::unknown_closed_enum_value(&value) {
+                    Some(v) => unknown_fields.add_varint(field_number, v as i64 as u64),
+                    None => self.vec.push(value),
+                }
+            }
+        }
         Ok(())
     }
 }
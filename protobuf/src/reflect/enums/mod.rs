@@ -200,6 +200,12 @@ impl EnumDescriptor {
         })
     }
 
+    /// Enum is closed, i. e. declared in proto2 file,
+    /// so numbers without a declared value are not valid values of this enum.
+    pub(crate) fn is_closed(&self) -> bool {
+        self.file_descriptor.proto().get_syntax() != "proto3"
+    }
+
     /// Default enum value (first variant)
     pub fn get_default_value(&self) -> EnumValueDescriptor {
         EnumValueDescriptor {
@@ -0,0 +1,22 @@
+use crate::descriptor::FieldDescriptorProto;
+use crate::reflect::field::index::ForwardRuntimeFieldType;
+use crate::reflect::field::index::ForwardRuntimeTypeBox;
+use crate::reflect::file::building::FileDescriptorBuilding;
+
+#[derive(Debug)]
+pub(crate) struct ExtensionIndex {
+    pub(crate) field_type: ForwardRuntimeFieldType,
+    pub(crate) extendee: ForwardRuntimeTypeBox,
+}
+
+impl ExtensionIndex {
+    pub fn index(
+        extension: &FieldDescriptorProto,
+        building: &FileDescriptorBuilding,
+    ) -> ExtensionIndex {
+        ExtensionIndex {
+            field_type: building.resolve_field_type(extension),
+            extendee: building.resolve_message(extension.get_extendee()),
+        }
+    }
+}
@@ -0,0 +1,346 @@
+use std::fmt;
+
+use crate::descriptor::field_descriptor_proto;
+use crate::descriptor::FieldDescriptorProto;
+use crate::error::ProtobufResult;
+use crate::message_dyn::MessageDyn;
+use crate::reflect::extension::index::ExtensionIndex;
+use crate::reflect::wire;
+use crate::reflect::FileDescriptor;
+use crate::reflect::MessageDescriptor;
+use crate::reflect::ReflectValueBox;
+use crate::reflect::ReflectValueRef;
+use crate::reflect::RuntimeFieldType;
+use crate::reflect::RuntimeTypeBox;
+
+pub(crate) mod index;
+
+/// Descriptor of extension field.
+///
+/// Extension values are stored in unknown fields of the extended message.
+#[derive(Clone, Eq, PartialEq)]
+pub struct ExtensionDescriptor {
+    file_descriptor: FileDescriptor,
+    index: usize,
+}
+
+impl fmt::Display for ExtensionDescriptor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.full_name())
+    }
+}
+
+impl fmt::Debug for ExtensionDescriptor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ExtensionDescriptor")
+            .field("full_name", &self.full_name())
+            .finish()
+    }
+}
+
+impl ExtensionDescriptor {
+    pub(crate) fn new(file_descriptor: FileDescriptor, index: usize) -> ExtensionDescriptor {
+        ExtensionDescriptor {
+            file_descriptor,
+            index,
+        }
+    }
+
+    fn get_index(&self) -> &ExtensionIndex {
+        self.file_descriptor.extension_index(self.index)
+    }
+
+    /// Get `.proto` description of extension field.
+    pub fn get_proto(&self) -> &FieldDescriptorProto {
+        self.file_descriptor.extension_proto(self.index)
+    }
+
+    /// Extension name as specified in `.proto` file.
+    pub fn get_name(&self) -> &str {
+        self.get_proto().get_name()
+    }
+
+    /// Fully qualified name of the extension (without leading dot).
+    pub fn full_name(&self) -> &str {
+        &self
+            .file_descriptor
+            .extension_index_entry(self.index)
+            .full_name
+    }
+
+    /// Extension field number.
+    pub fn get_number(&self) -> u32 {
+        self.get_proto().get_number() as u32
+    }
+
+    /// Message which is extended by this extension.
+    pub fn extendee(&self) -> MessageDescriptor {
+        self.get_index()
+            .extendee
+            .resolve_message(&self.file_descriptor)
+    }
+
+    /// Dynamic representation of extension type.
+    pub fn runtime_field_type(&self) -> RuntimeFieldType {
+        self.get_index().field_type.resolve(&self.file_descriptor)
+    }
+
+    /// File where the extension is declared.
+    pub fn file_descriptor(&self) -> &FileDescriptor {
+        &self.file_descriptor
+    }
+
+    /// Message where the extension is declared, `None` for top-level extensions.
+    pub fn containing_message(&self) -> Option<MessageDescriptor> {
+        self.file_descriptor
+            .extension_index_entry(self.index)
+            .scope
+            .map(|i| MessageDescriptor::new(self.file_descriptor.clone(), i))
+    }
+
+    /// Is this extension repeated?
+    pub fn is_repeated(&self) -> bool {
+        self.get_proto().get_label() == field_descriptor_proto::Label::LABEL_REPEATED
+    }
+
+    fn element_type(&self) -> RuntimeTypeBox {
+        match self.runtime_field_type() {
+            RuntimeFieldType::Singular(t) | RuntimeFieldType::Repeated(t) => t,
+            RuntimeFieldType::Map(..) => panic!("extension cannot be a map: {}", self),
+        }
+    }
+
+    fn proto_type(&self) -> field_descriptor_proto::Type {
+        self.get_proto().get_field_type()
+    }
+
+    /// Are values of this extension written using packed encoding.
+    pub fn is_packed(&self) -> bool {
+        wire::is_packed(self.get_proto(), self.file_descriptor.proto())
+    }
+
+    fn assert_extendee(&self, message: &dyn MessageDyn) {
+        // Compare by name, so extensions declared in dynamic files
+        // can be used with generated messages and vice versa
+        let extendee = self.extendee();
+        let descriptor = message.descriptor_dyn();
+        assert_eq!(
+            extendee.full_name(),
+            descriptor.full_name(),
+            "extension {} does not extend message {}",
+            self,
+            descriptor
+        );
+    }
+
+    fn assert_singular(&self) {
+        assert!(!self.is_repeated(), "extension is repeated: {}", self);
+    }
+
+    fn assert_repeated(&self) {
+        assert!(self.is_repeated(), "extension is not repeated: {}", self);
+    }
+
+    /// Decode all values of this extension stored in the message unknown fields.
+    pub(crate) fn get_values(
+        &self,
+        message: &dyn MessageDyn,
+    ) -> ProtobufResult<Vec<ReflectValueBox>> {
+        self.assert_extendee(message);
+        match message.get_unknown_fields_dyn().get(self.get_number()) {
+            Some(values) => {
+                wire::values_from_unknown(self.proto_type(), &self.element_type(), values)
+            }
+            None => Ok(Vec::new()),
+        }
+    }
+
+    /// Encode the value and append it to the message unknown fields.
+    ///
+    /// # Panics
+    ///
+    /// If value type does not match the extension type.
+    pub(crate) fn add_value(
+        &self,
+        message: &mut dyn MessageDyn,
+        value: ReflectValueRef,
+    ) -> ProtobufResult<()> {
+        self.assert_extendee(message);
+        let value = wire::value_to_unknown(self.proto_type(), &value)?;
+        message
+            .mut_unknown_fields_dyn()
+            .add_value(self.get_number(), value);
+        Ok(())
+    }
+
+    /// Check if the extension is set in a message.
+    ///
+    /// # Panics
+    ///
+    /// If the message is not extended by this extension.
+    pub fn has(&self, message: &dyn MessageDyn) -> bool {
+        self.assert_extendee(message);
+        message
+            .get_unknown_fields_dyn()
+            .get(self.get_number())
+            .is_some()
+    }
+
+    /// Get singular extension value.
+    ///
+    /// If the value is encoded several times, the last value is returned,
+    /// except for messages, which are merged.
+    ///
+    /// # Panics
+    ///
+    /// If the extension is repeated or the message is not extended by this extension.
+    pub fn get_singular(
+        &self,
+        message: &dyn MessageDyn,
+    ) -> ProtobufResult<Option<ReflectValueBox>> {
+        self.assert_singular();
+        Ok(wire::merge_singular(self.get_values(message)?))
+    }
+
+    /// Get repeated extension values.
+    ///
+    /// # Panics
+    ///
+    /// If the extension is not repeated or the message is not extended by this extension.
+    pub fn get_repeated(&self, message: &dyn MessageDyn) -> ProtobufResult<Vec<ReflectValueBox>> {
+        self.assert_repeated();
+        self.get_values(message)
+    }
+
+    /// Set singular extension value, replacing previous value.
+    ///
+    /// # Panics
+    ///
+    /// If the extension is repeated, value type does not match extension type,
+    /// or the message is not extended by this extension.
+    pub fn set_singular(
+        &self,
+        message: &mut dyn MessageDyn,
+        value: ReflectValueBox,
+    ) -> ProtobufResult<()> {
+        self.assert_singular();
+        self.clear(message);
+        self.add_value(message, value.as_value_ref())
+    }
+
+    /// Replace repeated extension values.
+    ///
+    /// Values are written using packed encoding if the extension is declared packed.
+    ///
+    /// # Panics
+    ///
+    /// If the extension is not repeated, value type does not match extension type,
+    /// or the message is not extended by this extension.
+    pub fn set_repeated(
+        &self,
+        message: &mut dyn MessageDyn,
+        values: Vec<ReflectValueBox>,
+    ) -> ProtobufResult<()> {
+        self.assert_repeated();
+        self.clear(message);
+        if self.is_packed() && !values.is_empty() {
+            return self.add_packed_values(message, values);
+        }
+        for value in values {
+            self.push_repeated(message, value)?;
+        }
+        Ok(())
+    }
+
+    fn add_packed_values(
+        &self,
+        message: &mut dyn MessageDyn,
+        values: Vec<ReflectValueBox>,
+    ) -> ProtobufResult<()> {
+        self.assert_extendee(message);
+        let values: Vec<ReflectValueRef> = values.iter().map(|v| v.as_value_ref()).collect();
+        let value = wire::values_to_unknown_packed(self.proto_type(), &values)?;
+        message
+            .mut_unknown_fields_dyn()
+            .add_value(self.get_number(), value);
+        Ok(())
+    }
+
+    /// Append a value to repeated extension.
+    ///
+    /// Packed extension value is appended as a separate packed chunk.
+    ///
+    /// # Panics
+    ///
+    /// If the extension is not repeated, value type does not match extension type,
+    /// or the message is not extended by this extension.
+    pub fn push_repeated(
+        &self,
+        message: &mut dyn MessageDyn,
+        value: ReflectValueBox,
+    ) -> ProtobufResult<()> {
+        self.assert_repeated();
+        if self.is_packed() {
+            return self.add_packed_values(message, vec![value]);
+        }
+        self.add_value(message, value.as_value_ref())
+    }
+
+    /// Remove the extension from a message.
+    ///
+    /// # Panics
+    ///
+    /// If the message is not extended by this extension.
+    pub fn clear(&self, message: &mut dyn MessageDyn) {
+        self.assert_extendee(message);
+        message.mut_unknown_fields_dyn().remove(self.get_number());
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::descriptor::field_descriptor_proto::Label;
+    use crate::descriptor::field_descriptor_proto::Type;
+    use crate::descriptor::FieldDescriptorProto;
+    use crate::descriptor::FileDescriptorProto;
+    use crate::descriptor::MessageOptions;
+    use crate::reflect::FileDescriptor;
+    use crate::reflect::ReflectValueBox;
+    use crate::Message;
+
+    #[test]
+    fn dynamic_extension_of_generated_message() {
+        let mut extension = FieldDescriptorProto::new();
+        extension.set_name("my_option".to_owned());
+        extension.set_number(50000);
+        extension.set_label(Label::LABEL_OPTIONAL);
+        extension.set_field_type(Type::TYPE_SINT32);
+        extension.set_extendee(".google.protobuf.MessageOptions".to_owned());
+
+        let mut proto = FileDescriptorProto::new();
+        proto.set_name("my_options.proto".to_owned());
+        proto.set_package("my_pkg".to_owned());
+        proto
+            .dependency
+            .push("google/protobuf/descriptor.proto".to_owned());
+        proto.extension.push(extension);
+
+        let file = FileDescriptor::new_dynamic(proto, vec![crate::descriptor::file_descriptor()]);
+        let extensions = file.extensions();
+        assert_eq!(1, extensions.len());
+        let my_option = &extensions[0];
+        assert_eq!("my_pkg.my_option", my_option.full_name());
+        assert_eq!(MessageOptions::descriptor_static(), my_option.extendee());
+
+        let mut options = MessageOptions::new();
+        assert_eq!(None, my_option.get_singular(&options).unwrap());
+        my_option
+            .set_singular(&mut options, ReflectValueBox::I32(-10))
+            .unwrap();
+        let options = MessageOptions::parse_from_bytes(&options.write_to_bytes().unwrap()).unwrap();
+        assert_eq!(
+            Some(ReflectValueBox::I32(-10)),
+            my_option.get_singular(&options).unwrap()
+        );
+    }
+}
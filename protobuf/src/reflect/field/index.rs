@@ -5,6 +5,7 @@ use crate::reflect::file::building::FileDescriptorBuilding;
 use crate::reflect::EnumDescriptor;
 use crate::reflect::EnumValueDescriptor;
 use crate::reflect::FieldDescriptor;
+use crate::reflect::FileDescriptor;
 use crate::reflect::MessageDescriptor;
 use crate::reflect::ReflectValueBox;
 use crate::reflect::ReflectValueRef;
@@ -19,16 +20,22 @@ pub(crate) enum ForwardRuntimeTypeBox {
 }
 
 impl ForwardRuntimeTypeBox {
-    fn resolve(&self, field: &FieldDescriptor) -> RuntimeTypeBox {
+    pub(crate) fn resolve(&self, file: &FileDescriptor) -> RuntimeTypeBox {
         match self {
             ForwardRuntimeTypeBox::RuntimeTypeBox(t) => t.clone(),
-            ForwardRuntimeTypeBox::CurrentFileMessage(m) => RuntimeTypeBox::Message(
-                MessageDescriptor::new(field.message_descriptor.file_descriptor().clone(), *m),
-            ),
-            ForwardRuntimeTypeBox::CurrentFileEnum(m) => RuntimeTypeBox::Enum(EnumDescriptor::new(
-                field.message_descriptor.file_descriptor().clone(),
-                *m,
-            )),
+            ForwardRuntimeTypeBox::CurrentFileMessage(m) => {
+                RuntimeTypeBox::Message(MessageDescriptor::new(file.clone(), *m))
+            }
+            ForwardRuntimeTypeBox::CurrentFileEnum(m) => {
+                RuntimeTypeBox::Enum(EnumDescriptor::new(file.clone(), *m))
+            }
+        }
+    }
+
+    pub(crate) fn resolve_message(&self, file: &FileDescriptor) -> MessageDescriptor {
+        match self.resolve(file) {
+            RuntimeTypeBox::Message(m) => m,
+            t => panic!("not a message: {}", t),
         }
     }
 }
@@ -41,12 +48,12 @@ pub(crate) enum ForwardRuntimeFieldType {
 }
 
 impl ForwardRuntimeFieldType {
-    pub fn resolve(&self, field: &FieldDescriptor) -> RuntimeFieldType {
+    pub fn resolve(&self, file: &FileDescriptor) -> RuntimeFieldType {
         match self {
-            ForwardRuntimeFieldType::Singular(t) => RuntimeFieldType::Singular(t.resolve(field)),
-            ForwardRuntimeFieldType::Repeated(t) => RuntimeFieldType::Repeated(t.resolve(field)),
+            ForwardRuntimeFieldType::Singular(t) => RuntimeFieldType::Singular(t.resolve(file)),
+            ForwardRuntimeFieldType::Repeated(t) => RuntimeFieldType::Repeated(t.resolve(file)),
             ForwardRuntimeFieldType::Map(k, v) => {
-                RuntimeFieldType::Map(k.resolve(field), v.resolve(field))
+                RuntimeFieldType::Map(k.resolve(file), v.resolve(file))
             }
         }
     }
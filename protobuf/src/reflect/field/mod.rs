@@ -19,6 +19,7 @@ use crate::reflect::reflect_eq::ReflectEqMode;
 use crate::reflect::repeated::ReflectRepeatedMut;
 use crate::reflect::repeated::ReflectRepeatedRef;
 use crate::reflect::value::value_ref::ReflectValueMut;
+use crate::reflect::wire;
 use crate::reflect::MessageDescriptor;
 use crate::reflect::ReflectValueBox;
 use crate::reflect::ReflectValueRef;
@@ -39,28 +40,29 @@ pub enum ReflectFieldRef<'a> {
     Map(ReflectMapRef<'a>),
 }
 
-impl<'a> ReflectFieldRef<'a> {
-    pub(crate) fn default_for_field(field: &FieldDescriptor) -> ReflectFieldRef<'a> {
-        match field.runtime_field_type() {
-            RuntimeFieldType::Singular(_) => ReflectFieldRef::Optional(None),
-            RuntimeFieldType::Repeated(elem) => {
-                ReflectFieldRef::Repeated(ReflectRepeatedRef::new_empty(elem))
-            }
-            RuntimeFieldType::Map(..) => unimplemented!(),
-        }
+impl<'a> ReflectEq for ReflectFieldRef<'a> {
+    fn reflect_eq(&self, that: &Self, mode: &ReflectEqMode) -> bool {
+        self.reflect_eq_at(that, mode, &mut String::new())
     }
 }
 
-impl<'a> ReflectEq for ReflectFieldRef<'a> {
-    fn reflect_eq(&self, that: &Self, mode: &ReflectEqMode) -> bool {
+impl<'a> ReflectFieldRef<'a> {
+    pub(crate) fn reflect_eq_at(
+        &self,
+        that: &Self,
+        mode: &ReflectEqMode,
+        path: &mut String,
+    ) -> bool {
         match (self, that) {
             (ReflectFieldRef::Optional(a), ReflectFieldRef::Optional(b)) => match (a, b) {
-                (Some(av), Some(bv)) => av.reflect_eq(&bv, mode),
+                (Some(av), Some(bv)) => av.reflect_eq_at(&bv, mode, path),
                 (None, None) => true,
                 _ => false,
             },
-            (ReflectFieldRef::Repeated(a), ReflectFieldRef::Repeated(b)) => a.reflect_eq(b, mode),
-            (ReflectFieldRef::Map(a), ReflectFieldRef::Map(b)) => a.reflect_eq(b, mode),
+            (ReflectFieldRef::Repeated(a), ReflectFieldRef::Repeated(b)) => {
+                a.reflect_eq_at(b, mode, path)
+            }
+            (ReflectFieldRef::Map(a), ReflectFieldRef::Map(b)) => a.reflect_eq_at(b, mode, path),
             _ => false,
         }
     }
@@ -114,7 +116,19 @@ impl FieldDescriptor {
     }
 
     /// Oneof descriptor containing this field.
+    ///
+    /// Synthetic oneofs of proto3 `optional` fields are not returned.
     pub fn containing_oneof(&self) -> Option<OneofDescriptor> {
+        if self.get_proto().get_proto3_optional() {
+            None
+        } else {
+            self.containing_oneof_including_synthetic()
+        }
+    }
+
+    /// Oneof descriptor containing this field, including synthetic oneof
+    /// generated for proto3 `optional` field.
+    pub fn containing_oneof_including_synthetic(&self) -> Option<OneofDescriptor> {
         let proto = self.get_proto();
         if proto.has_oneof_index() {
             Some(OneofDescriptor {
@@ -163,6 +177,45 @@ impl FieldDescriptor {
         }
     }
 
+    /// Is this field a repeated field written using packed encoding.
+    ///
+    /// Repeated scalar fields are packed in proto3 unless `[packed = false]` is specified.
+    pub fn is_packed(&self) -> bool {
+        wire::is_packed(
+            self.get_proto(),
+            self.message_descriptor.file_descriptor_proto(),
+        )
+    }
+
+    /// Key and value types of map field.
+    ///
+    /// Map entry message is always declared in the message containing the map field.
+    pub(crate) fn map_entry_types(
+        &self,
+    ) -> (field_descriptor_proto::Type, field_descriptor_proto::Type) {
+        let type_name = self.get_proto().get_type_name();
+        let entry_name = match type_name.rfind('.') {
+            Some(pos) => &type_name[pos + 1..],
+            None => type_name,
+        };
+        let entry = self
+            .message_descriptor
+            .get_proto()
+            .nested_type
+            .iter()
+            .find(|m| m.get_name() == entry_name)
+            .unwrap_or_else(|| panic!("map entry not found for field {}", self));
+        let field_type = |number| {
+            entry
+                .field
+                .iter()
+                .find(|f| f.get_number() == number)
+                .unwrap_or_else(|| panic!("map entry field {} not found: {}", number, self))
+                .get_field_type()
+        };
+        (field_type(1), field_type(2))
+    }
+
     fn get_impl(&self) -> FieldDescriptorImplRef {
         match self.message_descriptor.get_impl() {
             MessageDescriptorImplRef::Generated(g) => {
@@ -262,6 +315,23 @@ impl FieldDescriptor {
         }
     }
 
+    /// Merge `value` into a singular message field, initializing the field if unset.
+    pub(crate) fn merge_message(&self, m: &mut dyn MessageDyn, value: &dyn MessageDyn) {
+        if m.downcast_ref::<DynamicMessage>().is_none()
+            && self.containing_oneof_including_synthetic().is_some()
+        {
+            // generated oneof fields have no mutable accessor
+            let mut merged = match self.get_singular(m) {
+                Some(ReflectValueRef::Message(current)) => current.clone_box(),
+                _ => value.descriptor_dyn().new_instance(),
+            };
+            merged.merge_from_message_dyn(value);
+            self.set_singular_field(m, ReflectValueBox::Message(merged));
+        } else {
+            self.mut_message(m).merge_from_message_dyn(value);
+        }
+    }
+
     /// Default value.
     ///
     /// # Panics
@@ -297,6 +367,9 @@ impl FieldDescriptor {
 
     // Not public because it is not implemented for all types
     fn mut_singular_field_or_default<'a>(&self, m: &'a mut dyn MessageDyn) -> ReflectValueMut<'a> {
+        if m.downcast_ref::<DynamicMessage>().is_some() {
+            return DynamicMessage::downcast_mut(m).mut_singular_field_or_default(self);
+        }
         match self.singular() {
             SingularFieldAccessorRef::Generated(g) => g.accessor.mut_field_or_default(m),
             SingularFieldAccessorRef::Dynamic(..) => {
@@ -324,15 +397,50 @@ impl FieldDescriptor {
     /// If this field belongs to a different message type or
     /// field is not singular or value is of different type.
     pub fn set_singular_field(&self, m: &mut dyn MessageDyn, value: ReflectValueBox) {
+        if let Some(m) = m.downcast_mut::<DynamicMessage>() {
+            return m.set_field(self, value);
+        }
         match self.singular() {
             SingularFieldAccessorRef::Generated(g) => g.accessor.set_field(m, value),
             SingularFieldAccessorRef::Dynamic(d) => d.set_field(m, value),
         }
     }
 
+    /// Clear field: unset singular field or remove all elements
+    /// of repeated or map field.
+    ///
+    /// # Panics
+    ///
+    /// If this field belongs to a different message type.
+    pub fn clear_field(&self, m: &mut dyn MessageDyn) {
+        if let Some(m) = m.downcast_mut::<DynamicMessage>() {
+            return m.clear_field(self);
+        }
+        if self.is_repeated() {
+            self.mut_repeated(m).clear();
+        } else if self.is_map() {
+            self.mut_map(m).clear();
+        } else {
+            match self.singular() {
+                // `oneof` accessor clears the whole `oneof`,
+                // so it must not be called when other field is set
+                SingularFieldAccessorRef::Generated(g) => {
+                    if self.has_field(m) {
+                        g.accessor.clear_field(m)
+                    }
+                }
+                SingularFieldAccessorRef::Dynamic(..) => {
+                    DynamicMessage::downcast_mut(m).clear_field(self)
+                }
+            }
+        }
+    }
+
     /// Dynamic representation of field type.
     pub fn runtime_field_type(&self) -> RuntimeFieldType {
-        self.get_index().field_type.resolve(self)
+        self.get_index()
+            .field_type
+            .resolve(self.message_descriptor.file_descriptor())
     }
 
     /// Get field of any type.
@@ -341,6 +449,9 @@ impl FieldDescriptor {
     ///
     /// If this field belongs to a different message type.
     pub fn get_reflect<'a>(&self, m: &'a dyn MessageDyn) -> ReflectFieldRef<'a> {
+        if let Some(m) = m.downcast_ref::<DynamicMessage>() {
+            return m.get_reflect(self);
+        }
         match self.get_impl() {
             FieldDescriptorImplRef::Generated(g) => g.get_reflect(m),
             FieldDescriptorImplRef::Dynamic(d) => d.get_reflect(m),
@@ -381,6 +492,9 @@ impl FieldDescriptor {
     ///
     /// If this field belongs to a different message type or field is not `repeated`.
     pub fn mut_repeated<'a>(&self, m: &'a mut dyn MessageDyn) -> ReflectRepeatedMut<'a> {
+        if m.downcast_ref::<DynamicMessage>().is_some() {
+            return DynamicMessage::downcast_mut(m).mut_repeated(self);
+        }
         match self.repeated() {
             RepeatedFieldAccessorRef::Generated(g) => g.accessor.mut_repeated(m),
             RepeatedFieldAccessorRef::Dynamic(d) => d.mut_repeated(m),
@@ -407,6 +521,9 @@ impl FieldDescriptor {
     ///
     /// If this field belongs to a different message type or field is not `map`.
     pub fn mut_map<'a>(&self, m: &'a mut dyn MessageDyn) -> ReflectMapMut<'a> {
+        if m.downcast_ref::<DynamicMessage>().is_some() {
+            return DynamicMessage::downcast_mut(m).mut_map(self);
+        }
         match self.map() {
             MapFieldAccessorRef::Generated(g) => g.accessor.mut_reflect(m),
             MapFieldAccessorRef::Dynamic(d) => d.mut_map(m),
@@ -83,32 +83,36 @@ impl<'a> FileDescriptorBuilding<'a> {
         }
     }
 
+    /// Find message by fully-qualified name (starting with dot).
+    pub fn resolve_message(&self, type_name: &str) -> ForwardRuntimeTypeBox {
+        if let Some(name_to_package) =
+            protobuf_name_starts_with_package(type_name, self.current_file_descriptor.get_package())
+        {
+            if let Some(index) = self
+                .current_file_index
+                .message_by_name_to_package
+                .get(name_to_package)
+            {
+                return ForwardRuntimeTypeBox::CurrentFileMessage(*index);
+            }
+        }
+        for dep in self.deps_with_public {
+            if let Some(m) = dep.message_by_full_name(type_name) {
+                return ForwardRuntimeTypeBox::RuntimeTypeBox(RuntimeTypeBox::Message(m));
+            }
+        }
+        panic!(
+            "message not found: {}; files: {}",
+            type_name,
+            self.all_files_str()
+        );
+    }
+
     fn resolve_field_element_type(&self, field: &FieldDescriptorProto) -> ForwardRuntimeTypeBox {
         match field.get_field_type() {
             field_descriptor_proto::Type::TYPE_MESSAGE
             | field_descriptor_proto::Type::TYPE_GROUP => {
-                if let Some(name_to_package) = protobuf_name_starts_with_package(
-                    field.get_type_name(),
-                    self.current_file_descriptor.get_package(),
-                ) {
-                    if let Some(index) = self
-                        .current_file_index
-                        .message_by_name_to_package
-                        .get(name_to_package)
-                    {
-                        return ForwardRuntimeTypeBox::CurrentFileMessage(*index);
-                    }
-                }
-                for dep in self.deps_with_public {
-                    if let Some(m) = dep.message_by_full_name(field.get_type_name()) {
-                        return ForwardRuntimeTypeBox::RuntimeTypeBox(RuntimeTypeBox::Message(m));
-                    }
-                }
-                panic!(
-                    "message not found: {}; files: {}",
-                    field.get_type_name(),
-                    self.all_files_str()
-                );
+                self.resolve_message(field.get_type_name())
             }
             field_descriptor_proto::Type::TYPE_ENUM => {
                 if let Some(name_to_package) = protobuf_name_starts_with_package(
@@ -1,11 +1,13 @@
 use crate::descriptor::DescriptorProto;
 use crate::descriptor::FileDescriptorProto;
 use crate::reflect::enums::dynamic::DynamicEnumDescriptor;
+use crate::reflect::extension::index::ExtensionIndex;
 use crate::reflect::file::building::FileDescriptorBuilding;
 use crate::reflect::file::fds::fds_extend_with_public;
 use crate::reflect::file::index::FileIndex;
 use crate::reflect::message::dynamic::DynamicMessageDescriptor;
 use crate::reflect::message::path::MessagePath;
+use crate::reflect::service::index::ServiceIndex;
 use crate::reflect::FileDescriptor;
 use std::sync::Arc;
 
@@ -15,6 +17,8 @@ pub(crate) struct DynamicFileDescriptor {
     pub dependencies: Vec<FileDescriptor>,
     pub messages: Vec<DynamicMessageDescriptor>,
     pub enums: Vec<DynamicEnumDescriptor>,
+    pub extensions: Vec<ExtensionIndex>,
+    pub services: Vec<ServiceIndex>,
     pub index: FileIndex,
 }
 
@@ -26,25 +30,35 @@ impl DynamicFileDescriptor {
         let proto = Arc::new(proto);
 
         let index = FileIndex::index(&*proto);
+        let deps_with_public = fds_extend_with_public(dependencies.clone());
+        let building = FileDescriptorBuilding {
+            current_file_index: &index,
+            current_file_descriptor: &proto,
+            deps_with_public: &deps_with_public,
+        };
 
         let messages = index
             .messages
             .iter()
             .map(|message_index_entry| {
-                DynamicMessageDescriptor::new(
-                    &*proto,
-                    &message_index_entry.path,
-                    &FileDescriptorBuilding {
-                        current_file_index: &index,
-                        current_file_descriptor: &proto,
-                        deps_with_public: &fds_extend_with_public(dependencies.clone()),
-                    },
-                )
+                DynamicMessageDescriptor::new(&*proto, &message_index_entry.path, &building)
             })
             .collect();
 
+        let extensions = (0..index.extensions.len())
+            .map(|i| ExtensionIndex::index(index.extension_proto(&proto, i), &building))
+            .collect();
+
+        let services = proto
+            .service
+            .iter()
+            .map(|s| ServiceIndex::index(s, &building))
+            .collect();
+
         DynamicFileDescriptor {
             messages,
+            extensions,
+            services,
             enums: Self::enums(&proto),
             proto,
             dependencies,
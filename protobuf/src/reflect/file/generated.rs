@@ -1,9 +1,11 @@
 use crate::descriptor::FileDescriptorProto;
 use crate::reflect::enums::generated::GeneratedEnumDescriptor;
+use crate::reflect::extension::index::ExtensionIndex;
 use crate::reflect::file::building::FileDescriptorBuilding;
 use crate::reflect::file::fds::fds_extend_with_public;
 use crate::reflect::file::index::FileIndex;
 use crate::reflect::message::generated::GeneratedMessageDescriptor;
+use crate::reflect::service::index::ServiceIndex;
 use crate::reflect::FileDescriptor;
 use crate::reflect::GeneratedEnumDescriptorData;
 use crate::reflect::GeneratedMessageDescriptorData;
@@ -17,6 +19,8 @@ pub struct GeneratedFileDescriptor {
     pub(crate) dependencies: Vec<FileDescriptor>,
     pub(crate) messages: Vec<GeneratedMessageDescriptor>,
     pub(crate) enums: Vec<GeneratedEnumDescriptor>,
+    pub(crate) extensions: Vec<ExtensionIndex>,
+    pub(crate) services: Vec<ServiceIndex>,
     pub(crate) index: FileIndex,
 }
 
@@ -29,6 +33,12 @@ impl GeneratedFileDescriptor {
         enums: Vec<GeneratedEnumDescriptorData>,
     ) -> GeneratedFileDescriptor {
         let index = FileIndex::index(file_descriptor_proto);
+        let deps_with_public = fds_extend_with_public(dependencies.clone());
+        let building = FileDescriptorBuilding {
+            current_file_index: &index,
+            current_file_descriptor: file_descriptor_proto,
+            deps_with_public: &deps_with_public,
+        };
 
         let mut messages: HashMap<&str, GeneratedMessageDescriptorData> = messages
             .into_iter()
@@ -49,11 +59,7 @@ impl GeneratedFileDescriptor {
                         message,
                         file_descriptor_proto,
                         &index,
-                        &FileDescriptorBuilding {
-                            current_file_index: &index,
-                            current_file_descriptor: file_descriptor_proto,
-                            deps_with_public: &fds_extend_with_public(dependencies.clone()),
-                        },
+                        &building,
                     )
                 }
             })
@@ -65,11 +71,25 @@ impl GeneratedFileDescriptor {
             .map(|(i, e)| GeneratedEnumDescriptor::new(e, i, file_descriptor_proto))
             .collect();
 
+        let extensions = (0..index.extensions.len())
+            .map(|i| {
+                ExtensionIndex::index(index.extension_proto(file_descriptor_proto, i), &building)
+            })
+            .collect();
+
+        let services = file_descriptor_proto
+            .service
+            .iter()
+            .map(|s| ServiceIndex::index(s, &building))
+            .collect();
+
         GeneratedFileDescriptor {
             proto: file_descriptor_proto,
             dependencies,
             messages,
             enums,
+            extensions,
+            services,
             index,
         }
     }
@@ -1,4 +1,5 @@
 use crate::descriptor::DescriptorProto;
+use crate::descriptor::FieldDescriptorProto;
 use crate::descriptor::FileDescriptorProto;
 use crate::reflect::message::path::MessagePath;
 use crate::reflect::name::concat_paths;
@@ -23,6 +24,15 @@ pub(crate) struct FileIndexEnumEntry {
     pub name_to_package: String,
 }
 
+#[derive(Debug)]
+pub(crate) struct FileIndexExtensionEntry {
+    /// Message where the extension is declared, `None` for top-level extensions.
+    pub scope: Option<usize>,
+    /// Index of the extension in `extension` field of the scope.
+    pub index_in_scope: usize,
+    pub full_name: String,
+}
+
 #[derive(Debug)]
 pub(crate) struct FileIndex {
     pub(crate) messages: Vec<FileIndexMessageEntry>,
@@ -30,6 +40,7 @@ pub(crate) struct FileIndex {
     pub(crate) top_level_messages: Vec<usize>,
     pub(crate) enums: Vec<FileIndexEnumEntry>,
     pub(crate) enums_by_name_to_package: HashMap<String, usize>,
+    pub(crate) extensions: Vec<FileIndexExtensionEntry>,
 }
 
 impl FileIndex {
@@ -40,6 +51,7 @@ impl FileIndex {
             enums: Vec::new(),
             top_level_messages: Vec::with_capacity(file.message_type.len()),
             enums_by_name_to_package: HashMap::new(),
+            extensions: Vec::new(),
         };
 
         // Top-level enums start with zero
@@ -59,6 +71,7 @@ impl FileIndex {
 
         index.build_message_by_name_to_package();
         index.build_enum_by_name_to_package();
+        index.index_extensions(file);
 
         index
     }
@@ -114,6 +127,45 @@ impl FileIndex {
         message_index
     }
 
+    pub fn extension_proto<'a>(
+        &self,
+        file: &'a FileDescriptorProto,
+        index: usize,
+    ) -> &'a FieldDescriptorProto {
+        let entry = &self.extensions[index];
+        match entry.scope {
+            None => &file.extension[entry.index_in_scope],
+            Some(message_index) => {
+                &self.messages[message_index]
+                    .path
+                    .eval(file)
+                    .unwrap()
+                    .extension[entry.index_in_scope]
+            }
+        }
+    }
+
+    fn index_extensions(&mut self, file: &FileDescriptorProto) {
+        for (i, ext) in file.extension.iter().enumerate() {
+            self.extensions.push(FileIndexExtensionEntry {
+                scope: None,
+                index_in_scope: i,
+                full_name: concat_paths(file.get_package(), ext.get_name()),
+            });
+        }
+
+        for (message_index, message_entry) in self.messages.iter().enumerate() {
+            let message = message_entry.path.eval(file).unwrap();
+            for (i, ext) in message.extension.iter().enumerate() {
+                self.extensions.push(FileIndexExtensionEntry {
+                    scope: Some(message_index),
+                    index_in_scope: i,
+                    full_name: format!("{}.{}", message_entry.full_name, ext.get_name()),
+                });
+            }
+        }
+    }
+
     fn build_message_by_name_to_package(&mut self) {
         self.message_by_name_to_package = self
             .messages
@@ -1,13 +1,19 @@
 use crate::descriptor::DescriptorProto;
+use crate::descriptor::FieldDescriptorProto;
 use crate::descriptor::FileDescriptorProto;
+use crate::reflect::extension::index::ExtensionIndex;
 use crate::reflect::file::dynamic::DynamicFileDescriptor;
 use crate::reflect::file::fds::FdsBuilder;
 use crate::reflect::file::index::FileIndex;
+use crate::reflect::file::index::FileIndexExtensionEntry;
 use crate::reflect::file::index::FileIndexMessageEntry;
 use crate::reflect::name::protobuf_name_starts_with_package;
+use crate::reflect::service::index::ServiceIndex;
 use crate::reflect::EnumDescriptor;
+use crate::reflect::ExtensionDescriptor;
 use crate::reflect::GeneratedFileDescriptor;
 use crate::reflect::MessageDescriptor;
+use crate::reflect::ServiceDescriptor;
 use std::collections::HashMap;
 use std::collections::HashSet;
 use std::hash::Hash;
@@ -84,6 +90,65 @@ impl FileDescriptor {
             .unwrap()
     }
 
+    pub(crate) fn extension_index_entry(&self, index: usize) -> &FileIndexExtensionEntry {
+        &self.index().extensions[index]
+    }
+
+    pub(crate) fn extension_proto(&self, index: usize) -> &FieldDescriptorProto {
+        self.index().extension_proto(self.proto(), index)
+    }
+
+    pub(crate) fn extension_index(&self, index: usize) -> &ExtensionIndex {
+        match &self.imp {
+            FileDescriptorImpl::Generated(g) => &g.extensions[index],
+            FileDescriptorImpl::Dynamic(d) => &d.extensions[index],
+        }
+    }
+
+    /// Get all extensions declared in this file, including extensions nested in messages.
+    pub(crate) fn all_extensions(&self) -> Vec<ExtensionDescriptor> {
+        (0..self.index().extensions.len())
+            .map(|i| ExtensionDescriptor::new(self.clone(), i))
+            .collect()
+    }
+
+    /// Extensions declared in given message, or top-level extensions if scope is `None`.
+    pub(crate) fn extensions_in_scope(&self, scope: Option<usize>) -> Vec<ExtensionDescriptor> {
+        self.index()
+            .extensions
+            .iter()
+            .enumerate()
+            .filter(|(_, e)| e.scope == scope)
+            .map(|(i, _)| ExtensionDescriptor::new(self.clone(), i))
+            .collect()
+    }
+
+    /// Get top-level extensions.
+    pub fn extensions(&self) -> Vec<ExtensionDescriptor> {
+        self.extensions_in_scope(None)
+    }
+
+    pub(crate) fn service_index(&self, index: usize) -> &ServiceIndex {
+        match &self.imp {
+            FileDescriptorImpl::Generated(g) => &g.services[index],
+            FileDescriptorImpl::Dynamic(d) => &d.services[index],
+        }
+    }
+
+    /// Get services declared in this file.
+    pub fn services(&self) -> Vec<ServiceDescriptor> {
+        (0..self.proto().service.len())
+            .map(|i| ServiceDescriptor::new(self.clone(), i))
+            .collect()
+    }
+
+    /// Find service by name relative to the package.
+    ///
+    /// Only search in the current file, not in any dependencies.
+    pub fn service_by_package_relative_name(&self, name: &str) -> Option<ServiceDescriptor> {
+        self.services().into_iter().find(|s| s.get_name() == name)
+    }
+
     /// Get top-level messages.
     pub fn messages(&self) -> Vec<MessageDescriptor> {
         self.index()
@@ -93,6 +158,13 @@ impl FileDescriptor {
             .collect()
     }
 
+    /// Get all messages, including nested messages and map entries.
+    pub(crate) fn all_messages(&self) -> Vec<MessageDescriptor> {
+        (0..self.index().messages.len())
+            .map(|i| MessageDescriptor::new(self.clone(), i))
+            .collect()
+    }
+
     /// Get top-level enums.
     pub fn enums(&self) -> Vec<EnumDescriptor> {
         self.proto()
@@ -211,7 +283,8 @@ impl FileDescriptor {
             .collect()
     }
 
-    fn _all_files(&self) -> Vec<&FileDescriptor> {
+    /// This file and all its transitive dependencies.
+    pub(crate) fn all_files(&self) -> Vec<&FileDescriptor> {
         let mut r = Vec::new();
         let mut visited = HashSet::new();
 
@@ -102,6 +102,17 @@ impl<'a> ReflectMapRef<'a> {
 
 impl<'a> ReflectEq for ReflectMapRef<'a> {
     fn reflect_eq(&self, that: &Self, mode: &ReflectEqMode) -> bool {
+        self.reflect_eq_at(that, mode, &mut String::new())
+    }
+}
+
+impl<'a> ReflectMapRef<'a> {
+    pub(crate) fn reflect_eq_at(
+        &self,
+        that: &Self,
+        mode: &ReflectEqMode,
+        path: &mut String,
+    ) -> bool {
         let len = self.len();
 
         if len != that.len() {
@@ -114,7 +125,7 @@ impl<'a> ReflectEq for ReflectMapRef<'a> {
                 None => return false,
             };
 
-            if !va.reflect_eq(&vb, mode) {
+            if !va.reflect_eq_at(&vb, mode, path) {
                 return false;
             }
         }
@@ -205,3 +216,24 @@ impl<'a, 'b> IntoIterator for &'b ReflectMapRef<'a> {
         }
     }
 }
+
+/// Map key as it is written in paths, e.g. `1` or `"a"`.
+///
+/// String keys are quoted when `quote_string` is set.
+/// `None` for types which are not valid map key types.
+pub(crate) fn map_key_to_string(key: &ReflectValueRef, quote_string: bool) -> Option<String> {
+    match key {
+        ReflectValueRef::U32(v) => Some(v.to_string()),
+        ReflectValueRef::U64(v) => Some(v.to_string()),
+        ReflectValueRef::I32(v) => Some(v.to_string()),
+        ReflectValueRef::I64(v) => Some(v.to_string()),
+        ReflectValueRef::Bool(v) => Some(v.to_string()),
+        ReflectValueRef::String(v) if quote_string => Some(format!("{:?}", v)),
+        ReflectValueRef::String(v) => Some((*v).to_owned()),
+        ReflectValueRef::F32(..)
+        | ReflectValueRef::F64(..)
+        | ReflectValueRef::Bytes(..)
+        | ReflectValueRef::Enum(..)
+        | ReflectValueRef::Message(..) => None,
+    }
+}
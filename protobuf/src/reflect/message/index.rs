@@ -9,6 +9,8 @@ pub(crate) struct MessageIndex {
     pub index_by_name: HashMap<String, usize>,
     pub index_by_name_or_json_name: HashMap<String, usize>,
     pub index_by_number: HashMap<u32, usize>,
+    // indices of oneofs except synthetic oneofs of proto3 `optional` fields
+    pub oneofs: Vec<usize>,
 }
 
 impl MessageIndex {
@@ -38,11 +40,22 @@ impl MessageIndex {
             }
         }
 
+        let oneofs = (0..proto.oneof_decl.len())
+            .filter(|&i| {
+                !proto.field.iter().any(|f| {
+                    f.has_oneof_index()
+                        && f.get_oneof_index() as usize == i
+                        && f.get_proto3_optional()
+                })
+            })
+            .collect();
+
         MessageIndex {
             fields,
             index_by_name,
             index_by_name_or_json_name,
             index_by_number,
+            oneofs,
         }
     }
 }
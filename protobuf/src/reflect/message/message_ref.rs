@@ -50,6 +50,15 @@ impl<'a> MessageRef<'a> {
         }
     }
 
+    /// Referenced message with the lifetime of this reference,
+    /// `None` for an owned empty message.
+    pub(crate) fn as_message_dyn(&self) -> Option<&'a dyn MessageDyn> {
+        match &self.imp {
+            MessageRefImpl::Message(m) => Some(*m),
+            MessageRefImpl::EmptyDynamic(..) => None,
+        }
+    }
+
     /// Default (empty) instance of given message type.
     pub fn default_instance(message: &MessageDescriptor) -> MessageRef<'static> {
         // Note we create a native generated instance for generated types
@@ -1,4 +1,5 @@
 use std::fmt;
+use std::ops::Range;
 
 use crate::message::Message;
 
@@ -12,12 +13,14 @@ use crate::reflect::file::FileDescriptorImpl;
 use crate::reflect::message::dynamic::DynamicMessageDescriptor;
 use crate::reflect::message::generated::GeneratedMessageDescriptor;
 use crate::reflect::message::index::MessageIndex;
-use crate::reflect::reflect_eq::ReflectEq;
 use crate::reflect::reflect_eq::ReflectEqMode;
 use crate::reflect::EnumDescriptor;
+use crate::reflect::ExtensionDescriptor;
 use crate::reflect::FieldDescriptor;
 use crate::reflect::FileDescriptor;
 use crate::reflect::OneofDescriptor;
+use crate::reflect::ReflectFieldRef;
+use crate::reflect::ReflectValueRef;
 
 pub(crate) mod dynamic;
 pub(crate) mod generated;
@@ -173,6 +176,9 @@ impl MessageDescriptor {
     /// Clone a message
     pub(crate) fn clone_message(&self, message: &dyn MessageDyn) -> Box<dyn MessageDyn> {
         assert!(&message.descriptor_dyn() == self);
+        if let Some(message) = message.downcast_ref::<DynamicMessage>() {
+            return Box::new(message.clone());
+        }
         match self.get_impl() {
             MessageDescriptorImplRef::Generated(g) => g.non_map().factory.clone(message),
             MessageDescriptorImplRef::Dynamic(..) => {
@@ -204,29 +210,106 @@ impl MessageDescriptor {
         a: &dyn MessageDyn,
         b: &dyn MessageDyn,
         mode: &ReflectEqMode,
+    ) -> bool {
+        self.reflect_eq_at(a, b, mode, &mut String::new())
+    }
+
+    /// `reflect_eq` for messages located at `path` relative to the compared root.
+    fn reflect_eq_at(
+        &self,
+        a: &dyn MessageDyn,
+        b: &dyn MessageDyn,
+        mode: &ReflectEqMode,
+        path: &mut String,
     ) -> bool {
         // Explicitly force panic even if field list is empty
         assert_eq!(self, &a.descriptor_dyn());
         assert_eq!(self, &b.descriptor_dyn());
 
         for field in self.fields() {
-            let af = field.get_reflect(a);
-            let bf = field.get_reflect(b);
-            if !af.reflect_eq(&bf, mode) {
+            let len = path.len();
+            if len != 0 {
+                path.push('.');
+            }
+            path.push_str(field.get_name());
+
+            let eq = mode.is_field_ignored(path) || {
+                if mode.default_equals_unset && !field.is_repeated_or_map() {
+                    let av = field.get_singular_field_or_default(a);
+                    let bv = field.get_singular_field_or_default(b);
+                    av.reflect_eq_at(&bv, mode, path)
+                } else {
+                    let af = field.get_reflect(a);
+                    let bf = field.get_reflect(b);
+                    af.reflect_eq_at(&bf, mode, path)
+                }
+            };
+
+            path.truncate(len);
+            if !eq {
                 return false;
             }
         }
-        true
+
+        mode.ignore_unknown_fields || a.get_unknown_fields_dyn() == b.get_unknown_fields_dyn()
+    }
+
+    /// Merge fields of `src` into `dst`.
+    ///
+    /// # Panics
+    ///
+    /// Is any message has different type than this descriptor.
+    pub(crate) fn merge_from_message(&self, dst: &mut dyn MessageDyn, src: &dyn MessageDyn) {
+        assert_eq!(self, &dst.descriptor_dyn());
+        assert_eq!(self, &src.descriptor_dyn());
+
+        for field in self.fields() {
+            match field.get_reflect(src) {
+                ReflectFieldRef::Optional(Some(ReflectValueRef::Message(m))) => {
+                    field.merge_message(dst, &*m);
+                }
+                ReflectFieldRef::Optional(Some(v)) => {
+                    field.set_singular_field(dst, v.to_box());
+                }
+                ReflectFieldRef::Optional(None) => {}
+                ReflectFieldRef::Repeated(repeated) => {
+                    let mut dst_repeated = field.mut_repeated(dst);
+                    for v in repeated {
+                        dst_repeated.push(v.to_box());
+                    }
+                }
+                ReflectFieldRef::Map(map) => {
+                    let mut dst_map = field.mut_map(dst);
+                    for (k, v) in &map {
+                        dst_map.insert(k.to_box(), v.to_box());
+                    }
+                }
+            }
+        }
+
+        let unknown_fields = dst.mut_unknown_fields_dyn();
+        for (number, value) in src.get_unknown_fields_dyn().iter_values() {
+            unknown_fields.add_value(number, value.to_value());
+        }
     }
 
     pub(crate) fn reflect_eq_maybe_unrelated(
         a: &dyn MessageDyn,
         b: &dyn MessageDyn,
         mode: &ReflectEqMode,
+    ) -> bool {
+        MessageDescriptor::reflect_eq_maybe_unrelated_at(a, b, mode, &mut String::new())
+    }
+
+    pub(crate) fn reflect_eq_maybe_unrelated_at(
+        a: &dyn MessageDyn,
+        b: &dyn MessageDyn,
+        mode: &ReflectEqMode,
+        path: &mut String,
     ) -> bool {
         let ad = a.descriptor_dyn();
         let bd = b.descriptor_dyn();
-        ad == bd && ad.reflect_eq(a, b, mode)
+        ad == bd && ad.reflect_eq_at(a, b, mode, path)
     }
 
     /// Message name as given in `.proto` file
@@ -247,8 +330,19 @@ impl MessageDescriptor {
         })
     }
 
-    /// Nested oneofs
+    /// Nested oneofs, except synthetic oneofs of proto3 `optional` fields.
     pub fn oneofs<'a>(&'a self) -> impl ExactSizeIterator<Item = OneofDescriptor> + 'a {
+        self.get_index()
+            .oneofs
+            .iter()
+            .map(move |&index| OneofDescriptor {
+                message_descriptor: self.clone(),
+                index,
+            })
+    }
+
+    /// Nested oneofs including synthetic.
+    pub fn all_oneofs<'a>(&'a self) -> impl ExactSizeIterator<Item = OneofDescriptor> + 'a {
         self.get_proto()
             .oneof_decl
             .iter()
@@ -259,6 +353,30 @@ impl MessageDescriptor {
             })
     }
 
+    /// Extensions declared in this message.
+    ///
+    /// Note these are not extensions of this message,
+    /// but extensions of any messages declared in the scope of this message.
+    pub fn extensions(&self) -> Vec<ExtensionDescriptor> {
+        self.file_descriptor.extensions_in_scope(Some(self.index))
+    }
+
+    /// Field number ranges reserved for extensions of this message.
+    ///
+    /// Range end is exclusive like in `DescriptorProto.ExtensionRange`.
+    pub fn extension_ranges(&self) -> Vec<Range<u32>> {
+        self.get_proto()
+            .extension_range
+            .iter()
+            .map(|r| r.get_start() as u32..r.get_end() as u32)
+            .collect()
+    }
+
+    /// Check if given field number is in one of extension ranges of this message.
+    pub fn is_extension_number(&self, number: u32) -> bool {
+        self.extension_ranges().iter().any(|r| r.contains(&number))
+    }
+
     pub(crate) fn get_index(&self) -> &MessageIndex {
         match self.get_impl() {
             MessageDescriptorImplRef::Generated(g) => &g.non_map().index,
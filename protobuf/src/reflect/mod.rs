@@ -1,23 +1,30 @@
 //! Reflection implementation for protobuf types.
 
 mod acc;
+mod diff;
 mod dynamic;
 mod enums;
+pub(crate) mod extension;
 mod field;
 mod file;
 mod find_message_or_enum;
-mod map;
+pub(crate) mod map;
 pub(crate) mod message;
 mod oneof;
 mod repeated;
+mod required;
 mod runtime_type_box;
+mod service;
 mod type_dynamic;
+mod type_registry;
 pub(crate) mod value;
+pub(crate) mod wire;
 
 pub mod runtime_types;
 pub mod types;
 
 pub(crate) mod reflect_eq;
+pub(crate) mod reflect_hash;
 
 pub mod rt;
 
@@ -43,12 +50,19 @@ pub use self::message::generated::GeneratedMessageDescriptorData;
 pub use self::message::message_ref::MessageRef;
 pub use self::message::MessageDescriptor;
 
+pub use self::extension::ExtensionDescriptor;
+
 pub use self::field::FieldDescriptor;
 pub use self::field::ReflectFieldRef;
 pub use self::field::RuntimeFieldType;
 
 pub use self::oneof::OneofDescriptor;
 
+pub use self::dynamic::DynamicMessage;
+
+pub use self::service::MethodDescriptor;
+pub use self::service::ServiceDescriptor;
+
 #[doc(hidden)]
 pub use self::file::generated::GeneratedFileDescriptor;
 pub use self::file::FileDescriptor;
@@ -57,3 +71,15 @@ pub use self::runtime_type_box::RuntimeTypeBox;
 
 pub use self::reflect_eq::ReflectEq;
 pub use self::reflect_eq::ReflectEqMode;
+pub use self::reflect_hash::ReflectHash;
+pub use self::reflect_hash::ReflectHashKey;
+
+pub use self::required::find_missing_required_fields;
+
+pub use self::diff::diff_messages;
+pub use self::diff::DiffKind;
+pub use self::diff::DiffOptions;
+pub use self::diff::FieldDiff;
+pub use self::diff::MessageDiff;
+
+pub use self::type_registry::TypeRegistry;
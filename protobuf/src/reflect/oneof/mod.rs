@@ -20,10 +20,19 @@ impl OneofDescriptor {
         self.get_proto().get_name()
     }
 
+    /// Is this oneof synthetic, i.e. generated for proto3 `optional` field.
+    pub fn is_synthetic(&self) -> bool {
+        self.message_descriptor.get_proto().field.iter().any(|f| {
+            f.has_oneof_index()
+                && f.get_oneof_index() as usize == self.index
+                && f.get_proto3_optional()
+        })
+    }
+
     /// Fields in this oneof.
     pub fn fields<'a>(&'a self) -> impl Iterator<Item = FieldDescriptor> + 'a {
         self.message_descriptor
             .fields()
-            .filter(move |f| f.containing_oneof().as_ref() == Some(self))
+            .filter(move |f| f.containing_oneof_including_synthetic().as_ref() == Some(self))
     }
 }
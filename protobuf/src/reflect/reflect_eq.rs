@@ -1,8 +1,39 @@
 /// Parameter for [`ReflectEq`].
+///
+/// Fields are public, so options can be combined:
+///
+/// ```
+/// # use protobuf::reflect::ReflectEqMode;
+/// let mut mode = ReflectEqMode::nan_equal();
+/// mode.float_absolute_tolerance = 1e-9;
+/// mode.ignore_fields.push("header.timestamp".to_owned());
+/// ```
 #[derive(Debug, Default)]
 pub struct ReflectEqMode {
     /// When `true`, `NaN` values are considered equal to each other.
     pub nan_equal: bool,
+    /// Field paths which are not compared, e.g. `header.timestamp`.
+    ///
+    /// Path components are field names starting from the compared message.
+    /// Repeated and map fields do not add components, so `items.id`
+    /// ignores field `id` in every element of repeated field `items`.
+    pub ignore_fields: Vec<String>,
+    /// Floats are equal if their difference is not greater than this value.
+    pub float_absolute_tolerance: f64,
+    /// Floats are equal if their difference is not greater than
+    /// this value multiplied by the larger of absolute values.
+    pub float_relative_tolerance: f64,
+    /// Compare repeated fields as multisets, ignoring element order.
+    ///
+    /// Elements are matched greedily, so results are only reliable
+    /// when element equality is transitive (e.g. no float tolerance).
+    pub unordered_repeated: bool,
+    /// Unset singular field is equal to the field set to default value.
+    ///
+    /// Affects proto2 fields and proto3 `optional` fields.
+    pub default_equals_unset: bool,
+    /// Do not compare unknown fields.
+    pub ignore_unknown_fields: bool,
     _non_exhausitve: (),
 }
 
@@ -21,6 +52,23 @@ impl ReflectEqMode {
             ..Default::default()
         }
     }
+
+    pub(crate) fn is_field_ignored(&self, path: &str) -> bool {
+        self.ignore_fields.iter().any(|p| p == path)
+    }
+
+    pub(crate) fn float_eq(&self, a: f64, b: f64) -> bool {
+        if a.is_nan() || b.is_nan() {
+            return a.is_nan() == b.is_nan() && self.nan_equal;
+        }
+        if a == b {
+            // also handles infinities
+            return true;
+        }
+        let diff = (a - b).abs();
+        diff <= self.float_absolute_tolerance
+            || diff <= self.float_relative_tolerance * a.abs().max(b.abs())
+    }
 }
 
 /// Special version of eq.
@@ -31,3 +79,27 @@ pub trait ReflectEq {
     /// Perform the equality comparison.
     fn reflect_eq(&self, that: &Self, mode: &ReflectEqMode) -> bool;
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn float_eq() {
+        let mut mode = ReflectEqMode::default();
+        assert!(mode.float_eq(1.0, 1.0));
+        assert!(!mode.float_eq(1.0, 1.0 + 1e-12));
+        assert!(!mode.float_eq(f64::NAN, f64::NAN));
+        assert!(mode.float_eq(f64::INFINITY, f64::INFINITY));
+
+        mode.float_absolute_tolerance = 0.1;
+        assert!(mode.float_eq(1.0, 1.05));
+        assert!(!mode.float_eq(1.0, 1.2));
+        assert!(!mode.float_eq(f64::INFINITY, f64::NEG_INFINITY));
+
+        mode.float_absolute_tolerance = 0.0;
+        mode.float_relative_tolerance = 0.01;
+        assert!(mode.float_eq(1000.0, 1005.0));
+        assert!(!mode.float_eq(1.0, 1.05));
+    }
+}
@@ -0,0 +1,259 @@
+use std::collections::hash_map::DefaultHasher;
+use std::hash::Hash;
+use std::hash::Hasher;
+
+use crate::message::Message;
+use crate::message_dyn::MessageDyn;
+use crate::reflect::map::ReflectMapRef;
+use crate::reflect::message::message_ref::MessageRef;
+use crate::reflect::reflect_eq::ReflectEqMode;
+use crate::reflect::repeated::ReflectRepeatedRef;
+use crate::reflect::ReflectFieldRef;
+use crate::reflect::ReflectValueRef;
+
+/// Hash consistent with [`ReflectEq`](crate::reflect::ReflectEq).
+///
+/// Values equal according to `reflect_eq` with a `mode`
+/// have equal hashes when hashed with the same `mode`.
+pub trait ReflectHash {
+    /// Feed this value into the given `Hasher`.
+    fn reflect_hash(&self, state: &mut dyn Hasher, mode: &ReflectEqMode);
+}
+
+impl<'a> ReflectHash for ReflectValueRef<'a> {
+    fn reflect_hash(&self, state: &mut dyn Hasher, mode: &ReflectEqMode) {
+        hash_value(self, state, mode, &mut String::new())
+    }
+}
+
+impl<'a> ReflectHash for ReflectFieldRef<'a> {
+    fn reflect_hash(&self, state: &mut dyn Hasher, mode: &ReflectEqMode) {
+        hash_field(self, state, mode, &mut String::new())
+    }
+}
+
+impl<'a> ReflectHash for ReflectRepeatedRef<'a> {
+    fn reflect_hash(&self, state: &mut dyn Hasher, mode: &ReflectEqMode) {
+        hash_repeated(self, state, mode, &mut String::new())
+    }
+}
+
+impl<'a> ReflectHash for ReflectMapRef<'a> {
+    fn reflect_hash(&self, state: &mut dyn Hasher, mode: &ReflectEqMode) {
+        hash_map(self, state, mode, &mut String::new())
+    }
+}
+
+impl<'a> ReflectHash for MessageRef<'a> {
+    fn reflect_hash(&self, state: &mut dyn Hasher, mode: &ReflectEqMode) {
+        hash_message(&**self, state, mode, &mut String::new())
+    }
+}
+
+/// Hash an item with a fresh hasher, for order-independent combining.
+fn hash_separately(f: impl FnOnce(&mut dyn Hasher)) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    f(&mut hasher);
+    hasher.finish()
+}
+
+fn hash_float(f: f64, state: &mut dyn Hasher, mode: &ReflectEqMode) {
+    if mode.float_absolute_tolerance != 0.0 || mode.float_relative_tolerance != 0.0 {
+        // values within tolerance are equal, so no bits are stable
+        return;
+    }
+    let bits = if f.is_nan() {
+        // all `NaN`s are equal with `nan_equal`, and never equal otherwise
+        f64::NAN.to_bits()
+    } else if f == 0.0 {
+        // `-0.0 == 0.0`
+        0
+    } else {
+        f.to_bits()
+    };
+    state.write_u64(bits);
+}
+
+fn hash_value(
+    v: &ReflectValueRef,
+    state: &mut dyn Hasher,
+    mode: &ReflectEqMode,
+    path: &mut String,
+) {
+    match v {
+        ReflectValueRef::U32(v) => state.write_u32(*v),
+        ReflectValueRef::U64(v) => state.write_u64(*v),
+        ReflectValueRef::I32(v) => state.write_i32(*v),
+        ReflectValueRef::I64(v) => state.write_i64(*v),
+        ReflectValueRef::F32(v) => hash_float(*v as f64, state, mode),
+        ReflectValueRef::F64(v) => hash_float(*v, state, mode),
+        ReflectValueRef::Bool(v) => state.write_u8(*v as u8),
+        ReflectValueRef::String(v) => v.hash(&mut &mut *state),
+        ReflectValueRef::Bytes(v) => v.hash(&mut &mut *state),
+        ReflectValueRef::Enum(_, v) => state.write_i32(*v),
+        ReflectValueRef::Message(m) => hash_message(&**m, state, mode, path),
+    }
+}
+
+fn hash_field(
+    f: &ReflectFieldRef,
+    state: &mut dyn Hasher,
+    mode: &ReflectEqMode,
+    path: &mut String,
+) {
+    match f {
+        ReflectFieldRef::Optional(None) => state.write_u8(0),
+        ReflectFieldRef::Optional(Some(v)) => {
+            state.write_u8(1);
+            hash_value(v, state, mode, path);
+        }
+        ReflectFieldRef::Repeated(r) => hash_repeated(r, state, mode, path),
+        ReflectFieldRef::Map(m) => hash_map(m, state, mode, path),
+    }
+}
+
+fn hash_repeated(
+    r: &ReflectRepeatedRef,
+    state: &mut dyn Hasher,
+    mode: &ReflectEqMode,
+    path: &mut String,
+) {
+    state.write_usize(r.len());
+    if mode.unordered_repeated {
+        let mut sum: u64 = 0;
+        for v in r {
+            sum = sum.wrapping_add(hash_separately(|h| hash_value(&v, h, mode, path)));
+        }
+        state.write_u64(sum);
+    } else {
+        for v in r {
+            hash_value(&v, state, mode, path);
+        }
+    }
+}
+
+fn hash_map(m: &ReflectMapRef, state: &mut dyn Hasher, mode: &ReflectEqMode, path: &mut String) {
+    // map iteration order is unspecified
+    state.write_usize(m.len());
+    let mut sum: u64 = 0;
+    for (k, v) in m {
+        sum = sum.wrapping_add(hash_separately(|h| {
+            hash_value(&k, h, mode, path);
+            hash_value(&v, h, mode, path);
+        }));
+    }
+    state.write_u64(sum);
+}
+
+fn hash_message(
+    m: &dyn MessageDyn,
+    state: &mut dyn Hasher,
+    mode: &ReflectEqMode,
+    path: &mut String,
+) {
+    for field in m.descriptor_dyn().fields() {
+        let len = path.len();
+        if len != 0 {
+            path.push('.');
+        }
+        path.push_str(field.get_name());
+
+        if !mode.is_field_ignored(path) {
+            if mode.default_equals_unset && !field.is_repeated_or_map() {
+                hash_value(&field.get_singular_field_or_default(m), state, mode, path);
+            } else {
+                hash_field(&field.get_reflect(m), state, mode, path);
+            }
+        }
+
+        path.truncate(len);
+    }
+
+    if !mode.ignore_unknown_fields {
+        m.get_unknown_fields_dyn().hash(&mut &mut *state);
+    }
+}
+
+/// Wrapper implementing `Hash` and `Eq` for messages by reflection,
+/// so messages, including dynamic, can be used as `HashMap` or `HashSet` keys.
+///
+/// Messages are compared with [`ReflectEqMode::nan_equal()`],
+/// because `Eq` requires every value to be equal to itself.
+///
+/// ```
+/// # use std::collections::HashSet;
+/// # use protobuf::reflect::ReflectHashKey;
+/// # use protobuf::well_known_types::Duration;
+/// let mut set = HashSet::new();
+/// set.insert(ReflectHashKey(Duration::new()));
+/// assert!(set.contains(&ReflectHashKey(Duration::new())));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ReflectHashKey<M>(pub M);
+
+impl<M: Message> Hash for ReflectHashKey<M> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.reflect_hash(state, &ReflectEqMode::nan_equal())
+    }
+}
+
+impl<M: Message> PartialEq for ReflectHashKey<M> {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.reflect_eq(&other.0, &ReflectEqMode::nan_equal())
+    }
+}
+
+impl<M: Message> Eq for ReflectHashKey<M> {}
+
+impl Hash for ReflectHashKey<Box<dyn MessageDyn>> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.reflect_hash_dyn(state, &ReflectEqMode::nan_equal())
+    }
+}
+
+impl PartialEq for ReflectHashKey<Box<dyn MessageDyn>> {
+    fn eq(&self, other: &Self) -> bool {
+        self.0
+            .reflect_eq_dyn(&*other.0, &ReflectEqMode::nan_equal())
+    }
+}
+
+impl Eq for ReflectHashKey<Box<dyn MessageDyn>> {}
+
+pub(crate) fn reflect_hash_message(
+    m: &dyn MessageDyn,
+    state: &mut dyn Hasher,
+    mode: &ReflectEqMode,
+) {
+    hash_message(m, state, mode, &mut String::new())
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::hash_map::DefaultHasher;
+    use std::hash::Hasher;
+
+    use super::*;
+    use crate::reflect::ReflectEq;
+
+    fn hash(v: &ReflectValueRef, mode: &ReflectEqMode) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        v.reflect_hash(&mut hasher, mode);
+        hasher.finish()
+    }
+
+    #[test]
+    fn float_consistent_with_eq() {
+        let mode = ReflectEqMode::nan_equal();
+        for (a, b) in &[(0.0, -0.0), (f64::NAN, -f64::NAN), (1.5, 1.5)] {
+            let a = ReflectValueRef::F64(*a);
+            let b = ReflectValueRef::F64(*b);
+            assert!(a.reflect_eq(&b, &mode));
+            assert_eq!(hash(&a, &mode), hash(&b, &mode));
+        }
+        assert_ne!(
+            hash(&ReflectValueRef::F64(1.0), &mode),
+            hash(&ReflectValueRef::F64(2.0), &mode)
+        );
+    }
+}
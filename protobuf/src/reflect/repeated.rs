@@ -1,7 +1,6 @@
 use std::fmt;
 use std::slice;
 
-use crate::reflect::dynamic::repeated::DynamicRepeated;
 use crate::reflect::reflect_eq::ReflectEq;
 use crate::reflect::reflect_eq::ReflectEqMode;
 use crate::reflect::value::value_ref::ReflectValueRef;
@@ -103,10 +102,30 @@ impl<'a, V: ProtobufValue + 'static> ReflectRepeatedIterTrait<'a>
     }
 }
 
+struct ReflectRepeatedIterImplBoxes<'a> {
+    iter: slice::Iter<'a, ReflectValueBox>,
+}
+
+impl<'a> ReflectRepeatedIterTrait<'a> for ReflectRepeatedIterImplBoxes<'a> {
+    fn next(&mut self) -> Option<ReflectValueRef<'a>> {
+        self.iter.next().map(ReflectValueBox::as_value_ref)
+    }
+}
+
 pub struct ReflectRepeatedIter<'a> {
     imp: Box<dyn ReflectRepeatedIterTrait<'a> + 'a>,
 }
 
+impl<'a> ReflectRepeatedIter<'a> {
+    pub(crate) fn new_boxes(values: &'a [ReflectValueBox]) -> ReflectRepeatedIter<'a> {
+        ReflectRepeatedIter {
+            imp: Box::new(ReflectRepeatedIterImplBoxes {
+                iter: values.iter(),
+            }),
+        }
+    }
+}
+
 impl<'a> Iterator for ReflectRepeatedIter<'a> {
     type Item = ReflectValueRef<'a>;
 
@@ -127,14 +146,12 @@ impl<'a> IntoIterator for &'a dyn ReflectRepeated {
 #[derive(Clone)]
 enum ReflectRepeatedRefImpl<'a> {
     Generated(&'a dyn ReflectRepeated),
-    DynamicEmpty(DynamicRepeated),
 }
 
 impl<'a> fmt::Debug for ReflectRepeatedRefImpl<'a> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             ReflectRepeatedRefImpl::Generated(r) => fmt::Debug::fmt(r, f),
-            ReflectRepeatedRefImpl::DynamicEmpty(r) => fmt::Debug::fmt(r, f),
         }
     }
 }
@@ -157,17 +174,10 @@ impl<'a> ReflectRepeatedRef<'a> {
         }
     }
 
-    pub(crate) fn new_empty(elem: RuntimeTypeBox) -> ReflectRepeatedRef<'static> {
-        ReflectRepeatedRef {
-            imp: ReflectRepeatedRefImpl::DynamicEmpty(DynamicRepeated::new(elem)),
-        }
-    }
-
     /// Number of elements in repeated field
     pub fn len(&self) -> usize {
         match &self.imp {
             ReflectRepeatedRefImpl::Generated(g) => g.len(),
-            ReflectRepeatedRefImpl::DynamicEmpty(d) => d.len(),
         }
     }
 
@@ -181,7 +191,6 @@ impl<'a> ReflectRepeatedRef<'a> {
     pub fn get(&self, index: usize) -> ReflectValueRef<'a> {
         match &self.imp {
             ReflectRepeatedRefImpl::Generated(r) => r.get(index),
-            ReflectRepeatedRefImpl::DynamicEmpty(..) => panic!("empty"),
         }
     }
 
@@ -189,23 +198,47 @@ impl<'a> ReflectRepeatedRef<'a> {
     pub fn element_type(&self) -> RuntimeTypeBox {
         match &self.imp {
             ReflectRepeatedRefImpl::Generated(r) => r.element_type(),
-            ReflectRepeatedRefImpl::DynamicEmpty(r) => r.element_type(),
         }
     }
 }
 
 impl<'a> ReflectEq for ReflectRepeatedRef<'a> {
     fn reflect_eq(&self, that: &Self, mode: &ReflectEqMode) -> bool {
+        self.reflect_eq_at(that, mode, &mut String::new())
+    }
+}
+
+impl<'a> ReflectRepeatedRef<'a> {
+    pub(crate) fn reflect_eq_at(
+        &self,
+        that: &Self,
+        mode: &ReflectEqMode,
+        path: &mut String,
+    ) -> bool {
         let len = self.len();
 
         if len != that.len() {
             return false;
         }
 
+        if mode.unordered_repeated {
+            let mut matched = vec![false; len];
+            'elements: for a in self {
+                for (j, b) in that.into_iter().enumerate() {
+                    if !matched[j] && a.reflect_eq_at(&b, mode, path) {
+                        matched[j] = true;
+                        continue 'elements;
+                    }
+                }
+                return false;
+            }
+            return true;
+        }
+
         for i in 0..len {
             let a = self.get(i);
             let b = that.get(i);
-            if !a.reflect_eq(&b, mode) {
+            if !a.reflect_eq_at(&b, mode, path) {
                 return false;
             }
         }
@@ -0,0 +1,81 @@
+//! Find missing required fields.
+
+use crate::descriptor::field_descriptor_proto;
+use crate::reflect::map::map_key_to_string;
+use crate::reflect::ReflectFieldRef;
+use crate::reflect::ReflectValueRef;
+use crate::MessageDyn;
+
+/// Find required fields which are not set in the message
+/// or in any of its nested messages.
+///
+/// Fields are returned as paths like `a.b[2].c`, map entries are
+/// referenced by key like `m["key"].c`.
+/// Empty result means the message is initialized.
+pub fn find_missing_required_fields(message: &dyn MessageDyn) -> Vec<String> {
+    let mut r = Vec::new();
+    find_missing_required_fields_to(message, "", &mut r);
+    r
+}
+
+fn find_missing_required_fields_to(message: &dyn MessageDyn, prefix: &str, r: &mut Vec<String>) {
+    if message.is_initialized_dyn() {
+        return;
+    }
+    for field in message.descriptor_dyn().fields() {
+        let path = format!("{}{}", prefix, field.get_name());
+        match field.get_reflect(message) {
+            ReflectFieldRef::Optional(None) => {
+                if field.get_proto().get_label() == field_descriptor_proto::Label::LABEL_REQUIRED {
+                    r.push(path);
+                }
+            }
+            ReflectFieldRef::Optional(Some(v)) => {
+                find_missing_in_value(v, &path, r);
+            }
+            ReflectFieldRef::Repeated(repeated) => {
+                for (i, v) in repeated.into_iter().enumerate() {
+                    find_missing_in_value(v, &format!("{}[{}]", path, i), r);
+                }
+            }
+            ReflectFieldRef::Map(map) => {
+                for (k, v) in &map {
+                    let k = map_key_to_string(&k, true).unwrap_or_else(|| "?".to_owned());
+                    find_missing_in_value(v, &format!("{}[{}]", path, k), r);
+                }
+            }
+        }
+    }
+}
+
+fn find_missing_in_value(value: ReflectValueRef, path: &str, r: &mut Vec<String>) {
+    if let ReflectValueRef::Message(m) = value {
+        find_missing_required_fields_to(&*m, &format!("{}.", path), r);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::find_missing_required_fields;
+    use crate::descriptor::uninterpreted_option::NamePart;
+    use crate::descriptor::FileDescriptorProto;
+    use crate::descriptor::UninterpretedOption;
+
+    #[test]
+    fn nested() {
+        let mut option = UninterpretedOption::new();
+        option.name.push(NamePart::new());
+        let mut part = NamePart::new();
+        part.set_name_part("a".to_owned());
+        option.name.push(part);
+        assert_eq!(
+            vec![
+                "name[0].name_part",
+                "name[0].is_extension",
+                "name[1].is_extension"
+            ],
+            find_missing_required_fields(&option)
+        );
+        assert!(find_missing_required_fields(&FileDescriptorProto::new()).is_empty());
+    }
+}
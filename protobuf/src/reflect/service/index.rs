@@ -0,0 +1,38 @@
+use crate::descriptor::ServiceDescriptorProto;
+use crate::reflect::field::index::ForwardRuntimeTypeBox;
+use crate::reflect::file::building::FileDescriptorBuilding;
+use crate::reflect::name::concat_paths;
+
+#[derive(Debug)]
+pub(crate) struct MethodIndex {
+    pub(crate) input_type: ForwardRuntimeTypeBox,
+    pub(crate) output_type: ForwardRuntimeTypeBox,
+}
+
+#[derive(Debug)]
+pub(crate) struct ServiceIndex {
+    pub(crate) full_name: String,
+    pub(crate) methods: Vec<MethodIndex>,
+}
+
+impl ServiceIndex {
+    pub fn index(
+        service: &ServiceDescriptorProto,
+        building: &FileDescriptorBuilding,
+    ) -> ServiceIndex {
+        ServiceIndex {
+            full_name: concat_paths(
+                building.current_file_descriptor.get_package(),
+                service.get_name(),
+            ),
+            methods: service
+                .method
+                .iter()
+                .map(|m| MethodIndex {
+                    input_type: building.resolve_message(m.get_input_type()),
+                    output_type: building.resolve_message(m.get_output_type()),
+                })
+                .collect(),
+        }
+    }
+}
@@ -0,0 +1,148 @@
+use std::fmt;
+
+use crate::descriptor::MethodDescriptorProto;
+use crate::descriptor::ServiceDescriptorProto;
+use crate::reflect::service::index::MethodIndex;
+use crate::reflect::service::index::ServiceIndex;
+use crate::reflect::FileDescriptor;
+use crate::reflect::MessageDescriptor;
+
+pub(crate) mod index;
+
+/// Dynamic representation of service type.
+///
+/// Methods input and output types are resolved to message descriptors.
+#[derive(Clone, Eq, PartialEq)]
+pub struct ServiceDescriptor {
+    file_descriptor: FileDescriptor,
+    index: usize,
+}
+
+impl fmt::Display for ServiceDescriptor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.full_name())
+    }
+}
+
+impl fmt::Debug for ServiceDescriptor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ServiceDescriptor")
+            .field("full_name", &self.full_name())
+            .finish()
+    }
+}
+
+impl ServiceDescriptor {
+    pub(crate) fn new(file_descriptor: FileDescriptor, index: usize) -> ServiceDescriptor {
+        ServiceDescriptor {
+            file_descriptor,
+            index,
+        }
+    }
+
+    fn get_index(&self) -> &ServiceIndex {
+        self.file_descriptor.service_index(self.index)
+    }
+
+    /// Get underlying `ServiceDescriptorProto` object.
+    pub fn get_proto(&self) -> &ServiceDescriptorProto {
+        &self.file_descriptor.proto().service[self.index]
+    }
+
+    /// Service name as specified in `.proto` file.
+    pub fn get_name(&self) -> &str {
+        self.get_proto().get_name()
+    }
+
+    /// Fully qualified service name (without leading dot).
+    pub fn full_name(&self) -> &str {
+        &self.get_index().full_name
+    }
+
+    /// File where the service is declared.
+    pub fn file_descriptor(&self) -> &FileDescriptor {
+        &self.file_descriptor
+    }
+
+    /// Service methods.
+    pub fn methods(&self) -> Vec<MethodDescriptor> {
+        (0..self.get_proto().method.len())
+            .map(|index| MethodDescriptor {
+                service_descriptor: self.clone(),
+                index,
+            })
+            .collect()
+    }
+
+    /// Find method by name.
+    pub fn method_by_name(&self, name: &str) -> Option<MethodDescriptor> {
+        self.methods().into_iter().find(|m| m.get_name() == name)
+    }
+}
+
+/// Service method descriptor.
+#[derive(Clone, Eq, PartialEq)]
+pub struct MethodDescriptor {
+    service_descriptor: ServiceDescriptor,
+    index: usize,
+}
+
+impl fmt::Display for MethodDescriptor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}", self.service_descriptor, self.get_name())
+    }
+}
+
+impl fmt::Debug for MethodDescriptor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MethodDescriptor")
+            .field("service", &self.service_descriptor.full_name())
+            .field("name", &self.get_name())
+            .finish()
+    }
+}
+
+impl MethodDescriptor {
+    fn get_index(&self) -> &MethodIndex {
+        &self.service_descriptor.get_index().methods[self.index]
+    }
+
+    /// Get underlying `MethodDescriptorProto` object.
+    pub fn get_proto(&self) -> &MethodDescriptorProto {
+        &self.service_descriptor.get_proto().method[self.index]
+    }
+
+    /// Method name as specified in `.proto` file.
+    pub fn get_name(&self) -> &str {
+        self.get_proto().get_name()
+    }
+
+    /// Service containing this method.
+    pub fn service_descriptor(&self) -> &ServiceDescriptor {
+        &self.service_descriptor
+    }
+
+    /// Method input type.
+    pub fn input_type(&self) -> MessageDescriptor {
+        self.get_index()
+            .input_type
+            .resolve_message(&self.service_descriptor.file_descriptor)
+    }
+
+    /// Method output type.
+    pub fn output_type(&self) -> MessageDescriptor {
+        self.get_index()
+            .output_type
+            .resolve_message(&self.service_descriptor.file_descriptor)
+    }
+
+    /// Client sends a stream of input messages.
+    pub fn is_client_streaming(&self) -> bool {
+        self.get_proto().get_client_streaming()
+    }
+
+    /// Server sends a stream of output messages.
+    pub fn is_server_streaming(&self) -> bool {
+        self.get_proto().get_server_streaming()
+    }
+}
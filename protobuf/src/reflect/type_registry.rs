@@ -0,0 +1,171 @@
+use std::collections::HashMap;
+
+use crate::message::Message;
+use crate::reflect::ExtensionDescriptor;
+use crate::reflect::FileDescriptor;
+use crate::reflect::MessageDescriptor;
+use crate::well_known_types;
+use crate::well_known_types::Any;
+
+/// A set of message types and extensions which can be found by fully-qualified name.
+///
+/// Registry is used to resolve type URLs of `google.protobuf.Any`
+/// when printing or parsing JSON or text format,
+/// and to resolve extension fields in text format.
+///
+/// Well-known types (like `google.protobuf.Duration`) are always resolved
+/// to generated types, even if they are not added to the registry.
+///
+/// # Examples
+///
+/// ```
+/// use protobuf::reflect::TypeRegistry;
+/// use protobuf::well_known_types::Duration;
+///
+/// let mut registry = TypeRegistry::new();
+/// registry.add_message_type::<Duration>();
+/// assert!(registry
+///     .find_message_by_type_url("type.googleapis.com/google.protobuf.Duration")
+///     .is_some());
+/// ```
+#[derive(Default, Clone, Debug)]
+pub struct TypeRegistry {
+    messages: HashMap<String, MessageDescriptor>,
+    extensions: HashMap<String, ExtensionDescriptor>,
+    extensions_by_extendee: HashMap<(String, u32), ExtensionDescriptor>,
+}
+
+impl TypeRegistry {
+    /// Empty registry.
+    pub fn new() -> TypeRegistry {
+        Default::default()
+    }
+
+    /// Register a message type.
+    pub fn add_message(&mut self, descriptor: MessageDescriptor) {
+        self.messages
+            .insert(descriptor.full_name().to_owned(), descriptor);
+    }
+
+    /// Register a generated message type.
+    pub fn add_message_type<M: Message>(&mut self) {
+        self.add_message(M::descriptor_static());
+    }
+
+    /// Register an extension.
+    pub fn add_extension(&mut self, extension: ExtensionDescriptor) {
+        self.extensions_by_extendee.insert(
+            (
+                extension.extendee().full_name().to_owned(),
+                extension.get_number(),
+            ),
+            extension.clone(),
+        );
+        self.extensions
+            .insert(extension.full_name().to_owned(), extension);
+    }
+
+    /// Register all messages (including nested messages) and extensions
+    /// from given file and files it depends on.
+    pub fn add_file(&mut self, file: &FileDescriptor) {
+        for file in file.all_files() {
+            for message in file.all_messages() {
+                if !message.is_map_entry() {
+                    self.add_message(message);
+                }
+            }
+            for extension in file.all_extensions() {
+                self.add_extension(extension);
+            }
+        }
+    }
+
+    /// Find message by fully-qualified name (without leading dot).
+    pub fn find_message_by_full_name(&self, full_name: &str) -> Option<MessageDescriptor> {
+        if let Some(descriptor) = find_well_known_message(full_name) {
+            return Some(descriptor);
+        }
+        self.messages.get(full_name).cloned()
+    }
+
+    /// Find message by `google.protobuf.Any` type URL.
+    ///
+    /// Only the part of URL after last slash is used.
+    pub fn find_message_by_type_url(&self, type_url: &str) -> Option<MessageDescriptor> {
+        let full_name = Any::get_type_name_from_type_url(type_url)?;
+        self.find_message_by_full_name(full_name)
+    }
+
+    /// Find extension by fully-qualified name (without leading dot).
+    pub fn find_extension_by_full_name(&self, full_name: &str) -> Option<ExtensionDescriptor> {
+        self.extensions.get(full_name).cloned()
+    }
+
+    /// Find extension of given message by field number.
+    pub fn find_extension_by_number(
+        &self,
+        extendee: &MessageDescriptor,
+        number: u32,
+    ) -> Option<ExtensionDescriptor> {
+        self.extensions_by_extendee
+            .get(&(extendee.full_name().to_owned(), number))
+            .cloned()
+    }
+}
+
+fn find_well_known_message(full_name: &str) -> Option<MessageDescriptor> {
+    let name_to_package = full_name.strip_prefix("google.protobuf.")?;
+    let files = [
+        well_known_types::file_descriptors::any,
+        well_known_types::file_descriptors::api,
+        well_known_types::file_descriptors::duration,
+        well_known_types::file_descriptors::empty,
+        well_known_types::file_descriptors::field_mask,
+        well_known_types::file_descriptors::source_context,
+        well_known_types::file_descriptors::struct_pb,
+        well_known_types::file_descriptors::timestamp,
+        well_known_types::file_descriptors::type_pb,
+        well_known_types::file_descriptors::wrappers,
+    ];
+    files
+        .iter()
+        .find_map(|file| file().message_by_package_relative_name(name_to_package))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::descriptor::FileDescriptorProto;
+    use crate::well_known_types::Duration;
+
+    #[test]
+    fn well_known_types_are_always_found() {
+        let registry = TypeRegistry::new();
+        assert_eq!(
+            Duration::descriptor_static(),
+            registry
+                .find_message_by_type_url("type.googleapis.com/google.protobuf.Duration")
+                .unwrap()
+        );
+        assert!(registry
+            .find_message_by_full_name("google.protobuf.Nonexistent")
+            .is_none());
+    }
+
+    #[test]
+    fn add_file() {
+        let mut registry = TypeRegistry::new();
+        registry.add_file(&crate::descriptor::file_descriptor());
+        assert_eq!(
+            FileDescriptorProto::descriptor_static(),
+            registry
+                .find_message_by_full_name("google.protobuf.FileDescriptorProto")
+                .unwrap()
+        );
+        // nested message
+        assert!(registry
+            .find_message_by_type_url("x/google.protobuf.DescriptorProto.ExtensionRange")
+            .is_some());
+        assert!(registry.find_message_by_type_url("x/Foo").is_none());
+    }
+}
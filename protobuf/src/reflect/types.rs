@@ -12,6 +12,7 @@ use crate::chars::Chars;
 
 use crate::coded_input_stream::CodedInputStream;
 use crate::coded_output_stream::CodedOutputStream;
+use crate::descriptor::field_descriptor_proto::Type;
 use crate::enums::ProtobufEnum;
 use crate::enums::ProtobufEnumOrUnknown;
 use crate::error::ProtobufResult;
@@ -42,6 +43,9 @@ pub trait ProtobufType: Send + Sync + Clone + 'static {
     /// Wire type for encoding objects of this type
     const WIRE_TYPE: WireType;
 
+    /// Type of `.proto` fields of this type
+    const FIELD_TYPE: Type;
+
     /// Read a value from `CodedInputStream`
     fn read(is: &mut CodedInputStream) -> ProtobufResult<Self::ProtobufValue>;
 
@@ -164,6 +168,7 @@ impl ProtobufType for ProtobufTypeFloat {
     type ProtobufValue = f32;
 
     const WIRE_TYPE: WireType = WireType::WireTypeFixed32;
+    const FIELD_TYPE: Type = Type::TYPE_FLOAT;
 
     fn read(is: &mut CodedInputStream) -> ProtobufResult<f32> {
         is.read_float()
@@ -199,6 +204,7 @@ impl ProtobufType for ProtobufTypeDouble {
     type ProtobufValue = f64;
 
     const WIRE_TYPE: WireType = WireType::WireTypeFixed64;
+    const FIELD_TYPE: Type = Type::TYPE_DOUBLE;
 
     fn read(is: &mut CodedInputStream) -> ProtobufResult<f64> {
         is.read_double()
@@ -234,6 +240,7 @@ impl ProtobufType for ProtobufTypeInt32 {
     type ProtobufValue = i32;
 
     const WIRE_TYPE: WireType = WireType::WireTypeVarint;
+    const FIELD_TYPE: Type = Type::TYPE_INT32;
 
     fn read(is: &mut CodedInputStream) -> ProtobufResult<i32> {
         is.read_int32()
@@ -264,6 +271,7 @@ impl ProtobufType for ProtobufTypeInt64 {
     type ProtobufValue = i64;
 
     const WIRE_TYPE: WireType = WireType::WireTypeVarint;
+    const FIELD_TYPE: Type = Type::TYPE_INT64;
 
     fn read(is: &mut CodedInputStream) -> ProtobufResult<i64> {
         is.read_int64()
@@ -290,6 +298,7 @@ impl ProtobufType for ProtobufTypeUint32 {
     type ProtobufValue = u32;
 
     const WIRE_TYPE: WireType = WireType::WireTypeVarint;
+    const FIELD_TYPE: Type = Type::TYPE_UINT32;
 
     fn read(is: &mut CodedInputStream) -> ProtobufResult<u32> {
         is.read_uint32()
@@ -316,6 +325,7 @@ impl ProtobufType for ProtobufTypeUint64 {
     type ProtobufValue = u64;
 
     const WIRE_TYPE: WireType = WireType::WireTypeVarint;
+    const FIELD_TYPE: Type = Type::TYPE_UINT64;
 
     fn read(is: &mut CodedInputStream) -> ProtobufResult<u64> {
         is.read_uint64()
@@ -342,6 +352,7 @@ impl ProtobufType for ProtobufTypeSint32 {
     type ProtobufValue = i32;
 
     const WIRE_TYPE: WireType = WireType::WireTypeVarint;
+    const FIELD_TYPE: Type = Type::TYPE_SINT32;
 
     fn read(is: &mut CodedInputStream) -> ProtobufResult<i32> {
         is.read_sint32()
@@ -368,6 +379,7 @@ impl ProtobufType for ProtobufTypeSint64 {
     type ProtobufValue = i64;
 
     const WIRE_TYPE: WireType = WireType::WireTypeVarint;
+    const FIELD_TYPE: Type = Type::TYPE_SINT64;
 
     fn read(is: &mut CodedInputStream) -> ProtobufResult<i64> {
         is.read_sint64()
@@ -394,6 +406,7 @@ impl ProtobufType for ProtobufTypeFixed32 {
     type ProtobufValue = u32;
 
     const WIRE_TYPE: WireType = WireType::WireTypeFixed32;
+    const FIELD_TYPE: Type = Type::TYPE_FIXED32;
 
     fn read(is: &mut CodedInputStream) -> ProtobufResult<u32> {
         is.read_fixed32()
@@ -424,6 +437,7 @@ impl ProtobufType for ProtobufTypeFixed64 {
     type ProtobufValue = u64;
 
     const WIRE_TYPE: WireType = WireType::WireTypeFixed64;
+    const FIELD_TYPE: Type = Type::TYPE_FIXED64;
 
     fn read(is: &mut CodedInputStream) -> ProtobufResult<u64> {
         is.read_fixed64()
@@ -454,6 +468,7 @@ impl ProtobufType for ProtobufTypeSfixed32 {
     type ProtobufValue = i32;
 
     const WIRE_TYPE: WireType = WireType::WireTypeFixed32;
+    const FIELD_TYPE: Type = Type::TYPE_SFIXED32;
 
     fn read(is: &mut CodedInputStream) -> ProtobufResult<i32> {
         is.read_sfixed32()
@@ -484,6 +499,7 @@ impl ProtobufType for ProtobufTypeSfixed64 {
     type ProtobufValue = i64;
 
     const WIRE_TYPE: WireType = WireType::WireTypeFixed64;
+    const FIELD_TYPE: Type = Type::TYPE_SFIXED64;
 
     fn read(is: &mut CodedInputStream) -> ProtobufResult<i64> {
         is.read_sfixed64()
@@ -514,6 +530,7 @@ impl ProtobufType for ProtobufTypeBool {
     type ProtobufValue = bool;
 
     const WIRE_TYPE: WireType = WireType::WireTypeVarint;
+    const FIELD_TYPE: Type = Type::TYPE_BOOL;
 
     fn read(is: &mut CodedInputStream) -> ProtobufResult<bool> {
         is.read_bool()
@@ -540,6 +557,7 @@ impl ProtobufType for ProtobufTypeString {
     type ProtobufValue = String;
 
     const WIRE_TYPE: WireType = WireType::WireTypeLengthDelimited;
+    const FIELD_TYPE: Type = Type::TYPE_STRING;
 
     fn read(is: &mut CodedInputStream) -> ProtobufResult<String> {
         is.read_string()
@@ -568,6 +586,7 @@ impl ProtobufType for ProtobufTypeBytes {
     type ProtobufValue = Vec<u8>;
 
     const WIRE_TYPE: WireType = WireType::WireTypeLengthDelimited;
+    const FIELD_TYPE: Type = Type::TYPE_BYTES;
 
     fn read(is: &mut CodedInputStream) -> ProtobufResult<Vec<u8>> {
         is.read_bytes()
@@ -595,6 +614,7 @@ impl ProtobufType for ProtobufTypeCarllercheBytes {
     type ProtobufValue = bytes::Bytes;
 
     const WIRE_TYPE: WireType = ProtobufTypeBytes::WIRE_TYPE;
+    const FIELD_TYPE: Type = Type::TYPE_BYTES;
 
     fn read(is: &mut CodedInputStream) -> ProtobufResult<Bytes> {
         is.read_carllerche_bytes()
@@ -622,6 +642,7 @@ impl ProtobufType for ProtobufTypeCarllercheChars {
     type ProtobufValue = Chars;
 
     const WIRE_TYPE: WireType = ProtobufTypeBytes::WIRE_TYPE;
+    const FIELD_TYPE: Type = Type::TYPE_STRING;
 
     fn read(is: &mut CodedInputStream) -> ProtobufResult<Chars> {
         is.read_carllerche_chars()
@@ -648,6 +669,7 @@ impl<E: ProtobufEnum + ProtobufValue + fmt::Debug> ProtobufType for ProtobufType
     type ProtobufValue = E;
 
     const WIRE_TYPE: WireType = WireType::WireTypeVarint;
+    const FIELD_TYPE: Type = Type::TYPE_ENUM;
 
     fn read(is: &mut CodedInputStream) -> ProtobufResult<E> {
         is.read_enum()
@@ -676,6 +698,7 @@ impl<E: ProtobufEnum + ProtobufValue + fmt::Debug> ProtobufType for ProtobufType
     type ProtobufValue = ProtobufEnumOrUnknown<E>;
 
     const WIRE_TYPE: WireType = WireType::WireTypeVarint;
+    const FIELD_TYPE: Type = Type::TYPE_ENUM;
 
     fn read(is: &mut CodedInputStream) -> ProtobufResult<ProtobufEnumOrUnknown<E>> {
         is.read_enum_or_unknown()
@@ -703,9 +726,16 @@ impl<M: Message + Clone + ProtobufValue + Default> ProtobufType for ProtobufType
     type ProtobufValue = M;
 
     const WIRE_TYPE: WireType = WireType::WireTypeLengthDelimited;
+    const FIELD_TYPE: Type = Type::TYPE_MESSAGE;
 
+    /// Read nested message.
+    ///
+    /// Required fields are not checked here, they are checked once
+    /// in the outermost message, which reports all missing fields.
     fn read(is: &mut CodedInputStream) -> ProtobufResult<M> {
-        is.read_message()
+        let mut m = M::new();
+        is.merge_message(&mut m)?;
+        Ok(m)
     }
 
     fn get_from_unknown(unknown_values: &UnknownValues) -> Option<M> {
@@ -58,7 +58,7 @@ impl<'a> ReflectValueRef<'a> {
     }
 
     /// Value is "non-zero"?
-    fn _is_non_zero(&self) -> bool {
+    pub(crate) fn is_non_zero(&self) -> bool {
         match self {
             ReflectValueRef::U32(v) => *v != 0,
             ReflectValueRef::U64(v) => *v != 0,
@@ -183,31 +183,33 @@ pub enum ReflectValueMut<'a> {
 
 impl<'a> ReflectEq for ReflectValueRef<'a> {
     fn reflect_eq(&self, that: &Self, mode: &ReflectEqMode) -> bool {
+        self.reflect_eq_at(that, mode, &mut String::new())
+    }
+}
+
+impl<'a> ReflectValueRef<'a> {
+    /// `reflect_eq` where `path` is the path of the field containing the values.
+    pub(crate) fn reflect_eq_at(
+        &self,
+        that: &Self,
+        mode: &ReflectEqMode,
+        path: &mut String,
+    ) -> bool {
         use crate::reflect::value::value_ref::ReflectValueRef::*;
         match (self, that) {
             (U32(a), U32(b)) => a == b,
             (U64(a), U64(b)) => a == b,
             (I32(a), I32(b)) => a == b,
             (I64(a), I64(b)) => a == b,
-            (F32(a), F32(b)) => {
-                if a.is_nan() || b.is_nan() {
-                    a.is_nan() == b.is_nan() && mode.nan_equal
-                } else {
-                    a == b
-                }
-            }
-            (F64(a), F64(b)) => {
-                if a.is_nan() || b.is_nan() {
-                    a.is_nan() == b.is_nan() && mode.nan_equal
-                } else {
-                    a == b
-                }
-            }
+            (F32(a), F32(b)) => mode.float_eq(*a as f64, *b as f64),
+            (F64(a), F64(b)) => mode.float_eq(*a, *b),
             (Bool(a), Bool(b)) => a == b,
             (String(a), String(b)) => a == b,
             (Bytes(a), Bytes(b)) => a == b,
             (Enum(ad, a), Enum(bd, b)) => ad == bd && a == b,
-            (Message(a), Message(b)) => a.reflect_eq(b, mode),
+            (Message(a), Message(b)) => {
+                MessageDescriptor::reflect_eq_maybe_unrelated_at(&**a, &**b, mode, path)
+            }
             _ => false,
         }
     }
@@ -0,0 +1,498 @@
+//! Serialization of reflective field values.
+//!
+//! This is the codec shared by dynamic messages and extensions.
+//!
+//! Runtime types do not carry enough information to encode a value
+//! (e.g. `int32` and `sint32` are both `i32`), so all functions here
+//! take field type from `.proto` file.
+
+use std::cmp;
+
+use crate::coded_output_stream::WithCodedOutputStream;
+use crate::descriptor::field_descriptor_proto::Label;
+use crate::descriptor::field_descriptor_proto::Type;
+use crate::descriptor::FieldDescriptorProto;
+use crate::descriptor::FileDescriptorProto;
+use crate::error::ProtobufResult;
+use crate::message_dyn::MessageDyn;
+use crate::reflect::dynamic::map::cmp_keys;
+use crate::reflect::types::ProtobufType;
+use crate::reflect::types::ProtobufTypeInt32;
+use crate::reflect::types::ProtobufTypeInt64;
+use crate::reflect::types::ProtobufTypeSint32;
+use crate::reflect::types::ProtobufTypeSint64;
+use crate::reflect::types::ProtobufTypeUint32;
+use crate::reflect::types::ProtobufTypeUint64;
+use crate::reflect::FieldDescriptor;
+use crate::reflect::ReflectFieldRef;
+use crate::reflect::ReflectValueBox;
+use crate::reflect::ReflectValueRef;
+use crate::reflect::RuntimeTypeBox;
+use crate::rt;
+use crate::unknown::UnknownValue;
+use crate::unknown::UnknownValueRef;
+use crate::unknown::UnknownValues;
+use crate::wire_format::WireType;
+use crate::CodedInputStream;
+use crate::CodedOutputStream;
+
+/// Wire type used to encode values of given type.
+pub(crate) fn wire_type(t: Type) -> WireType {
+    match t {
+        Type::TYPE_FIXED32 | Type::TYPE_SFIXED32 | Type::TYPE_FLOAT => WireType::WireTypeFixed32,
+        Type::TYPE_FIXED64 | Type::TYPE_SFIXED64 | Type::TYPE_DOUBLE => WireType::WireTypeFixed64,
+        Type::TYPE_STRING | Type::TYPE_BYTES | Type::TYPE_MESSAGE => {
+            WireType::WireTypeLengthDelimited
+        }
+        Type::TYPE_GROUP => WireType::WireTypeStartGroup,
+        Type::TYPE_INT32
+        | Type::TYPE_INT64
+        | Type::TYPE_UINT32
+        | Type::TYPE_UINT64
+        | Type::TYPE_SINT32
+        | Type::TYPE_SINT64
+        | Type::TYPE_BOOL
+        | Type::TYPE_ENUM => WireType::WireTypeVarint,
+    }
+}
+
+/// Can values of this type be stored in packed repeated field.
+pub(crate) fn is_packable(t: Type) -> bool {
+    !matches!(
+        wire_type(t),
+        WireType::WireTypeLengthDelimited | WireType::WireTypeStartGroup
+    )
+}
+
+/// Is field written using packed encoding.
+///
+/// Repeated fields of packable types are packed if `[packed = true]` is set,
+/// and in proto3 also when `packed` option is not specified.
+pub(crate) fn is_packed(field: &FieldDescriptorProto, file: &FileDescriptorProto) -> bool {
+    let options = field.options.get_or_default();
+    let packed = if options.has_packed() {
+        options.get_packed()
+    } else {
+        file.get_syntax() == "proto3"
+    };
+    packed && field.get_label() == Label::LABEL_REPEATED && is_packable(field.get_field_type())
+}
+
+fn wrong_type(t: Type, value: &ReflectValueRef) -> ! {
+    panic!("value {:?} does not match field type {:?}", value, t)
+}
+
+/// Size of encoded value without tag, but with length prefix for length-delimited values.
+///
+/// Nested message sizes are computed and cached.
+pub(crate) fn compute_size_no_tag(t: Type, value: &ReflectValueRef) -> u32 {
+    match (t, value) {
+        (Type::TYPE_INT32, ReflectValueRef::I32(v)) => ProtobufTypeInt32::compute_size(v),
+        (Type::TYPE_INT64, ReflectValueRef::I64(v)) => ProtobufTypeInt64::compute_size(v),
+        (Type::TYPE_UINT32, ReflectValueRef::U32(v)) => ProtobufTypeUint32::compute_size(v),
+        (Type::TYPE_UINT64, ReflectValueRef::U64(v)) => ProtobufTypeUint64::compute_size(v),
+        (Type::TYPE_SINT32, ReflectValueRef::I32(v)) => ProtobufTypeSint32::compute_size(v),
+        (Type::TYPE_SINT64, ReflectValueRef::I64(v)) => ProtobufTypeSint64::compute_size(v),
+        (Type::TYPE_ENUM, ReflectValueRef::Enum(_, v)) => ProtobufTypeInt32::compute_size(v),
+        (Type::TYPE_BOOL, ReflectValueRef::Bool(..)) => 1,
+        (Type::TYPE_FIXED32, ReflectValueRef::U32(..))
+        | (Type::TYPE_SFIXED32, ReflectValueRef::I32(..))
+        | (Type::TYPE_FLOAT, ReflectValueRef::F32(..)) => 4,
+        (Type::TYPE_FIXED64, ReflectValueRef::U64(..))
+        | (Type::TYPE_SFIXED64, ReflectValueRef::I64(..))
+        | (Type::TYPE_DOUBLE, ReflectValueRef::F64(..)) => 8,
+        (Type::TYPE_STRING, ReflectValueRef::String(v)) => {
+            rt::compute_raw_varint64_size(v.len() as u64) + v.len() as u32
+        }
+        (Type::TYPE_BYTES, ReflectValueRef::Bytes(v)) => {
+            rt::compute_raw_varint64_size(v.len() as u64) + v.len() as u32
+        }
+        (Type::TYPE_MESSAGE, ReflectValueRef::Message(m)) => {
+            let size = m.compute_size_dyn();
+            rt::compute_raw_varint32_size(size) + size
+        }
+        (t, value) => wrong_type(t, value),
+    }
+}
+
+/// Write a value without tag.
+///
+/// Sizes of nested messages must be computed before calling this function.
+pub(crate) fn write_no_tag(
+    t: Type,
+    value: &ReflectValueRef,
+    os: &mut CodedOutputStream,
+) -> ProtobufResult<()> {
+    match (t, value) {
+        (Type::TYPE_INT32, ReflectValueRef::I32(v)) => os.write_int32_no_tag(*v),
+        (Type::TYPE_INT64, ReflectValueRef::I64(v)) => os.write_int64_no_tag(*v),
+        (Type::TYPE_UINT32, ReflectValueRef::U32(v)) => os.write_uint32_no_tag(*v),
+        (Type::TYPE_UINT64, ReflectValueRef::U64(v)) => os.write_uint64_no_tag(*v),
+        (Type::TYPE_SINT32, ReflectValueRef::I32(v)) => os.write_sint32_no_tag(*v),
+        (Type::TYPE_SINT64, ReflectValueRef::I64(v)) => os.write_sint64_no_tag(*v),
+        (Type::TYPE_ENUM, ReflectValueRef::Enum(_, v)) => os.write_enum_no_tag(*v),
+        (Type::TYPE_BOOL, ReflectValueRef::Bool(v)) => os.write_bool_no_tag(*v),
+        (Type::TYPE_FIXED32, ReflectValueRef::U32(v)) => os.write_fixed32_no_tag(*v),
+        (Type::TYPE_SFIXED32, ReflectValueRef::I32(v)) => os.write_sfixed32_no_tag(*v),
+        (Type::TYPE_FLOAT, ReflectValueRef::F32(v)) => os.write_float_no_tag(*v),
+        (Type::TYPE_FIXED64, ReflectValueRef::U64(v)) => os.write_fixed64_no_tag(*v),
+        (Type::TYPE_SFIXED64, ReflectValueRef::I64(v)) => os.write_sfixed64_no_tag(*v),
+        (Type::TYPE_DOUBLE, ReflectValueRef::F64(v)) => os.write_double_no_tag(*v),
+        (Type::TYPE_STRING, ReflectValueRef::String(v)) => os.write_string_no_tag(v),
+        (Type::TYPE_BYTES, ReflectValueRef::Bytes(v)) => os.write_bytes_no_tag(v),
+        (Type::TYPE_MESSAGE, ReflectValueRef::Message(m)) => {
+            os.write_raw_varint32(m.get_cached_size_dyn())?;
+            m.write_to_with_cached_sizes_dyn(os)
+        }
+        (t, value) => wrong_type(t, value),
+    }
+}
+
+/// Size of a value with a tag.
+pub(crate) fn compute_size(field_number: u32, t: Type, value: &ReflectValueRef) -> u32 {
+    rt::tag_size(field_number) + compute_size_no_tag(t, value)
+}
+
+/// Write a value with a tag.
+pub(crate) fn write(
+    field_number: u32,
+    t: Type,
+    value: &ReflectValueRef,
+    os: &mut CodedOutputStream,
+) -> ProtobufResult<()> {
+    os.write_tag(field_number, wire_type(t))?;
+    write_no_tag(t, value, os)
+}
+
+/// Merge length-delimited message from the stream.
+pub(crate) fn merge_message(
+    message: &mut dyn MessageDyn,
+    is: &mut CodedInputStream,
+) -> ProtobufResult<()> {
+    is.incr_recursion()?;
+    let res = is.nested(|is| {
+        let len = is.read_raw_varint64()?;
+        let old_limit = is.push_limit(len)?;
+        message.merge_from_dyn(is)?;
+        is.pop_limit(old_limit);
+        Ok(())
+    });
+    is.decr_recursion();
+    res
+}
+
+/// Read a value encoded with wire type matching the field type.
+pub(crate) fn read(
+    t: Type,
+    rt: &RuntimeTypeBox,
+    is: &mut CodedInputStream,
+) -> ProtobufResult<ReflectValueBox> {
+    Ok(match (t, rt) {
+        (Type::TYPE_INT32, _) => ReflectValueBox::I32(is.read_int32()?),
+        (Type::TYPE_INT64, _) => ReflectValueBox::I64(is.read_int64()?),
+        (Type::TYPE_UINT32, _) => ReflectValueBox::U32(is.read_uint32()?),
+        (Type::TYPE_UINT64, _) => ReflectValueBox::U64(is.read_uint64()?),
+        (Type::TYPE_SINT32, _) => ReflectValueBox::I32(is.read_sint32()?),
+        (Type::TYPE_SINT64, _) => ReflectValueBox::I64(is.read_sint64()?),
+        (Type::TYPE_BOOL, _) => ReflectValueBox::Bool(is.read_bool()?),
+        (Type::TYPE_FIXED32, _) => ReflectValueBox::U32(is.read_fixed32()?),
+        (Type::TYPE_SFIXED32, _) => ReflectValueBox::I32(is.read_sfixed32()?),
+        (Type::TYPE_FLOAT, _) => ReflectValueBox::F32(is.read_float()?),
+        (Type::TYPE_FIXED64, _) => ReflectValueBox::U64(is.read_fixed64()?),
+        (Type::TYPE_SFIXED64, _) => ReflectValueBox::I64(is.read_sfixed64()?),
+        (Type::TYPE_DOUBLE, _) => ReflectValueBox::F64(is.read_double()?),
+        (Type::TYPE_STRING, _) => ReflectValueBox::String(is.read_string()?),
+        (Type::TYPE_BYTES, _) => ReflectValueBox::Bytes(is.read_bytes()?),
+        (Type::TYPE_ENUM, RuntimeTypeBox::Enum(e)) => {
+            ReflectValueBox::Enum(e.clone(), is.read_int32()?)
+        }
+        (Type::TYPE_MESSAGE, RuntimeTypeBox::Message(m)) => {
+            let mut message = m.new_instance();
+            merge_message(&mut *message, is)?;
+            ReflectValueBox::Message(message)
+        }
+        (Type::TYPE_GROUP, _) => {
+            return Err(rt::unexpected_wire_type(WireType::WireTypeStartGroup))
+        }
+        (t, rt) => panic!("field type {:?} does not match runtime type {}", t, rt),
+    })
+}
+
+/// Value of closed enum which is not declared in the enum.
+///
+/// Such values are stored in unknown fields like generated code does.
+pub(crate) fn unknown_closed_enum_value(value: &ReflectValueBox) -> Option<i32> {
+    match value {
+        ReflectValueBox::Enum(e, v) if e.is_closed() && e.get_value_by_number(*v).is_none() => {
+            Some(*v)
+        }
+        _ => None,
+    }
+}
+
+/// Read a value, merging it into existing value if the value is a message.
+pub(crate) fn merge(
+    t: Type,
+    target: &mut ReflectValueBox,
+    is: &mut CodedInputStream,
+) -> ProtobufResult<()> {
+    match target {
+        ReflectValueBox::Message(m) => merge_message(&mut **m, is),
+        target => {
+            *target = read(t, &target.get_type(), is)?;
+            Ok(())
+        }
+    }
+}
+
+/// Read packed values until the end of the stream (or current limit).
+fn read_packed_data(
+    t: Type,
+    rt: &RuntimeTypeBox,
+    is: &mut CodedInputStream,
+    target: &mut Vec<ReflectValueBox>,
+) -> ProtobufResult<()> {
+    while !is.eof()? {
+        is.check_repeated_push(target)?;
+        target.push(read(t, rt, is)?);
+    }
+    Ok(())
+}
+
+/// Read packed values of repeated field.
+pub(crate) fn read_packed(
+    t: Type,
+    rt: &RuntimeTypeBox,
+    is: &mut CodedInputStream,
+    target: &mut Vec<ReflectValueBox>,
+) -> ProtobufResult<()> {
+    let len = is.read_raw_varint64()?;
+    let old_limit = is.push_limit(len)?;
+    read_packed_data(t, rt, is, target)?;
+    is.pop_limit(old_limit);
+    Ok(())
+}
+
+fn message_to_bytes(m: &dyn MessageDyn) -> ProtobufResult<Vec<u8>> {
+    let mut bytes = Vec::new();
+    m.compute_size_dyn();
+    (&mut bytes).with_coded_output_stream(|os| m.write_to_with_cached_sizes_dyn(os))?;
+    Ok(bytes)
+}
+
+/// Encode a value as unknown value, e.g. to store it as extension.
+///
+/// Group values are encoded as group content, without start and end group tags.
+/// Messages are encoded without checking required fields,
+/// like message fields are not checked when they are set.
+///
+/// # Panics
+///
+/// If value does not match the type.
+pub(crate) fn value_to_unknown(t: Type, value: &ReflectValueRef) -> ProtobufResult<UnknownValue> {
+    match (t, value) {
+        (Type::TYPE_GROUP, ReflectValueRef::Message(m)) => {
+            Ok(UnknownValue::Group(message_to_bytes(&**m)?))
+        }
+        (t, value) => {
+            let mut bytes = Vec::new();
+            compute_size_no_tag(t, value);
+            (&mut bytes).with_coded_output_stream(|os| write_no_tag(t, value, os))?;
+            let mut is = CodedInputStream::from_bytes(&bytes);
+            is.read_unknown(wire_type(t))
+        }
+    }
+}
+
+/// Encode values of packed repeated field as single unknown value.
+///
+/// # Panics
+///
+/// If type is not packable or values do not match the type.
+pub(crate) fn values_to_unknown_packed(
+    t: Type,
+    values: &[ReflectValueRef],
+) -> ProtobufResult<UnknownValue> {
+    assert!(is_packable(t), "type is not packable: {:?}", t);
+    let mut bytes = Vec::new();
+    (&mut bytes).with_coded_output_stream(|os| {
+        for value in values {
+            write_no_tag(t, value, os)?;
+        }
+        Ok(())
+    })?;
+    Ok(UnknownValue::LengthDelimited(bytes))
+}
+
+/// Decode unknown value of group type.
+fn group_to_value(rt: &RuntimeTypeBox, bytes: &[u8]) -> ProtobufResult<ReflectValueBox> {
+    match rt {
+        RuntimeTypeBox::Message(m) => {
+            let mut message = m.new_instance();
+            message.merge_from_bytes_dyn(bytes)?;
+            Ok(ReflectValueBox::Message(message))
+        }
+        _ => unreachable!(),
+    }
+}
+
+/// Combine decoded occurrences of singular field:
+/// messages are merged, for other types the last value wins.
+pub(crate) fn merge_singular(values: Vec<ReflectValueBox>) -> Option<ReflectValueBox> {
+    let mut values = values.into_iter();
+    let mut last = values.next()?;
+    for value in values {
+        match (&mut last, value) {
+            (ReflectValueBox::Message(last), ReflectValueBox::Message(value)) => {
+                last.merge_from_message_dyn(&*value);
+            }
+            (last, value) => *last = value,
+        }
+    }
+    Some(last)
+}
+
+/// Decode all unknown values of field of given type.
+///
+/// Both packed and unpacked encodings are accepted for packable types.
+/// Values of wire types not matching the field type are reported as errors.
+pub(crate) fn values_from_unknown(
+    t: Type,
+    rt: &RuntimeTypeBox,
+    values: &UnknownValues,
+) -> ProtobufResult<Vec<ReflectValueBox>> {
+    let mut r = Vec::new();
+    for value in values {
+        match value {
+            UnknownValueRef::Group(bytes) if t == Type::TYPE_GROUP => {
+                r.push(group_to_value(rt, bytes)?);
+            }
+            UnknownValueRef::LengthDelimited(bytes) if is_packable(t) => {
+                read_packed_data(t, rt, &mut CodedInputStream::from_bytes(bytes), &mut r)?;
+            }
+            value if value.wire_type() == wire_type(t) => {
+                let mut bytes = Vec::new();
+                (&mut bytes).with_coded_output_stream(|os| os.write_unknown_no_tag(value))?;
+                r.push(read(t, rt, &mut CodedInputStream::from_bytes(&bytes))?);
+            }
+            value => return Err(rt::unexpected_wire_type(value.wire_type())),
+        }
+    }
+    Ok(r)
+}
+
+/// Write message with unknown values placed where they were parsed.
+///
+/// Known fields are written in field number order, map entries sorted by key.
+/// Each parsed unknown value is written right after the known field which
+/// preceded it in the input, other unknown values are written last.
+/// Nested messages are written the same way.
+pub(crate) fn write_preserving_order(
+    message: &dyn MessageDyn,
+    os: &mut CodedOutputStream,
+) -> ProtobufResult<()> {
+    let mut fields: Vec<FieldDescriptor> = message.descriptor_dyn().fields().collect();
+    fields.sort_by_key(|f| f.get_number());
+
+    // Slot `0` is before all known fields, slot `i + 1` is after `fields[i]`,
+    // and the last slot is after all known fields.
+    let last_slot = fields.len() + 1;
+    let mut unknown: Vec<_> = message
+        .get_unknown_fields_dyn()
+        .iter_values_with_positions()
+        .map(|(after, number, value)| {
+            let slot = match after {
+                0 => 0,
+                after => fields
+                    .binary_search_by_key(&after, |f| f.get_number())
+                    .map_or(last_slot, |i| i + 1),
+            };
+            (slot, number, value)
+        })
+        .collect();
+    // Stable sort keeps the relative order of unknown values
+    unknown.sort_by_key(|&(slot, ..)| slot);
+
+    let mut written = 0;
+    for (slot, number, value) in unknown {
+        let end = cmp::min(slot, fields.len());
+        for field in &fields[written..end] {
+            write_field_preserving_order(message, field, os)?;
+        }
+        written = end;
+        os.write_unknown(number, value)?;
+    }
+    for field in &fields[written..] {
+        write_field_preserving_order(message, field, os)?;
+    }
+    Ok(())
+}
+
+fn write_field_preserving_order(
+    message: &dyn MessageDyn,
+    field: &FieldDescriptor,
+    os: &mut CodedOutputStream,
+) -> ProtobufResult<()> {
+    let number = field.get_number();
+    let t = field.get_proto().get_field_type();
+    match field.get_reflect(message) {
+        ReflectFieldRef::Optional(Some(value)) => {
+            write_value_preserving_order(number, t, &value, os)
+        }
+        ReflectFieldRef::Optional(None) => Ok(()),
+        ReflectFieldRef::Repeated(repeated) if field.is_packed() => {
+            if repeated.is_empty() {
+                return Ok(());
+            }
+            let mut bytes = Vec::new();
+            (&mut bytes).with_coded_output_stream(|os| {
+                for value in &repeated {
+                    write_no_tag(t, &value, os)?;
+                }
+                Ok(())
+            })?;
+            os.write_bytes(number, &bytes)
+        }
+        ReflectFieldRef::Repeated(repeated) => {
+            for value in &repeated {
+                write_value_preserving_order(number, t, &value, os)?;
+            }
+            Ok(())
+        }
+        ReflectFieldRef::Map(map) => {
+            let (key_type, value_type) = field.map_entry_types();
+            let mut entries: Vec<_> = map.into_iter().collect();
+            entries.sort_by(|a, b| cmp_keys(&a.0, &b.0));
+            for (k, v) in entries {
+                let mut entry = Vec::new();
+                (&mut entry).with_coded_output_stream(|os| {
+                    write(1, key_type, &k, os)?;
+                    write_value_preserving_order(2, value_type, &v, os)
+                })?;
+                os.write_bytes(number, &entry)?;
+            }
+            Ok(())
+        }
+    }
+}
+
+fn write_value_preserving_order(
+    field_number: u32,
+    t: Type,
+    value: &ReflectValueRef,
+    os: &mut CodedOutputStream,
+) -> ProtobufResult<()> {
+    match (t, value) {
+        (Type::TYPE_MESSAGE, ReflectValueRef::Message(m)) => {
+            let mut bytes = Vec::new();
+            (&mut bytes).with_coded_output_stream(|os| write_preserving_order(&**m, os))?;
+            os.write_bytes(field_number, &bytes)
+        }
+        (Type::TYPE_GROUP, ReflectValueRef::Message(m)) => {
+            os.write_tag(field_number, WireType::WireTypeStartGroup)?;
+            write_preserving_order(&**m, os)?;
+            os.write_tag(field_number, WireType::WireTypeEndGroup)
+        }
+        (t, value) => write(field_number, t, value, os),
+    }
+}
@@ -5,6 +5,7 @@
 use std::collections::HashMap;
 use std::default::Default;
 use std::hash::Hash;
+use std::mem;
 
 #[cfg(feature = "bytes")]
 use crate::bytes::Bytes;
@@ -13,6 +14,7 @@ use crate::chars::Chars;
 
 use crate::coded_input_stream::CodedInputStream;
 use crate::coded_output_stream::CodedOutputStream;
+use crate::coded_output_stream::WithCodedOutputStream;
 use crate::enums::ProtobufEnum;
 use crate::error::ProtobufError;
 use crate::error::ProtobufResult;
@@ -30,6 +32,7 @@ use crate::MessageField;
 use crate::ProtobufEnumOrUnknown;
 
 use crate::unknown::UnknownFields;
+use crate::unknown::UnknownValue;
 
 pub use crate::cached_size::CachedSize;
 pub use crate::lazy_v2::LazyV2;
@@ -294,6 +297,11 @@ pub fn unknown_fields_size(unknown_fields: &UnknownFields) -> u32 {
         for bytes in &values.length_delimited {
             r += bytes_size_no_tag(&bytes);
         }
+
+        r += tag_size(number) * 2 * values.group.len() as u32;
+        for bytes in &values.group {
+            r += bytes.len() as u32;
+        }
     }
     r
 }
@@ -307,6 +315,7 @@ pub fn read_repeated_int32_into(
     match wire_type {
         WireTypeLengthDelimited => is.read_repeated_packed_int32_into(target),
         WireTypeVarint => {
+            is.check_repeated_push(target)?;
             target.push(is.read_int32()?);
             Ok(())
         }
@@ -323,6 +332,7 @@ pub fn read_repeated_int64_into(
     match wire_type {
         WireTypeLengthDelimited => is.read_repeated_packed_int64_into(target),
         WireTypeVarint => {
+            is.check_repeated_push(target)?;
             target.push(is.read_int64()?);
             Ok(())
         }
@@ -339,6 +349,7 @@ pub fn read_repeated_uint32_into(
     match wire_type {
         WireTypeLengthDelimited => is.read_repeated_packed_uint32_into(target),
         WireTypeVarint => {
+            is.check_repeated_push(target)?;
             target.push(is.read_uint32()?);
             Ok(())
         }
@@ -355,6 +366,7 @@ pub fn read_repeated_uint64_into(
     match wire_type {
         WireTypeLengthDelimited => is.read_repeated_packed_uint64_into(target),
         WireTypeVarint => {
+            is.check_repeated_push(target)?;
             target.push(is.read_uint64()?);
             Ok(())
         }
@@ -371,6 +383,7 @@ pub fn read_repeated_sint32_into(
     match wire_type {
         WireTypeLengthDelimited => is.read_repeated_packed_sint32_into(target),
         WireTypeVarint => {
+            is.check_repeated_push(target)?;
             target.push(is.read_sint32()?);
             Ok(())
         }
@@ -387,6 +400,7 @@ pub fn read_repeated_sint64_into(
     match wire_type {
         WireTypeLengthDelimited => is.read_repeated_packed_sint64_into(target),
         WireTypeVarint => {
+            is.check_repeated_push(target)?;
             target.push(is.read_sint64()?);
             Ok(())
         }
@@ -403,6 +417,7 @@ pub fn read_repeated_fixed32_into(
     match wire_type {
         WireTypeLengthDelimited => is.read_repeated_packed_fixed32_into(target),
         WireTypeFixed32 => {
+            is.check_repeated_push(target)?;
             target.push(is.read_fixed32()?);
             Ok(())
         }
@@ -419,6 +434,7 @@ pub fn read_repeated_fixed64_into(
     match wire_type {
         WireTypeLengthDelimited => is.read_repeated_packed_fixed64_into(target),
         WireTypeFixed64 => {
+            is.check_repeated_push(target)?;
             target.push(is.read_fixed64()?);
             Ok(())
         }
@@ -435,6 +451,7 @@ pub fn read_repeated_sfixed32_into(
     match wire_type {
         WireTypeLengthDelimited => is.read_repeated_packed_sfixed32_into(target),
         WireTypeFixed32 => {
+            is.check_repeated_push(target)?;
             target.push(is.read_sfixed32()?);
             Ok(())
         }
@@ -451,6 +468,7 @@ pub fn read_repeated_sfixed64_into(
     match wire_type {
         WireTypeLengthDelimited => is.read_repeated_packed_sfixed64_into(target),
         WireTypeFixed64 => {
+            is.check_repeated_push(target)?;
             target.push(is.read_sfixed64()?);
             Ok(())
         }
@@ -467,6 +485,7 @@ pub fn read_repeated_double_into(
     match wire_type {
         WireTypeLengthDelimited => is.read_repeated_packed_double_into(target),
         WireTypeFixed64 => {
+            is.check_repeated_push(target)?;
             target.push(is.read_double()?);
             Ok(())
         }
@@ -483,6 +502,7 @@ pub fn read_repeated_float_into(
     match wire_type {
         WireTypeLengthDelimited => is.read_repeated_packed_float_into(target),
         WireTypeFixed32 => {
+            is.check_repeated_push(target)?;
             target.push(is.read_float()?);
             Ok(())
         }
@@ -499,6 +519,7 @@ pub fn read_repeated_bool_into(
     match wire_type {
         WireTypeLengthDelimited => is.read_repeated_packed_bool_into(target),
         WireTypeVarint => {
+            is.check_repeated_push(target)?;
             target.push(is.read_bool()?);
             Ok(())
         }
@@ -516,6 +537,7 @@ pub fn read_repeated_enum_into<E: ProtobufEnum + ProtobufValue>(
     match wire_type {
         WireTypeLengthDelimited => is.read_repeated_packed_enum_into(target),
         WireTypeVarint => {
+            is.check_repeated_push(target)?;
             target.push(is.read_enum()?);
             Ok(())
         }
@@ -542,16 +564,23 @@ where
     Ok(())
 }
 
-fn read_repeated_packed_enum_with_unknown_fields_into<E: ProtobufEnum>(
+fn read_repeated_packed_enum_with_unknown_fields_into<E: ProtobufEnum, T>(
     is: &mut CodedInputStream,
-    target: &mut Vec<E>,
+    target: &mut Vec<T>,
+    into: fn(E) -> T,
     field_number: u32,
     unknown_fields: &mut UnknownFields,
 ) -> ProtobufResult<()> {
     let len = is.read_raw_varint64()?;
     let old_limit = is.push_limit(len)?;
     while !is.eof()? {
-        read_enum_with_unknown_fields_into(is, |e| target.push(e), field_number, unknown_fields)?;
+        is.check_repeated_push(target)?;
+        read_enum_with_unknown_fields_into(
+            is,
+            |e| target.push(into(e)),
+            field_number,
+            unknown_fields,
+        )?;
     }
     is.pop_limit(old_limit);
     Ok(())
@@ -564,6 +593,7 @@ fn read_repeated_packed_enum_or_unknown_into<E: ProtobufEnum>(
     let len = is.read_raw_varint64()?;
     let old_limit = is.push_limit(len)?;
     while !is.eof()? {
+        is.check_repeated_push(target)?;
         target.push(is.read_enum_or_unknown()?);
     }
     is.pop_limit(old_limit);
@@ -587,16 +617,48 @@ pub fn read_repeated_enum_with_unknown_fields_into<E: ProtobufEnum>(
         WireTypeLengthDelimited => read_repeated_packed_enum_with_unknown_fields_into(
             is,
             target,
+            |e| e,
             field_number,
             unknown_fields,
         ),
         WireTypeVarint => {
+            is.check_repeated_push(target)?;
             read_enum_with_unknown_fields_into(is, |e| target.push(e), field_number, unknown_fields)
         }
         _ => Err(unexpected_wire_type(wire_type)),
     }
 }
 
+/// Read repeated closed `enum` field stored as `ProtobufEnumOrUnknown` into given vec,
+/// and when value is unknown store it in unknown fields.
+pub fn read_repeated_enum_or_unknown_with_unknown_fields_into<E: ProtobufEnum>(
+    wire_type: WireType,
+    is: &mut CodedInputStream,
+    target: &mut Vec<ProtobufEnumOrUnknown<E>>,
+    field_number: u32,
+    unknown_fields: &mut UnknownFields,
+) -> ProtobufResult<()> {
+    match wire_type {
+        WireTypeLengthDelimited => read_repeated_packed_enum_with_unknown_fields_into(
+            is,
+            target,
+            ProtobufEnumOrUnknown::new,
+            field_number,
+            unknown_fields,
+        ),
+        WireTypeVarint => {
+            is.check_repeated_push(target)?;
+            read_enum_with_unknown_fields_into(
+                is,
+                |e| target.push(ProtobufEnumOrUnknown::new(e)),
+                field_number,
+                unknown_fields,
+            )
+        }
+        _ => Err(unexpected_wire_type(wire_type)),
+    }
+}
+
 /// Read repeated `enum` field into given vec,
 /// and when value is unknown store it in unknown fields
 /// which matches proto2 spec.
@@ -611,6 +673,7 @@ pub fn read_repeated_enum_or_unknown_into<E: ProtobufEnum>(
     match wire_type {
         WireTypeLengthDelimited => read_repeated_packed_enum_or_unknown_into(is, target),
         WireTypeVarint => {
+            is.check_repeated_push(target)?;
             target.push(is.read_enum_or_unknown()?);
             Ok(())
         }
@@ -658,6 +721,48 @@ pub fn read_proto2_enum_with_unknown_fields_into<E: ProtobufEnum>(
     read_enum_with_unknown_fields_into(is, |e| *target = Some(e), field_number, unknown_fields)
 }
 
+/// Read singular closed `enum` field stored as `ProtobufEnumOrUnknown`,
+/// and when value is unknown store it in unknown fields.
+pub fn read_proto3_enum_or_unknown_with_unknown_fields_into<E: ProtobufEnum>(
+    wire_type: WireType,
+    is: &mut CodedInputStream,
+    target: &mut ProtobufEnumOrUnknown<E>,
+    field_number: u32,
+    unknown_fields: &mut UnknownFields,
+) -> ProtobufResult<()> {
+    if wire_type != WireType::WireTypeVarint {
+        return Err(unexpected_wire_type(wire_type));
+    }
+
+    read_enum_with_unknown_fields_into(
+        is,
+        |e| *target = ProtobufEnumOrUnknown::new(e),
+        field_number,
+        unknown_fields,
+    )
+}
+
+/// Read optional closed `enum` field stored as `ProtobufEnumOrUnknown`,
+/// and when value is unknown store it in unknown fields.
+pub fn read_proto2_enum_or_unknown_with_unknown_fields_into<E: ProtobufEnum>(
+    wire_type: WireType,
+    is: &mut CodedInputStream,
+    target: &mut Option<ProtobufEnumOrUnknown<E>>,
+    field_number: u32,
+    unknown_fields: &mut UnknownFields,
+) -> ProtobufResult<()> {
+    if wire_type != WireType::WireTypeVarint {
+        return Err(unexpected_wire_type(wire_type));
+    }
+
+    read_enum_with_unknown_fields_into(
+        is,
+        |e| *target = Some(ProtobufEnumOrUnknown::new(e)),
+        field_number,
+        unknown_fields,
+    )
+}
+
 /// Read repeated `string` field into given vec.
 pub fn read_repeated_string_into(
     wire_type: WireType,
@@ -666,6 +771,7 @@ pub fn read_repeated_string_into(
 ) -> ProtobufResult<()> {
     match wire_type {
         WireTypeLengthDelimited => {
+            is.check_repeated_push(target)?;
             target.push(is.read_string()?);
             Ok(())
         }
@@ -682,6 +788,7 @@ pub fn read_repeated_carllerche_string_into(
 ) -> ProtobufResult<()> {
     match wire_type {
         WireTypeLengthDelimited => {
+            is.check_repeated_push(target)?;
             target.push(is.read_carllerche_chars()?);
             Ok(())
         }
@@ -741,6 +848,7 @@ pub fn read_repeated_bytes_into(
 ) -> ProtobufResult<()> {
     match wire_type {
         WireTypeLengthDelimited => {
+            is.check_repeated_push(target)?;
             target.push(is.read_bytes()?);
             Ok(())
         }
@@ -757,6 +865,7 @@ pub fn read_repeated_carllerche_bytes_into(
 ) -> ProtobufResult<()> {
     match wire_type {
         WireTypeLengthDelimited => {
+            is.check_repeated_push(target)?;
             target.push(is.read_carllerche_bytes()?);
             Ok(())
         }
@@ -809,6 +918,9 @@ pub fn read_singular_proto3_carllerche_bytes_into(
 }
 
 /// Read repeated `message` field.
+///
+/// Required fields of read messages are not checked,
+/// they are checked in the outermost message.
 pub fn read_repeated_message_into_vec<M: Message + Default>(
     wire_type: WireType,
     is: &mut CodedInputStream,
@@ -816,13 +928,15 @@ pub fn read_repeated_message_into_vec<M: Message + Default>(
 ) -> ProtobufResult<()> {
     match wire_type {
         WireTypeLengthDelimited => {
+            is.check_repeated_push(target)?;
             is.incr_recursion()?;
-            let res = match is.read_message() {
-                Ok(m) => {
+            let mut m = M::new();
+            let res = match is.merge_message(&mut m) {
+                Ok(()) => {
                     target.push(m);
                     Ok(())
                 }
-                Err(e) => Err(e),
+                Err(e) => Err(e.with_repeated_index(target.len())),
             };
             is.decr_recursion();
             res
@@ -853,32 +967,26 @@ where
     }
 }
 
-fn skip_group(is: &mut CodedInputStream) -> ProtobufResult<()> {
-    loop {
-        let (_, wire_type) = is.read_tag_unpack()?;
-        if wire_type == wire_format::WireTypeEndGroup {
-            return Ok(());
-        }
-        is.skip_field(wire_type)?;
-    }
-}
-
 /// Handle unknown field in generated code.
-/// Either store a value in unknown, or skip a group.
+///
+/// Store a value (including group) in unknown fields.
 pub fn read_unknown_or_skip_group(
     field_number: u32,
     wire_type: WireType,
     is: &mut CodedInputStream,
     unknown_fields: &mut UnknownFields,
 ) -> ProtobufResult<()> {
-    match wire_type {
-        wire_format::WireTypeStartGroup => skip_group(is),
-        _ => {
-            let unknown = is.read_unknown(wire_type)?;
-            unknown_fields.add_value(field_number, unknown);
-            Ok(())
-        }
-    }
+    let count = unknown_fields
+        .get(field_number)
+        .map_or(0, |values| values.values_count());
+    is.check_repeated_element(count, mem::size_of::<UnknownValue>())?;
+    let unknown = match wire_type {
+        wire_format::WireTypeStartGroup => UnknownValue::Group(is.read_group(field_number)?),
+        _ => is.read_unknown(wire_type)?,
+    };
+    let after = is.unknown_field_position();
+    unknown_fields.add_parsed_value(field_number, unknown, after);
+    Ok(())
 }
 
 /// Create an error for unexpected wire type.
@@ -913,30 +1021,54 @@ where
     sum
 }
 
-/// Write map, message sizes must be already known.
-pub fn write_map_with_cached_sizes<K, V>(
+fn write_map_entry_with_cached_sizes<K, V>(
     field_number: u32,
-    map: &HashMap<K::ProtobufValue, V::ProtobufValue>,
+    k: &K::ProtobufValue,
+    v: &V::ProtobufValue,
     os: &mut CodedOutputStream,
 ) -> ProtobufResult<()>
 where
     K: ProtobufType,
     V: ProtobufType,
-    K::ProtobufValue: Eq + Hash,
 {
-    for (k, v) in map {
-        let key_tag_size = 1;
-        let value_tag_size = 1;
+    let key_tag_size = 1;
+    let value_tag_size = 1;
 
-        let key_len = K::get_cached_size_with_length_delimiter(k);
-        let value_len = V::get_cached_size_with_length_delimiter(v);
+    let key_len = K::get_cached_size_with_length_delimiter(k);
+    let value_len = V::get_cached_size_with_length_delimiter(v);
 
-        let entry_len = key_tag_size + key_len + value_tag_size + value_len;
+    let entry_len = key_tag_size + key_len + value_tag_size + value_len;
+
+    os.write_tag(field_number, WireType::WireTypeLengthDelimited)?;
+    os.write_raw_varint32(entry_len)?;
+    K::write_with_cached_size(1, k, os)?;
+    V::write_with_cached_size(2, v, os)?;
+    Ok(())
+}
 
-        os.write_tag(field_number, WireType::WireTypeLengthDelimited)?;
-        os.write_raw_varint32(entry_len)?;
-        K::write_with_cached_size(1, k, os)?;
-        V::write_with_cached_size(2, v, os)?;
+/// Write map, message sizes must be already known.
+///
+/// Entries are sorted by key if the stream is deterministic.
+pub fn write_map_with_cached_sizes<K, V>(
+    field_number: u32,
+    map: &HashMap<K::ProtobufValue, V::ProtobufValue>,
+    os: &mut CodedOutputStream,
+) -> ProtobufResult<()>
+where
+    K: ProtobufType,
+    V: ProtobufType,
+    K::ProtobufValue: Eq + Hash + Ord,
+{
+    if os.is_deterministic() {
+        let mut entries: Vec<_> = map.iter().collect();
+        entries.sort_by(|a, b| a.0.cmp(b.0));
+        for (k, v) in entries {
+            write_map_entry_with_cached_sizes::<K, V>(field_number, k, v, os)?;
+        }
+    } else {
+        for (k, v) in map {
+            write_map_entry_with_cached_sizes::<K, V>(field_number, k, v, os)?;
+        }
     }
     Ok(())
 }
@@ -970,11 +1102,85 @@ where
         return Err(unexpected_wire_type(wire_type));
     }
 
+    is.check_repeated_element(
+        target.len(),
+        mem::size_of::<(K::ProtobufValue, V::ProtobufValue)>(),
+    )?;
+
+    let (key, value) = is.nested(|is| {
+        let len = is.read_raw_varint32()?;
+        let old_limit = is.push_limit(len as u64)?;
+        let entry = read_map_entry::<K, V>(is)?;
+        is.pop_limit(old_limit);
+        Ok(entry)
+    })?;
+
+    target.insert(key, value);
+
+    Ok(())
+}
+
+/// Read `map` field with closed enum values.
+///
+/// Like `protoc`, when value is unknown, whole entry is stored in unknown fields.
+/// The entry is re-encoded, so unknown fields inside the entry are dropped.
+pub fn read_map_with_closed_enum_values_into<K, E>(
+    wire_type: WireType,
+    is: &mut CodedInputStream,
+    target: &mut HashMap<K::ProtobufValue, ProtobufEnumOrUnknown<E>>,
+    field_number: u32,
+    unknown_fields: &mut UnknownFields,
+) -> ProtobufResult<()>
+where
+    K: ProtobufType,
+    E: ProtobufEnum,
+    K::ProtobufValue: Eq + Hash,
+{
+    if wire_type != WireType::WireTypeLengthDelimited {
+        return Err(unexpected_wire_type(wire_type));
+    }
+
+    is.check_repeated_element(
+        target.len(),
+        mem::size_of::<(K::ProtobufValue, ProtobufEnumOrUnknown<E>)>(),
+    )?;
+
+    let (key, value) = is.nested(|is| {
+        let len = is.read_raw_varint32()?;
+        let old_limit = is.push_limit(len as u64)?;
+        let entry = read_map_entry::<K, ProtobufTypeEnumOrUnknown<E>>(is)?;
+        is.pop_limit(old_limit);
+        Ok(entry)
+    })?;
+
+    match value.enum_value() {
+        Ok(..) => {
+            target.insert(key, value);
+        }
+        Err(..) => {
+            let mut entry = Vec::new();
+            (&mut entry).with_coded_output_stream(|os| {
+                K::write_with_cached_size(1, &key, os)?;
+                ProtobufTypeEnumOrUnknown::<E>::write_with_cached_size(2, &value, os)
+            })?;
+            unknown_fields.add_length_delimited(field_number, entry);
+        }
+    }
+
+    Ok(())
+}
+
+/// Read map entry fields until the end of the stream (or current limit).
+fn read_map_entry<K, V>(
+    is: &mut CodedInputStream,
+) -> ProtobufResult<(K::ProtobufValue, V::ProtobufValue)>
+where
+    K: ProtobufType,
+    V: ProtobufType,
+{
     let mut key = Default::default();
     let mut value = Default::default();
 
-    let len = is.read_raw_varint32()?;
-    let old_limit = is.push_limit(len as u64)?;
     while !is.eof()? {
         let (field_number, wire_type) = is.read_tag_unpack()?;
         match field_number {
@@ -993,9 +1199,5 @@ where
             _ => is.skip_field(wire_type)?,
         }
     }
-    is.pop_limit(old_limit);
-
-    target.insert(key, value);
-
-    Ok(())
+    Ok((key, value))
 }
@@ -11,6 +11,7 @@ use super::loc::FIRST_COL;
 use super::str_lit::StrLit;
 use super::str_lit::StrLitDecodeError;
 use super::token::Token;
+use super::token::TokenComments;
 use super::token::TokenWithLocation;
 use super::ParserLanguage;
 use crate::text_format::lexer::JsonNumberLit;
@@ -82,6 +83,62 @@ impl From<float::ProtobufFloatParseError> for LexerError {
     }
 }
 
+/// Comments found between two tokens.
+#[derive(Default)]
+pub(crate) struct CollectedComments {
+    /// Trailing comment of the previous token
+    pub prev_trailing: Option<String>,
+    /// Detached comments before the next token
+    pub detached: Vec<String>,
+    /// Leading comment of the next token
+    pub next_leading: Option<String>,
+}
+
+/// Groups consecutive comments, port of `CommentCollector` from `protoc` tokenizer.
+#[derive(Default)]
+struct CommentCollector {
+    can_attach_to_prev: bool,
+    buffer: Option<String>,
+    buffer_is_line_comment: bool,
+    collected: CollectedComments,
+}
+
+impl CommentCollector {
+    fn add_line_comment(&mut self, comment: String) {
+        match self.buffer {
+            // Consecutive line comments are merged
+            Some(ref mut buffer) if self.buffer_is_line_comment => buffer.push_str(&comment),
+            _ => {
+                self.flush();
+                self.buffer = Some(comment);
+                self.buffer_is_line_comment = true;
+            }
+        }
+    }
+
+    fn add_block_comment(&mut self, comment: String) {
+        self.flush();
+        self.buffer = Some(comment);
+        self.buffer_is_line_comment = false;
+    }
+
+    fn flush(&mut self) {
+        if let Some(buffer) = self.buffer.take() {
+            if self.can_attach_to_prev {
+                self.collected.prev_trailing = Some(buffer);
+                self.can_attach_to_prev = false;
+            } else {
+                self.collected.detached.push(buffer);
+            }
+        }
+    }
+
+    fn finish(mut self) -> CollectedComments {
+        self.collected.next_leading = self.buffer.take();
+        self.collected
+    }
+}
+
 #[derive(Copy, Clone)]
 pub struct Lexer<'a> {
     language: ParserLanguage,
@@ -134,6 +191,9 @@ impl<'a> Lexer<'a> {
             if c == '\n' {
                 self.loc.line += 1;
                 self.loc.col = FIRST_COL;
+            } else if c == '\t' {
+                // Tab stops are 8 columns wide, like in `protoc`
+                self.loc.col += 8 - (self.loc.col - FIRST_COL) % 8;
             } else {
                 self.loc.col += 1;
             }
@@ -214,6 +274,117 @@ impl<'a> Lexer<'a> {
         }
     }
 
+    /// Skip whitespace without newlines
+    fn skip_whitespaces_no_newline(&mut self) {
+        self.take_while(|c| c.is_whitespace() && c != '\n');
+    }
+
+    /// Line comment content after `//`, including trailing newline if any
+    fn next_line_comment_opt(&mut self) -> Option<String> {
+        if self.skip_if_lookahead_is_str("//") {
+            let mut content = self.take_while(|c| c != '\n').to_owned();
+            if content.ends_with('\r') {
+                content.pop();
+            }
+            if self.next_char_if_eq('\n') {
+                content.push('\n');
+            }
+            Some(content)
+        } else {
+            None
+        }
+    }
+
+    /// Block comment content between `/*` and `*/`.
+    ///
+    /// Leading whitespace and asterisk are stripped from continuation lines.
+    fn next_block_comment_opt(&mut self) -> LexerResult<Option<String>> {
+        if !self.skip_if_lookahead_is_str("/*") {
+            return Ok(None);
+        }
+        let end = "*/";
+        let len = match self.rem_chars().find(end) {
+            Some(len) => len,
+            None => return Err(LexerError::UnexpectedEof),
+        };
+        let raw = self.skip_to_pos(self.pos + len);
+        self.skip_to_pos(self.pos + end.len());
+
+        let mut content = String::new();
+        for (i, line) in raw.split('\n').enumerate() {
+            if i != 0 {
+                content.push('\n');
+                let line = line.trim_start();
+                content.push_str(match line.starts_with('*') {
+                    true => &line[1..],
+                    false => line,
+                });
+            } else {
+                content.push_str(line);
+            }
+        }
+        Ok(Some(content))
+    }
+
+    /// Skip whitespace and comments like `skip_ws`, but collect comments
+    /// and attach them to surrounding tokens the same way `protoc` does.
+    ///
+    /// `after_token` is false only at the beginning of the file.
+    pub(crate) fn skip_ws_collect_comments(
+        &mut self,
+        after_token: bool,
+    ) -> LexerResult<CollectedComments> {
+        let mut collector = CommentCollector {
+            can_attach_to_prev: after_token,
+            ..CommentCollector::default()
+        };
+
+        if after_token {
+            // A comment on the same line is attached to the previous token
+            self.skip_whitespaces_no_newline();
+            if let Some(comment) = self.next_line_comment_opt() {
+                collector.add_line_comment(comment);
+                // Comments on subsequent lines cannot be attached to a trailing comment
+                collector.flush();
+            } else if let Some(comment) = self.next_block_comment_opt()? {
+                collector.add_block_comment(comment);
+                self.skip_whitespaces_no_newline();
+                if !self.next_char_if_eq('\n') {
+                    // Next token is on the same line, so we have no idea
+                    // which token the comment belongs to
+                    return Ok(CollectedComments::default());
+                }
+                collector.flush();
+            } else if !self.next_char_if_eq('\n') {
+                // Next token is on the same line, no comments
+                return Ok(CollectedComments::default());
+            }
+        }
+
+        // Now we are on the line after the previous token
+        loop {
+            self.skip_whitespaces_no_newline();
+            if let Some(comment) = self.next_line_comment_opt() {
+                collector.add_line_comment(comment);
+            } else if let Some(comment) = self.next_block_comment_opt()? {
+                collector.add_block_comment(comment);
+                // Consume the rest of the line so it is not treated as a blank line
+                self.skip_whitespaces_no_newline();
+                self.next_char_if_eq('\n');
+            } else if self.next_char_if_eq('\n') {
+                // Blank line
+                collector.flush();
+                collector.can_attach_to_prev = false;
+            } else {
+                if self.eof() || self.lookahead_char_is_in("}])") {
+                    // End of scope, nothing to attach comments to
+                    collector.flush();
+                }
+                return Ok(collector.finish());
+            }
+        }
+    }
+
     pub fn take_while<F>(&mut self, f: F) -> &'a str
     where
         F: Fn(char) -> bool,
@@ -614,7 +785,7 @@ impl<'a> Lexer<'a> {
         Ok(Some(JsonNumberLit(s)))
     }
 
-    fn next_token_inner(&mut self) -> LexerResult<Token> {
+    pub(crate) fn next_token_inner(&mut self) -> LexerResult<Token> {
         if self.language == ParserLanguage::Json {
             if let Some(v) = self.next_json_number_opt()? {
                 return Ok(Token::JsonNumber(v));
@@ -637,11 +808,20 @@ impl<'a> Lexer<'a> {
             let pos = clone.pos;
             if let Ok(_) = clone.next_float_lit() {
                 let f = float::parse_protobuf_float(&self.input[pos..clone.pos])?;
+                if self.language == ParserLanguage::TextFormat {
+                    clone.next_char_if_in("fF");
+                }
                 *self = clone;
                 return Ok(Token::FloatLit(f));
             }
 
             if let Some(lit) = self.next_int_lit_opt()? {
+                // Text format allows `f` suffix after integer, e. g. `1f`
+                if self.language == ParserLanguage::TextFormat
+                    && self.next_char_if_in("fF").is_some()
+                {
+                    return Ok(Token::FloatLit(lit as f64));
+                }
                 return Ok(Token::IntLit(lit));
             }
         }
@@ -670,10 +850,16 @@ impl<'a> Lexer<'a> {
             None
         } else {
             let token = self.next_token_inner()?;
+            let end_loc = self.loc;
             // Skip whitespace here to update location
             // to the beginning of the next token
             self.skip_ws()?;
-            Some(TokenWithLocation { token, loc })
+            Some(TokenWithLocation {
+                token,
+                loc,
+                end_loc,
+                comments: TokenComments::default(),
+            })
         })
     }
 }
@@ -716,4 +902,16 @@ mod test {
         let mess = lex(msg, |p| p.next_token_inner());
         assert_eq!(Token::FloatLit(12.3), mess);
     }
+
+    #[test]
+    fn test_lexer_float_lit_suffix() {
+        for (input, expected) in &[("1.5f", 1.5), ("2F", 2.0)] {
+            let mut lexer = Lexer::new(input, ParserLanguage::TextFormat);
+            assert_eq!(
+                Token::FloatLit(*expected),
+                lexer.next_token_inner().unwrap()
+            );
+            assert!(lexer.eof());
+        }
+    }
 }
@@ -20,6 +20,7 @@ pub use self::parser_language::ParserLanguage;
 pub use self::str_lit::StrLit;
 pub use self::str_lit::StrLitDecodeError;
 pub use self::token::Token;
+pub use self::token::TokenComments;
 pub use self::token::TokenWithLocation;
 pub use self::tokenizer::Tokenizer;
 pub use self::tokenizer::TokenizerError;
@@ -40,8 +40,24 @@ impl Token {
     }
 }
 
+/// Comments around a token.
+///
+/// Only collected when parsing `.proto` files.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TokenComments {
+    /// Comments before the token separated from it by blank lines
+    pub detached: Vec<String>,
+    /// Comment immediately before the token
+    pub leading: Option<String>,
+    /// Comment immediately after the token
+    pub trailing: Option<String>,
+}
+
 #[derive(Clone)]
 pub struct TokenWithLocation {
     pub token: Token,
     pub loc: Loc,
+    /// Location right after the token
+    pub end_loc: Loc,
+    pub comments: TokenComments,
 }
@@ -5,6 +5,7 @@ use crate::text_format::lexer::ParserLanguage;
 use crate::text_format::lexer::StrLit;
 use crate::text_format::lexer::StrLitDecodeError;
 use crate::text_format::lexer::Token;
+use crate::text_format::lexer::TokenComments;
 use crate::text_format::lexer::TokenWithLocation;
 use std::fmt;
 
@@ -71,6 +72,11 @@ pub struct Tokenizer<'a> {
     lexer: Lexer<'a>,
     next_token: Option<TokenWithLocation>,
     last_token_loc: Option<Loc>,
+    last_token_end_loc: Option<Loc>,
+    collect_comments: bool,
+    /// Comments before the token not yet returned by lexer
+    pending_comments: Option<TokenComments>,
+    last_token_comments: TokenComments,
 }
 
 impl<'a> Tokenizer<'a> {
@@ -79,9 +85,88 @@ impl<'a> Tokenizer<'a> {
             lexer: Lexer::new(input, comment_style),
             next_token: None,
             last_token_loc: None,
+            last_token_end_loc: None,
+            collect_comments: comment_style == ParserLanguage::Proto,
+            pending_comments: None,
+            last_token_comments: TokenComments::default(),
         }
     }
 
+    /// Comments of the next token, available after lookahead.
+    ///
+    /// Only `detached` and `leading` comments are known before the token is consumed.
+    pub fn lookahead_comments(&mut self) -> TokenizerResult<TokenComments> {
+        self.lookahead()?;
+        Ok(self
+            .next_token
+            .as_ref()
+            .map(|t| TokenComments {
+                trailing: None,
+                ..t.comments.clone()
+            })
+            .unwrap_or_default())
+    }
+
+    /// Location right after the last consumed token.
+    ///
+    /// Location of the lexer if no tokens were consumed yet.
+    pub fn last_token_end_loc(&self) -> Loc {
+        self.last_token_end_loc.unwrap_or(self.lexer.loc)
+    }
+
+    /// Comments of the last consumed token.
+    pub fn last_token_comments(&self) -> &TokenComments {
+        &self.last_token_comments
+    }
+
+    fn next_token_with_comments(&mut self) -> TokenizerResult<Option<TokenWithLocation>> {
+        let pending = match self.pending_comments.take() {
+            Some(pending) => pending,
+            None => {
+                // Beginning of the file
+                let collected = self.lexer.skip_ws_collect_comments(false)?;
+                TokenComments {
+                    detached: collected.detached,
+                    leading: collected.next_leading,
+                    trailing: None,
+                }
+            }
+        };
+
+        let loc = self.lexer.loc;
+        if self.lexer.eof() {
+            self.pending_comments = Some(TokenComments::default());
+            return Ok(None);
+        }
+        let token = self.lexer.next_token_inner()?;
+        let end_loc = self.lexer.loc;
+
+        let collected = self.lexer.skip_ws_collect_comments(true)?;
+        self.pending_comments = Some(TokenComments {
+            detached: collected.detached,
+            leading: collected.next_leading,
+            trailing: None,
+        });
+        Ok(Some(TokenWithLocation {
+            token,
+            loc,
+            end_loc,
+            comments: TokenComments {
+                trailing: collected.prev_trailing,
+                ..pending
+            },
+        }))
+    }
+
+    fn take_next_token(&mut self) -> Option<TokenWithLocation> {
+        let token = self.next_token.take();
+        if let Some(ref token) = token {
+            self.last_token_end_loc = Some(token.end_loc);
+            self.last_token_comments = token.comments.clone();
+        }
+        token
+    }
+
     pub fn loc(&self) -> Loc {
         // After lookahead return the location of the next token
         self.next_token
@@ -103,7 +188,10 @@ impl<'a> Tokenizer<'a> {
         Ok(match self.next_token {
             Some(ref token) => Some(&token.token),
             None => {
-                self.next_token = self.lexer.next_token()?;
+                self.next_token = match self.collect_comments {
+                    true => self.next_token_with_comments()?,
+                    false => self.lexer.next_token()?,
+                };
                 self.last_token_loc = self.next_token.as_ref().map(|t| t.loc.clone());
                 match self.next_token {
                     Some(ref token) => Some(&token.token),
@@ -123,8 +211,7 @@ impl<'a> Tokenizer<'a> {
     fn next(&mut self) -> TokenizerResult<Option<Token>> {
         self.lookahead()?;
         Ok(self
-            .next_token
-            .take()
+            .take_next_token()
             .map(|TokenWithLocation { token, .. }| token))
     }
 
@@ -137,8 +224,7 @@ impl<'a> Tokenizer<'a> {
 
     /// Can be called only after lookahead, otherwise it's error
     pub fn advance(&mut self) -> TokenizerResult<Token> {
-        self.next_token
-            .take()
+        self.take_next_token()
             .map(|TokenWithLocation { token, .. }| token)
             .ok_or(TokenizerError::InternalError)
     }
@@ -160,7 +246,7 @@ impl<'a> Tokenizer<'a> {
             },
             _ => return Ok(None),
         };
-        self.next_token = None;
+        self.take_next_token();
         Ok(Some(v))
     }
 
@@ -174,7 +260,7 @@ impl<'a> Tokenizer<'a> {
             Some(ref token) => p(&token.token)?,
             None => return Err(TokenizerError::UnexpectedEof.into()),
         };
-        self.next_token = None;
+        self.take_next_token();
         Ok(r)
     }
 
@@ -348,4 +434,67 @@ mod test {
             mess
         );
     }
+
+    #[test]
+    fn test_comments() {
+        let input = r#"
+// detached
+
+// leading
+/* block
+ * comment */
+a; // trailing
+// next line of trailing
+
+b; /* block trailing */
+// leading of c
+   c;
+// dangling
+}
+"#;
+        let mut tokenizer = Tokenizer::new(input, ParserLanguage::Proto);
+        let mut comments = Vec::new();
+        while let Some(token) = tokenizer.next().unwrap() {
+            comments.push((token.format(), tokenizer.last_token_comments().clone()));
+        }
+        let find = |t: &str| {
+            comments
+                .iter()
+                .find(|(token, _)| token == t)
+                .map(|(_, c)| c.clone())
+                .unwrap()
+        };
+
+        assert_eq!(
+            TokenComments {
+                detached: vec![" detached\n".to_owned(), " leading\n".to_owned()],
+                leading: Some(" block\n comment ".to_owned()),
+                trailing: None,
+            },
+            find("a")
+        );
+        assert_eq!(Some(" trailing\n".to_owned()), find(";").trailing);
+        assert_eq!(
+            TokenComments {
+                detached: vec![" next line of trailing\n".to_owned()],
+                leading: None,
+                trailing: None,
+            },
+            find("b")
+        );
+        assert_eq!(
+            TokenComments {
+                detached: vec![],
+                leading: Some(" leading of c\n".to_owned()),
+                trailing: None,
+            },
+            find("c")
+        );
+        // Comment before the end of scope is attached to the previous token
+        assert_eq!(
+            Some(" dangling\n".to_owned()),
+            comments[comments.len() - 2].1.trailing
+        );
+        assert_eq!(TokenComments::default(), find("}"));
+    }
 }
@@ -31,11 +31,17 @@ pub mod lexer;
 pub use self::print::fmt;
 pub use self::print::print_to;
 pub use self::print::print_to_string;
+pub use self::print::print_to_string_with_options;
+pub use self::print::print_to_with_options;
+pub use self::print::PrintOptions;
 #[doc(hidden)]
 pub use self::print::quote_bytes_to;
 #[doc(hidden)]
 pub use self::print::quote_escape_bytes;
 
 pub use self::parse::merge_from_str;
+pub use self::parse::merge_from_str_with_options;
 pub use self::parse::parse_from_str;
+pub use self::parse::parse_from_str_with_options;
 pub use self::parse::ParseError;
+pub use self::parse::ParseOptions;
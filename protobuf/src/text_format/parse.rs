@@ -3,19 +3,24 @@ use std::str;
 
 use crate::message::Message;
 
+use crate::descriptor::field_descriptor_proto;
+use crate::error::ProtobufError;
 use crate::message_dyn::MessageDyn;
 use crate::reflect::EnumDescriptor;
-use crate::reflect::EnumValueDescriptor;
+use crate::reflect::FieldDescriptor;
 use crate::reflect::MessageDescriptor;
 use crate::reflect::ReflectValueBox;
 use crate::reflect::RuntimeFieldType;
 use crate::reflect::RuntimeTypeBox;
+use crate::reflect::TypeRegistry;
 use crate::text_format::lexer::int;
 use crate::text_format::lexer::Loc;
 use crate::text_format::lexer::ParserLanguage;
 use crate::text_format::lexer::StrLitDecodeError;
+use crate::text_format::lexer::Token;
 use crate::text_format::lexer::Tokenizer;
 use crate::text_format::lexer::TokenizerError;
+use crate::well_known_types::Any;
 
 #[derive(Debug)]
 pub enum ParseErrorWithoutLoc {
@@ -26,7 +31,50 @@ pub enum ParseErrorWithoutLoc {
     MapFieldIsSpecifiedMoreThanOnce(String),
     IntegerOverflow,
     ExpectingBool,
-    MessageNotInitialized,
+    /// Required fields are not set, contains paths of missing fields.
+    MessageNotInitialized(Vec<String>),
+    /// Extension is not found in type registry or does not extend the message.
+    UnknownExtension(String),
+    /// Type URL of expanded `Any` is not found in type registry.
+    AnyUnknownType(String),
+    /// Expanded `Any` syntax used in message which is not `google.protobuf.Any`.
+    AnyExpansionInNonAny(String),
+    /// Failed to serialize message into `Any` or extension field.
+    EncodeError(ProtobufError),
+    /// Messages are nested deeper than `ParseOptions::recursion_limit`.
+    RecursionLimitExceeded,
+}
+
+impl fmt::Display for ParseErrorWithoutLoc {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseErrorWithoutLoc::TokenizerError(e) => write!(f, "{}", e),
+            ParseErrorWithoutLoc::StrLitDecodeError(e) => write!(f, "{}", e),
+            ParseErrorWithoutLoc::UnknownField(n) => write!(f, "unknown field: {}", n),
+            ParseErrorWithoutLoc::UnknownEnumValue(n) => write!(f, "unknown enum value: {}", n),
+            ParseErrorWithoutLoc::MapFieldIsSpecifiedMoreThanOnce(n) => {
+                write!(f, "map field specified more than once: {}", n)
+            }
+            ParseErrorWithoutLoc::IntegerOverflow => write!(f, "integer overflow"),
+            ParseErrorWithoutLoc::ExpectingBool => write!(f, "expecting bool"),
+            ParseErrorWithoutLoc::MessageNotInitialized(missing) => {
+                write!(f, "message not initialized")?;
+                if !missing.is_empty() {
+                    write!(f, ", missing required fields: {}", missing.join(", "))?;
+                }
+                Ok(())
+            }
+            ParseErrorWithoutLoc::UnknownExtension(n) => write!(f, "unknown extension: {}", n),
+            ParseErrorWithoutLoc::AnyUnknownType(u) => {
+                write!(f, "type URL of Any is not found in registry: {}", u)
+            }
+            ParseErrorWithoutLoc::AnyExpansionInNonAny(u) => {
+                write!(f, "expanded Any in message which is not Any: {}", u)
+            }
+            ParseErrorWithoutLoc::EncodeError(e) => write!(f, "failed to encode message: {}", e),
+            ParseErrorWithoutLoc::RecursionLimitExceeded => write!(f, "recursion limit exceeded"),
+        }
+    }
 }
 
 impl From<TokenizerError> for ParseErrorWithoutLoc {
@@ -65,9 +113,31 @@ impl std::error::Error for ParseError {}
 pub type ParseResult<A> = Result<A, ParseErrorWithoutLoc>;
 pub type ParseWithLocResult<A> = Result<A, ParseError>;
 
+/// Name of the field in text format.
+enum FieldName {
+    /// Regular field name.
+    Regular(String),
+    /// Extension name in square brackets, e. g. `[pkg.ext_field]`.
+    Extension(String),
+    /// Type URL of expanded `Any`, e. g. `[type.googleapis.com/pkg.Msg]`.
+    AnyTypeUrl(String),
+}
+
+/// Value of float identifier, case-insensitive `inf`, `infinity` or `nan`.
+fn float_ident_value(ident: &str) -> Option<f64> {
+    match ident.to_lowercase().as_str() {
+        "inf" | "infinity" => Some(f64::INFINITY),
+        "nan" => Some(f64::NAN),
+        _ => None,
+    }
+}
+
 #[derive(Clone)]
 struct Parser<'a> {
     tokenizer: Tokenizer<'a>,
+    parse_options: &'a ParseOptions,
+    /// Nesting level of the message being parsed
+    depth: u32,
 }
 
 impl<'a> Parser<'a> {
@@ -77,31 +147,94 @@ impl<'a> Parser<'a> {
         Ok(self.tokenizer.next_ident()?)
     }
 
+    /// Read either regular field name, extension name or `Any` type URL.
+    fn next_field_name_or_bracketed(&mut self) -> ParseResult<FieldName> {
+        if !self.tokenizer.next_symbol_if_eq('[')? {
+            return Ok(FieldName::Regular(self.next_field_name()?));
+        }
+
+        let mut name = self.tokenizer.next_ident()?;
+        let mut type_url = false;
+        loop {
+            if self.tokenizer.next_symbol_if_eq('.')? {
+                name.push('.');
+            } else if self.tokenizer.next_symbol_if_eq('/')? {
+                name.push('/');
+                type_url = true;
+            } else {
+                break;
+            }
+            name.push_str(&self.tokenizer.next_ident()?);
+        }
+        self.tokenizer.next_symbol_expect_eq(']')?;
+
+        Ok(if type_url {
+            FieldName::AnyTypeUrl(name)
+        } else {
+            FieldName::Extension(name)
+        })
+    }
+
     fn read_colon(&mut self) -> ParseResult<()> {
         Ok(self.tokenizer.next_symbol_expect_eq(':')?)
     }
 
-    fn read_enum<'e>(&mut self, e: &'e EnumDescriptor) -> ParseResult<EnumValueDescriptor> {
-        self.read_colon()?;
+    /// Colon after field name is optional for message values and required otherwise.
+    fn read_colon_before(&mut self, t: &RuntimeTypeBox) -> ParseResult<()> {
+        match t {
+            RuntimeTypeBox::Message(..) => {
+                self.tokenizer.next_symbol_if_eq(':')?;
+                Ok(())
+            }
+            _ => self.read_colon(),
+        }
+    }
+
+    /// Read either a single value or a list of values like `[a, b, c]`.
+    fn read_list_or_single(
+        &mut self,
+        mut read: impl FnMut(&mut Self) -> ParseResult<()>,
+    ) -> ParseResult<()> {
+        if !self.tokenizer.next_symbol_if_eq('[')? {
+            return read(self);
+        }
+        if self.tokenizer.next_symbol_if_eq(']')? {
+            return Ok(());
+        }
+        loop {
+            read(self)?;
+            if self.tokenizer.next_symbol_if_eq(']')? {
+                return Ok(());
+            }
+            self.tokenizer.next_symbol_expect_eq(',')?;
+        }
+    }
+
+    /// Read enum value by name or by number.
+    ///
+    /// Numbers without declared value are only allowed for open (proto3) enums.
+    fn read_enum<'e>(&mut self, e: &'e EnumDescriptor) -> ParseResult<i32> {
+        if self.tokenizer.lookahead_is_symbol('-')? || self.tokenizer.lookahead_is_int_lit()? {
+            let number = self.read_i32()?;
+            if e.is_closed() && e.get_value_by_number(number).is_none() {
+                return Err(ParseErrorWithoutLoc::UnknownEnumValue(number.to_string()));
+            }
+            return Ok(number);
+        }
 
-        // TODO: read integer?
         let ident = self.tokenizer.next_ident()?;
         let value = match e.get_value_by_name(&ident) {
             Some(value) => value,
             None => return Err(ParseErrorWithoutLoc::UnknownEnumValue(ident)),
         };
-        Ok(value)
+        Ok(value.value())
     }
 
     fn read_u64(&mut self) -> ParseResult<u64> {
-        self.read_colon()?;
-
         Ok(self.tokenizer.next_int_lit()?)
     }
 
     fn read_u32(&mut self) -> ParseResult<u32> {
-        self.read_colon()?;
-
         let int_lit = self.tokenizer.next_int_lit()?;
         let value_u32 = int_lit as u32;
         if value_u32 as u64 != int_lit {
@@ -111,8 +244,6 @@ impl<'a> Parser<'a> {
     }
 
     fn read_i64(&mut self) -> ParseResult<i64> {
-        self.read_colon()?;
-
         if self.tokenizer.next_symbol_if_eq('-')? {
             let int_lit = self.tokenizer.next_int_lit()?;
             Ok(int::neg(int_lit)?)
@@ -134,12 +265,15 @@ impl<'a> Parser<'a> {
     }
 
     fn read_f64(&mut self) -> ParseResult<f64> {
-        self.read_colon()?;
-
         let minus = self.tokenizer.next_symbol_if_eq('-')?;
 
         let value = if let Ok(value) = self.tokenizer.next_int_lit() {
             value as f64
+        } else if let Some(value) = self.tokenizer.next_token_if_map(|token| match token {
+            Token::Ident(ident) => float_ident_value(ident),
+            _ => None,
+        })? {
+            value
         } else {
             self.tokenizer.next_float_lit()?
         };
@@ -152,47 +286,68 @@ impl<'a> Parser<'a> {
     }
 
     fn read_bool(&mut self) -> ParseResult<bool> {
-        self.read_colon()?;
+        if let Some(ident) = self
+            .tokenizer
+            .next_ident_if_in(&["true", "True", "t", "false", "False", "f"])?
+        {
+            return Ok(ident.starts_with(['t', 'T']));
+        }
+        if self.tokenizer.lookahead_is_int_lit()? {
+            match self.tokenizer.next_int_lit()? {
+                0 => return Ok(false),
+                1 => return Ok(true),
+                _ => {}
+            }
+        }
+        Err(ParseErrorWithoutLoc::ExpectingBool)
+    }
 
-        if self.tokenizer.next_ident_if_eq("true")? {
-            Ok(true)
-        } else if self.tokenizer.next_ident_if_eq("false")? {
-            Ok(false)
-        } else {
-            Err(ParseErrorWithoutLoc::ExpectingBool)
+    /// Read adjacent string literals as single concatenated value.
+    fn read_str_lit_bytes(&mut self) -> ParseResult<Vec<u8>> {
+        let mut bytes = self.tokenizer.next_str_lit()?.decode_bytes()?;
+        while self.tokenizer.lookahead_is_str_lit()? {
+            bytes.extend(self.tokenizer.next_str_lit()?.decode_bytes()?);
         }
+        Ok(bytes)
     }
 
     fn read_string(&mut self) -> ParseResult<String> {
-        self.read_colon()?;
-
-        Ok(self
-            .tokenizer
-            .next_str_lit()
-            .and_then(|s| s.decode_utf8().map_err(From::from))?)
+        let bytes = self.read_str_lit_bytes()?;
+        Ok(String::from_utf8(bytes).map_err(StrLitDecodeError::from)?)
     }
 
     fn read_bytes(&mut self) -> ParseResult<Vec<u8>> {
-        self.read_colon()?;
-
-        Ok(self
-            .tokenizer
-            .next_str_lit()
-            .and_then(|s| s.decode_bytes().map_err(From::from))?)
+        self.read_str_lit_bytes()
     }
 
+    /// Read message body in `{ ... }` or `< ... >`.
     fn read_message(&mut self, descriptor: &MessageDescriptor) -> ParseResult<Box<dyn MessageDyn>> {
         let mut message = descriptor.new_instance();
 
-        let symbol = self.tokenizer.next_symbol_expect_eq_oneof(&['{', '<'])?;
-        let terminator = if symbol == '{' { '}' } else { '>' };
+        let terminator = self.enter_message()?;
         while !self.tokenizer.lookahead_is_symbol(terminator)? {
             self.merge_field(&mut *message, descriptor)?;
         }
-        self.tokenizer.next_symbol_expect_eq(terminator)?;
+        self.exit_message(terminator)?;
         Ok(message)
     }
 
+    /// Read message opening `{` or `<` and return the matching terminator.
+    fn enter_message(&mut self) -> ParseResult<char> {
+        if self.depth >= self.parse_options.recursion_limit {
+            return Err(ParseErrorWithoutLoc::RecursionLimitExceeded);
+        }
+        self.depth += 1;
+        let symbol = self.tokenizer.next_symbol_expect_eq_oneof(&['{', '<'])?;
+        Ok(if symbol == '{' { '}' } else { '>' })
+    }
+
+    fn exit_message(&mut self, terminator: char) -> ParseResult<()> {
+        self.tokenizer.next_symbol_expect_eq(terminator)?;
+        self.depth -= 1;
+        Ok(())
+    }
+
     fn read_map_entry(
         &mut self,
         k: &RuntimeTypeBox,
@@ -203,8 +358,8 @@ impl<'a> Parser<'a> {
 
         let mut key = None;
         let mut value = None;
-        self.tokenizer.next_symbol_expect_eq('{')?;
-        while !self.tokenizer.lookahead_is_symbol('}')? {
+        let terminator = self.enter_message()?;
+        while !self.tokenizer.lookahead_is_symbol(terminator)? {
             let ident = self.next_field_name()?;
             let (field, field_type) = if ident == key_field_name {
                 (&mut key, k)
@@ -218,11 +373,13 @@ impl<'a> Parser<'a> {
                 return Err(ParseErrorWithoutLoc::MapFieldIsSpecifiedMoreThanOnce(ident));
             }
 
+            self.read_colon_before(field_type)?;
             let field_value = self.read_value_of_type(field_type)?;
+            self.skip_field_separator()?;
 
             *field = Some(field_value);
         }
-        self.tokenizer.next_symbol_expect_eq('}')?;
+        self.exit_message(terminator)?;
         let key = match key {
             Some(key) => key,
             None => k.default_value_ref().to_box(),
@@ -237,7 +394,7 @@ impl<'a> Parser<'a> {
     fn read_value_of_type(&mut self, t: &RuntimeTypeBox) -> ParseResult<ReflectValueBox> {
         Ok(match t {
             RuntimeTypeBox::Enum(d) => {
-                let value = self.read_enum(&d)?.value();
+                let value = self.read_enum(&d)?;
                 ReflectValueBox::Enum(d.clone(), value)
             }
             RuntimeTypeBox::U32 => ReflectValueBox::U32(self.read_u32()?),
@@ -253,37 +410,185 @@ impl<'a> Parser<'a> {
         })
     }
 
+    /// Optional `;` or `,` after field.
+    fn skip_field_separator(&mut self) -> ParseResult<()> {
+        if !self.tokenizer.next_symbol_if_eq(';')? {
+            self.tokenizer.next_symbol_if_eq(',')?;
+        }
+        Ok(())
+    }
+
+    /// Skip scalar value of unknown field.
+    fn skip_scalar_value(&mut self) -> ParseResult<()> {
+        if self.tokenizer.lookahead_is_str_lit()? {
+            self.read_str_lit_bytes()?;
+            return Ok(());
+        }
+        self.tokenizer.next_symbol_if_eq('-')?;
+        self.tokenizer.next_token_check_map(|token| match token {
+            Token::Ident(..) | Token::IntLit(..) | Token::FloatLit(..) => Ok(()),
+            _ => Err(TokenizerError::IncorrectInput),
+        })?;
+        Ok(())
+    }
+
+    /// Skip message value of unknown field, fields are not resolved.
+    fn skip_message_value(&mut self) -> ParseResult<()> {
+        let terminator = self.enter_message()?;
+        while !self.tokenizer.lookahead_is_symbol(terminator)? {
+            self.next_field_name_or_bracketed()?;
+            self.skip_field_value()?;
+        }
+        self.exit_message(terminator)
+    }
+
+    /// Skip value of unknown field, e. g. `: 1`, `: [1, 2]` or `{ ... }`.
+    fn skip_field_value(&mut self) -> ParseResult<()> {
+        let colon = self.tokenizer.next_symbol_if_eq(':')?;
+        self.read_list_or_single(|p| {
+            if colon
+                && !p.tokenizer.lookahead_is_symbol('{')?
+                && !p.tokenizer.lookahead_is_symbol('<')?
+            {
+                p.skip_scalar_value()
+            } else {
+                p.skip_message_value()
+            }
+        })?;
+        self.skip_field_separator()
+    }
+
+    fn merge_any_expansion(
+        &mut self,
+        message: &mut dyn MessageDyn,
+        type_url: String,
+    ) -> ParseResult<()> {
+        let any = match message.downcast_mut::<Any>() {
+            Some(any) => any,
+            None => return Err(ParseErrorWithoutLoc::AnyExpansionInNonAny(type_url)),
+        };
+
+        let descriptor = match self
+            .parse_options
+            .type_registry
+            .find_message_by_type_url(&type_url)
+        {
+            Some(descriptor) => descriptor,
+            None => return Err(ParseErrorWithoutLoc::AnyUnknownType(type_url)),
+        };
+
+        self.tokenizer.next_symbol_if_eq(':')?;
+        let value = self.read_message(&descriptor)?;
+
+        any.type_url = type_url;
+        any.value = value
+            .write_to_bytes_dyn()
+            .map_err(ParseErrorWithoutLoc::EncodeError)?;
+        Ok(())
+    }
+
+    fn merge_extension(
+        &mut self,
+        message: &mut dyn MessageDyn,
+        descriptor: &MessageDescriptor,
+        name: String,
+    ) -> ParseResult<()> {
+        let extension = match self
+            .parse_options
+            .type_registry
+            .find_extension_by_full_name(&name)
+        {
+            Some(extension) if extension.extendee().full_name() == descriptor.full_name() => {
+                extension
+            }
+            _ if self.parse_options.ignore_unknown_fields => return self.skip_field_value(),
+            _ => return Err(ParseErrorWithoutLoc::UnknownExtension(name)),
+        };
+
+        let (t, repeated) = match extension.runtime_field_type() {
+            RuntimeFieldType::Singular(t) => (t, false),
+            RuntimeFieldType::Repeated(t) => (t, true),
+            RuntimeFieldType::Map(..) => unreachable!(),
+        };
+        self.read_colon_before(&t)?;
+        let mut read = |p: &mut Self| {
+            let value = p.read_value_of_type(&t)?;
+            extension
+                .add_value(message, value.as_value_ref())
+                .map_err(ParseErrorWithoutLoc::EncodeError)
+        };
+        if repeated {
+            self.read_list_or_single(read)?;
+        } else {
+            read(self)?;
+        }
+        self.skip_field_separator()
+    }
+
+    /// Find group field by group message name, e. g. `MyGroup { ... }`.
+    fn find_group_field(descriptor: &MessageDescriptor, name: &str) -> Option<FieldDescriptor> {
+        descriptor.fields().find(|field| {
+            if field.get_proto().get_field_type() != field_descriptor_proto::Type::TYPE_GROUP {
+                return false;
+            }
+            match field.runtime_field_type() {
+                RuntimeFieldType::Singular(RuntimeTypeBox::Message(m))
+                | RuntimeFieldType::Repeated(RuntimeTypeBox::Message(m)) => m.get_name() == name,
+                _ => false,
+            }
+        })
+    }
+
     fn merge_field(
         &mut self,
         message: &mut dyn MessageDyn,
         descriptor: &MessageDescriptor,
     ) -> ParseResult<()> {
-        let field_name = self.next_field_name()?;
+        let field_name = match self.next_field_name_or_bracketed()? {
+            FieldName::Regular(field_name) => field_name,
+            FieldName::Extension(name) => {
+                return self.merge_extension(message, descriptor, name);
+            }
+            FieldName::AnyTypeUrl(type_url) => {
+                self.merge_any_expansion(message, type_url)?;
+                return self.skip_field_separator();
+            }
+        };
 
-        let field = match descriptor.get_field_by_name(&field_name) {
+        let field = match descriptor
+            .get_field_by_name(&field_name)
+            .or_else(|| Parser::find_group_field(descriptor, &field_name))
+        {
             Some(field) => field,
-            None => {
-                // TODO: shouldn't unknown fields be quietly skipped?
-                return Err(ParseErrorWithoutLoc::UnknownField(field_name));
-            }
+            None if self.parse_options.ignore_unknown_fields => return self.skip_field_value(),
+            None => return Err(ParseErrorWithoutLoc::UnknownField(field_name)),
         };
 
         match field.runtime_field_type() {
             RuntimeFieldType::Singular(t) => {
+                self.read_colon_before(&t)?;
                 let value = self.read_value_of_type(&t)?;
                 field.set_singular_field(message, value);
             }
             RuntimeFieldType::Repeated(t) => {
-                let value = self.read_value_of_type(&t)?;
-                field.mut_repeated(message).push(value);
+                self.read_colon_before(&t)?;
+                self.read_list_or_single(|p| {
+                    let value = p.read_value_of_type(&t)?;
+                    field.mut_repeated(message).push(value);
+                    Ok(())
+                })?;
             }
             RuntimeFieldType::Map(k, v) => {
-                let (k, v) = self.read_map_entry(&k, &v)?;
-                field.mut_map(message).insert(k, v);
+                self.tokenizer.next_symbol_if_eq(':')?;
+                self.read_list_or_single(|p| {
+                    let (k, v) = p.read_map_entry(&k, &v)?;
+                    field.mut_map(message).insert(k, v);
+                    Ok(())
+                })?;
             }
         };
 
-        Ok(())
+        self.skip_field_separator()
     }
 
     fn merge_inner(&mut self, message: &mut dyn MessageDyn) -> ParseResult<()> {
@@ -308,25 +613,100 @@ impl<'a> Parser<'a> {
     }
 }
 
+/// Text format parse options.
+///
+/// # Examples
+///
+/// ```
+/// use protobuf::reflect::TypeRegistry;
+/// use protobuf::text_format;
+/// use protobuf::well_known_types::Duration;
+///
+/// let mut type_registry = TypeRegistry::new();
+/// type_registry.add_message_type::<Duration>();
+/// let parse_options = text_format::ParseOptions {
+///     type_registry,
+///     ..Default::default()
+/// };
+/// ```
+#[derive(Debug, Clone)]
+pub struct ParseOptions {
+    /// Types used to parse expanded `google.protobuf.Any` messages
+    /// (`[type.googleapis.com/pkg.Msg] { ... }`) and extension fields
+    /// (`[pkg.ext_field]: ...`).
+    ///
+    /// Parsing fails if type URL or extension cannot be resolved.
+    pub type_registry: TypeRegistry,
+    /// Ignore unknown fields when parsing.
+    ///
+    /// When `true` fields with unknown names and extensions not found
+    /// in the type registry are skipped.
+    /// When `false` parser returns an error on such fields.
+    pub ignore_unknown_fields: bool,
+    /// Do not check that required fields are set in `parse_from_str*` functions.
+    pub allow_partial: bool,
+    /// Maximum nesting depth of messages, `100` by default.
+    pub recursion_limit: u32,
+    /// Prevent initializing `ParseOptions` enumerating all field.
+    pub _future_options: (),
+}
+
+impl Default for ParseOptions {
+    fn default() -> ParseOptions {
+        ParseOptions {
+            type_registry: TypeRegistry::default(),
+            ignore_unknown_fields: false,
+            allow_partial: false,
+            recursion_limit: 100,
+            _future_options: (),
+        }
+    }
+}
+
 /// Parse text format message.
 ///
 /// This function does not check if message required fields are set.
-pub fn merge_from_str(message: &mut dyn MessageDyn, input: &str) -> ParseWithLocResult<()> {
+pub fn merge_from_str_with_options(
+    message: &mut dyn MessageDyn,
+    input: &str,
+    parse_options: &ParseOptions,
+) -> ParseWithLocResult<()> {
     let mut parser = Parser {
         tokenizer: Tokenizer::new(input, ParserLanguage::TextFormat),
+        parse_options,
+        depth: 0,
     };
     parser.merge(message)
 }
 
 /// Parse text format message.
-pub fn parse_from_str<M: Message>(input: &str) -> ParseWithLocResult<M> {
+///
+/// This function does not check if message required fields are set.
+pub fn merge_from_str(message: &mut dyn MessageDyn, input: &str) -> ParseWithLocResult<()> {
+    merge_from_str_with_options(message, input, &ParseOptions::default())
+}
+
+/// Parse text format message.
+pub fn parse_from_str_with_options<M: Message>(
+    input: &str,
+    parse_options: &ParseOptions,
+) -> ParseWithLocResult<M> {
     let mut m = M::new();
-    merge_from_str(&mut m, input)?;
-    if let Err(_) = m.check_initialized() {
+    merge_from_str_with_options(&mut m, input, parse_options)?;
+    if parse_options.allow_partial {
+        return Ok(m);
+    }
+    if let Err(ProtobufError::MessageNotInitialized { missing_fields, .. }) = m.check_initialized()
+    {
         return Err(ParseError {
-            error: ParseErrorWithoutLoc::MessageNotInitialized,
+            error: ParseErrorWithoutLoc::MessageNotInitialized(missing_fields),
             loc: Loc::start(),
         });
     }
     Ok(m)
 }
+
+/// Parse text format message.
+pub fn parse_from_str<M: Message>(input: &str) -> ParseWithLocResult<M> {
+    parse_from_str_with_options(input, &ParseOptions::default())
+}
@@ -1,10 +1,14 @@
 use std::fmt;
 use std::fmt::Write;
 
+use crate::message::Message;
 use crate::message_dyn::MessageDyn;
-use crate::reflect::MessageRef;
+use crate::raw_message::decode_unknown_message;
 use crate::reflect::ReflectFieldRef;
 use crate::reflect::ReflectValueRef;
+use crate::reflect::TypeRegistry;
+use crate::well_known_types::Any;
+use crate::well_known_types::Empty;
 
 #[doc(hidden)]
 pub fn quote_bytes_to(bytes: &[u8], buf: &mut String) {
@@ -40,173 +44,343 @@ pub fn quote_escape_bytes(bytes: &[u8]) -> String {
     r
 }
 
-fn print_str_to(s: &str, buf: &mut String) {
-    // TODO: keep printable Unicode
-    quote_escape_bytes_to(s.as_bytes(), buf);
-}
-
-fn do_indent(buf: &mut String, pretty: bool, indent: usize) {
-    if pretty && indent > 0 {
-        for _ in 0..indent {
-            buf.push_str("  ");
+/// Print quoted string, non-ASCII characters are escaped unless `utf8` is set.
+fn print_str_to(s: &str, utf8: bool, buf: &mut String) {
+    if !utf8 {
+        quote_escape_bytes_to(s.as_bytes(), buf);
+        return;
+    }
+    buf.push('"');
+    for c in s.chars() {
+        if c.is_ascii() || c.is_control() {
+            quote_bytes_to(c.encode_utf8(&mut [0; 4]).as_bytes(), buf);
+        } else {
+            buf.push(c);
         }
     }
+    buf.push('"');
 }
 
 trait FieldName: fmt::Display {}
 impl<'a> FieldName for &'a str {}
 impl FieldName for u32 {}
 
-fn print_start_field<F: FieldName>(
-    buf: &mut String,
-    pretty: bool,
-    indent: usize,
-    first: &mut bool,
-    field_name: F,
-) {
-    if !*first && !pretty {
-        buf.push_str(" ");
+/// Name of extension or `Any` type URL printed in square brackets.
+struct BracketedName<'a>(&'a str);
+
+impl<'a> fmt::Display for BracketedName<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[{}]", self.0)
     }
-    do_indent(buf, pretty, indent);
-    *first = false;
-    write!(buf, "{}", field_name).unwrap();
 }
 
-fn print_end_field(buf: &mut String, pretty: bool) {
-    if pretty {
-        buf.push_str("\n");
-    }
+impl<'a> FieldName for BracketedName<'a> {}
+
+struct Printer<'a> {
+    buf: &'a mut String,
+    print_options: &'a PrintOptions,
 }
 
-fn print_field<F: FieldName>(
-    buf: &mut String,
-    pretty: bool,
-    indent: usize,
-    first: &mut bool,
-    field_name: F,
-    value: ReflectValueRef,
-) {
-    print_start_field(buf, pretty, indent, first, field_name);
-
-    match value {
-        ReflectValueRef::Message(m) => {
-            buf.push_str(" {");
-            if pretty {
-                buf.push_str("\n");
-            }
-            print_to_internal(&m, buf, pretty, indent + 1);
-            do_indent(buf, pretty, indent);
-            buf.push_str("}");
-        }
-        ReflectValueRef::Enum(d, v) => {
-            buf.push_str(": ");
-            match d.get_value_by_number(v) {
-                Some(e) => buf.push_str(e.get_name()),
-                None => write!(buf, ": {}", v).unwrap(),
+impl<'a> Printer<'a> {
+    fn pretty(&self) -> bool {
+        !self.print_options.single_line
+    }
+
+    fn do_indent(&mut self, indent: usize) {
+        if self.pretty() {
+            for _ in 0..indent * self.print_options.indent_width {
+                self.buf.push(' ');
             }
         }
-        ReflectValueRef::String(s) => {
-            buf.push_str(": ");
-            print_str_to(s, buf);
-        }
-        ReflectValueRef::Bytes(b) => {
-            buf.push_str(": ");
-            quote_escape_bytes_to(b, buf);
-        }
-        ReflectValueRef::I32(v) => {
-            write!(buf, ": {}", v).unwrap();
-        }
-        ReflectValueRef::I64(v) => {
-            write!(buf, ": {}", v).unwrap();
-        }
-        ReflectValueRef::U32(v) => {
-            write!(buf, ": {}", v).unwrap();
+    }
+
+    fn print_start_field<F: FieldName>(&mut self, indent: usize, first: &mut bool, field_name: F) {
+        if !*first && !self.pretty() {
+            self.buf.push_str(" ");
         }
-        ReflectValueRef::U64(v) => {
-            write!(buf, ": {}", v).unwrap();
+        self.do_indent(indent);
+        *first = false;
+        write!(self.buf, "{}", field_name).unwrap();
+    }
+
+    fn print_end_field(&mut self) {
+        if self.pretty() {
+            self.buf.push_str("\n");
         }
-        ReflectValueRef::Bool(v) => {
-            write!(buf, ": {}", v).unwrap();
+    }
+
+    fn print_message_value(&mut self, m: &dyn MessageDyn, indent: usize) {
+        self.buf.push_str(" {");
+        if self.pretty() {
+            self.buf.push_str("\n");
         }
-        ReflectValueRef::F32(v) => {
-            write!(buf, ": {}", v).unwrap();
+        self.print_message(m, indent + 1);
+        self.do_indent(indent);
+        self.buf.push_str("}");
+    }
+
+    fn print_field<F: FieldName>(
+        &mut self,
+        indent: usize,
+        first: &mut bool,
+        field_name: F,
+        value: ReflectValueRef,
+    ) {
+        self.print_start_field(indent, first, field_name);
+
+        let utf8 = self.print_options.utf8;
+        let buf = &mut *self.buf;
+        match value {
+            ReflectValueRef::Message(m) => {
+                self.print_message_value(&*m, indent);
+            }
+            ReflectValueRef::Enum(d, v) => {
+                buf.push_str(": ");
+                match d.get_value_by_number(v) {
+                    Some(e) => buf.push_str(e.get_name()),
+                    None => write!(buf, "{}", v).unwrap(),
+                }
+            }
+            ReflectValueRef::String(s) => {
+                buf.push_str(": ");
+                print_str_to(s, utf8, buf);
+            }
+            ReflectValueRef::Bytes(b) => {
+                buf.push_str(": ");
+                quote_escape_bytes_to(b, buf);
+            }
+            ReflectValueRef::I32(v) => {
+                write!(buf, ": {}", v).unwrap();
+            }
+            ReflectValueRef::I64(v) => {
+                write!(buf, ": {}", v).unwrap();
+            }
+            ReflectValueRef::U32(v) => {
+                write!(buf, ": {}", v).unwrap();
+            }
+            ReflectValueRef::U64(v) => {
+                write!(buf, ": {}", v).unwrap();
+            }
+            ReflectValueRef::Bool(v) => {
+                write!(buf, ": {}", v).unwrap();
+            }
+            ReflectValueRef::F32(v) => {
+                write!(buf, ": {}", v).unwrap();
+            }
+            ReflectValueRef::F64(v) => {
+                write!(buf, ": {}", v).unwrap();
+            }
         }
-        ReflectValueRef::F64(v) => {
-            write!(buf, ": {}", v).unwrap();
+
+        self.print_end_field();
+    }
+
+    /// Print `Any` as `[type_url] { ... }` if its type is found in the registry.
+    ///
+    /// Return `false` if `Any` cannot be expanded.
+    fn print_any_expanded(&mut self, any: &Any, indent: usize, first: &mut bool) -> bool {
+        let descriptor = match self
+            .print_options
+            .type_registry
+            .find_message_by_type_url(&any.type_url)
+        {
+            Some(descriptor) => descriptor,
+            None => return false,
+        };
+        let mut value = descriptor.new_instance();
+        if value.merge_from_bytes_dyn(&any.value).is_err() {
+            return false;
         }
+
+        self.print_start_field(indent, first, BracketedName(&any.type_url));
+        self.print_message_value(&*value, indent);
+        self.print_end_field();
+        true
     }
 
-    print_end_field(buf, pretty);
-}
+    fn print_message(&mut self, m: &dyn MessageDyn, indent: usize) {
+        let d = m.descriptor_dyn();
+        let mut first = true;
 
-fn print_to_internal(m: &MessageRef, buf: &mut String, pretty: bool, indent: usize) {
-    let d = m.descriptor_dyn();
-    let mut first = true;
-    for f in d.fields() {
-        match f.get_reflect(&**m) {
-            ReflectFieldRef::Map(map) => {
-                for (k, v) in &map {
-                    print_start_field(buf, pretty, indent, &mut first, f.get_name());
-                    buf.push_str(" {");
-                    if pretty {
-                        buf.push_str("\n");
-                    }
+        let any_expanded = match m.downcast_ref::<Any>() {
+            Some(any) if self.print_options.expand_any => {
+                self.print_any_expanded(any, indent, &mut first)
+            }
+            _ => false,
+        };
+
+        if !any_expanded {
+            for f in d.fields() {
+                match f.get_reflect(m) {
+                    ReflectFieldRef::Map(map) => {
+                        for (k, v) in &map {
+                            self.print_start_field(indent, &mut first, f.get_name());
+                            self.buf.push_str(" {");
+                            if self.pretty() {
+                                self.buf.push_str("\n");
+                            }
 
-                    let mut entry_first = true;
+                            let mut entry_first = true;
 
-                    print_field(buf, pretty, indent + 1, &mut entry_first, "key", k);
-                    print_field(buf, pretty, indent + 1, &mut entry_first, "value", v);
-                    do_indent(buf, pretty, indent);
-                    buf.push_str("}");
-                    print_end_field(buf, pretty);
+                            self.print_field(indent + 1, &mut entry_first, "key", k);
+                            self.print_field(indent + 1, &mut entry_first, "value", v);
+                            self.do_indent(indent);
+                            self.buf.push_str("}");
+                            self.print_end_field();
+                        }
+                    }
+                    ReflectFieldRef::Repeated(repeated) => {
+                        // TODO: do not print zeros for v3
+                        for v in repeated {
+                            self.print_field(indent, &mut first, f.get_name(), v);
+                        }
+                    }
+                    ReflectFieldRef::Optional(optional) => {
+                        if let Some(v) = optional {
+                            self.print_field(indent, &mut first, f.get_name(), v);
+                        }
+                    }
                 }
             }
-            ReflectFieldRef::Repeated(repeated) => {
-                // TODO: do not print zeros for v3
-                for v in repeated {
-                    print_field(buf, pretty, indent, &mut first, f.get_name(), v);
+        }
+
+        // Unknown fields are printed in the order of first occurrence
+        for (n, values) in m.get_unknown_fields_dyn() {
+            let extension = self
+                .print_options
+                .type_registry
+                .find_extension_by_number(&d, n);
+            if let Some(extension) = extension {
+                // Print raw unknown values if extension cannot be decoded
+                if let Ok(values) = extension.get_values(m) {
+                    for v in &values {
+                        self.print_field(
+                            indent,
+                            &mut first,
+                            BracketedName(extension.full_name()),
+                            v.as_value_ref(),
+                        );
+                    }
+                    continue;
                 }
             }
-            ReflectFieldRef::Optional(optional) => {
-                if let Some(v) = optional {
-                    print_field(buf, pretty, indent, &mut first, f.get_name(), v);
+
+            if self.print_options.skip_unknown_fields {
+                continue;
+            }
+
+            for v in values {
+                if self.print_options.decode_unknown_messages {
+                    if let Some(fields) = decode_unknown_message(&v, indent as u32) {
+                        let mut message = Empty::new();
+                        *message.mut_unknown_fields() = fields;
+                        self.print_start_field(indent, &mut first, n);
+                        self.print_message_value(&message, indent);
+                        self.print_end_field();
+                        continue;
+                    }
                 }
+                self.print_field(indent, &mut first, n, v.to_reflect_value_ref());
             }
         }
     }
+}
+
+/// Text format print options.
+///
+/// # Examples
+///
+/// ```
+/// use protobuf::reflect::TypeRegistry;
+/// use protobuf::text_format;
+///
+/// let mut type_registry = TypeRegistry::new();
+/// type_registry.add_file(&protobuf::descriptor::file_descriptor());
+/// let print_options = text_format::PrintOptions {
+///     type_registry,
+///     ..Default::default()
+/// };
+/// ```
+#[derive(Debug, Clone)]
+pub struct PrintOptions {
+    /// Types used to print `google.protobuf.Any` in expanded form
+    /// (`[type.googleapis.com/pkg.Msg] { ... }`) and extension fields
+    /// (`[pkg.ext_field]: ...`).
+    ///
+    /// `Any` with unresolved type URL and unresolved extensions
+    /// are printed as regular fields.
+    pub type_registry: TypeRegistry,
+    /// Print whole message in one line (default).
+    ///
+    /// When `false`, each field is printed on its own line.
+    pub single_line: bool,
+    /// Number of spaces per nesting level when not in single line mode, `2` by default.
+    pub indent_width: usize,
+    /// Do not print unknown fields.
+    pub skip_unknown_fields: bool,
+    /// Print unknown length-delimited values which can be parsed as messages
+    /// as nested messages rather than as bytes.
+    ///
+    /// Values are decoded the same way as in [`raw_message`](crate::raw_message).
+    pub decode_unknown_messages: bool,
+    /// Print non-ASCII characters of strings as is rather than as octal escapes.
+    pub utf8: bool,
+    /// Print `google.protobuf.Any` in expanded form when its type is found
+    /// in `type_registry` (default).
+    pub expand_any: bool,
+    /// Prevent initializing `PrintOptions` enumerating all field.
+    pub _future_options: (),
+}
 
-    let unknown_fields = m.get_unknown_fields_dyn();
-    let mut numbers: Vec<u32> = m.get_unknown_fields_dyn().iter().map(|(n, _)| n).collect();
-    // Sort for stable output
-    numbers.sort();
-    for &n in &numbers {
-        for v in unknown_fields.get(n).unwrap() {
-            // TODO: try decode nested message for length-delimited
-            print_field(buf, pretty, indent, &mut first, n, v.to_reflect_value_ref());
+impl Default for PrintOptions {
+    fn default() -> PrintOptions {
+        PrintOptions {
+            type_registry: TypeRegistry::default(),
+            single_line: true,
+            indent_width: 2,
+            skip_unknown_fields: false,
+            decode_unknown_messages: false,
+            utf8: false,
+            expand_any: true,
+            _future_options: (),
         }
     }
 }
 
+fn print_to_internal(m: &dyn MessageDyn, buf: &mut String, print_options: &PrintOptions) {
+    let mut printer = Printer { buf, print_options };
+    printer.print_message(m, 0);
+}
+
 /// Text-format
 pub fn print_to(m: &dyn MessageDyn, buf: &mut String) {
-    print_to_internal(&MessageRef::from(m), buf, false, 0)
+    print_to_with_options(m, buf, &PrintOptions::default())
 }
 
-fn print_to_string_internal(m: &dyn MessageDyn, pretty: bool) -> String {
-    let mut r = String::new();
-    print_to_internal(&MessageRef::from(m), &mut r, pretty, 0);
-    r.to_string()
+/// Text-format
+pub fn print_to_with_options(m: &dyn MessageDyn, buf: &mut String, print_options: &PrintOptions) {
+    print_to_internal(m, buf, print_options)
 }
 
 /// Text-format
 pub fn print_to_string(m: &dyn MessageDyn) -> String {
-    print_to_string_internal(m, false)
+    print_to_string_with_options(m, &PrintOptions::default())
+}
+
+/// Text-format
+pub fn print_to_string_with_options(m: &dyn MessageDyn, print_options: &PrintOptions) -> String {
+    let mut r = String::new();
+    print_to_with_options(m, &mut r, print_options);
+    r
 }
 
 /// Text-format to `fmt::Formatter`.
 pub fn fmt(m: &dyn MessageDyn, f: &mut fmt::Formatter) -> fmt::Result {
-    let pretty = f.alternate();
-    f.write_str(&print_to_string_internal(m, pretty))
+    let print_options = PrintOptions {
+        single_line: !f.alternate(),
+        ..PrintOptions::default()
+    };
+    f.write_str(&print_to_string_with_options(m, &print_options))
 }
 
 #[cfg(test)]
@@ -1,4 +1,3 @@
-use std::collections::hash_map;
 use std::collections::hash_map::DefaultHasher;
 use std::collections::HashMap;
 use std::default::Default;
@@ -18,7 +17,7 @@ use crate::CodedOutputStream;
 /// Unknown value.
 ///
 /// See [`UnknownFields`](crate::UnknownFields) for the explanations.
-#[derive(Debug)]
+#[derive(Clone, PartialEq, Eq, Debug)]
 pub enum UnknownValue {
     /// 32-bit unknown (e. g. `fixed32` or `float`)
     Fixed32(u32),
@@ -28,6 +27,8 @@ pub enum UnknownValue {
     Varint(u64),
     /// Length-delimited unknown (e. g. `message` or `string`)
     LengthDelimited(Vec<u8>),
+    /// Group unknown: encoded group content without start and end group tags
+    Group(Vec<u8>),
 }
 
 impl UnknownValue {
@@ -43,6 +44,7 @@ impl UnknownValue {
             UnknownValue::Fixed64(fixed64) => UnknownValueRef::Fixed64(fixed64),
             UnknownValue::Varint(varint) => UnknownValueRef::Varint(varint),
             UnknownValue::LengthDelimited(ref bytes) => UnknownValueRef::LengthDelimited(&bytes),
+            UnknownValue::Group(ref bytes) => UnknownValueRef::Group(bytes),
         }
     }
 
@@ -99,6 +101,8 @@ pub enum UnknownValueRef<'o> {
     Varint(u64),
     /// Length-delimited unknown
     LengthDelimited(&'o [u8]),
+    /// Group unknown (content without start and end group tags)
+    Group(&'o [u8]),
 }
 
 impl<'o> UnknownValueRef<'o> {
@@ -109,6 +113,18 @@ impl<'o> UnknownValueRef<'o> {
             UnknownValueRef::Fixed64(_) => wire_format::WireTypeFixed64,
             UnknownValueRef::Varint(_) => wire_format::WireTypeVarint,
             UnknownValueRef::LengthDelimited(_) => wire_format::WireTypeLengthDelimited,
+            UnknownValueRef::Group(_) => wire_format::WireTypeStartGroup,
+        }
+    }
+
+    /// Copy this value into an owned value.
+    pub fn to_value(&self) -> UnknownValue {
+        match *self {
+            UnknownValueRef::Fixed32(v) => UnknownValue::Fixed32(v),
+            UnknownValueRef::Fixed64(v) => UnknownValue::Fixed64(v),
+            UnknownValueRef::Varint(v) => UnknownValue::Varint(v),
+            UnknownValueRef::LengthDelimited(v) => UnknownValue::LengthDelimited(v.to_vec()),
+            UnknownValueRef::Group(v) => UnknownValue::Group(v.to_vec()),
         }
     }
 
@@ -118,6 +134,7 @@ impl<'o> UnknownValueRef<'o> {
             UnknownValueRef::Fixed64(v) => ReflectValueRef::U64(*v),
             UnknownValueRef::Varint(v) => ReflectValueRef::U64(*v),
             UnknownValueRef::LengthDelimited(v) => ReflectValueRef::Bytes(v),
+            UnknownValueRef::Group(v) => ReflectValueRef::Bytes(v),
         }
     }
 }
@@ -135,6 +152,8 @@ pub struct UnknownValues {
     pub varint: Vec<u64>,
     /// Length-delimited unknowns
     pub length_delimited: Vec<Vec<u8>>,
+    /// Group unknowns
+    pub group: Vec<Vec<u8>>,
 }
 
 impl UnknownValues {
@@ -147,9 +166,19 @@ impl UnknownValues {
             UnknownValue::LengthDelimited(length_delimited) => {
                 self.length_delimited.push(length_delimited)
             }
+            UnknownValue::Group(group) => self.group.push(group),
         };
     }
 
+    /// Total number of values.
+    pub(crate) fn values_count(&self) -> usize {
+        self.fixed32.len()
+            + self.fixed64.len()
+            + self.varint.len()
+            + self.length_delimited.len()
+            + self.group.len()
+    }
+
     /// Iterate over unknown values
     pub fn iter<'s>(&'s self) -> UnknownValuesIter<'s> {
         UnknownValuesIter {
@@ -157,6 +186,7 @@ impl UnknownValues {
             fixed64: self.fixed64.iter(),
             varint: self.varint.iter(),
             length_delimited: self.length_delimited.iter(),
+            group: self.group.iter(),
         }
     }
 }
@@ -176,6 +206,7 @@ pub struct UnknownValuesIter<'o> {
     fixed64: slice::Iter<'o, u64>,
     varint: slice::Iter<'o, u64>,
     length_delimited: slice::Iter<'o, Vec<u8>>,
+    group: slice::Iter<'o, Vec<u8>>,
 }
 
 impl<'o> Iterator for UnknownValuesIter<'o> {
@@ -198,10 +229,17 @@ impl<'o> Iterator for UnknownValuesIter<'o> {
         if length_delimited.is_some() {
             return Some(UnknownValueRef::LengthDelimited(&length_delimited.unwrap()));
         }
+        if let Some(group) = self.group.next() {
+            return Some(UnknownValueRef::Group(group));
+        }
         None
     }
 }
 
+/// Position of unknown values which were not read by parser:
+/// they are written after all known fields.
+pub(crate) const AFTER_KNOWN_FIELDS: u32 = u32::MAX;
+
 /// Hold "unknown" fields in parsed message.
 ///
 /// Field may be unknown if it they are added in newer version of `.proto`.
@@ -210,28 +248,127 @@ impl<'o> Iterator for UnknownValuesIter<'o> {
 ///
 /// For example, in this operation: load from DB, modify, store to DB,
 /// even when working with older `.proto` file, new fields won't be lost.
-#[derive(Clone, PartialEq, Eq, Debug, Default)]
+///
+/// Values are serialized in the order they were added,
+/// so parsing and serializing a message preserves relative order of unknown fields.
+/// Regular serialization writes unknown fields after all known fields.
+/// Parser also remembers which known field preceded each unknown value, and
+/// [`Message::write_to_bytes_preserving_order`](crate::Message::write_to_bytes_preserving_order)
+/// writes unknown values back at those positions.
+/// Equality and hash ignore that order.
+#[derive(Clone, Debug, Default)]
 pub struct UnknownFields {
-    /// The map.
     // option is needed, because HashMap constructor performs allocation,
     // and very expensive
-    fields: Option<Box<HashMap<u32, UnknownValues>>>,
+    fields: Option<Box<UnknownFieldsData>>,
+}
+
+#[derive(Clone, Debug, Default)]
+struct UnknownFieldsData {
+    /// Values grouped by field number, in the order of first occurrence of the number.
+    fields: Vec<(u32, UnknownValues)>,
+    /// Field number to index in `fields`.
+    index: HashMap<u32, usize>,
+    /// All values in the order they were added:
+    /// index in `fields`, wire type, index in `UnknownValues` vec of that wire type,
+    /// and position relative to known fields (see [`UnknownFields::add_parsed_value`]).
+    order: Vec<(usize, wire_format::WireType, usize, u32)>,
+}
+
+impl UnknownFieldsData {
+    fn add_value(&mut self, number: u32, value: UnknownValue, after: u32) {
+        let fields = &mut self.fields;
+        let field_index = *self.index.entry(number).or_insert_with(|| {
+            fields.push((number, UnknownValues::default()));
+            fields.len() - 1
+        });
+        let values = &mut self.fields[field_index].1;
+        let wire_type = value.wire_type();
+        let value_index = match wire_type {
+            wire_format::WireTypeFixed32 => values.fixed32.len(),
+            wire_format::WireTypeFixed64 => values.fixed64.len(),
+            wire_format::WireTypeVarint => values.varint.len(),
+            wire_format::WireTypeLengthDelimited => values.length_delimited.len(),
+            wire_format::WireTypeStartGroup => values.group.len(),
+            wire_format::WireTypeEndGroup => unreachable!(),
+        };
+        values.add_value(value);
+        self.order
+            .push((field_index, wire_type, value_index, after));
+    }
+
+    fn value(
+        &self,
+        field_index: usize,
+        wire_type: wire_format::WireType,
+        value_index: usize,
+    ) -> UnknownValueRef<'_> {
+        let values = &self.fields[field_index].1;
+        match wire_type {
+            wire_format::WireTypeFixed32 => UnknownValueRef::Fixed32(values.fixed32[value_index]),
+            wire_format::WireTypeFixed64 => UnknownValueRef::Fixed64(values.fixed64[value_index]),
+            wire_format::WireTypeVarint => UnknownValueRef::Varint(values.varint[value_index]),
+            wire_format::WireTypeLengthDelimited => {
+                UnknownValueRef::LengthDelimited(&values.length_delimited[value_index])
+            }
+            wire_format::WireTypeStartGroup => UnknownValueRef::Group(&values.group[value_index]),
+            wire_format::WireTypeEndGroup => unreachable!(),
+        }
+    }
+
+    fn remove(&mut self, number: u32) {
+        let removed = match self.index.remove(&number) {
+            Some(removed) => removed,
+            None => return,
+        };
+        self.fields.remove(removed);
+        for index in self.index.values_mut() {
+            if *index > removed {
+                *index -= 1;
+            }
+        }
+        self.order.retain(|&(index, ..)| index != removed);
+        for (index, ..) in &mut self.order {
+            if *index > removed {
+                *index -= 1;
+            }
+        }
+    }
+
+    fn clear(&mut self) {
+        self.fields.clear();
+        self.index.clear();
+        self.order.clear();
+    }
 }
 
+/// Field order is not important.
+impl PartialEq for UnknownFields {
+    fn eq(&self, other: &UnknownFields) -> bool {
+        let len = self.iter().count();
+        len == other.iter().count()
+            && self
+                .iter()
+                .all(|(number, values)| other.get(number) == Some(values))
+    }
+}
+
+impl Eq for UnknownFields {}
+
 /// Very simple hash implementation of `Hash` for `UnknownFields`.
-/// Since map is unordered, we cannot put entry hashes into hasher,
+/// Since field order is not important, we cannot put entry hashes into hasher,
 /// instead we summing hashes of entries.
 impl Hash for UnknownFields {
     fn hash<H: Hasher>(&self, state: &mut H) {
-        if let Some(ref map) = self.fields {
-            if !map.is_empty() {
+        if let Some(ref data) = self.fields {
+            if !data.fields.is_empty() {
                 let mut hash: u64 = 0;
-                for (k, v) in &**map {
+                for (k, v) in &data.fields {
                     let mut entry_hasher = DefaultHasher::new();
                     Hash::hash(&(k, v), &mut entry_hasher);
                     hash = hash.wrapping_add(entry_hasher.finish());
                 }
-                Hash::hash(&map.len(), state);
+                Hash::hash(&data.fields.len(), state);
                 Hash::hash(&hash, state);
             }
         }
@@ -244,66 +381,102 @@ impl UnknownFields {
         UnknownFields { fields: None }
     }
 
-    fn init_map(&mut self) {
+    fn data(&mut self) -> &mut UnknownFieldsData {
         if self.fields.is_none() {
             self.fields = Some(Default::default());
         }
-    }
-
-    fn find_field<'a>(&'a mut self, number: &'a u32) -> &'a mut UnknownValues {
-        self.init_map();
-
-        match self.fields.as_mut().unwrap().entry(*number) {
-            hash_map::Entry::Occupied(e) => e.into_mut(),
-            hash_map::Entry::Vacant(e) => e.insert(Default::default()),
-        }
+        self.fields.as_mut().unwrap()
     }
 
     /// Add unknown fixed 32-bit
     pub fn add_fixed32(&mut self, number: u32, fixed32: u32) {
-        self.find_field(&number).fixed32.push(fixed32);
+        self.add_value(number, UnknownValue::Fixed32(fixed32));
     }
 
     /// Add unknown fixed 64-bit
     pub fn add_fixed64(&mut self, number: u32, fixed64: u64) {
-        self.find_field(&number).fixed64.push(fixed64);
+        self.add_value(number, UnknownValue::Fixed64(fixed64));
     }
 
     /// Add unknown varint
     pub fn add_varint(&mut self, number: u32, varint: u64) {
-        self.find_field(&number).varint.push(varint);
+        self.add_value(number, UnknownValue::Varint(varint));
     }
 
     /// Add unknown length delimited
     pub fn add_length_delimited(&mut self, number: u32, length_delimited: Vec<u8>) {
-        self.find_field(&number)
-            .length_delimited
-            .push(length_delimited);
+        self.add_value(number, UnknownValue::LengthDelimited(length_delimited));
+    }
+
+    /// Add unknown group content
+    pub fn add_group(&mut self, number: u32, group: Vec<u8>) {
+        self.add_value(number, UnknownValue::Group(group));
     }
 
     /// Add unknown value
     pub fn add_value(&mut self, number: u32, value: UnknownValue) {
-        self.find_field(&number).add_value(value);
+        self.data().add_value(number, value, AFTER_KNOWN_FIELDS);
+    }
+
+    /// Add unknown value read by parser after known field number `after`,
+    /// zero if the value precedes all known fields.
+    pub(crate) fn add_parsed_value(&mut self, number: u32, value: UnknownValue, after: u32) {
+        self.data().add_value(number, value, after);
     }
 
     /// Remove unknown field by number
     pub fn remove(&mut self, field_number: u32) {
         if let Some(fields) = &mut self.fields {
-            fields.remove(&field_number);
+            fields.remove(field_number);
         }
     }
 
-    /// Iterate over all unknowns
+    /// Iterate over all unknowns grouped by field number.
+    ///
+    /// Fields are returned in the order of the first occurrence of the field number.
     pub fn iter<'s>(&'s self) -> UnknownFieldsIter<'s> {
         UnknownFieldsIter {
-            entries: self.fields.as_ref().map(|m| m.iter()),
+            entries: self.fields.as_ref().map(|m| m.fields.iter()),
+        }
+    }
+
+    /// Iterate over all unknown values in the order they were added.
+    ///
+    /// This is the order in which values are serialized.
+    pub fn iter_values<'s>(&'s self) -> UnknownFieldsValuesIter<'s> {
+        UnknownFieldsValuesIter {
+            data: self.fields.as_deref(),
+            pos: 0,
         }
     }
 
+    /// Iterate over all unknown values in the order they were added,
+    /// together with the number of known field after which the value was parsed
+    /// (or [`AFTER_KNOWN_FIELDS`] if the value was not parsed).
+    pub(crate) fn iter_values_with_positions<'s>(
+        &'s self,
+    ) -> impl Iterator<Item = (u32, u32, UnknownValueRef<'s>)> + 's {
+        let data = self.fields.as_deref();
+        data.into_iter().flat_map(|data| {
+            data.order
+                .iter()
+                .map(move |&(field_index, wire_type, value_index, after)| {
+                    (
+                        after,
+                        data.fields[field_index].0,
+                        data.value(field_index, wire_type, value_index),
+                    )
+                })
+        })
+    }
+
     /// Find unknown field by number
     pub fn get(&self, field_number: u32) -> Option<&UnknownValues> {
         match self.fields {
-            Some(ref map) => map.get(&field_number),
+            Some(ref data) => data
+                .index
+                .get(&field_number)
+                .map(|&index| &data.fields[index].1),
             None => None,
         }
     }
@@ -312,8 +485,7 @@ impl UnknownFields {
     pub fn write_to_bytes(&self) -> Vec<u8> {
         let mut r = Vec::with_capacity(rt::unknown_fields_size(self) as usize);
         let mut stream = CodedOutputStream::vec(&mut r);
-        // TODO: figure out how to make output stable
-        stream.write_unknown_fields_sorted(self).unwrap();
+        stream.write_unknown_fields(self).unwrap();
         stream.flush().unwrap();
         drop(stream);
         r
@@ -339,7 +511,7 @@ impl<'a> IntoIterator for &'a UnknownFields {
 
 /// Iterator over [`UnknownFields`](crate::UnknownFields)
 pub struct UnknownFieldsIter<'s> {
-    entries: Option<hash_map::Iter<'s, u32, UnknownValues>>,
+    entries: Option<slice::Iter<'s, (u32, UnknownValues)>>,
 }
 
 impl<'s> Iterator for UnknownFieldsIter<'s> {
@@ -347,15 +519,37 @@ impl<'s> Iterator for UnknownFieldsIter<'s> {
 
     fn next(&mut self) -> Option<(u32, &'s UnknownValues)> {
         match self.entries {
-            Some(ref mut entries) => entries.next().map(|(&number, values)| (number, values)),
+            Some(ref mut entries) => entries.next().map(|(number, values)| (*number, values)),
             None => None,
         }
     }
 }
 
+/// Iterator over values of [`UnknownFields`](crate::UnknownFields)
+/// in the order they were added.
+pub struct UnknownFieldsValuesIter<'s> {
+    data: Option<&'s UnknownFieldsData>,
+    pos: usize,
+}
+
+impl<'s> Iterator for UnknownFieldsValuesIter<'s> {
+    type Item = (u32, UnknownValueRef<'s>);
+
+    fn next(&mut self) -> Option<(u32, UnknownValueRef<'s>)> {
+        let data = self.data?;
+        let &(field_index, wire_type, value_index, _) = data.order.get(self.pos)?;
+        self.pos += 1;
+        Some((
+            data.fields[field_index].0,
+            data.value(field_index, wire_type, value_index),
+        ))
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::UnknownFields;
+    use super::UnknownValue;
     use std::collections::hash_map::DefaultHasher;
     use std::hash::Hash;
     use std::hash::Hasher;
@@ -382,5 +576,45 @@ mod test {
         }
 
         assert_eq!(hash(&unknown_fields_1), hash(&unknown_fields_2));
+        assert_eq!(unknown_fields_1, unknown_fields_2);
+    }
+
+    #[test]
+    fn unknown_fields_values_order() {
+        let mut unknown_fields = UnknownFields::new();
+        unknown_fields.add_varint(10, 1);
+        unknown_fields.add_fixed32(12, 2);
+        unknown_fields.add_varint(11, 3);
+        unknown_fields.add_varint(10, 4);
+        unknown_fields.add_fixed64(12, 5);
+
+        fn values(unknown_fields: &UnknownFields) -> Vec<(u32, UnknownValue)> {
+            unknown_fields
+                .iter_values()
+                .map(|(n, v)| (n, v.to_value()))
+                .collect()
+        }
+
+        assert_eq!(
+            vec![
+                (10, UnknownValue::Varint(1)),
+                (12, UnknownValue::Fixed32(2)),
+                (11, UnknownValue::Varint(3)),
+                (10, UnknownValue::Varint(4)),
+                (12, UnknownValue::Fixed64(5)),
+            ],
+            values(&unknown_fields)
+        );
+
+        unknown_fields.remove(12);
+        assert_eq!(
+            vec![
+                (10, UnknownValue::Varint(1)),
+                (11, UnknownValue::Varint(3)),
+                (10, UnknownValue::Varint(4)),
+            ],
+            values(&unknown_fields)
+        );
+        assert_eq!(Some(&vec![3]), unknown_fields.get(11).map(|v| &v.varint));
     }
 }
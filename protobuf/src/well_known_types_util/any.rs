@@ -9,7 +9,7 @@ impl Any {
         format!("{}/{}", type_url_prefix, descriptor.full_name())
     }
 
-    fn get_type_name_from_type_url(type_url: &str) -> Option<&str> {
+    pub(crate) fn get_type_name_from_type_url(type_url: &str) -> Option<&str> {
         match type_url.rfind('/') {
             Some(i) => Some(&type_url[i + 1..]),
             None => None,